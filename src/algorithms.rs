@@ -0,0 +1,404 @@
+//! Remote sshd algorithm audit, in the spirit of ssh-audit. A scan opens the
+//! SSH handshake far enough to read the server's version banner and its
+//! KEXINIT, which lists the key exchange, host key, cipher and MAC algorithms
+//! it offers, then hangs up before any key exchange. So it needs no login
+//! and no known_hosts entry. Weak algorithms are rated with the local
+//! audit's severities.
+
+use crate::audit::Severity;
+use crate::models::ServerConnection;
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+
+pub const SCAN_TIMEOUT: Duration = Duration::from_secs(10);
+
+const CLIENT_BANNER: &[u8] = b"SSH-2.0-Ghost_scan\r\n";
+const MSG_KEXINIT: u8 = 20;
+/// RFC 4253 asks implementations to take packets this big
+const MAX_PACKET: usize = 35000;
+/// Lines a server may send before its version (RFC 4253 4.2)
+const MAX_PRE_BANNER_LINES: usize = 50;
+const MAX_LINE: usize = 255;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    Kex,
+    HostKey,
+    Cipher,
+    Mac,
+}
+
+impl Category {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Category::Kex => "Key exchange",
+            Category::HostKey => "Host keys",
+            Category::Cipher => "Ciphers",
+            Category::Mac => "MACs",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Algorithm {
+    pub name: String,
+    /// How bad offering it is, and why
+    pub weakness: Option<(Severity, &'static str)>,
+}
+
+/// What a server offers, in its order of preference
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Offer {
+    /// Version line, e.g. `SSH-2.0-OpenSSH_9.6`
+    pub banner: String,
+    pub kex: Vec<Algorithm>,
+    pub host_keys: Vec<Algorithm>,
+    /// Both directions, which servers practically always offer alike
+    pub ciphers: Vec<Algorithm>,
+    pub macs: Vec<Algorithm>,
+}
+
+impl Offer {
+    pub fn sections(&self) -> [(Category, &[Algorithm]); 4] {
+        [
+            (Category::Kex, &self.kex),
+            (Category::HostKey, &self.host_keys),
+            (Category::Cipher, &self.ciphers),
+            (Category::Mac, &self.macs),
+        ]
+    }
+
+    pub fn weak(&self) -> impl Iterator<Item = &Algorithm> {
+        self.sections().into_iter().flat_map(|(_, algorithms)| algorithms).filter(|a| a.weakness.is_some())
+    }
+
+    pub fn worst(&self) -> Option<Severity> {
+        self.weak().filter_map(|a| a.weakness.map(|(severity, _)| severity)).max()
+    }
+}
+
+/// Why offering `name` weakens a server, if it does. Flags SHA-1 key
+/// exchange, SHA-1 RSA and DSA signatures, CBC and broken ciphers, and MD5
+/// (plus, mildly, SHA-1) MACs.
+pub fn weakness(category: Category, name: &str) -> Option<(Severity, &'static str)> {
+    match category {
+        Category::Kex if name.contains("group1-sha1") => Some((Severity::High, "1024-bit group with SHA-1")),
+        Category::Kex if name.contains("sha1") => Some((Severity::Medium, "SHA-1 key exchange")),
+        Category::HostKey if name.starts_with("ssh-dss") => Some((Severity::High, "DSA, 1024 bits with SHA-1")),
+        Category::HostKey if name.starts_with("ssh-rsa") => Some((Severity::Medium, "RSA signatures with SHA-1")),
+        Category::Cipher if name == "none" => Some((Severity::High, "no encryption")),
+        Category::Cipher if name.starts_with("arcfour") || name.starts_with("des") || name.starts_with("3des") => {
+            Some((Severity::High, "broken cipher"))
+        }
+        Category::Cipher if name.contains("cbc") => Some((Severity::Medium, "CBC mode")),
+        Category::Mac if name == "none" => Some((Severity::High, "no integrity check")),
+        Category::Mac if name.starts_with("hmac-md5") => Some((Severity::High, "MD5")),
+        Category::Mac if name.starts_with("hmac-sha1") => Some((Severity::Low, "SHA-1")),
+        _ => None,
+    }
+}
+
+/// Read what the sshd at `host:port` offers
+pub async fn scan(host: &str, port: u16) -> Result<Offer> {
+    tokio::time::timeout(SCAN_TIMEOUT, async {
+        let mut stream = TcpStream::connect((host, port)).await.with_context(|| format!("Couldn't connect to {}:{}", host, port))?;
+        // Both sides may send their version at once
+        stream.write_all(CLIENT_BANNER).await?;
+        read_offer(&mut BufReader::new(stream)).await
+    })
+    .await
+    .map_err(|_| anyhow!("Timed out after {}s", SCAN_TIMEOUT.as_secs()))?
+}
+
+/// The version line, then the first packet, which servers send unencrypted
+/// as their KEXINIT
+async fn read_offer<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Offer> {
+    let mut banner = String::new();
+    for _ in 0..MAX_PRE_BANNER_LINES {
+        let mut line = Vec::new();
+        (&mut *reader).take(MAX_LINE as u64 + 1).read_until(b'\n', &mut line).await?;
+        if line.is_empty() {
+            bail!("Connection closed before the SSH version");
+        }
+        let text = String::from_utf8_lossy(&line).trim_end().to_string();
+        if text.starts_with("SSH-") {
+            banner = text;
+            break;
+        }
+    }
+    if banner.is_empty() {
+        bail!("Not an SSH server");
+    }
+    if !(banner.starts_with("SSH-2.0-") || banner.starts_with("SSH-1.99-")) {
+        bail!("Only speaks SSH protocol 1 ({})", banner);
+    }
+
+    let length = reader.read_u32().await.context("No KEXINIT from the server")? as usize;
+    if !(5..=MAX_PACKET).contains(&length) {
+        bail!("Bad packet length {}", length);
+    }
+    let mut packet = vec![0; length];
+    reader.read_exact(&mut packet).await?;
+    let padding = packet[0] as usize;
+    let payload = packet.get(1..length.saturating_sub(padding)).unwrap_or_default();
+    let mut offer = parse_kexinit(payload).ok_or_else(|| anyhow!("Malformed KEXINIT"))?;
+    offer.banner = banner;
+    Ok(offer)
+}
+
+fn parse_kexinit(payload: &[u8]) -> Option<Offer> {
+    let (&kind, rest) = payload.split_first()?;
+    if kind != MSG_KEXINIT {
+        return None;
+    }
+    // 16 byte cookie, then kex, host key, ciphers and MACs each way, ...
+    let mut rest = rest.get(16..)?;
+    let mut lists = Vec::new();
+    for _ in 0..6 {
+        let length = u32::from_be_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+        let list = std::str::from_utf8(rest.get(4..4 + length)?).ok()?;
+        lists.push(list.split(',').filter(|n| !n.is_empty()).map(str::to_string).collect::<Vec<_>>());
+        rest = &rest[4 + length..];
+    }
+    let rate = |category, names: Vec<String>| -> Vec<Algorithm> {
+        let mut algorithms: Vec<Algorithm> = Vec::new();
+        for name in names {
+            if !algorithms.iter().any(|a| a.name == name) {
+                algorithms.push(Algorithm { weakness: weakness(category, &name), name });
+            }
+        }
+        algorithms
+    };
+    let mut lists = lists.into_iter();
+    let mut next = || lists.next().unwrap_or_default();
+    let (kex, host_keys) = (next(), next());
+    let ciphers = [next(), next()].concat();
+    let macs = [next(), next()].concat();
+    Some(Offer {
+        banner: String::new(),
+        kex: rate(Category::Kex, kex),
+        host_keys: rate(Category::HostKey, host_keys),
+        ciphers: rate(Category::Cipher, ciphers),
+        macs: rate(Category::Mac, macs),
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScanState {
+    Running,
+    Done(Offer),
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerScan {
+    pub name: String,
+    pub host: String,
+    pub port: u16,
+    pub state: ScanState,
+}
+
+/// Weak algorithms across the fleet
+#[derive(Debug, Default, PartialEq)]
+pub struct FleetSummary {
+    pub scanned: usize,
+    pub failed: usize,
+    pub running: usize,
+    /// Servers offering at least one weak algorithm
+    pub weak_servers: usize,
+    /// Each weak algorithm with the number of servers offering it, most
+    /// widespread first
+    pub common: Vec<(String, Severity, usize)>,
+}
+
+/// The last fleet scan; started from the audit view
+#[derive(Debug)]
+pub struct AlgorithmScan {
+    pub servers: Vec<ServerScan>,
+    pub selected: usize,
+    /// Results of an older scan are dropped
+    generation: u64,
+    tx: mpsc::UnboundedSender<(u64, usize, Result<Offer, String>)>,
+    rx: mpsc::UnboundedReceiver<(u64, usize, Result<Offer, String>)>,
+}
+
+impl Default for AlgorithmScan {
+    fn default() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self { servers: Vec::new(), selected: 0, generation: 0, tx, rx }
+    }
+}
+
+impl AlgorithmScan {
+    /// Scan every server at once, replacing the previous results
+    pub fn start<'a>(&mut self, servers: impl IntoIterator<Item = &'a ServerConnection>) {
+        self.generation += 1;
+        self.servers = servers
+            .into_iter()
+            .map(|s| ServerScan { name: s.name.clone(), host: s.host.clone(), port: s.port, state: ScanState::Running })
+            .collect();
+        self.servers.sort_by(|a, b| a.name.cmp(&b.name));
+        self.selected = 0;
+        for (index, server) in self.servers.iter().enumerate() {
+            let (generation, tx) = (self.generation, self.tx.clone());
+            let (host, port) = (server.host.clone(), server.port);
+            tokio::spawn(async move {
+                let result = scan(&host, port).await.map_err(|e| format!("{:#}", e));
+                let _ = tx.send((generation, index, result));
+            });
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.servers.iter().any(|s| s.state == ScanState::Running)
+    }
+
+    /// Apply finished scans. Returns true when the last one just came in.
+    pub fn poll_events(&mut self) -> bool {
+        let mut changed = false;
+        while let Ok((generation, index, result)) = self.rx.try_recv() {
+            let Some(server) = self.servers.get_mut(index).filter(|_| generation == self.generation) else {
+                continue;
+            };
+            server.state = match result {
+                Ok(offer) => ScanState::Done(offer),
+                Err(e) => ScanState::Failed(e),
+            };
+            changed = true;
+        }
+        changed && !self.is_running()
+    }
+
+    pub fn selected_scan(&self) -> Option<&ServerScan> {
+        self.servers.get(self.selected)
+    }
+
+    pub fn move_selection(&mut self, down: bool) {
+        if down {
+            self.selected = (self.selected + 1).min(self.servers.len().saturating_sub(1));
+        } else {
+            self.selected = self.selected.saturating_sub(1);
+        }
+    }
+
+    pub fn summary(&self) -> FleetSummary {
+        let mut summary = FleetSummary::default();
+        let mut common: BTreeMap<&str, (Severity, usize)> = BTreeMap::new();
+        for server in &self.servers {
+            match &server.state {
+                ScanState::Running => summary.running += 1,
+                ScanState::Failed(_) => summary.failed += 1,
+                ScanState::Done(offer) => {
+                    summary.scanned += 1;
+                    let mut weak = offer.weak().peekable();
+                    if weak.peek().is_some() {
+                        summary.weak_servers += 1;
+                    }
+                    for algorithm in weak {
+                        let severity = algorithm.weakness.map_or(Severity::Low, |(s, _)| s);
+                        common.entry(&algorithm.name).or_insert((severity, 0)).1 += 1;
+                    }
+                }
+            }
+        }
+        summary.common = common.into_iter().map(|(name, (severity, count))| (name.to_string(), severity, count)).collect();
+        summary.common.sort_by(|a, b| b.2.cmp(&a.2).then(b.1.cmp(&a.1)));
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kexinit(lists: [&str; 10]) -> Vec<u8> {
+        let mut payload = vec![MSG_KEXINIT];
+        payload.extend([7; 16]);
+        for list in lists {
+            payload.extend((list.len() as u32).to_be_bytes());
+            payload.extend(list.as_bytes());
+        }
+        payload.extend([0, 0, 0, 0, 0]);
+        let padding = 8 - (payload.len() + 5) % 8 + 4;
+        let mut packet = ((payload.len() + padding + 1) as u32).to_be_bytes().to_vec();
+        packet.push(padding as u8);
+        packet.extend(payload);
+        packet.extend(vec![0; padding]);
+        packet
+    }
+
+    #[tokio::test]
+    async fn reads_the_offer_after_pre_banner_lines() {
+        let mut stream = b"Authorized use only\r\nSSH-2.0-OpenSSH_7.4\r\n".to_vec();
+        stream.extend(kexinit([
+            "curve25519-sha256,diffie-hellman-group14-sha1,diffie-hellman-group1-sha1",
+            "ssh-rsa,rsa-sha2-512,ssh-ed25519",
+            "aes128-ctr,aes256-cbc",
+            "aes128-ctr,aes256-cbc,3des-cbc",
+            "hmac-sha2-256,hmac-sha1,hmac-md5",
+            "hmac-sha2-256,hmac-sha1",
+            "none",
+            "none",
+            "",
+            "",
+        ]));
+        let offer = read_offer(&mut stream.as_slice()).await.unwrap();
+
+        assert_eq!(offer.banner, "SSH-2.0-OpenSSH_7.4");
+        let names = |algorithms: &[Algorithm]| algorithms.iter().map(|a| a.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&offer.ciphers), ["aes128-ctr", "aes256-cbc", "3des-cbc"]);
+        let weak: Vec<(&str, Severity)> = offer.weak().map(|a| (a.name.as_str(), a.weakness.unwrap().0)).collect();
+        assert_eq!(
+            weak,
+            [
+                ("diffie-hellman-group14-sha1", Severity::Medium),
+                ("diffie-hellman-group1-sha1", Severity::High),
+                ("ssh-rsa", Severity::Medium),
+                ("aes256-cbc", Severity::Medium),
+                ("3des-cbc", Severity::High),
+                ("hmac-sha1", Severity::Low),
+                ("hmac-md5", Severity::High),
+            ]
+        );
+        assert_eq!(offer.worst(), Some(Severity::High));
+
+        assert!(read_offer(&mut &b"HTTP/1.1 400 Bad Request\r\n\r\n"[..]).await.is_err());
+        assert!(read_offer(&mut &b"SSH-1.5-old\r\n"[..]).await.unwrap_err().to_string().contains("protocol 1"));
+    }
+
+    #[tokio::test]
+    async fn scans_a_listening_server_and_summarises_the_fleet() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut reply = b"SSH-2.0-Test\r\n".to_vec();
+            reply.extend(kexinit(["curve25519-sha256", "ssh-rsa", "aes256-cbc", "aes256-cbc", "hmac-sha2-256", "hmac-sha2-256", "none", "none", "", ""]));
+            socket.write_all(&reply).await.unwrap();
+            let mut banner = [0; CLIENT_BANNER.len()];
+            socket.read_exact(&mut banner).await.unwrap();
+            assert_eq!(banner, CLIENT_BANNER);
+        });
+
+        let server = |name: &str| ServerConnection::new(name.to_string(), "127.0.0.1".to_string(), port, "me".to_string());
+        // Nothing listens on port 1
+        let servers = [server("web"), ServerConnection { port: 1, ..server("down") }];
+        let mut fleet = AlgorithmScan::default();
+        fleet.start(&servers);
+        while !fleet.poll_events() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        assert!(matches!(fleet.servers[0].state, ScanState::Failed(_)));
+        let summary = fleet.summary();
+        assert_eq!((summary.scanned, summary.failed, summary.weak_servers), (1, 1, 1));
+        assert_eq!(
+            summary.common,
+            [("aes256-cbc".to_string(), Severity::Medium, 1), ("ssh-rsa".to_string(), Severity::Medium, 1)]
+        );
+    }
+}
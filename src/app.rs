@@ -1,13 +1,30 @@
-use crate::config::{AppSettings, ConfigManager};
-use crate::forms::ServerForm;
+use crate::askpass::Askpass;
+use crate::audit;
+use crate::config::{AppSettings, ConfigManager, CredentialStore, DeepCheckConfig, KeybindingsConfig, SessionRecord, TerminalConfig};
+use crate::credentials::{CredentialPrompt, CredentialPromptKind, Credentials, FollowUp};
+use crate::embedded::EmbeddedSession;
+use crate::forms::{FormTarget, ServerForm};
+use crate::guard::{protected_names, Guarded, NameConfirm};
 use crate::health::{HealthMonitor, HealthUpdate};
-use crate::models::{AppMode, AppState, HealthStatus, ServerConnection, SessionInfo};
-use crate::ssh::ConnectionMode;
+use crate::keymap::{Action, KeyBinding, KeyContext, KeyLookup, Keymap};
+use crate::keys::{KeyManager, KeyPrompt, KeyPromptKind};
+use crate::native::{NeedsSecret, Secrets};
+use crate::models::{rect_contains, AppMode, AppState, AuthMethod, HealthStatus, IdleAction, LoadingContext, PanelLayout, ServerConnection, SessionInfo, SessionKind};
+use crate::palette::{self, PaletteCommand, PaletteState};
+use crate::process::{self, Activity, ProcessState};
+use crate::sftp::{PromptKind, SftpBrowser};
+use crate::transfers::{resolve_targets, JobState, TransferForm, TransferMode};
+use crate::ssh::{self, terminal_config_errors, ConnectionMode, SessionPid};
+use crate::background;
+use crate::themes::{load_custom_themes, ThemeVariant};
 use crate::ui::ui;
 use anyhow::Result;
 use chrono::Utc;
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers,
+        MouseButton, MouseEvent, MouseEventKind,
+    },
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     ExecutableCommand,
 };
@@ -16,9 +33,27 @@ use ratatui::{
     Terminal,
 };
 use std::{
+    collections::HashMap,
     io::stdout,
     time::{Duration, Instant},
 };
+use zeroize::Zeroizing;
+
+/// Two clicks on the same row within this interval count as a double-click
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+/// How often external sessions are checked for having exited
+const SESSION_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A connection finished off the UI task, picked up on the next tick
+enum LaunchEvent {
+    /// A native login is through, or failed; `secrets` are the ones it was
+    /// tried with, to ask for what was missing
+    Native { server_id: String, secrets: Secrets, result: Result<EmbeddedSession> },
+    /// The pid of a session launched elsewhere was looked for; `None` if it
+    /// couldn't be found
+    Tracked { server_id: String, kind: SessionKind, pid: Option<u32> },
+}
 
 pub struct App {
     pub state: AppState,
@@ -29,10 +64,33 @@ pub struct App {
     pub health_monitor: HealthMonitor,
     pub health_task: Option<tokio::task::JoinHandle<()>>,
     pub connection_mode: ConnectionMode,
+    /// `[keybindings]` as written in config.toml, kept so saving doesn't drop it
+    pub keybindings: KeybindingsConfig,
+    /// `[terminal]` section, likewise kept for saving
+    pub terminal_config: TerminalConfig,
+    /// `[deep_check]` section, likewise
+    pub deep_check: DeepCheckConfig,
+    /// When the first key of an unfinished chord was pressed
+    pub pending_since: Option<Instant>,
+    /// Last left click on the server list (time, row index), for double-click
+    pub last_click: Option<(Instant, usize)>,
+    /// Panel border being dragged with the mouse
+    pub dragging_border: Option<usize>,
+    /// Last time session processes were inspected
+    pub last_session_poll: Instant,
+    /// What `sessions.json` holds, to only rewrite it on changes
+    pub saved_sessions: Vec<SessionRecord>,
+    /// Passwords and key passphrases typed for the next native login, by
+    /// server id
+    login_secrets: HashMap<String, Secrets>,
+    launch_tx: tokio::sync::mpsc::UnboundedSender<LaunchEvent>,
+    launch_rx: tokio::sync::mpsc::UnboundedReceiver<LaunchEvent>,
 }
 
 impl App {
     pub fn new(tick_rate: Duration, connection_mode: ConnectionMode) -> Result<Self> {
+        // Without an OpenSSH client, Ghost's own is the only way to connect
+        let connection_mode = if ssh::openssh_available() { connection_mode } else { ConnectionMode::Native };
         let config_manager = ConfigManager::new()?;
         let config = config_manager.load_config()?;
         let connections = config_manager.config_to_connections(&config);
@@ -40,17 +98,80 @@ impl App {
         let mut state = AppState::default();
         state.server_manager.connections = connections;
         state.server_manager.show_only_online = config.settings.show_only_online;
-        state.theme_manager.set_theme(config.settings.theme);
+        state.credentials = Credentials::new(config.credentials.clone(), config_manager.vault_path());
+        // Sessions still running from before a restart
+        let saved_sessions = config_manager.load_sessions();
+        let adopted = state.server_manager.adopt_sessions(&saved_sessions);
+        state.layout = PanelLayout {
+            mode: config.settings.layout_mode.clone(),
+            panel_sizes: config.settings.panel_sizes,
+            auto: config.settings.layout_auto,
+        }
+        .validated();
+        state.layout_presets = config.settings.layout_presets.clone();
+
+        let (custom_themes, theme_errors) = load_custom_themes(&config_manager.themes_dir());
+        state.theme_manager.set_custom_themes(custom_themes);
+        // Asking the terminal can hold up startup, so only done for auto;
+        // stdin is ours until the TUI starts. Picking Auto later in the
+        // theme selector falls back to the dark theme
+        let background = (config.settings.theme == ThemeVariant::Auto).then(background::detect).flatten();
+        state.theme_manager.set_auto_themes(
+            config.settings.light_theme.clone(),
+            config.settings.dark_theme.clone(),
+            background,
+        );
+        state.theme_manager.set_theme(config.settings.theme.clone());
+
+        let mut startup_warnings: Vec<String> = theme_errors
+            .iter()
+            .map(|e| format!("Theme error: {}", e))
+            .collect();
+        let (keymap, keymap_errors) = Keymap::from_config(&config.keybindings);
+        state.keymap = keymap;
+        startup_warnings.extend(keymap_errors.iter().map(|e| format!("Keybinding error: {}", e)));
+        startup_warnings.extend(terminal_config_errors(&config.terminal).into_iter().map(|e| format!("Terminal error: {}", e)));
+
+        for theme in [&config.settings.theme, &config.settings.light_theme, &config.settings.dark_theme] {
+            if !state.theme_manager.has_variant(theme) {
+                startup_warnings.push(format!(
+                    "Theme '{}' not found, using {}",
+                    theme.name(),
+                    ThemeVariant::default().name()
+                ));
+            }
+        }
+        if !startup_warnings.is_empty() {
+            state.show_popup = true;
+            state.popup_message = format!("⚠️ {}", startup_warnings.join("\n"));
+            state.popup_shown_at = Some(Utc::now());
+        } else if adopted > 0 {
+            state.show_popup = true;
+            state.popup_message = format!("🔗 Reattached to {} running SSH session{}", adopted, if adopted == 1 { "" } else { "s" });
+            state.popup_shown_at = Some(Utc::now());
+        }
+        let (launch_tx, launch_rx) = tokio::sync::mpsc::unbounded_channel();
         
         Ok(Self {
             state,
             last_tick: Instant::now(),
             tick_rate,
             config_manager,
+            health_monitor: HealthMonitor::new(30, config.terminal.clone(), config.settings.verify_login, config.deep_check.clone()), // Check every 30 seconds
             app_settings: config.settings,
-            health_monitor: HealthMonitor::new(30), // Check every 30 seconds
             health_task: None,
             connection_mode,
+            keybindings: config.keybindings,
+            terminal_config: config.terminal,
+            deep_check: config.deep_check,
+            pending_since: None,
+            last_click: None,
+            dragging_border: None,
+            last_session_poll: Instant::now(),
+            saved_sessions,
+            login_secrets: HashMap::new(),
+            launch_tx,
+            launch_rx,
         })
     }
 
@@ -58,6 +179,7 @@ impl App {
         // Setup terminal
         enable_raw_mode()?;
         stdout().execute(EnterAlternateScreen)?;
+        stdout().execute(EnableMouseCapture)?;
 
         let backend = CrosstermBackend::new(stdout());
         let mut terminal = Terminal::new(backend)?;
@@ -71,6 +193,12 @@ impl App {
 
         let result = self.run_app(&mut terminal).await;
 
+        // Embedded sessions can't outlive the pane they're drawn in
+        for session in &mut self.state.terminals.sessions {
+            session.kill();
+        }
+        self.persist_sessions();
+
         // Stop health monitoring
         self.health_monitor.stop().await;
         if let Some(task) = self.health_task.take() {
@@ -78,6 +206,7 @@ impl App {
         }
 
         // Cleanup terminal
+        stdout().execute(DisableMouseCapture)?;
         disable_raw_mode()?;
         stdout().execute(LeaveAlternateScreen)?;
 
@@ -95,10 +224,12 @@ impl App {
             let timeout = self.tick_rate.saturating_sub(self.last_tick.elapsed());
             
             if event::poll(timeout)? {
-                if let Event::Key(key) = event::read()? {
-                    if key.kind == KeyEventKind::Press {
+                match event::read()? {
+                    Event::Key(key) if key.kind == KeyEventKind::Press => {
                         self.handle_key_event(key.code, key.modifiers).await?;
                     }
+                    Event::Mouse(mouse) => self.handle_mouse_event(mouse).await,
+                    _ => {}
                 }
             }
 
@@ -115,168 +246,1232 @@ impl App {
     }
 
     async fn handle_key_event(&mut self, key: KeyCode, modifiers: KeyModifiers) -> Result<()> {
-        match self.state.mode {
-            AppMode::Normal => self.handle_normal_mode(key, modifiers).await?,
-            AppMode::AddServer => self.handle_add_server_mode(key).await?,
-            AppMode::EditServer(_) => self.handle_edit_server_mode(key).await?,
-            AppMode::ConfirmDelete(_) => self.handle_confirm_delete_mode(key).await?,
-            AppMode::ConfirmDiscard => self.handle_confirm_discard_mode(key).await?,
-            AppMode::Help => self.handle_help_mode(key).await?,
-            AppMode::Connecting(_) => self.handle_connecting_mode(key).await?,
-            AppMode::Loading(_) => self.handle_loading_mode(key).await?,
-            AppMode::History => self.handle_history_mode(key).await?,
-            AppMode::Analytics => self.handle_analytics_mode(key).await?,
-            AppMode::Sessions => self.handle_sessions_mode(key).await?,
+        // Text entry and the modal waits keep their fixed keys; everything
+        // else goes through the keymap.
+        let context = if self.state.show_theme_selector {
+            // The theme selector floats over whatever view is active and
+            // takes all input while it's open.
+            KeyContext::ThemeSelector
+        } else {
+            match self.state.mode {
+                AppMode::Normal => KeyContext::Normal,
+                AppMode::Sessions => KeyContext::Sessions,
+                AppMode::Help => KeyContext::Help,
+                AppMode::History => KeyContext::History,
+                AppMode::Analytics => KeyContext::Analytics,
+                AppMode::ConfirmDelete(_) | AppMode::ConfirmDiscard => KeyContext::Confirm,
+                AppMode::Sftp if self.state.sftp.as_ref().is_some_and(|b| b.prompt.is_some()) => {
+                    return self.handle_sftp_prompt(key).await
+                }
+                AppMode::Sftp => KeyContext::Sftp,
+                AppMode::Transfers => KeyContext::Transfers,
+                AppMode::Keys if self.state.keys.as_ref().is_some_and(|k| k.prompt.is_some()) => {
+                    return self.handle_keys_prompt(key).await
+                }
+                AppMode::Keys => KeyContext::Keys,
+                AppMode::Audit => KeyContext::Audit,
+                AppMode::Algorithms => KeyContext::Algorithms,
+                AppMode::TransferForm => return self.handle_transfer_form(key).await,
+                AppMode::CredentialPrompt => return self.handle_credential_prompt(key).await,
+                AppMode::ConfirmName => return self.handle_name_confirm(key).await,
+                AppMode::AddServer => return self.handle_add_server_mode(key).await,
+                AppMode::EditServer(_) => return self.handle_edit_server_mode(key).await,
+                AppMode::Connecting(_) => return self.handle_connecting_mode(key).await,
+                AppMode::Loading(_) => return self.handle_loading_mode(key).await,
+                AppMode::CommandPalette => return self.handle_palette_input(key, modifiers).await,
+                AppMode::Terminal => return self.handle_terminal_input(key, modifiers).await,
+            }
+        };
+
+        // Esc/Enter dismiss a visible popup before anything else sees them
+        if context == KeyContext::Normal
+            && self.state.show_popup
+            && self.state.pending_keys.is_empty()
+            && matches!(key, KeyCode::Esc | KeyCode::Enter)
+        {
+            self.state.show_popup = false;
+            self.state.popup_message.clear();
+            self.state.popup_shown_at = None;
+            return Ok(());
+        }
+
+        if let Some(action) = self.resolve_key(context, KeyBinding::new(key, modifiers)) {
+            self.perform_action(context, action).await?;
         }
         Ok(())
     }
 
-    async fn handle_normal_mode(&mut self, key: KeyCode, modifiers: KeyModifiers) -> Result<()> {
-        match key {
-            KeyCode::Char('q') => {
-                self.state.should_quit = true;
+    /// Mouse input, mapped through the regions recorded by the last render
+    async fn handle_mouse_event(&mut self, mouse: MouseEvent) {
+        let (column, row) = (mouse.column, mouse.row);
+        let scroll = match mouse.kind {
+            MouseEventKind::ScrollDown => Some(Action::MoveDown),
+            MouseEventKind::ScrollUp => Some(Action::MoveUp),
+            _ => None,
+        };
+
+        if self.state.show_theme_selector {
+            if let Some(action) = scroll {
+                self.handle_theme_selector(action);
             }
-            KeyCode::Esc => {
-                // First check if there's a popup to dismiss
+            return;
+        }
+
+        match self.state.mode {
+            AppMode::Normal => self.handle_server_list_mouse(mouse).await,
+            AppMode::Sessions => match (mouse.kind, scroll) {
+                (_, Some(Action::MoveDown)) => self.move_session_selection_down(),
+                (_, Some(_)) => self.move_session_selection_up(),
+                (MouseEventKind::Down(MouseButton::Left), _) => {
+                    let index = self.state.hit_areas.session_list.and_then(|l| l.index_at(column, row));
+                    if let Some(index) = index.filter(|i| *i < self.state.get_filtered_sessions().len()) {
+                        self.state.session_selected_index = index;
+                    }
+                }
+                _ => {}
+            },
+            AppMode::Help => match scroll {
+                Some(Action::MoveDown) => self.state.help_scroll = self.state.help_scroll.saturating_add(3),
+                Some(_) => self.state.help_scroll = self.state.help_scroll.saturating_sub(3),
+                None => {}
+            },
+            AppMode::CommandPalette => {
+                if let Some(action) = scroll {
+                    let count = palette::filter_entries(palette::build_entries(&self.state), &self.state.palette.query).len();
+                    self.move_palette_selection(count, action == Action::MoveDown);
+                }
+            }
+            AppMode::Transfers => {
+                if let Some(action) = scroll {
+                    self.state.transfers.move_selection(action == Action::MoveDown);
+                }
+            }
+            AppMode::Sftp => {
+                if let (Some(action), Some(browser)) = (scroll, self.state.sftp.as_mut()) {
+                    browser.move_selection(action == Action::MoveDown);
+                }
+            }
+            AppMode::Keys => {
+                if let (Some(action), Some(keys)) = (scroll, self.state.keys.as_mut()) {
+                    keys.move_selection(action == Action::MoveDown);
+                }
+            }
+            AppMode::Audit => {
+                if let Some(action) = scroll {
+                    self.move_audit_selection(action == Action::MoveDown);
+                }
+            }
+            AppMode::Algorithms => {
+                if let Some(action) = scroll {
+                    self.state.algorithm_scan.move_selection(action == Action::MoveDown);
+                }
+            }
+            AppMode::AddServer | AppMode::EditServer(_) if mouse.kind == MouseEventKind::Down(MouseButton::Left) => {
+                self.handle_form_click(column, row);
+            }
+            _ => {}
+        }
+    }
+
+    async fn handle_server_list_mouse(&mut self, mouse: MouseEvent) {
+        let (column, row) = (mouse.column, mouse.row);
+        match mouse.kind {
+            MouseEventKind::ScrollDown => self.move_selection_down(),
+            MouseEventKind::ScrollUp => self.move_selection_up(),
+            MouseEventKind::Down(MouseButton::Left) => {
                 if self.state.show_popup {
                     self.state.show_popup = false;
                     self.state.popup_message.clear();
                     self.state.popup_shown_at = None;
+                    return;
+                }
+
+                // Borders are one cell wide on each side of the split
+                let on_border = self.state.hit_areas.main_panels.filter(|a| rect_contains(*a, column, row)).and_then(|_| {
+                    self.state.hit_areas.panel_borders.iter().position(|&x| column == x || column + 1 == x)
+                });
+                if on_border.is_some() {
+                    self.dragging_border = on_border;
+                    return;
+                }
+
+                let index = self.state.hit_areas.server_list.and_then(|l| l.index_at(column, row));
+                let Some(index) = index.filter(|i| *i < self.state.server_manager.filtered_connections().len()) else {
+                    return;
+                };
+                let double_click = matches!(self.last_click, Some((at, i)) if i == index && at.elapsed() < DOUBLE_CLICK);
+                self.state.server_manager.selected_index = index;
+                if double_click {
+                    self.last_click = None;
+                    if let Some(connection) = self.get_selected_connection() {
+                        self.connect_to_server(connection.id.clone()).await;
+                    }
+                } else {
+                    self.last_click = Some((Instant::now(), index));
+                }
+            }
+            MouseEventKind::Drag(MouseButton::Left) => {
+                if let (Some(border), Some(area)) = (self.dragging_border, self.state.hit_areas.main_panels) {
+                    let offset = column.saturating_sub(area.x) as u32;
+                    let percent = (offset * 100 / area.width.max(1) as u32) as u16;
+                    self.state.layout.drag_border(border, percent);
+                }
+            }
+            MouseEventKind::Up(MouseButton::Left) if self.dragging_border.is_some() => {
+                self.dragging_border = None;
+                self.persist_layout();
+            }
+            _ => {}
+        }
+    }
+
+    /// Focus the clicked form element; clicking a focused auth method
+    /// dropdown cycles it, clicking in a text field moves the cursor there.
+    fn handle_form_click(&mut self, column: u16, row: u16) {
+        let target = self
+            .state
+            .hit_areas
+            .form_fields
+            .iter()
+            .find(|(_, area)| rect_contains(*area, column, row))
+            .copied();
+        let (Some((target, area)), Some(form)) = (target, self.state.server_form.as_mut()) else {
+            return;
+        };
+
+        if target == FormTarget::AuthMethod && form.auth_method_focused {
+            form.next_auth_method();
+            return;
+        }
+        form.focus(target);
+        if let Some(field) = form.current_field_mut() {
+            field.set_cursor(column.saturating_sub(area.x + 1) as usize);
+        }
+    }
+
+    /// Feed a key press to the keymap, buffering it while it's the start of
+    /// a chord. A key that breaks a chord is retried on its own.
+    fn resolve_key(&mut self, context: KeyContext, key: KeyBinding) -> Option<Action> {
+        self.state.pending_keys.push(key);
+        self.pending_since = Some(Instant::now());
+        match self.state.keymap.lookup(context, &self.state.pending_keys) {
+            KeyLookup::Action(action) => {
+                self.state.pending_keys.clear();
+                Some(action)
+            }
+            KeyLookup::Pending => None,
+            KeyLookup::Unbound => {
+                let retry = self.state.pending_keys.len() > 1;
+                self.state.pending_keys.clear();
+                if retry {
+                    self.resolve_key(context, key)
                 } else {
-                    self.state.should_quit = true;
+                    None
                 }
             }
-            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+        }
+    }
+
+    /// Run a keymap action. The same action can mean different things in
+    /// different contexts (moving through servers vs. sessions vs. themes).
+    async fn perform_action(&mut self, context: KeyContext, action: Action) -> Result<()> {
+        match (context, action) {
+            (KeyContext::ThemeSelector, action) => self.handle_theme_selector(action),
+            (KeyContext::Confirm, Action::Confirm) => self.confirm_prompt(),
+            (KeyContext::Confirm, _) => self.cancel_prompt(),
+            (KeyContext::Sftp, action) => self.handle_sftp_action(action).await,
+            (KeyContext::Keys, action) if action != Action::Back => self.handle_keys_action(action).await,
+            (KeyContext::Audit, Action::MoveDown | Action::MoveUp) => {
+                self.move_audit_selection(action == Action::MoveDown);
+            }
+            (KeyContext::Audit, Action::Refresh) | (_, Action::ShowAudit) => self.run_audit(),
+            (KeyContext::Algorithms, Action::MoveDown | Action::MoveUp) => {
+                self.state.algorithm_scan.move_selection(action == Action::MoveDown);
+            }
+            (_, Action::ScanAlgorithms) => self.scan_algorithms(),
+            (KeyContext::Transfers, Action::MoveDown | Action::MoveUp) => {
+                self.state.transfers.move_selection(action == Action::MoveDown);
+            }
+            (KeyContext::Sessions, Action::MoveDown) => self.move_session_selection_down(),
+            (KeyContext::Sessions, Action::MoveUp) => self.move_session_selection_up(),
+            (KeyContext::Help, Action::ScrollDown) => {
+                self.state.help_scroll = self.state.help_scroll.saturating_add(1);
+            }
+            (KeyContext::Help, Action::ScrollUp) => {
+                self.state.help_scroll = self.state.help_scroll.saturating_sub(1);
+            }
+            (_, Action::Back) => {
+                self.state.mode = AppMode::Normal;
+            }
+            (_, Action::Quit) | (_, Action::ForceQuit) => {
                 self.state.should_quit = true;
             }
-            KeyCode::Char('x') if modifiers.contains(KeyModifiers::CONTROL) => {
+            (_, Action::KillAllSessions) => {
                 self.kill_all_sessions().await;
             }
-            KeyCode::Char('j') | KeyCode::Down => {
+            (_, Action::MoveDown) => {
                 self.move_selection_down();
             }
-            KeyCode::Char('k') | KeyCode::Up => {
+            (_, Action::MoveUp) => {
                 self.move_selection_up();
             }
-            KeyCode::Char('a') => {
+            (_, Action::AddServer) => {
                 self.state.server_form = Some(ServerForm::new_add_form());
                 self.state.mode = AppMode::AddServer;
             }
-            KeyCode::Char('d') => {
+            (_, Action::DeleteServer) => {
                 if let Some(connection) = self.get_selected_connection() {
-                    self.state.mode = AppMode::ConfirmDelete(connection.id.clone());
+                    let id = connection.id.clone();
+                    let protected = protected_names([connection]);
+                    // Typing the name stands in for the y/n prompt
+                    if protected.is_empty() {
+                        self.state.mode = AppMode::ConfirmDelete(id);
+                    } else {
+                        self.guarded(Guarded::Delete(id), protected).await;
+                    }
                 }
             }
-            KeyCode::Char('e') => {
+            (_, Action::EditServer) => {
                 if let Some(connection) = self.get_selected_connection() {
                     let connection_id = connection.id.clone();
                     self.state.server_form = Some(ServerForm::new_edit_form(connection));
                     self.state.mode = AppMode::EditServer(connection_id);
                 }
             }
-            KeyCode::Char('r') => {
+            (_, Action::Refresh) => {
                 self.refresh_connections().await;
             }
-            KeyCode::Char('f') => {
+            (_, Action::OpenSftp) => {
+                self.open_sftp().await;
+            }
+            (_, Action::SendFiles) | (_, Action::FetchFiles) => {
+                let mode = if action == Action::SendFiles { TransferMode::Send } else { TransferMode::Fetch };
+                let targets = self.get_selected_connection().map(|c| c.name.clone()).unwrap_or_default();
+                self.state.transfer_form = Some(TransferForm::new(mode, &targets));
+                self.state.mode = AppMode::TransferForm;
+            }
+            (_, Action::ShowTransfers) => {
+                self.state.mode = AppMode::Transfers;
+            }
+            (_, Action::ShowKeys) => {
+                self.open_keys().await;
+            }
+            (_, Action::RetryTransfer) => {
+                let failed = self.state.transfers.selected_job().filter(|j| matches!(j.state, JobState::Failed(_)));
+                match failed.map(|job| (job.id, protected_names([&job.server]))) {
+                    Some((id, protected)) => self.guarded(Guarded::Retry(vec![id]), protected).await,
+                    None => {
+                        self.state.show_popup = true;
+                        self.state.popup_message = "Only failed transfers can be retried".to_string();
+                        self.state.popup_shown_at = Some(Utc::now());
+                    }
+                }
+            }
+            (_, Action::RetryFailed) => {
+                let failed = self.state.transfers.failed();
+                let ids: Vec<u64> = failed.iter().map(|j| j.id).collect();
+                let protected = protected_names(failed.iter().map(|j| &j.server));
+                if ids.is_empty() {
+                    self.state.show_popup = true;
+                    self.state.popup_message = "No failed transfers to retry".to_string();
+                    self.state.popup_shown_at = Some(Utc::now());
+                } else {
+                    self.guarded(Guarded::Retry(ids), protected).await;
+                }
+            }
+            (_, Action::ClearFinished) => {
+                self.state.transfers.clear_finished();
+            }
+            (_, Action::SavePassword) => {
+                self.start_save_password();
+            }
+            (_, Action::LockVault) => {
+                self.state.show_popup = true;
+                self.state.popup_message = if self.state.credentials.lock() {
+                    "🔒 Vault locked".to_string()
+                } else {
+                    "The vault isn't unlocked".to_string()
+                };
+                self.state.popup_shown_at = Some(Utc::now());
+            }
+            (_, Action::ToggleOnlineFilter) => {
                 self.state.server_manager.show_only_online = !self.state.server_manager.show_only_online;
             }
-            KeyCode::Char('h') | KeyCode::F(1) => {
+            (_, Action::ShowHelp) => {
+                self.state.help_scroll = 0;
                 self.state.mode = AppMode::Help;
             }
-            KeyCode::Char('H') => {
+            (_, Action::CommandPalette) => {
+                self.state.palette = PaletteState::default();
+                self.state.mode = AppMode::CommandPalette;
+            }
+            (_, Action::ShowHistory) => {
                 self.state.mode = AppMode::History;
             }
-            KeyCode::Char('A') => {
+            (_, Action::ShowAnalytics) => {
                 self.state.mode = AppMode::Analytics;
             }
-            KeyCode::Char('S') => {
+            (_, Action::ShowSessions) => {
                 self.state.mode = AppMode::Sessions;
             }
-            KeyCode::Char('t') => {
-                // Toggle theme selector
-                self.state.show_theme_selector = !self.state.show_theme_selector;
+            (_, Action::ThemeSelector) => {
+                self.open_theme_selector();
             }
-            KeyCode::Char('T') => {
+            (_, Action::NextTheme) => {
                 // Quick theme cycle
                 self.state.theme_manager.next_theme();
-                // Save theme preference
-                self.app_settings.theme = self.state.theme_manager.current_variant();
-                if let Err(e) = self.save_config() {
-                    self.state.show_popup = true;
-                    self.state.popup_message = format!("Failed to save theme: {}", e);
-                    self.state.popup_shown_at = Some(Utc::now());
-                } else {
-                    self.state.show_popup = true;
-                    self.state.popup_message = format!("🎨 Switched to {}", self.state.theme_manager.current_variant().name());
-                    self.state.popup_shown_at = Some(Utc::now());
-                }
+                self.persist_theme();
             }
-            KeyCode::Char('l') => {
+            (_, Action::CycleLayout) => {
                 // Cycle layout mode
                 self.state.layout.cycle_layout();
                 self.state.show_popup = true;
-                self.state.popup_message = format!("📐 Layout: {:?}", self.state.layout.mode);
+                self.state.popup_message = format!("📐 Layout: {}", self.state.layout.label());
                 self.state.popup_shown_at = Some(Utc::now());
+                self.persist_layout();
             }
-            KeyCode::Char('[') => {
-                // Resize panels - decrease left, increase right
-                self.state.layout.resize_panels(-5);
-                self.state.show_popup = true;
-                self.state.popup_message = format!("⚖️  Panel sizes: {}% | {}% | {}%", 
-                    self.state.layout.panel_sizes[0], 
-                    self.state.layout.panel_sizes[1], 
-                    self.state.layout.panel_sizes[2]);
-                self.state.popup_shown_at = Some(Utc::now());
+            (_, Action::NextLayoutPreset) => {
+                // Presets in name order, starting after the one applied last
+                let names: Vec<String> = self.state.layout_presets.keys().cloned().collect();
+                if names.is_empty() {
+                    self.state.show_popup = true;
+                    self.state.popup_message =
+                        "📐 No layout presets - add [settings.layout_presets.<name>] to config.toml".to_string();
+                    self.state.popup_shown_at = Some(Utc::now());
+                } else {
+                    let next = names
+                        .iter()
+                        .position(|n| *n == self.app_settings.panel_layout)
+                        .map_or(0, |i| (i + 1) % names.len());
+                    self.apply_layout_preset(&names[next]);
+                }
             }
-            KeyCode::Char(']') => {
-                // Resize panels - increase left, decrease right
-                self.state.layout.resize_panels(5);
+            (_, Action::ShrinkPanel) | (_, Action::GrowPanel) => {
+                // Resize panels - shrink grows the right side, grow the left
+                let delta = if action == Action::GrowPanel { 5 } else { -5 };
+                self.state.layout.resize_panels(delta);
                 self.state.show_popup = true;
                 self.state.popup_message = format!("⚖️  Panel sizes: {}% | {}% | {}%", 
                     self.state.layout.panel_sizes[0], 
                     self.state.layout.panel_sizes[1], 
                     self.state.layout.panel_sizes[2]);
                 self.state.popup_shown_at = Some(Utc::now());
+                self.persist_layout();
             }
-            KeyCode::Char('?') => {
+            (_, Action::ShowTooltip) => {
                 // Show contextual tooltip based on current mode/selection
                 self.show_contextual_tooltip();
             }
-            KeyCode::F(2) => {
+            (_, Action::ToggleTooltips) => {
                 // Toggle tooltips on/off
                 self.state.toggle_tooltips();
                 self.state.show_popup = true;
                 self.state.popup_message = if self.state.show_tooltips {
-                    "📊 Tooltips enabled - Press ? for contextual help".to_string()
+                    format!(
+                        "📊 Tooltips enabled - Press {} for contextual help",
+                        self.state.keymap.hint(KeyContext::Normal, Action::ShowTooltip)
+                    )
                 } else {
                     "❌ Tooltips disabled".to_string()
                 };
                 self.state.popup_shown_at = Some(Utc::now());
             }
-            KeyCode::Char(c) if c.is_ascii_digit() => {
-                let num = c.to_digit(10).unwrap() as usize;
-                if num > 0 && num <= 9 {
-                    let connections = self.state.server_manager.filtered_connections();
-                    if let Some(connection) = connections.get(num - 1) {
-                        self.connect_to_server(connection.id.clone()).await;
+            (_, Action::QuickConnect(num)) => {
+                let connections = self.state.server_manager.filtered_connections();
+                if let Some(connection) = connections.get(num as usize - 1) {
+                    self.connect_to_server(connection.id.clone()).await;
+                }
+            }
+            (_, Action::Connect) => {
+                if let Some(connection) = self.get_selected_connection() {
+                    self.connect_to_server(connection.id.clone()).await;
+                }
+            }
+            (_, Action::KillSession) => {
+                // Kill selected session
+                if let Some((pid, kind)) = self.get_selected_session().map(|s| (s.pid, s.kind.clone())) {
+                    let killed = self.state.kill_session(pid);
+                    // zellij has no way to close another pane, so say where
+                    // the ssh went rather than leave the pane unexplained
+                    if let (Ok(()), SessionKind::Zellij(pane)) = (killed, kind) {
+                        self.state.show_popup = true;
+                        self.state.popup_message = format!(
+                            "🔫 Ended ssh in zellij pane '{}'\nzellij doesn't let Ghost close the pane; it stays open until you close it (Ctrl+p x)",
+                            pane
+                        );
+                        self.state.popup_shown_at = Some(Utc::now());
                     }
                 }
             }
+            (_, Action::RefreshSessions) => {
+                // Refresh sessions
+                self.refresh_all_sessions().await;
+            }
+            (_, Action::SessionInfo) => {
+                // Bring session to foreground (placeholder)
+                if let Some(session) = self.get_selected_session() {
+                    let message = format!("Session for {} is running in PID {}\nWindow: {}", 
+                        session.server_name, session.pid, session.window_title);
+                    self.state.show_popup = true;
+                    self.state.popup_message = message;
+                    self.state.popup_shown_at = Some(chrono::Utc::now());
+                }
+            }
+            (_, Action::FocusTerminal) => {
+                if self.state.terminals.sessions.is_empty() {
+                    self.state.show_popup = true;
+                    self.state.popup_message =
+                        "No embedded sessions - start Ghost with --embedded to open connections in tabs".to_string();
+                    self.state.popup_shown_at = Some(Utc::now());
+                } else {
+                    self.state.mode = AppMode::Terminal;
+                }
+            }
+            (_, Action::NextTab) | (_, Action::PrevTab) => {
+                self.state.terminals.switch(action == Action::NextTab);
+            }
+            // Only meaningful in the contexts matched above
+            (_, Action::ScrollDown | Action::ScrollUp | Action::Confirm | Action::Cancel | Action::CycleFilter | Action::Apply) => {}
+            (
+                _,
+                Action::SwitchPane
+                | Action::OpenDir
+                | Action::ParentDir
+                | Action::Transfer
+                | Action::RenameEntry
+                | Action::MakeDir
+                | Action::DeleteEntry
+                | Action::GenerateKey
+                | Action::AddToAgent
+                | Action::InstallKey,
+            ) => {}
+        }
+        Ok(())
+    }
+
+    /// Embedded terminal: the few terminal-context bindings are handled
+    /// here, every other key is typed into the active session. Only single
+    /// keys are looked up, so a shell never waits on a half-typed chord.
+    async fn handle_terminal_input(&mut self, key: KeyCode, modifiers: KeyModifiers) -> Result<()> {
+        let binding = KeyBinding::new(key, modifiers);
+        if let KeyLookup::Action(action) = self.state.keymap.lookup(KeyContext::Terminal, &[binding]) {
+            return self.perform_action(KeyContext::Terminal, action).await;
+        }
+        if let Some(session) = self.state.terminals.active_mut() {
+            session.send_key(key, modifiers);
+        }
+        Ok(())
+    }
+
+    /// Open the file browser on the selected server. The open browser is
+    /// reused when it's for the same server, and never replaced while it
+    /// still has transfers or deletes running. Protected servers are
+    /// confirmed by name first, as files can be deleted from the browser.
+    async fn open_sftp(&mut self) {
+        let Some(connection) = self.get_selected_connection().cloned() else {
+            return;
+        };
+        match &self.state.sftp {
+            Some(browser) if browser.server_id == connection.id && browser.error.is_none() => {
+                self.state.mode = AppMode::Sftp;
+            }
+            Some(browser) if browser.is_busy() => {
+                self.state.show_popup = true;
+                self.state.popup_message = format!(
+                    "📁 Transfers or deletes on {} are still running - wait for them to finish first",
+                    browser.server_name
+                );
+                self.state.popup_shown_at = Some(Utc::now());
+            }
+            _ => {
+                let protected = protected_names([&connection]);
+                self.guarded(Guarded::OpenSftp(connection.id), protected).await;
+            }
+        }
+    }
+
+    /// Open a fresh file browser on the server with `id`
+    fn browse_server(&mut self, id: &str) {
+        let Some(connection) = self.state.server_manager.get_connection(id).cloned() else {
+            return;
+        };
+        let secrets = Secrets { password: self.stored_password(&connection), passphrase: None };
+        self.state.sftp = Some(SftpBrowser::open(&connection, secrets));
+        self.state.mode = AppMode::Sftp;
+    }
+
+    /// Open the key manager on ~/.ssh, freshly scanned
+    async fn open_keys(&mut self) {
+        let Some(home) = dirs::home_dir() else {
+            self.state.show_popup = true;
+            self.state.popup_message = "❌ No home directory to look for ~/.ssh in".to_string();
+            self.state.popup_shown_at = Some(Utc::now());
+            return;
+        };
+        let mut keys = KeyManager::new(home.join(".ssh"), self.state.server_manager.connections.values());
+        if let Some(previous) = self.state.keys.take() {
+            keys.installs = previous.installs;
+            keys.prompt = previous.prompt;
+        }
+        keys.reload().await;
+        self.state.keys = Some(keys);
+        self.state.mode = AppMode::Keys;
+    }
+
+    /// Audit the servers and ~/.ssh afresh and show the report
+    fn run_audit(&mut self) {
+        let Some(home) = dirs::home_dir() else {
+            self.state.show_popup = true;
+            self.state.popup_message = "❌ No home directory to look for ~/.ssh in".to_string();
+            self.state.popup_shown_at = Some(Utc::now());
+            return;
+        };
+        let report = audit::audit(self.state.server_manager.connections.values(), &home.join(".ssh"));
+        self.state.audit_selected = self.state.audit_selected.min(report.findings.len().saturating_sub(1));
+        self.state.audit = Some(report);
+        self.state.mode = AppMode::Audit;
+    }
+
+    /// Scan every server's sshd, unless a scan is still running, and show
+    /// the results as they come in
+    fn scan_algorithms(&mut self) {
+        let scan = &mut self.state.algorithm_scan;
+        if !scan.is_running() {
+            scan.start(self.state.server_manager.connections.values());
+        }
+        self.state.mode = AppMode::Algorithms;
+    }
+
+    fn move_audit_selection(&mut self, down: bool) {
+        let count = self.state.audit.as_ref().map_or(0, |r| r.findings.len());
+        let selected = &mut self.state.audit_selected;
+        *selected = if down { (*selected + 1).min(count.saturating_sub(1)) } else { selected.saturating_sub(1) };
+    }
+
+    /// Key manager keys. What a generate or add did, or why it failed, is
+    /// shown in a popup.
+    async fn handle_keys_action(&mut self, action: Action) {
+        // Installs start out aimed at the server selected in the list
+        let selected_server = self.get_selected_connection().map(|c| c.name.clone()).unwrap_or_default();
+        let Some(keys) = self.state.keys.as_mut() else {
+            self.state.mode = AppMode::Normal;
+            return;
+        };
+        let result = match action {
+            Action::MoveDown | Action::MoveUp => {
+                keys.move_selection(action == Action::MoveDown);
+                Ok(None)
+            }
+            Action::GenerateKey => {
+                keys.start_generate();
+                Ok(None)
+            }
+            Action::AddToAgent => keys.add_selected_to_agent(None).await,
+            Action::InstallKey => keys.start_install(&selected_server).map(|_| None),
+            Action::Refresh => {
+                keys.reload().await;
+                Ok(None)
+            }
+            _ => Ok(None),
+        };
+        self.show_keys_result(result);
+    }
+
+    /// Text entry for the key manager's prompts
+    async fn handle_keys_prompt(&mut self, key: KeyCode) -> Result<()> {
+        let Some(keys) = self.state.keys.as_mut() else {
+            return Ok(());
+        };
+        let Some(prompt) = keys.prompt.as_mut() else {
+            return Ok(());
+        };
+        if let KeyPromptKind::SwitchAuth { batch, .. } = prompt.kind {
+            keys.prompt = None;
+            if matches!(key, KeyCode::Char('y' | 'Y')) {
+                self.switch_to_key_auth(batch).await;
+            }
+            return Ok(());
+        }
+        let input = &mut prompt.input;
+        match key {
+            KeyCode::Esc => keys.prompt = None,
+            KeyCode::Enter if matches!(prompt.kind, KeyPromptKind::InstallTargets { .. }) => self.start_key_install().await,
             KeyCode::Enter => {
-                // First check if there's a popup to dismiss
-                if self.state.show_popup {
-                    self.state.show_popup = false;
-                    self.state.popup_message.clear();
-                    self.state.popup_shown_at = None;
-                } else if let Some(connection) = self.get_selected_connection() {
-                    self.connect_to_server(connection.id.clone()).await;
+                let result = keys.submit_prompt().await;
+                self.show_keys_result(result);
+            }
+            KeyCode::Char(c) => input.insert_char(c),
+            KeyCode::Backspace => input.delete_char(),
+            KeyCode::Delete => input.delete_char_forward(),
+            KeyCode::Left => input.move_cursor_left(),
+            KeyCode::Right => input.move_cursor_right(),
+            KeyCode::Home => input.move_cursor_to_start(),
+            KeyCode::End => input.move_cursor_to_end(),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Install the key of the open install prompt on the servers typed
+    /// into it, logging in to each with its stored password if it has one
+    async fn start_key_install(&mut self) {
+        let Some(keys) = self.state.keys.as_mut() else {
+            return;
+        };
+        let Some(KeyPrompt { kind: KeyPromptKind::InstallTargets { key }, input }) = keys.prompt.take() else {
+            return;
+        };
+        let servers = match resolve_targets(&input.value, self.state.server_manager.connections.values()) {
+            Ok(servers) => servers,
+            Err(e) => {
+                keys.prompt = Some(KeyPrompt { kind: KeyPromptKind::InstallTargets { key }, input });
+                self.show_keys_result(Err(e));
+                return;
+            }
+        };
+        let protected = protected_names(&servers);
+        let servers = servers
+            .into_iter()
+            .map(|server| {
+                let secrets = Secrets { password: self.stored_password(&server), passphrase: None };
+                (server, secrets)
+            })
+            .collect();
+        self.guarded(Guarded::InstallKey(key, servers), protected).await;
+    }
+
+    /// Have the servers that took the key in install `batch` log in with it
+    async fn switch_to_key_auth(&mut self, batch: u64) {
+        let Some(keys) = self.state.keys.as_ref() else {
+            return;
+        };
+        let switchable = keys.installs.switchable(batch);
+        for (server, key_path) in &switchable {
+            if let Some(connection) = self.state.server_manager.connections.get_mut(&server.id) {
+                connection.auth_method = AuthMethod::PublicKey { key_path: key_path.clone() };
+            }
+        }
+        let saved = self.save_config();
+        self.open_keys().await;
+        self.show_keys_result(saved.map(|_| {
+            Some(format!(
+                "🔑 {} server{} now log{} in with the key",
+                switchable.len(),
+                if switchable.len() == 1 { "" } else { "s" },
+                if switchable.len() == 1 { "s" } else { "" }
+            ))
+        }));
+    }
+
+    fn show_keys_result(&mut self, result: Result<Option<String>>) {
+        let message = match result {
+            Ok(Some(message)) => message,
+            Ok(None) => return,
+            Err(e) => format!("❌ {:#}", e),
+        };
+        self.state.show_popup = true;
+        self.state.popup_message = message;
+        self.state.popup_shown_at = Some(Utc::now());
+    }
+
+    /// Send/fetch form: Tab and the arrows move between fields, Enter queues
+    /// the transfer and shows the queue, Esc closes the form.
+    async fn handle_transfer_form(&mut self, key: KeyCode) -> Result<()> {
+        let Some(form) = self.state.transfer_form.as_mut() else {
+            self.state.mode = AppMode::Normal;
+            return Ok(());
+        };
+        match key {
+            KeyCode::Esc => {
+                self.state.transfer_form = None;
+                self.state.mode = AppMode::Normal;
+            }
+            KeyCode::Enter => match form.parse(self.state.server_manager.connections.values()) {
+                Ok((kind, servers)) => {
+                    let protected = protected_names(&servers);
+                    let servers = servers
+                        .into_iter()
+                        .map(|server| {
+                            let secrets = Secrets { password: self.stored_password(&server), passphrase: None };
+                            (server, secrets)
+                        })
+                        .collect();
+                    self.guarded(Guarded::Transfer(kind, servers), protected).await;
+                }
+                Err(e) => {
+                    self.state.show_popup = true;
+                    self.state.popup_message = format!("❌ {}", e);
+                    self.state.popup_shown_at = Some(Utc::now());
+                }
+            },
+            KeyCode::Tab | KeyCode::Down => form.next_field(),
+            KeyCode::BackTab | KeyCode::Up => form.previous_field(),
+            KeyCode::Char(c) => form.focused_field_mut().insert_char(c),
+            KeyCode::Backspace => form.focused_field_mut().delete_char(),
+            KeyCode::Delete => form.focused_field_mut().delete_char_forward(),
+            KeyCode::Left => form.focused_field_mut().move_cursor_left(),
+            KeyCode::Right => form.focused_field_mut().move_cursor_right(),
+            KeyCode::Home => form.focused_field_mut().move_cursor_to_start(),
+            KeyCode::End => form.focused_field_mut().move_cursor_to_end(),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Store a password for the selected server, unlocking (or creating)
+    /// the vault first if it's in use and locked
+    fn start_save_password(&mut self) {
+        let Some(server) = self.get_selected_connection() else {
+            return;
+        };
+        let (server_id, server_name) = (server.id.clone(), server.name.clone());
+        let refusal = if self.state.credentials.store() == CredentialStore::None {
+            Some("🔑 Password storage is off - set [credentials] store = \"vault\" or \"keyring\" in config.toml".to_string())
+        } else if !matches!(server.auth_method, AuthMethod::Password) {
+            Some(format!("🔑 {} doesn't use password authentication", server_name))
+        } else {
+            None
+        };
+        if let Some(message) = refusal {
+            self.state.show_popup = true;
+            self.state.popup_message = message;
+            self.state.popup_shown_at = Some(Utc::now());
+            return;
+        }
+
+        let kind = if !self.state.credentials.is_locked() {
+            CredentialPromptKind::Password
+        } else if self.state.credentials.vault_exists() {
+            CredentialPromptKind::Unlock
+        } else {
+            CredentialPromptKind::CreateVault { first: None }
+        };
+        self.state.credential_prompt = Some(CredentialPrompt::new(kind, server_id, server_name, FollowUp::SavePassword));
+        self.state.mode = AppMode::CredentialPrompt;
+    }
+
+    /// Vault passphrase and server password popup
+    async fn handle_credential_prompt(&mut self, key: KeyCode) -> Result<()> {
+        let Some(prompt) = self.state.credential_prompt.as_mut() else {
+            self.state.mode = AppMode::Normal;
+            return Ok(());
+        };
+        let input = &mut prompt.input;
+        match key {
+            KeyCode::Esc => {
+                self.state.mode = AppMode::Normal;
+                if let Some(prompt) = self.state.credential_prompt.take() {
+                    match prompt.kind {
+                        // A native login can't go on without what it asked for
+                        CredentialPromptKind::LoginPassword | CredentialPromptKind::KeyPassphrase { .. } => {
+                            self.login_secrets.remove(&prompt.server_id);
+                        }
+                        // Connecting goes ahead without the stored password; ssh asks instead
+                        _ if prompt.then == FollowUp::Connect => self.launch_session(prompt.server_id.clone()).await,
+                        _ => {}
+                    }
                 }
             }
+            KeyCode::Enter => self.submit_credential_prompt().await,
+            KeyCode::Char(c) => input.insert_char(c),
+            KeyCode::Backspace => input.delete_char(),
+            KeyCode::Delete => input.delete_char_forward(),
+            KeyCode::Left => input.move_cursor_left(),
+            KeyCode::Right => input.move_cursor_right(),
+            KeyCode::Home => input.move_cursor_to_start(),
+            KeyCode::End => input.move_cursor_to_end(),
             _ => {}
         }
         Ok(())
     }
 
+    async fn submit_credential_prompt(&mut self) {
+        let Some(mut prompt) = self.state.credential_prompt.take() else {
+            return;
+        };
+        let value = prompt.take_input();
+        let unlocked = match &mut prompt.kind {
+            CredentialPromptKind::CreateVault { first: first @ None } => {
+                *first = Some(value);
+                prompt.input.label = "Repeat passphrase".to_string();
+                prompt.input.placeholder.clear();
+                self.state.credential_prompt = Some(prompt);
+                return;
+            }
+            CredentialPromptKind::CreateVault { first: Some(first) } if *first != value => {
+                Err(anyhow::anyhow!("The passphrases don't match"))
+            }
+            CredentialPromptKind::CreateVault { .. } | CredentialPromptKind::Unlock => {
+                self.state.credentials.unlock(&value)
+            }
+            CredentialPromptKind::Password => {
+                let stored = if value.is_empty() {
+                    self.state.credentials.remove(&prompt.server_id).map(|removed| match removed {
+                        true => format!("🔑 Forgot the stored password for {}", prompt.server_name),
+                        false => format!("🔑 No password was stored for {}", prompt.server_name),
+                    })
+                } else {
+                    self.state
+                        .credentials
+                        .set(&prompt.server_id, &value)
+                        .map(|()| format!("🔑 Stored the password for {}", prompt.server_name))
+                };
+                self.state.show_popup = true;
+                self.state.popup_message = stored.unwrap_or_else(|e| format!("❌ {:#}", e));
+                self.state.popup_shown_at = Some(Utc::now());
+                self.state.mode = AppMode::Normal;
+                return;
+            }
+            CredentialPromptKind::LoginPassword | CredentialPromptKind::KeyPassphrase { .. } => {
+                let secrets = self.login_secrets.entry(prompt.server_id.clone()).or_default();
+                match prompt.kind {
+                    CredentialPromptKind::LoginPassword => secrets.password = Some(value),
+                    _ => secrets.passphrase = Some(value),
+                }
+                self.state.mode = AppMode::Normal;
+                self.launch_session(prompt.server_id.clone()).await;
+                return;
+            }
+        };
+
+        match unlocked {
+            Err(e) => {
+                self.state.show_popup = true;
+                self.state.popup_message = format!("❌ {:#}", e);
+                self.state.popup_shown_at = Some(Utc::now());
+                // Try again; a new vault starts over from the first passphrase
+                if matches!(prompt.kind, CredentialPromptKind::CreateVault { .. }) {
+                    prompt = CredentialPrompt::new(
+                        CredentialPromptKind::CreateVault { first: None },
+                        prompt.server_id.clone(),
+                        prompt.server_name.clone(),
+                        prompt.then,
+                    );
+                }
+                self.state.credential_prompt = Some(prompt);
+            }
+            Ok(()) => match prompt.then {
+                FollowUp::Connect => {
+                    self.state.mode = AppMode::Normal;
+                    self.launch_session(prompt.server_id.clone()).await;
+                }
+                FollowUp::SavePassword => {
+                    self.state.credential_prompt = Some(CredentialPrompt::new(
+                        CredentialPromptKind::Password,
+                        prompt.server_id.clone(),
+                        prompt.server_name.clone(),
+                        FollowUp::SavePassword,
+                    ));
+                }
+            },
+        }
+    }
+
+    /// The server's stored password, if it has one and the store is open
+    fn stored_password(&mut self, server: &ServerConnection) -> Option<Zeroizing<String>> {
+        self.read_stored_password(server).ok().flatten()
+    }
+
+    fn read_stored_password(&mut self, server: &ServerConnection) -> Result<Option<Zeroizing<String>>> {
+        if !matches!(server.auth_method, AuthMethod::Password) || self.state.credentials.is_locked() {
+            return Ok(None);
+        }
+        self.state.credentials.get(&server.id)
+    }
+
+    /// The server's stored password, offered to its ssh through `askpass`
+    fn offer_password(&mut self, server: &ServerConnection) -> Option<Askpass> {
+        let offered = self
+            .read_stored_password(server)
+            .and_then(|secret| secret.map(Askpass::offer).transpose());
+        match offered {
+            Ok(askpass) => askpass,
+            Err(e) => {
+                self.state.show_popup = true;
+                self.state.popup_message = format!("⚠️ Stored password unavailable, ssh will ask: {:#}", e);
+                self.state.popup_shown_at = Some(Utc::now());
+                None
+            }
+        }
+    }
+
+    /// SFTP browser keys. Failed file operations are reported in a popup
+    /// and leave the browser as it was.
+    async fn handle_sftp_action(&mut self, action: Action) {
+        let Some(browser) = self.state.sftp.as_mut().filter(|_| action != Action::Back) else {
+            self.state.mode = AppMode::Normal;
+            return;
+        };
+        let result = match action {
+            Action::MoveDown | Action::MoveUp => {
+                browser.move_selection(action == Action::MoveDown);
+                Ok(())
+            }
+            Action::SwitchPane => {
+                browser.switch_pane();
+                Ok(())
+            }
+            Action::OpenDir => browser.open_selected().await,
+            Action::ParentDir => browser.parent().await,
+            Action::Refresh => browser.reload(browser.focus).await,
+            Action::Transfer => browser.start_transfer(),
+            Action::RenameEntry | Action::MakeDir | Action::DeleteEntry => {
+                browser.start_prompt(match action {
+                    Action::RenameEntry => PromptKind::Rename,
+                    Action::MakeDir => PromptKind::MakeDir,
+                    _ => PromptKind::Delete,
+                });
+                Ok(())
+            }
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            self.state.show_popup = true;
+            self.state.popup_message = format!("❌ {:#}", e);
+            self.state.popup_shown_at = Some(Utc::now());
+        }
+    }
+
+    /// Text entry for the rename and mkdir prompts. The delete prompt only
+    /// goes ahead on y; any other key cancels it.
+    async fn handle_sftp_prompt(&mut self, key: KeyCode) -> Result<()> {
+        let Some(browser) = self.state.sftp.as_mut() else {
+            return Ok(());
+        };
+        let Some(prompt) = browser.prompt.as_mut() else {
+            return Ok(());
+        };
+        let input = &mut prompt.input;
+        let submit = match (prompt.kind, key) {
+            (PromptKind::Delete, KeyCode::Char('y' | 'Y')) | (PromptKind::Rename | PromptKind::MakeDir, KeyCode::Enter) => {
+                true
+            }
+            (PromptKind::Delete, _) | (_, KeyCode::Esc) => {
+                browser.prompt = None;
+                false
+            }
+            (_, KeyCode::Char(c)) => {
+                input.insert_char(c);
+                false
+            }
+            (_, KeyCode::Backspace) => {
+                input.delete_char();
+                false
+            }
+            (_, KeyCode::Delete) => {
+                input.delete_char_forward();
+                false
+            }
+            (_, KeyCode::Left) => {
+                input.move_cursor_left();
+                false
+            }
+            (_, KeyCode::Right) => {
+                input.move_cursor_right();
+                false
+            }
+            (_, KeyCode::Home) => {
+                input.move_cursor_to_start();
+                false
+            }
+            (_, KeyCode::End) => {
+                input.move_cursor_to_end();
+                false
+            }
+            _ => false,
+        };
+        if submit {
+            if let Err(e) = browser.submit_prompt().await {
+                self.state.show_popup = true;
+                self.state.popup_message = format!("❌ {:#}", e);
+                self.state.popup_shown_at = Some(Utc::now());
+            }
+        }
+        Ok(())
+    }
+
+    /// Command palette: typing filters, arrows (or Ctrl+N/Ctrl+P) move,
+    /// Enter runs the selected command, Esc closes.
+    async fn handle_palette_input(&mut self, key: KeyCode, modifiers: KeyModifiers) -> Result<()> {
+        let ctrl = modifiers.contains(KeyModifiers::CONTROL);
+        let matches = palette::filter_entries(palette::build_entries(&self.state), &self.state.palette.query);
+        let count = matches.len();
+
+        match key {
+            KeyCode::Esc => {
+                self.state.mode = AppMode::Normal;
+            }
+            KeyCode::Enter => {
+                self.state.mode = AppMode::Normal;
+                if let Some(selected) = matches.into_iter().nth(self.state.palette.selected_index) {
+                    self.run_palette_command(selected.entry.command).await?;
+                }
+            }
+            KeyCode::Down | KeyCode::Tab => self.move_palette_selection(count, true),
+            KeyCode::Char('n') | KeyCode::Char('j') if ctrl => self.move_palette_selection(count, true),
+            KeyCode::Up | KeyCode::BackTab => self.move_palette_selection(count, false),
+            KeyCode::Char('p') | KeyCode::Char('k') if ctrl => self.move_palette_selection(count, false),
+            KeyCode::Backspace => {
+                self.state.palette.query.pop();
+                self.state.palette.selected_index = 0;
+            }
+            KeyCode::Char('u') if ctrl => {
+                self.state.palette.query.clear();
+                self.state.palette.selected_index = 0;
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.state.palette.query.push(c);
+                self.state.palette.selected_index = 0;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn move_palette_selection(&mut self, count: usize, down: bool) {
+        if count == 0 {
+            return;
+        }
+        let index = &mut self.state.palette.selected_index;
+        *index = if down {
+            (*index + 1) % count
+        } else if *index == 0 {
+            count - 1
+        } else {
+            *index - 1
+        };
+    }
+
+    async fn run_palette_command(&mut self, command: PaletteCommand) -> Result<()> {
+        match command {
+            PaletteCommand::Action(action) => self.perform_action(KeyContext::Normal, action).await?,
+            PaletteCommand::Connect(server_id) => self.connect_to_server(server_id).await,
+            PaletteCommand::Edit(server_id) => {
+                if let Some(connection) = self.state.server_manager.get_connection(&server_id) {
+                    self.state.server_form = Some(ServerForm::new_edit_form(connection));
+                    self.state.mode = AppMode::EditServer(server_id);
+                }
+            }
+            PaletteCommand::SetTheme(variant) => {
+                self.state.theme_manager.set_theme(variant);
+                self.persist_theme();
+            }
+            PaletteCommand::SetLayout(name) => self.apply_layout_preset(&name),
+        }
+        Ok(())
+    }
+
+    fn open_theme_selector(&mut self) {
+        let current = self.state.theme_manager.current_variant();
+        self.state.theme_selector.filter = crate::models::ThemeFilter::All;
+        self.state.theme_selector.selected_index = self
+            .state
+            .theme_selector_entries()
+            .iter()
+            .position(|v| *v == current)
+            .unwrap_or(0);
+        self.state.theme_selector.original = Some(current);
+        self.state.show_theme_selector = true;
+    }
+
+    /// Theme selector: moving the cursor previews the theme immediately,
+    /// Apply keeps it, Cancel restores the theme that was active on open.
+    fn handle_theme_selector(&mut self, action: Action) {
+        match action {
+            Action::MoveDown => {
+                let count = self.state.theme_selector_entries().len();
+                if count > 0 {
+                    self.state.theme_selector.selected_index =
+                        (self.state.theme_selector.selected_index + 1) % count;
+                }
+                self.preview_selected_theme();
+            }
+            Action::MoveUp => {
+                let count = self.state.theme_selector_entries().len();
+                if count > 0 {
+                    self.state.theme_selector.selected_index =
+                        if self.state.theme_selector.selected_index == 0 {
+                            count - 1
+                        } else {
+                            self.state.theme_selector.selected_index - 1
+                        };
+                }
+                self.preview_selected_theme();
+            }
+            Action::CycleFilter => {
+                // Cycle All → Dark → Light, keeping the cursor on the previewed
+                // theme when it's still listed.
+                let current = self.state.theme_manager.current_variant();
+                self.state.theme_selector.filter = self.state.theme_selector.filter.next();
+                self.state.theme_selector.selected_index = self
+                    .state
+                    .theme_selector_entries()
+                    .iter()
+                    .position(|v| *v == current)
+                    .unwrap_or(0);
+                self.preview_selected_theme();
+            }
+            Action::Apply => {
+                self.state.show_theme_selector = false;
+                self.state.theme_selector.original = None;
+                self.persist_theme();
+            }
+            Action::Cancel => {
+                if let Some(original) = self.state.theme_selector.original.take() {
+                    self.state.theme_manager.set_theme(original);
+                }
+                self.state.show_theme_selector = false;
+            }
+            _ => {}
+        }
+    }
+
+    fn preview_selected_theme(&mut self) {
+        let entries = self.state.theme_selector_entries();
+        if let Some(variant) = entries.get(self.state.theme_selector.selected_index) {
+            self.state.theme_manager.set_theme(variant.clone());
+        }
+    }
+
+    /// Save the active theme as the preferred one and report the switch.
+    fn persist_theme(&mut self) {
+        self.app_settings.theme = self.state.theme_manager.current_variant();
+        if let Err(e) = self.save_config() {
+            self.state.show_popup = true;
+            self.state.popup_message = format!("Failed to save theme: {}", e);
+            self.state.popup_shown_at = Some(Utc::now());
+        } else {
+            self.state.show_popup = true;
+            self.state.popup_message = format!("🎨 Switched to {}", self.state.theme_manager.current_variant().name());
+            self.state.popup_shown_at = Some(Utc::now());
+        }
+    }
+
+    /// Save the current layout mode and panel sizes so they're restored on
+    /// the next launch. Only failures are reported; callers show the change.
+    fn persist_layout(&mut self) {
+        self.app_settings.layout_mode = self.state.layout.mode.clone();
+        self.app_settings.panel_sizes = self.state.layout.panel_sizes;
+        self.app_settings.layout_auto = self.state.layout.auto;
+        if let Err(e) = self.save_config() {
+            self.state.show_popup = true;
+            self.state.popup_message = format!("Failed to save layout: {}", e);
+            self.state.popup_shown_at = Some(Utc::now());
+        }
+    }
+
+    fn apply_layout_preset(&mut self, name: &str) {
+        let Some(preset) = self.state.layout_presets.get(name) else {
+            return;
+        };
+        self.state.layout = preset.clone().validated();
+        self.app_settings.panel_layout = name.to_string();
+        self.state.show_popup = true;
+        self.state.popup_message = format!("📐 Layout preset: {}", name);
+        self.state.popup_shown_at = Some(Utc::now());
+        self.persist_layout();
+    }
+
     async fn handle_add_server_mode(&mut self, key: KeyCode) -> Result<()> {
         self.handle_form_input(key).await
     }
@@ -285,36 +1480,107 @@ impl App {
         self.handle_form_input(key).await
     }
 
-    async fn handle_confirm_delete_mode(&mut self, key: KeyCode) -> Result<()> {
-        match key {
-            KeyCode::Char('y') | KeyCode::Char('Y') => {
-                if let AppMode::ConfirmDelete(id) = &self.state.mode.clone() {
-                    self.state.server_manager.remove_connection(id);
-                    // Auto-save configuration
-                    if let Err(e) = self.save_config() {
-                        self.state.show_popup = true;
-                        self.state.popup_message = format!("Failed to save config: {}", e);
-                        self.state.popup_shown_at = Some(Utc::now());
-                    }
-                }
+    /// "Yes" in a delete or discard-changes prompt
+    fn confirm_prompt(&mut self) {
+        match self.state.mode.clone() {
+            AppMode::ConfirmDelete(id) => self.delete_server(&id),
+            AppMode::ConfirmDiscard => {
+                // Discard the form and return to the main view.
+                self.state.server_form = None;
                 self.state.mode = AppMode::Normal;
             }
-            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
-                self.state.mode = AppMode::Normal;
+            _ => {}
+        }
+    }
+
+    fn delete_server(&mut self, id: &str) {
+        // Best effort: a locked vault keeps the entry, which does no harm
+        let _ = self.state.credentials.remove(id);
+        self.state.server_manager.remove_connection(id);
+        // Auto-save configuration
+        if let Err(e) = self.save_config() {
+            self.state.show_popup = true;
+            self.state.popup_message = format!("Failed to save config: {}", e);
+            self.state.popup_shown_at = Some(Utc::now());
+        }
+        self.state.mode = AppMode::Normal;
+    }
+
+    /// Run `action` now, or once the protected servers it touches are
+    /// confirmed by name
+    async fn guarded(&mut self, action: Guarded, protected: Vec<String>) {
+        if protected.is_empty() {
+            self.run_guarded(action).await;
+        } else {
+            self.state.name_confirm = Some(NameConfirm::new(action, protected, self.state.mode.clone()));
+            self.state.mode = AppMode::ConfirmName;
+        }
+    }
+
+    async fn run_guarded(&mut self, action: Guarded) {
+        match action {
+            Guarded::Connect(id) => self.unlock_and_connect(id).await,
+            Guarded::Delete(id) => self.delete_server(&id),
+            Guarded::Transfer(kind, servers) => {
+                self.state.transfers.enqueue(servers, kind);
+                self.state.transfer_form = None;
+                self.state.mode = AppMode::Transfers;
+            }
+            Guarded::InstallKey(key, servers) => {
+                if let Some(keys) = self.state.keys.as_mut() {
+                    keys.installs.start(&key, servers);
+                }
+            }
+            Guarded::OpenSftp(id) => self.browse_server(&id),
+            Guarded::Retry(ids) => {
+                let count = ids.into_iter().filter(|id| self.state.transfers.retry(*id)).count();
+                self.state.show_popup = true;
+                self.state.popup_message = format!("🔁 Retrying {} transfer{}", count, if count == 1 { "" } else { "s" });
+                self.state.popup_shown_at = Some(Utc::now());
+            }
+        }
+    }
+
+    /// Text entry for typing a protected server's name
+    async fn handle_name_confirm(&mut self, key: KeyCode) -> Result<()> {
+        let Some(confirm) = self.state.name_confirm.as_mut() else {
+            self.state.mode = AppMode::Normal;
+            return Ok(());
+        };
+        if !matches!(key, KeyCode::Enter) {
+            confirm.mismatch = false;
+        }
+        match key {
+            KeyCode::Esc => {
+                self.state.mode = confirm.previous.clone();
+                self.state.name_confirm = None;
+            }
+            KeyCode::Enter if confirm.matches() => {
+                if let Some(confirm) = self.state.name_confirm.take() {
+                    self.state.mode = confirm.previous;
+                    self.run_guarded(confirm.action).await;
+                }
             }
+            KeyCode::Enter => confirm.mismatch = true,
+            KeyCode::Char(c) => confirm.input.insert_char(c),
+            KeyCode::Backspace => confirm.input.delete_char(),
+            KeyCode::Delete => confirm.input.delete_char_forward(),
+            KeyCode::Left => confirm.input.move_cursor_left(),
+            KeyCode::Right => confirm.input.move_cursor_right(),
+            KeyCode::Home => confirm.input.move_cursor_to_start(),
+            KeyCode::End => confirm.input.move_cursor_to_end(),
             _ => {}
         }
         Ok(())
     }
 
-    async fn handle_confirm_discard_mode(&mut self, key: KeyCode) -> Result<()> {
-        match key {
-            KeyCode::Char('y') | KeyCode::Char('Y') => {
-                // Discard the form and return to the main view.
-                self.state.server_form = None;
+    /// "No" in a delete or discard-changes prompt
+    fn cancel_prompt(&mut self) {
+        match self.state.mode {
+            AppMode::ConfirmDelete(_) => {
                 self.state.mode = AppMode::Normal;
             }
-            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+            AppMode::ConfirmDiscard => {
                 // Keep editing: restore the form mode it came from.
                 self.state.mode = match &self.state.server_form {
                     Some(form) if form.is_editing => {
@@ -326,17 +1592,6 @@ impl App {
             }
             _ => {}
         }
-        Ok(())
-    }
-
-    async fn handle_help_mode(&mut self, key: KeyCode) -> Result<()> {
-        match key {
-            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('h') => {
-                self.state.mode = AppMode::Normal;
-            }
-            _ => {}
-        }
-        Ok(())
     }
 
     async fn handle_connecting_mode(&mut self, key: KeyCode) -> Result<()> {
@@ -358,63 +1613,6 @@ impl App {
         }
         Ok(())
     }
-    
-    async fn handle_history_mode(&mut self, key: KeyCode) -> Result<()> {
-        match key {
-            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('H') => {
-                self.state.mode = AppMode::Normal;
-            }
-            _ => {}
-        }
-        Ok(())
-    }
-    
-    async fn handle_analytics_mode(&mut self, key: KeyCode) -> Result<()> {
-        match key {
-            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('A') => {
-                self.state.mode = AppMode::Normal;
-            }
-            _ => {}
-        }
-        Ok(())
-    }
-    
-    async fn handle_sessions_mode(&mut self, key: KeyCode) -> Result<()> {
-        match key {
-            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('S') => {
-                self.state.mode = AppMode::Normal;
-            }
-            KeyCode::Char('j') | KeyCode::Down => {
-                self.move_session_selection_down();
-            }
-            KeyCode::Char('k') | KeyCode::Up => {
-                self.move_session_selection_up();
-            }
-            KeyCode::Char('d') => {
-                // Kill selected session
-                if let Some(session) = self.get_selected_session() {
-                    let _ = self.state.kill_session(session.pid);
-                }
-            }
-            KeyCode::Char('r') => {
-                // Refresh sessions
-                self.refresh_all_sessions().await;
-            }
-            KeyCode::Enter => {
-                // Bring session to foreground (placeholder)
-                if let Some(session) = self.get_selected_session() {
-                    let message = format!("Session for {} is running in PID {}\nWindow: {}", 
-                        session.server_name, session.pid, session.window_title);
-                    self.state.show_popup = true;
-                    self.state.popup_message = message;
-                    self.state.popup_shown_at = Some(chrono::Utc::now());
-                }
-            }
-            _ => {}
-        }
-        Ok(())
-    }
-
     fn move_selection_down(&mut self) {
         let connections = self.state.server_manager.filtered_connections();
         if !connections.is_empty() {
@@ -441,8 +1639,6 @@ impl App {
     }
 
     async fn refresh_connections(&mut self) {
-        use crate::models::LoadingContext;
-        
         let server_count = self.state.server_manager.connections.len();
         if server_count == 0 {
             self.state.show_popup = true;
@@ -467,28 +1663,33 @@ impl App {
             connection.health_status = HealthStatus::Connecting;
         }
         
-        // Perform real health checks with progress tracking
+        // The checks run in the background; their results count towards
+        // the progress as they arrive on the tick
         let servers: Vec<ServerConnection> = self.state.server_manager.connections.values().cloned().collect();
-        let mut completed_count = 0;
-        
-        for server in servers {
-            let result = self.health_monitor.check_server_now(&server).await;
-            
-            if let Some(connection) = self.state.server_manager.get_connection_mut(&server.id) {
-                result.update_server_stats(connection);
-            }
-            
-            completed_count += 1;
-            
-            // Update progress
-            if let AppMode::Loading(LoadingContext::RefreshingHealth { ref mut completed, .. }) = self.state.mode {
-                *completed = completed_count;
-            }
+        let servers = servers
+            .into_iter()
+            .map(|server| {
+                let secrets = Secrets { password: self.stored_password(&server), passphrase: None };
+                (server, secrets)
+            })
+            .collect();
+        self.health_monitor.check_servers_now(servers).await;
+    }
+
+    /// Count a result of the user's refresh, and wrap up once all are in.
+    /// Results arriving after the refresh was cancelled with Esc only
+    /// update their server.
+    fn refresh_progressed(&mut self) {
+        let AppMode::Loading(LoadingContext::RefreshingHealth { completed, total }) = &mut self.state.mode else {
+            return;
+        };
+        *completed += 1;
+        if *completed < *total {
+            return;
         }
-        
-        // Complete loading
+        let server_count = *total;
         self.state.complete_loading();
-        
+
         // Show completion message
         self.state.show_popup = true;
         self.state.popup_message = format!("🔄 Refreshed {} server(s) | Avg time: {}ms", 
@@ -497,101 +1698,242 @@ impl App {
         self.state.popup_shown_at = Some(Utc::now());
     }
 
+    /// Connect, once confirmed by name if the server is protected
     async fn connect_to_server(&mut self, server_id: String) {
+        let protected = protected_names(self.state.server_manager.get_connection(&server_id));
+        self.guarded(Guarded::Connect(server_id), protected).await;
+    }
+
+    async fn unlock_and_connect(&mut self, server_id: String) {
+        // A stored password can only be handed over once the vault is open
+        if let Some(server) = self.state.server_manager.get_connection(&server_id) {
+            let credentials = &self.state.credentials;
+            if matches!(server.auth_method, AuthMethod::Password) && credentials.is_locked() && credentials.vault_exists() {
+                self.state.credential_prompt = Some(CredentialPrompt::new(
+                    CredentialPromptKind::Unlock,
+                    server_id,
+                    server.name.clone(),
+                    FollowUp::Connect,
+                ));
+                self.state.mode = AppMode::CredentialPrompt;
+                return;
+            }
+        }
+        self.launch_session(server_id).await;
+    }
+
+    async fn launch_session(&mut self, server_id: String) {
         self.state.mode = AppMode::Connecting(server_id.clone());
         
         if let Some(server) = self.state.server_manager.get_connection(&server_id).cloned() {
+            // Native logins take the stored password directly
+            let askpass = match self.connection_mode {
+                ConnectionMode::Native => None,
+                _ => self.offer_password(&server),
+            };
             // Update connection status to connecting
             if let Some(connection) = self.state.server_manager.get_connection_mut(&server_id) {
                 connection.health_status = HealthStatus::Connecting;
             }
-            // Attempt real SSH connection with the configured mode
-            match self.health_monitor.connect_to_server_with_mode(&server, self.connection_mode.clone()).await {
-                Ok(pid) => {
-                    self.state.show_popup = true;
-                    self.state.popup_message = format!("🚀 Launched SSH session for {}!\nPID: {} | Check your terminal windows.", server.name, pid);
-                    self.state.popup_shown_at = Some(Utc::now());
-                    
-                    // Update connection status and add session tracking
-                    if let Some(connection) = self.state.server_manager.get_connection_mut(&server_id) {
-                        connection.health_status = HealthStatus::Online;
-                        connection.stats.connection_count += 1;
-                        connection.stats.last_connected = Some(Utc::now());
-                        
-                        // Track the active session
-                        let window_title = format!("Ghost SSH: {}", server.name);
-                        connection.add_session(pid, window_title);
+            // Attempt real SSH connection with the configured mode. Tabs are
+            // sized to roughly the pane; the first draw resizes them exactly
+            let (cols, rows) = crossterm::terminal::size().unwrap_or((80, 24));
+            let (rows, cols) = (rows.saturating_sub(8), cols * 7 / 10);
+            let launched = match self.connection_mode {
+                ConnectionMode::Embedded => self
+                    .health_monitor
+                    .spawn_embedded(&server, rows, cols, askpass.as_ref())
+                    .await
+                    .map(|session| self.push_tab(session)),
+                ConnectionMode::Native => {
+                    let mut secrets = self.login_secrets.remove(&server_id).unwrap_or_default();
+                    if secrets.password.is_none() {
+                        secrets.password = self.stored_password(&server);
+                    }
+                    // Logging in can take a while; stay in Connecting until
+                    // `handle_launch_event` hears back
+                    let opening = self.health_monitor.open_native(server.clone(), rows, cols, secrets.clone());
+                    let tx = self.launch_tx.clone();
+                    tokio::spawn(async move {
+                        let result = opening.await;
+                        let _ = tx.send(LaunchEvent::Native { server_id, secrets, result });
+                    });
+                    return;
+                }
+                _ => {
+                    let tracked: Vec<u32> = self.state.get_all_sessions().iter().map(|s| s.pid).collect();
+                    self.health_monitor
+                        .connect_to_server_with_mode(&server, self.connection_mode.clone(), askpass.as_ref(), &tracked)
+                        .await
+                }
+            };
+            self.session_launched(&server, launched);
+            return;
+        }
+        
+        self.state.mode = AppMode::Normal;
+    }
+
+    /// Handle a connection that finished in the background
+    fn handle_launch_event(&mut self, event: LaunchEvent) {
+        match event {
+            LaunchEvent::Native { server_id, secrets, result } => {
+                let Some(server) = self.state.server_manager.get_connection(&server_id).cloned() else {
+                    return;
+                };
+                // Cancelled with Esc while logging in: dropping the session
+                // closes it
+                if self.state.mode != AppMode::Connecting(server_id) {
+                    if let Some(connection) = self.state.server_manager.get_connection_mut(&server.id) {
+                        connection.health_status = HealthStatus::Unknown;
+                    }
+                    return;
+                }
+                let launched = match result {
+                    Ok(session) => Ok(self.push_tab(session)),
+                    Err(error) => match error.downcast_ref::<NeedsSecret>() {
+                        Some(needed) => {
+                            let needed = needed.clone();
+                            self.ask_login_secret(&server, needed, secrets);
+                            return;
+                        }
+                        None => Err(format!("Connection failed: {:#}", error)),
+                    },
+                };
+                self.session_launched(&server, launched);
+            }
+            LaunchEvent::Tracked { server_id, kind, pid } => {
+                let Some(connection) = self.state.server_manager.get_connection_mut(&server_id) else {
+                    return;
+                };
+                match pid {
+                    Some(pid) => {
+                        let window_title = format!("Ghost SSH: {}", connection.name);
+                        connection.add_session(pid, window_title, kind);
+                        self.state.server_manager.update_session_count();
+                    }
+                    None => {
+                        self.state.show_popup = true;
+                        self.state.popup_message = format!(
+                            "⚠️ The session for {} opened, but its ssh process couldn't be found to track it",
+                            connection.name
+                        );
+                        self.state.popup_shown_at = Some(Utc::now());
                     }
-                    
-                    // Add to connection history
-                    self.state.server_manager.add_to_history(server_id.clone(), server.name.clone());
-                    
-                    // Update session counts
-                    self.state.server_manager.update_session_count();
                 }
-                Err(error) => {
+            }
+        }
+    }
+
+    /// Track a launched session and leave Connecting, or report why it
+    /// didn't launch
+    fn session_launched(&mut self, server: &ServerConnection, launched: std::result::Result<(SessionPid, SessionKind), String>) {
+        let server_id = server.id.clone();
+        match launched {
+            Ok((pid, kind)) => {
+                // A pid still being looked for is tracked once it's found,
+                // see `handle_launch_event`
+                let pid = match pid {
+                    SessionPid::Known(pid) => Some(pid),
+                    SessionPid::Pending(finding) => {
+                        let (tx, server_id, kind) = (self.launch_tx.clone(), server_id.clone(), kind.clone());
+                        tokio::spawn(async move {
+                            let pid = finding.await.ok().flatten();
+                            let _ = tx.send(LaunchEvent::Tracked { server_id, kind, pid });
+                        });
+                        None
+                    }
+                };
+                let where_to_look = match &kind {
+                    SessionKind::Embedded => None,
+                    SessionKind::Tmux(pane) => Some(format!("Opened in tmux pane {}.", pane)),
+                    SessionKind::Zellij(_) => Some("Opened in a new zellij pane.".to_string()),
+                    SessionKind::External => Some("Check your terminal windows.".to_string()),
+                };
+                if let Some(where_to_look) = where_to_look {
                     self.state.show_popup = true;
-                    self.state.popup_message = format!("⚠️ Connection Error:\n{}", error);
+                    self.state.popup_message = match pid {
+                        Some(pid) => format!("🚀 Launched SSH session for {}!\nPID: {} | {}", server.name, pid, where_to_look),
+                        None => format!("🚀 Launched SSH session for {}!\n{}", server.name, where_to_look),
+                    };
                     self.state.popup_shown_at = Some(Utc::now());
+                }
+                let embedded = kind == SessionKind::Embedded;
+                
+                // Update connection status and add session tracking
+                if let Some(connection) = self.state.server_manager.get_connection_mut(&server_id) {
+                    connection.health_status = HealthStatus::Online;
+                    connection.stats.connection_count += 1;
+                    connection.stats.last_connected = Some(Utc::now());
                     
-                    // Update connection status
-                    if let Some(connection) = self.state.server_manager.get_connection_mut(&server_id) {
-                        connection.health_status = HealthStatus::Offline;
-                        connection.stats.failed_attempts += 1;
+                    // Track the active session
+                    if let Some(pid) = pid {
+                        let window_title = format!("Ghost SSH: {}", server.name);
+                        connection.add_session(pid, window_title, kind);
                     }
                 }
+                
+                // Add to connection history
+                self.state.server_manager.add_to_history(server_id.clone(), server.name.clone());
+                
+                // Update session counts
+                self.state.server_manager.update_session_count();
+
+                if embedded {
+                    self.state.mode = AppMode::Terminal;
+                    return;
+                }
+            }
+            Err(error) => {
+                self.state.show_popup = true;
+                self.state.popup_message = format!("⚠️ Connection Error:\n{}", error);
+                self.state.popup_shown_at = Some(Utc::now());
+                
+                // Update connection status
+                if let Some(connection) = self.state.server_manager.get_connection_mut(&server_id) {
+                    connection.health_status = HealthStatus::Offline;
+                    connection.stats.failed_attempts += 1;
+                }
             }
         }
         
         self.state.mode = AppMode::Normal;
     }
     
+    /// Show a new embedded session as the active tab
+    fn push_tab(&mut self, session: EmbeddedSession) -> (SessionPid, SessionKind) {
+        let pid = session.pid;
+        self.state.terminals.push(session);
+        (SessionPid::Known(pid), SessionKind::Embedded)
+    }
+
+    /// Ask for the password or key passphrase a native login is missing,
+    /// then try again with it on top of `secrets`
+    fn ask_login_secret(&mut self, server: &ServerConnection, needed: NeedsSecret, secrets: Secrets) {
+        let kind = match needed {
+            NeedsSecret::Password => CredentialPromptKind::LoginPassword,
+            NeedsSecret::Passphrase(key_path) => CredentialPromptKind::KeyPassphrase { key_path },
+        };
+        self.login_secrets.insert(server.id.clone(), secrets);
+        if let Some(connection) = self.state.server_manager.get_connection_mut(&server.id) {
+            connection.health_status = HealthStatus::Unknown;
+        }
+        self.state.credential_prompt = Some(CredentialPrompt::new(kind, server.id.clone(), server.name.clone(), FollowUp::Connect));
+        self.state.mode = AppMode::CredentialPrompt;
+    }
+
     async fn kill_all_sessions(&mut self) {
         let mut killed_count = 0;
         let mut failed_kills = Vec::new();
         
         // Collect all active sessions
-        let mut sessions_to_kill = Vec::new();
-        for connection in self.state.server_manager.connections.values() {
-            for session in &connection.active_sessions {
-                sessions_to_kill.push((session.pid, connection.name.clone()));
-            }
-        }
+        let pids: Vec<u32> = self.state.get_all_sessions().iter().map(|s| s.pid).collect();
         
-        // Kill each session
-        for (pid, server_name) in sessions_to_kill {
-            #[cfg(unix)]
-            {
-                use std::process::Command;
-                match Command::new("kill").arg("-TERM").arg(pid.to_string()).output() {
-                    Ok(output) => {
-                        if output.status.success() {
-                            killed_count += 1;
-                        } else {
-                            failed_kills.push((pid, server_name.clone()));
-                        }
-                    }
-                    Err(_) => {
-                        failed_kills.push((pid, server_name.clone()));
-                    }
-                }
-            }
-            
-            #[cfg(windows)]
-            {
-                use std::process::Command;
-                match Command::new("taskkill").args(["/F", "/PID", &pid.to_string()]).output() {
-                    Ok(output) => {
-                        if output.status.success() {
-                            killed_count += 1;
-                        } else {
-                            failed_kills.push((pid, server_name.clone()));
-                        }
-                    }
-                    Err(_) => {
-                        failed_kills.push((pid, server_name.clone()));
-                    }
-                }
+        // Kill each session the way it was started (tmux pane, embedded tab, pid)
+        for pid in pids {
+            match self.state.kill_session(pid) {
+                Ok(()) => killed_count += 1,
+                Err(_) => failed_kills.push(pid),
             }
         }
         
@@ -631,27 +1973,103 @@ impl App {
             }
         }
         
+        // Give up on a half-typed chord after a second
+        if let Some(since) = self.pending_since {
+            if since.elapsed() >= Duration::from_secs(1) {
+                self.state.pending_keys.clear();
+                self.pending_since = None;
+            }
+        }
+
         // Auto-dismiss tooltips after 3 seconds
         if self.state.should_auto_dismiss_tooltip() {
             self.state.hide_tooltip();
         }
         
-        // Clean up ended SSH sessions
-        self.cleanup_ended_sessions().await;
+        // Close tabs whose ssh has exited, then clean up ended SSH sessions
+        self.reap_embedded_sessions();
+        if self.last_session_poll.elapsed() >= SESSION_POLL_INTERVAL {
+            self.cleanup_ended_sessions().await;
+        }
         
+        // Transfer progress, finished deletes and the result of connecting
+        // the file browser
+        if let Some(browser) = self.state.sftp.as_mut() {
+            let failures = browser.poll_events().await;
+            if !failures.is_empty() {
+                self.state.show_popup = true;
+                self.state.popup_message = format!("❌ {}", failures.join("\n"));
+                self.state.popup_shown_at = Some(Utc::now());
+            }
+        }
+
+        // Key installs: report each batch and offer to switch its servers
+        // over to the key
+        if let Some(keys) = self.state.keys.as_mut() {
+            let completed = keys.installs.poll_events();
+            if !completed.is_empty() {
+                let summaries: Vec<String> = completed.iter().map(|b| keys.installs.batch_summary(*b)).collect();
+                for batch in completed {
+                    keys.offer_switch(batch);
+                }
+                self.state.show_popup = true;
+                self.state.popup_message = format!("🔑 {}", summaries.join("\n🔑 "));
+                self.state.popup_shown_at = Some(Utc::now());
+            }
+        }
+
+        // Algorithm scan: sum it up for whoever left its view meanwhile
+        if self.state.algorithm_scan.poll_events() && self.state.mode != AppMode::Algorithms {
+            let summary = self.state.algorithm_scan.summary();
+            self.state.show_popup = true;
+            self.state.popup_message = format!(
+                "🛡 Algorithm scan done: {} of {} servers offer weak algorithms{}",
+                summary.weak_servers,
+                summary.scanned,
+                if summary.failed > 0 { format!(" ({} couldn't be scanned)", summary.failed) } else { String::new() }
+            );
+            self.state.popup_shown_at = Some(Utc::now());
+        }
+
+        // Sends and fetches: report each batch once all its servers are done
+        let completed = self.state.transfers.poll_events();
+        if !completed.is_empty() {
+            let summaries: Vec<String> = completed.iter().map(|b| self.state.transfers.batch_summary(*b)).collect();
+            self.state.show_popup = true;
+            self.state.popup_message = format!("📦 {}", summaries.join("\n📦 "));
+            self.state.popup_shown_at = Some(Utc::now());
+        }
+
+        if self.state.credentials.lock_if_idle() {
+            self.state.show_popup = true;
+            self.state.popup_message = format!(
+                "🔒 Vault locked after {} minutes without use",
+                self.state.credentials.lock_after_minutes()
+            );
+            self.state.popup_shown_at = Some(Utc::now());
+        }
+
         // Check for health updates from background monitoring
         while let Some(health_update) = self.health_monitor.try_recv_update().await {
             self.handle_health_update(health_update).await;
         }
+
+        while let Ok(event) = self.launch_rx.try_recv() {
+            self.handle_launch_event(event);
+        }
         
     }
 
     /// Save current configuration to file
     pub fn save_config(&self) -> Result<()> {
-        let config = self.config_manager.connections_to_config(
+        let mut config = self.config_manager.connections_to_config(
             &self.state.server_manager.connections,
             self.app_settings.clone(),
         );
+        config.keybindings = self.keybindings.clone();
+        config.terminal = self.terminal_config.clone();
+        config.credentials = self.state.credentials.config().clone();
+        config.deep_check = self.deep_check.clone();
         self.config_manager.save_config(&config)
     }
 
@@ -670,6 +2088,14 @@ impl App {
                             if is_editing {
                                 // Update existing server
                                 if let Some(id) = original_id {
+                                    let mut connection = connection;
+                                    // Keep what the form doesn't edit
+                                    if let Some(old) = self.state.server_manager.connections.get(&id) {
+                                        connection.idle_limit_minutes = old.idle_limit_minutes;
+                                        connection.idle_action = old.idle_action;
+                                        connection.environment = old.environment;
+                                        connection.protected = old.protected;
+                                    }
                                     self.state.server_manager.connections.insert(id, connection);
                                 }
                             } else {
@@ -729,12 +2155,10 @@ impl App {
                 KeyCode::BackTab => {
                     form.previous_field();
                 }
-                KeyCode::Enter => {
-                    if form.auth_method_focused {
-                        form.auth_method_focused = false;
-                        form.next_field();
-                    }
-                    // Form submission is handled above
+                // Form submission is handled above
+                KeyCode::Enter if form.auth_method_focused => {
+                    form.auth_method_focused = false;
+                    form.next_field();
                 }
                 KeyCode::Up => {
                     if form.auth_method_focused {
@@ -791,93 +2215,140 @@ impl App {
         Ok(())
     }
 
-    /// Clean up SSH sessions that have ended
+    /// Drop embedded tabs whose ssh process has exited, with a notice, and
+    /// leave the terminal view once the last one is gone.
+    fn reap_embedded_sessions(&mut self) {
+        let ended = self.state.terminals.reap();
+        if ended.is_empty() {
+            return;
+        }
+        for (session, code) in &ended {
+            if let Some(connection) = self.state.server_manager.get_connection_mut(&session.server_id) {
+                connection.active_sessions.retain(|s| s.pid != session.pid);
+            }
+            self.state.show_popup = true;
+            self.state.popup_message = if *code == 0 {
+                format!("✅ Disconnected from {}", session.server_name)
+            } else {
+                format!("❌ Session to {} ended with exit code {}", session.server_name, code)
+            };
+            self.state.popup_shown_at = Some(Utc::now());
+        }
+        self.state.server_manager.update_session_count();
+        if self.state.mode == AppMode::Terminal && self.state.terminals.sessions.is_empty() {
+            self.state.mode = AppMode::Normal;
+        }
+    }
+
+    /// Remember running sessions in `sessions.json` so a restarted Ghost
+    /// can pick them up again
+    fn persist_sessions(&mut self) {
+        let records = self.state.server_manager.session_records();
+        if records == self.saved_sessions {
+            return;
+        }
+        // Best effort: on failure, the next poll tries again
+        if self.config_manager.save_sessions(&records).is_ok() {
+            self.saved_sessions = records;
+        }
+    }
+
+    /// Refresh the state, resource usage and idleness of every session, drop
+    /// the ones whose process has exited, apply per-server idle limits and
+    /// SIGKILL killed sessions that ignored SIGTERM
     async fn cleanup_ended_sessions(&mut self) {
+        self.last_session_poll = Instant::now();
+        let now = Utc::now();
+        let idle_after = Duration::from_secs(self.app_settings.idle_after_minutes * 60);
         let mut sessions_ended = false;
-        
+        let mut over_limit = Vec::new();
+
         for connection in self.state.server_manager.connections.values_mut() {
-            let mut sessions_to_remove = Vec::new();
-            
-            for (i, session) in connection.active_sessions.iter().enumerate() {
-                // Check if the process is still running
-                #[cfg(unix)]
-                {
-                    use std::process::Command;
-                    match Command::new("kill").arg("-0").arg(session.pid.to_string()).output() {
-                        Ok(output) => {
-                            if !output.status.success() {
-                                // Process is not running anymore
-                                sessions_to_remove.push(i);
-                                sessions_ended = true;
-                            }
-                        }
-                        Err(_) => {
-                            // If we can't check the process, assume it's dead
-                            sessions_to_remove.push(i);
-                            sessions_ended = true;
-                        }
-                    }
+            connection.active_sessions.retain_mut(|session| match process::inspect(session.pid) {
+                Some(info) if info.state != ProcessState::Zombie => {
+                    session.process_state = info.state;
+                    session.usage = info.usage.or(session.usage);
+                    session.observe_activity(Activity::of(session.pid, info.usage), now, idle_after);
+                    true
                 }
-                
-                #[cfg(windows)]
-                {
-                    use std::process::Command;
-                    match Command::new("tasklist").args(["/FI", &format!("PID eq {}", session.pid)]).output() {
-                        Ok(output) => {
-                            let output_str = String::from_utf8_lossy(&output.stdout);
-                            if !output_str.contains(&session.pid.to_string()) {
-                                sessions_to_remove.push(i);
-                                sessions_ended = true;
-                            }
-                        }
-                        Err(_) => {
-                            sessions_to_remove.push(i);
-                            sessions_ended = true;
-                        }
-                    }
+                // Embedded tabs are reaped (with their exit code) on every tick
+                _ if session.kind == SessionKind::Embedded => true,
+                _ => {
+                    sessions_ended = true;
+                    false
+                }
+            });
+
+            let Some(limit) = connection.idle_limit_minutes else { continue };
+            for session in &mut connection.active_sessions {
+                let idle_for = session.idle_for(now);
+                if session.is_idle && !session.idle_warned && idle_for >= Duration::from_secs(limit * 60) {
+                    session.idle_warned = true;
+                    over_limit.push((session.pid, connection.name.clone(), connection.idle_action, idle_for));
                 }
-            }
-            
-            // Remove ended sessions in reverse order to maintain indices
-            for &i in sessions_to_remove.iter().rev() {
-                connection.active_sessions.remove(i);
             }
         }
-        
-        // Update session count if any sessions ended
+
         if sessions_ended {
             self.state.server_manager.update_session_count();
         }
+        if !self.state.terminations.is_empty() {
+            self.state.terminations.escalate();
+        }
+        self.persist_sessions();
+
+        if over_limit.is_empty() {
+            return;
+        }
+        let notices: Vec<String> = over_limit
+            .into_iter()
+            .map(|(pid, name, action, idle_for)| {
+                let minutes = idle_for.as_secs() / 60;
+                match action {
+                    IdleAction::Warn => format!("💤 Session to {} has been idle for {}m", name, minutes),
+                    IdleAction::Terminate => match self.state.kill_session(pid) {
+                        Ok(()) => format!("🔫 Closed session to {} after {}m idle", name, minutes),
+                        Err(e) => format!("⚠️ Couldn't close idle session to {}: {}", name, e),
+                    },
+                }
+            })
+            .collect();
+        self.state.show_popup = true;
+        self.state.popup_message = notices.join("\n");
+        self.state.popup_shown_at = Some(Utc::now());
     }
-    
+
     /// Handle health updates from background monitoring
     async fn handle_health_update(&mut self, update: HealthUpdate) {
+        if update.requested {
+            if let Some(connection) = self.state.server_manager.get_connection_mut(&update.server_id) {
+                update.result.update_server_stats(connection);
+            }
+            self.refresh_progressed();
+            return;
+        }
         if let Some(connection) = self.state.server_manager.get_connection_mut(&update.server_id) {
             update.result.update_server_stats(connection);
             
             // Show notification for status changes that might need attention
             match update.result.status {
-                HealthStatus::Offline => {
-                    if connection.health_status != HealthStatus::Offline {
-                        // Status changed to offline
-                        self.state.show_popup = true;
-                        self.state.popup_message = format!(
-                            "⚠️ {} went offline", 
-                            connection.name
-                        );
-                        self.state.popup_shown_at = Some(Utc::now());
-                    }
+                HealthStatus::Offline if connection.health_status != HealthStatus::Offline => {
+                    // Status changed to offline
+                    self.state.show_popup = true;
+                    self.state.popup_message = format!(
+                        "⚠️ {} went offline", 
+                        connection.name
+                    );
+                    self.state.popup_shown_at = Some(Utc::now());
                 }
-                HealthStatus::Online => {
-                    if connection.health_status == HealthStatus::Offline {
-                        // Status recovered to online
-                        self.state.show_popup = true;
-                        self.state.popup_message = format!(
-                            "✅ {} is back online", 
-                            connection.name
-                        );
-                        self.state.popup_shown_at = Some(Utc::now());
-                    }
+                HealthStatus::Online if connection.health_status == HealthStatus::Offline => {
+                    // Status recovered to online
+                    self.state.show_popup = true;
+                    self.state.popup_message = format!(
+                        "✅ {} is back online", 
+                        connection.name
+                    );
+                    self.state.popup_shown_at = Some(Utc::now());
                 }
                 _ => {}
             }
@@ -922,33 +2393,55 @@ impl App {
     /// Show contextual tooltips based on current state
     fn show_contextual_tooltip(&mut self) {
         use crate::models::{TooltipCategory, AppMode};
+        let keymap = &self.state.keymap;
+        let key = |context, action| keymap.hint(context, action);
         
         match self.state.mode {
             AppMode::Normal => {
                 if self.state.server_manager.connections.is_empty() {
+                    let add = key(KeyContext::Normal, Action::AddServer);
                     self.state.show_tooltip(
                         "Getting Started".to_string(),
-                        "Add your first server with 'a' key. You can also edit, delete, or connect to servers from this view.".to_string(),
-                        Some("Press 'a' to add server".to_string()),
+                        format!("Add your first server with '{}' key. You can also edit, delete, or connect to servers from this view.", add),
+                        Some(format!("Press '{}' to add server", add)),
                         TooltipCategory::Server,
                     );
                 } else if let Some(connection) = self.get_selected_connection() {
-                    let key_hints = ["Enter: Connect".to_string(),
-                        "e: Edit".to_string(), 
-                        "d: Delete".to_string(),
-                        "r: Refresh status".to_string()];
-                    
-                    self.state.show_tooltip(
+                    let key_hints = [format!("{}: Connect", key(KeyContext::Normal, Action::Connect)),
+                        format!("{}: Edit", key(KeyContext::Normal, Action::EditServer)), 
+                        format!("{}: Delete", key(KeyContext::Normal, Action::DeleteServer)),
+                        format!("{}: Refresh status", key(KeyContext::Normal, Action::Refresh))];
+                    let (title, text) = (
                         format!("Server: {}", connection.name),
                         format!("{}@{}:{} | Status: {}", connection.username, connection.host, connection.port, connection.health_status.as_str()),
+                    );
+                    
+                    self.state.show_tooltip(
+                        title,
+                        text,
                         Some(key_hints.join(" | ")),
                         TooltipCategory::Server,
                     );
                 } else {
+                    let navigate = format!("{}/{}", key(KeyContext::Normal, Action::MoveDown), key(KeyContext::Normal, Action::MoveUp));
+                    let text = format!(
+                        "Use {} or arrow keys to navigate. Press {}-{} for quick connect. '{}' to filter, '{}' for layout options.",
+                        navigate,
+                        key(KeyContext::Normal, Action::QuickConnect(1)),
+                        key(KeyContext::Normal, Action::QuickConnect(9)),
+                        key(KeyContext::Normal, Action::ToggleOnlineFilter),
+                        key(KeyContext::Normal, Action::CycleLayout),
+                    );
+                    let hints = format!(
+                        "{}: Navigate | {}: Connect | {}: Help",
+                        navigate,
+                        key(KeyContext::Normal, Action::Connect),
+                        key(KeyContext::Normal, Action::ShowTooltip),
+                    );
                     self.state.show_tooltip(
                         "Navigation Help".to_string(),
-                        "Use j/k or arrow keys to navigate. Press 1-9 for quick connect. 'f' to filter, 'l' for layout options.".to_string(),
-                        Some("j/k: Navigate | Enter: Connect | ?: Help".to_string()),
+                        text,
+                        Some(hints),
                         TooltipCategory::Navigation,
                     );
                 }
@@ -956,43 +2449,54 @@ impl App {
             AppMode::Sessions => {
                 let sessions = self.state.get_filtered_sessions();
                 if sessions.is_empty() {
+                    let hints = format!(
+                        "{}: Return to servers | {}: Connect to server",
+                        key(KeyContext::Sessions, Action::Back),
+                        key(KeyContext::Normal, Action::Connect),
+                    );
                     self.state.show_tooltip(
                         "No Active Sessions".to_string(),
                         "Connect to servers to see active SSH sessions here. Sessions are tracked automatically.".to_string(),
-                        Some("Esc: Return to servers | Enter: Connect to server".to_string()),
+                        Some(hints),
                         TooltipCategory::Session,
                     );
                 } else {
+                    let (kill, refresh) = (key(KeyContext::Sessions, Action::KillSession), key(KeyContext::Sessions, Action::RefreshSessions));
+                    let text = format!("{} active sessions. Use '{}' to kill sessions, '{}' to refresh.", sessions.len(), kill, refresh);
+                    let hints = format!("{}: Kill | {}: Refresh | {}: Info", kill, refresh, key(KeyContext::Sessions, Action::SessionInfo));
                     self.state.show_tooltip(
                         "Session Management".to_string(),
-                        format!("{} active sessions. Use 'd' to kill sessions, 'r' to refresh.", sessions.len()),
-                        Some("d: Kill | r: Refresh | Enter: Info".to_string()),
+                        text,
+                        Some(hints),
                         TooltipCategory::Session,
                     );
                 }
             }
             AppMode::Analytics => {
+                let hints = format!("{}: Return to servers", key(KeyContext::Analytics, Action::Back));
                 self.state.show_tooltip(
                     "Analytics Dashboard".to_string(),
                     "View connection statistics, most used servers, and usage patterns.".to_string(),
-                    Some("A: Return to servers | Esc: Exit".to_string()),
+                    Some(hints),
                     TooltipCategory::System,
                 );
             }
             AppMode::Help => {
+                let hints = format!("{}: Return", key(KeyContext::Help, Action::Back));
                 self.state.show_tooltip(
                     "Help System".to_string(),
                     "Browse all available keyboard shortcuts and features.".to_string(),
-                    Some("h/Esc: Return | F1: Toggle help".to_string()),
+                    Some(hints),
                     TooltipCategory::System,
                 );
             }
             AppMode::History => {
+                let hints = format!("{}: Return to servers", key(KeyContext::History, Action::Back));
                 self.state.show_tooltip(
                     "Connection History".to_string(),
                     format!("{} recent connections tracked. Shows your connection activity over time.", 
                         self.state.server_manager.connection_history.len()),
-                    Some("H: Return to servers | Esc: Exit".to_string()),
+                    Some(hints),
                     TooltipCategory::System,
                 );
             }
@@ -1000,7 +2504,7 @@ impl App {
                 self.state.show_tooltip(
                     "Context Help".to_string(),
                     "Press Esc to return to the main view, or follow the on-screen instructions.".to_string(),
-                    Some("Esc: Return | ?: Context help".to_string()),
+                    Some("Esc: Return".to_string()),
                     TooltipCategory::System,
                 );
             }
@@ -0,0 +1,199 @@
+//! Handing a stored password to ssh without putting it on a command line.
+//!
+//! ssh runs `$SSH_ASKPASS <prompt>` when it needs a password, and with
+//! `SSH_ASKPASS_REQUIRE=force` (OpenSSH 8.4+) does so even on a terminal.
+//! Ghost points that at its own executable, with `GHOST_ASKPASS` naming a
+//! socket in a directory only the user can enter; the helper fetches the
+//! password from the socket and prints it for ssh. The socket answers once
+//! and is gone after that (or after `OFFER_TIMEOUT`), so a rejected password
+//! and any other question, like confirming a new host key, are asked on the
+//! terminal as usual.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use zeroize::Zeroizing;
+
+/// Environment variable that switches `ghost` into askpass helper mode
+pub const SOCKET_ENV: &str = "GHOST_ASKPASS";
+
+/// How long an offered password waits for ssh to ask for it
+pub const OFFER_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// A password waiting for one ssh to ask for it
+#[derive(Debug, Clone)]
+pub struct Askpass {
+    socket: PathBuf,
+}
+
+impl Askpass {
+    /// Serve `secret` once on a fresh socket, from a background thread that
+    /// cleans up after itself.
+    #[cfg(unix)]
+    pub fn offer(secret: Zeroizing<String>) -> Result<Self> {
+        use std::io::Write;
+        use std::os::unix::fs::DirBuilderExt;
+        use std::time::Instant;
+
+        let base = dirs::runtime_dir().unwrap_or_else(std::env::temp_dir);
+        let dir = base.join(format!("ghost-askpass-{}", uuid::Uuid::new_v4()));
+        std::fs::DirBuilder::new()
+            .mode(0o700)
+            .create(&dir)
+            .context("Failed to create the askpass directory")?;
+        let socket = dir.join("socket");
+        let listener = std::os::unix::net::UnixListener::bind(&socket)
+            .and_then(|listener| listener.set_nonblocking(true).map(|_| listener));
+        let listener = match listener {
+            Ok(listener) => listener,
+            Err(e) => {
+                let _ = std::fs::remove_dir_all(&dir);
+                return Err(e).context("Failed to listen for the askpass helper");
+            }
+        };
+
+        std::thread::spawn(move || {
+            let deadline = Instant::now() + OFFER_TIMEOUT;
+            while Instant::now() < deadline {
+                match listener.accept() {
+                    Ok((mut stream, _)) => {
+                        let _ = stream.set_nonblocking(false);
+                        let _ = stream.write_all(secret.as_bytes());
+                        break;
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                        std::thread::sleep(Duration::from_millis(50));
+                    }
+                    Err(_) => break,
+                }
+            }
+            drop(listener);
+            let _ = std::fs::remove_dir_all(&dir);
+        });
+
+        Ok(Self { socket })
+    }
+
+    #[cfg(not(unix))]
+    pub fn offer(_secret: Zeroizing<String>) -> Result<Self> {
+        anyhow::bail!("Stored passwords can't be handed to ssh on this platform")
+    }
+
+    /// `program args` run through `env` with ssh pointed at the helper, or
+    /// `None` if Ghost's own executable can't be located.
+    pub fn wrap(&self, program: &str, args: &[String]) -> Option<(String, Vec<String>)> {
+        let exe = std::env::current_exe().ok()?;
+        let mut wrapped = vec![
+            format!("SSH_ASKPASS={}", exe.to_string_lossy()),
+            "SSH_ASKPASS_REQUIRE=force".to_string(),
+            format!("{}={}", SOCKET_ENV, self.socket.to_string_lossy()),
+            program.to_string(),
+        ];
+        wrapped.extend_from_slice(args);
+        Some(("env".to_string(), wrapped))
+    }
+}
+
+/// Helper mode: print the answer to `prompt` for ssh. A password comes from
+/// Ghost while it's still on offer; everything else is asked on the terminal.
+#[cfg(unix)]
+pub fn run(socket: &Path, prompt: &str) -> Result<()> {
+    let offered = if is_password_prompt(prompt) { fetch(socket).ok() } else { None };
+    let answer = match offered {
+        Some(secret) => secret,
+        None => ask_terminal(prompt)?,
+    };
+    println!("{}", answer.as_str());
+    Ok(())
+}
+
+/// ssh's password prompts look like `user@host's password: `; key
+/// passphrases and host key questions are left to the user
+fn is_password_prompt(prompt: &str) -> bool {
+    let prompt = prompt.to_lowercase();
+    prompt.contains("password") && !prompt.contains("passphrase")
+}
+
+/// The password offered on `socket`
+#[cfg(unix)]
+fn fetch(socket: &Path) -> Result<Zeroizing<String>> {
+    use std::io::Read;
+
+    let mut stream = std::os::unix::net::UnixStream::connect(socket).context("No password on offer")?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut secret = Zeroizing::new(String::new());
+    stream.read_to_string(&mut secret).context("Failed to receive the password")?;
+    Ok(secret)
+}
+
+/// Ask on the controlling terminal the way ssh would: answers to questions
+/// are echoed, secrets aren't.
+#[cfg(unix)]
+fn ask_terminal(prompt: &str) -> Result<Zeroizing<String>> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::io::AsRawFd;
+
+    let mut tty = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .context("No terminal to ask on")?;
+    write!(tty, "{}", prompt)?;
+    tty.flush()?;
+
+    let fd = tty.as_raw_fd();
+    // SAFETY: termios is plain data, filled in by tcgetattr before it's used
+    let mut saved: libc::termios = unsafe { std::mem::zeroed() };
+    let hide = !prompt.trim_end().ends_with('?') && unsafe { libc::tcgetattr(fd, &mut saved) } == 0;
+    if hide {
+        let mut silent = saved;
+        silent.c_lflag &= !libc::ECHO;
+        unsafe { libc::tcsetattr(fd, libc::TCSANOW, &silent) };
+    }
+
+    let mut answer = Zeroizing::new(String::new());
+    let read = BufReader::new(&tty).read_line(&mut answer);
+    if hide {
+        unsafe { libc::tcsetattr(fd, libc::TCSANOW, &saved) };
+        let _ = writeln!(tty);
+    }
+    read.context("Failed to read from the terminal")?;
+
+    let len = answer.trim_end_matches(['\r', '\n']).len();
+    answer.truncate(len);
+    Ok(answer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_password_prompts_are_answered_from_the_store() {
+        assert!(is_password_prompt("deploy@web-1's password: "));
+        assert!(is_password_prompt("(deploy@web-1) Password: "));
+        assert!(!is_password_prompt("Enter passphrase for key '/home/me/.ssh/id_ed25519': "));
+        assert!(!is_password_prompt("Are you sure you want to continue connecting (yes/no/[fingerprint])? "));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn offered_password_is_served_once() {
+        let askpass = Askpass::offer(Zeroizing::new("hunter2".to_string())).unwrap();
+        let (program, args) = askpass.wrap("ssh", &["-p".to_string(), "2222".to_string()]).unwrap();
+        assert_eq!(program, "env");
+        assert!(args[0].starts_with("SSH_ASKPASS=/"));
+        assert_eq!(args[1], "SSH_ASKPASS_REQUIRE=force");
+        assert_eq!(args[2], format!("{}={}", SOCKET_ENV, askpass.socket.display()));
+        assert_eq!(args[3..], ["ssh", "-p", "2222"]);
+
+        assert_eq!(fetch(&askpass.socket).unwrap().as_str(), "hunter2");
+        // The socket and its directory go away after the one answer
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while askpass.socket.parent().unwrap().exists() && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert!(fetch(&askpass.socket).is_err());
+        assert!(!askpass.socket.parent().unwrap().exists());
+    }
+}
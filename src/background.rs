@@ -0,0 +1,187 @@
+//! Detection of the terminal's background colour, used by `theme = "auto"`
+//! to pick between the configured light and dark themes.
+//!
+//! The terminal is asked directly with an OSC 11 query; terminals that don't
+//! answer within a short timeout fall back to the `COLORFGBG` variable that
+//! rxvt, Konsole and friends export.
+
+use std::time::Duration;
+
+/// Whether the terminal background is light or dark
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Background {
+    Light,
+    Dark,
+}
+
+/// How long to wait for the terminal to answer the OSC 11 query. Terminals
+/// that support it answer within a few milliseconds; the rest never do.
+const QUERY_TIMEOUT: Duration = Duration::from_millis(150);
+
+/// Detect the terminal background. Must be called before the TUI takes over
+/// the terminal, as the reply is read straight from stdin.
+pub fn detect() -> Option<Background> {
+    query_osc11()
+        .or_else(|| std::env::var("COLORFGBG").ok().and_then(|v| parse_colorfgbg(&v)))
+}
+
+/// Classify an OSC 11 reply such as `ESC ] 11 ; rgb:1a1a/1b1b/2626 BEL`.
+pub fn parse_osc11_reply(reply: &str) -> Option<Background> {
+    let start = reply.find("rgb:")? + 4;
+    let rest = &reply[start..];
+    let end = rest
+        .find(|c: char| !(c.is_ascii_hexdigit() || c == '/'))
+        .unwrap_or(rest.len());
+
+    let mut channels = rest[..end].split('/').map(parse_channel);
+    let r = channels.next()??;
+    let g = channels.next()??;
+    let b = channels.next()??;
+
+    // Relative luminance (Rec. 709 weights) on the 0.0–1.0 channel values
+    let luminance = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    Some(if luminance > 0.5 { Background::Light } else { Background::Dark })
+}
+
+/// A 1–4 digit hex channel scaled to 0.0–1.0 (`ff` and `ffff` are both white).
+fn parse_channel(hex: &str) -> Option<f64> {
+    if hex.is_empty() || hex.len() > 4 {
+        return None;
+    }
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    let max = (1u32 << (4 * hex.len())) - 1;
+    Some(value as f64 / max as f64)
+}
+
+/// Classify a `COLORFGBG` value (`"15;0"`, or `"15;default;0"` from rxvt).
+/// The background is the last field, an ANSI colour index: 7 (white) and the
+/// bright colours above 8 are light, everything else is dark.
+pub fn parse_colorfgbg(value: &str) -> Option<Background> {
+    let bg: u8 = value.rsplit(';').next()?.trim().parse().ok()?;
+    Some(if bg == 7 || bg > 8 { Background::Light } else { Background::Dark })
+}
+
+#[cfg(unix)]
+fn query_osc11() -> Option<Background> {
+    use crossterm::terminal::{disable_raw_mode, enable_raw_mode, is_raw_mode_enabled};
+    use std::io::{IsTerminal, Write};
+    use std::os::unix::io::AsRawFd;
+
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+    if !stdin.is_terminal() || !stdout.is_terminal() {
+        return None;
+    }
+
+    // Raw mode keeps the reply from being echoed and line-buffered
+    let was_raw = is_raw_mode_enabled().unwrap_or(false);
+    if !was_raw {
+        enable_raw_mode().ok()?;
+    }
+
+    let result = (|| {
+        stdout.write_all(b"\x1b]11;?\x07").ok()?;
+        stdout.flush().ok()?;
+        let reply = read_reply(stdin.as_raw_fd(), QUERY_TIMEOUT)?;
+        parse_osc11_reply(&String::from_utf8_lossy(&reply))
+    })();
+
+    if !was_raw {
+        let _ = disable_raw_mode();
+    }
+    result
+}
+
+/// Read an OSC reply from `fd` one byte at a time until BEL or ST. The fd is
+/// read directly rather than through `std::io::Stdin`, whose buffer would
+/// swallow the rest of the reply and leave `poll` waiting on an empty fd.
+#[cfg(unix)]
+fn read_reply(fd: std::os::unix::io::RawFd, timeout: Duration) -> Option<Vec<u8>> {
+    use std::time::Instant;
+
+    let deadline = Instant::now() + timeout;
+    let mut reply = Vec::new();
+    let mut byte = [0u8; 1];
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return None;
+        }
+
+        let mut pollfd = libc::pollfd { fd, events: libc::POLLIN, revents: 0 };
+        // SAFETY: `pollfd` is a valid, initialised pollfd for the duration of the call
+        let ready = unsafe { libc::poll(&mut pollfd, 1, remaining.as_millis() as libc::c_int) };
+        if ready <= 0 {
+            return None;
+        }
+        // SAFETY: `byte` is a valid one-byte buffer for the duration of the call
+        let read = unsafe { libc::read(fd, byte.as_mut_ptr().cast(), 1) };
+        if read <= 0 {
+            return None;
+        }
+        reply.push(byte[0]);
+
+        // The reply ends with BEL or ST (ESC \)
+        if byte[0] == 0x07 || reply.ends_with(b"\x1b\\") {
+            return Some(reply);
+        }
+        if reply.len() > 64 {
+            return None;
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn query_osc11() -> Option<Background> {
+    // Windows consoles don't answer OSC 11; rely on COLORFGBG alone
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn osc11_replies_are_classified() {
+        assert_eq!(parse_osc11_reply("\x1b]11;rgb:1a1a/1b1b/2626\x07"), Some(Background::Dark));
+        assert_eq!(parse_osc11_reply("\x1b]11;rgb:fdfd/f6f6/e3e3\x1b\\"), Some(Background::Light));
+        assert_eq!(parse_osc11_reply("\x1b]11;rgb:ff/ff/ff\x07"), Some(Background::Light));
+        assert_eq!(parse_osc11_reply("\x1b]11;?\x07"), None);
+        assert_eq!(parse_osc11_reply("\x1b]11;rgb:zz/00/00\x07"), None);
+    }
+
+    #[test]
+    fn colorfgbg_uses_the_last_field() {
+        assert_eq!(parse_colorfgbg("15;0"), Some(Background::Dark));
+        assert_eq!(parse_colorfgbg("0;15"), Some(Background::Light));
+        assert_eq!(parse_colorfgbg("0;default;7"), Some(Background::Light));
+        assert_eq!(parse_colorfgbg("7;8"), Some(Background::Dark));
+        assert_eq!(parse_colorfgbg("default"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn replies_are_read_whole_from_the_fd() {
+        let mut fds = [0; 2];
+        // SAFETY: `fds` has room for the two descriptors pipe() writes
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let [read_fd, write_fd] = fds;
+
+        let reply = b"\x1b]11;rgb:fdfd/f6f6/e3e3\x1b\\";
+        // SAFETY: `reply` is valid for `reply.len()` bytes
+        let written = unsafe { libc::write(write_fd, reply.as_ptr().cast(), reply.len()) };
+        assert_eq!(written, reply.len() as isize);
+
+        let read = read_reply(read_fd, Duration::from_millis(500));
+        assert_eq!(read.as_deref(), Some(&reply[..]));
+        // A second query with nothing pending times out instead of hanging
+        assert_eq!(read_reply(read_fd, Duration::from_millis(20)), None);
+
+        // SAFETY: both descriptors are open and owned by this test
+        unsafe {
+            libc::close(read_fd);
+            libc::close(write_fd);
+        }
+    }
+}
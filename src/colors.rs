@@ -1,8 +1,10 @@
 use ratatui::style::Color;
 
 /// Tokyo Night color palette
+#[deprecated(note = "colors come from the active theme now; use `themes::ThemeManager::current_theme`")]
 pub struct TokyoNight;
 
+#[allow(deprecated)]
 impl TokyoNight {
     // Background colors
     pub const BG: Color = Color::Rgb(26, 27, 38);           // #1a1b26
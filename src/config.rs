@@ -1,10 +1,11 @@
-use crate::models::{AuthMethod, ServerConnection};
+use crate::models::{AuthMethod, Environment, IdleAction, LayoutMode, PanelLayout, ServerConnection, SessionKind};
 use crate::themes::ThemeVariant;
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Configuration structure for the Ghost SSH Manager
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -13,13 +14,133 @@ pub struct Config {
     pub settings: AppSettings,
     /// Server connection definitions
     pub servers: HashMap<String, ServerConfig>,
+    /// How new-terminal connections are launched
+    #[serde(default)]
+    pub terminal: TerminalConfig,
+    /// Where passwords of password-auth servers are kept, if anywhere
+    #[serde(default)]
+    pub credentials: CredentialsConfig,
+    /// Logging in during health checks to collect host facts
+    #[serde(default)]
+    pub deep_check: DeepCheckConfig,
+    /// Keybinding overrides per context (see `keymap`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub keybindings: KeybindingsConfig,
+}
+
+/// `[terminal]` section
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TerminalConfig {
+    /// Inside tmux or zellij: open each connection in a new window (tab) or
+    /// split the current one
+    pub multiplexer: MultiplexerTarget,
+    /// Terminal to open connections in: a built-in (`"kitty"`,
+    /// `"gnome-terminal"`, `"tmux"`, …) or a name from `templates`. Unset
+    /// means the first one detected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preferred: Option<String>,
+    /// Custom launchers (`[terminal.templates]`): name → argv, where a
+    /// `"{argv}"` element becomes the ssh command and `{title}` the window title
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub templates: BTreeMap<String, Vec<String>>,
+}
+
+/// Where a tmux/zellij connection opens
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MultiplexerTarget {
+    /// tmux window / zellij tab named after the server
+    #[default]
+    Window,
+    /// tmux split / zellij pane next to Ghost
+    Split,
+}
+
+/// `[credentials]` section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CredentialsConfig {
+    /// Where passwords are kept (see `credentials`)
+    pub store: CredentialStore,
+    /// Minutes without use after which an unlocked vault locks again;
+    /// 0 keeps it unlocked until Ghost exits
+    pub lock_after_minutes: u64,
+}
+
+impl Default for CredentialsConfig {
+    fn default() -> Self {
+        Self {
+            store: CredentialStore::None,
+            lock_after_minutes: 15,
+        }
+    }
+}
+
+/// `[deep_check]` section: health checks of reachable servers log in
+/// (natively, without prompting) and collect uptime, load, memory, disk and
+/// kernel facts. Going over a limit turns the server into a warning.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeepCheckConfig {
+    pub enabled: bool,
+    /// 1-minute load average per CPU
+    pub max_load_per_cpu: f32,
+    pub max_memory_percent: f32,
+    /// Usage of the `/` filesystem
+    pub max_disk_percent: f32,
+    /// Warn while the server is waiting to be rebooted (e.g. after a
+    /// kernel update)
+    pub warn_on_reboot: bool,
+}
+
+impl Default for DeepCheckConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_load_per_cpu: 2.0,
+            max_memory_percent: 90.0,
+            max_disk_percent: 90.0,
+            warn_on_reboot: true,
+        }
+    }
+}
+
+/// Backend for stored passwords
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialStore {
+    /// Never store passwords; ssh asks every time
+    #[default]
+    None,
+    /// `vault.json` next to the config, encrypted under a master passphrase
+    Vault,
+    /// The OS keyring (Secret Service on Linux, Keychain on macOS,
+    /// Credential Manager on Windows)
+    Keyring,
+}
+
+/// `[keybindings.<context>]` tables: action name → key sequence(s)
+pub type KeybindingsConfig = BTreeMap<String, BTreeMap<String, KeyList>>;
+
+/// One key sequence (`quit = "q"`) or several (`quit = ["q", "ctrl+d"]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum KeyList {
+    One(String),
+    Many(Vec<String>),
 }
 
 /// Application settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppSettings {
-    /// Current theme variant
+    /// Current theme variant, or `"auto"` to follow the terminal background
     pub theme: ThemeVariant,
+    /// Theme used by `auto` on a light terminal background
+    pub light_theme: ThemeVariant,
+    /// Theme used by `auto` on a dark terminal background
+    pub dark_theme: ThemeVariant,
     /// Auto-refresh interval in seconds
     pub refresh_interval: u64,
     /// Show only online servers by default
@@ -30,20 +151,45 @@ pub struct AppSettings {
     pub smooth_animations: bool,
     /// Show tooltips and help hints
     pub show_tooltips: bool,
-    /// Panel layout (future: different layouts)
+    /// Minutes without CPU or terminal activity after which a session
+    /// counts as idle
+    pub idle_after_minutes: u64,
+    /// Health checks also log in to reachable servers (with Ghost's own
+    /// SSH client), so a server whose login fails shows as a warning
+    pub verify_login: bool,
+    /// Name of the layout preset applied last (`"default"` if none)
     pub panel_layout: String,
+    /// Panel arrangement, restored on launch
+    pub layout_mode: LayoutMode,
+    /// Panel widths in percent: [server list, details, metrics]
+    pub panel_sizes: [u16; 3],
+    /// Adapt `layout_mode` to the terminal size; turned off by choosing a
+    /// layout with `cycle_layout` or a preset
+    pub layout_auto: bool,
+    /// Named layouts (`[settings.layout_presets.<name>]`), switched with
+    /// the `next_layout_preset` action or from the command palette
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub layout_presets: BTreeMap<String, PanelLayout>,
 }
 
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
             theme: ThemeVariant::TokyoNightDark,
+            light_theme: ThemeVariant::TokyoNightLight,
+            dark_theme: ThemeVariant::TokyoNightDark,
             refresh_interval: 30,
             show_only_online: false,
             animation_speed: 1.0,
             smooth_animations: true,
             show_tooltips: true,
+            idle_after_minutes: 10,
+            verify_login: false,
             panel_layout: "default".to_string(),
+            layout_mode: PanelLayout::default().mode,
+            panel_sizes: PanelLayout::default().panel_sizes,
+            layout_auto: true,
+            layout_presets: BTreeMap::new(),
         }
     }
 }
@@ -60,6 +206,19 @@ pub struct ServerConfig {
     pub tags: Vec<String>,
     /// Custom connection timeout in seconds
     pub timeout: Option<u64>,
+    /// Minutes a session may stay idle before `idle_action` is taken
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_limit_minutes: Option<u64>,
+    /// `"warn"` (default) or `"terminate"`
+    #[serde(default)]
+    pub idle_action: IdleAction,
+    /// `"prod"`, `"staging"` or `"dev"`, shown on the server's row
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<Environment>,
+    /// Ask for the server's name before connecting, deleting or including
+    /// it in a transfer or key install; always on for prod
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub protected: bool,
 }
 
 /// Authentication method configuration for TOML serialization
@@ -105,6 +264,10 @@ impl From<ServerConfig> for ServerConnection {
         connection.auth_method = config.auth_method.into();
         connection.description = config.description;
         connection.tags = config.tags;
+        connection.idle_limit_minutes = config.idle_limit_minutes;
+        connection.idle_action = config.idle_action;
+        connection.environment = config.environment;
+        connection.protected = config.protected;
         connection
     }
 }
@@ -120,8 +283,51 @@ impl From<ServerConnection> for ServerConfig {
             description: conn.description,
             tags: conn.tags,
             timeout: None, // Default timeout
+            idle_limit_minutes: conn.idle_limit_minutes,
+            idle_action: conn.idle_action,
+            environment: conn.environment,
+            protected: conn.protected,
+        }
+    }
+}
+
+/// Write `contents` to `path` atomically and readable only by the user:
+/// serialize to a temp file in the same directory, restrict its permissions,
+/// then rename it over the real file. A crash or full disk mid-write can't
+/// truncate/corrupt the existing file, and it's never world-readable.
+pub(crate) fn write_private(path: &Path, contents: &str) -> Result<()> {
+    // Temp file in the same directory (so the rename stays on one filesystem
+    // and is therefore atomic). The PID suffix avoids collisions between
+    // concurrent writers.
+    let tmp_path = path.with_extension(format!("tmp.{}", std::process::id()));
+
+    fs::write(&tmp_path, contents).context("Failed to write temporary file")?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Err(e) = fs::set_permissions(&tmp_path, fs::Permissions::from_mode(0o600)) {
+            let _ = fs::remove_file(&tmp_path); // best-effort cleanup
+            return Err(e).context("Failed to set file permissions");
         }
     }
+
+    fs::rename(&tmp_path, path).context("Failed to replace file")?;
+
+    Ok(())
+}
+
+/// A session as remembered across restarts (`sessions.json`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionRecord {
+    pub pid: u32,
+    pub server_id: String,
+    pub started_at: DateTime<Utc>,
+    /// Start time of the process (see `process::start_time`), so a reused
+    /// pid isn't mistaken for the session
+    pub process_start: Option<u64>,
+    pub window_title: String,
+    pub kind: SessionKind,
 }
 
 /// Configuration manager for Ghost SSH Manager
@@ -153,6 +359,15 @@ impl ConfigManager {
         Ok(config_dir)
     }
 
+    /// Directory holding user-defined theme files (`themes/*.toml`), next to
+    /// the main config file.
+    pub fn themes_dir(&self) -> PathBuf {
+        self.config_path
+            .parent()
+            .map(|dir| dir.join("themes"))
+            .unwrap_or_else(|| PathBuf::from("themes"))
+    }
+
     /// Load configuration from file
     pub fn load_config(&self) -> Result<Config> {
         if !self.config_path.exists() {
@@ -171,41 +386,37 @@ impl ConfigManager {
         Ok(config)
     }
 
-    /// Save configuration to file.
-    ///
-    /// Written atomically: serialize to a temp file in the same directory,
-    /// restrict its permissions, then rename it over the real config. A crash or
-    /// full disk mid-write can no longer truncate/corrupt the existing server
-    /// list, and the config is not left world-readable.
+    /// Save configuration to file, atomically and privately (see
+    /// `write_private`).
     pub fn save_config(&self, config: &Config) -> Result<()> {
         let toml_string = toml::to_string_pretty(config)
             .context("Failed to serialize config")?;
 
-        // Temp file in the same directory (so the rename stays on one filesystem
-        // and is therefore atomic). The PID suffix avoids collisions between
-        // concurrent writers.
-        let tmp_path = self
-            .config_path
-            .with_extension(format!("tmp.{}", std::process::id()));
-
-        fs::write(&tmp_path, &toml_string)
-            .context("Failed to write temporary config file")?;
-
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            if let Err(e) =
-                fs::set_permissions(&tmp_path, fs::Permissions::from_mode(0o600))
-            {
-                let _ = fs::remove_file(&tmp_path); // best-effort cleanup
-                return Err(e).context("Failed to set config file permissions");
-            }
-        }
+        write_private(&self.config_path, &toml_string).context("Failed to save config file")
+    }
+
+    /// Where session records are kept across restarts, next to the config
+    fn sessions_path(&self) -> PathBuf {
+        self.config_path.with_file_name("sessions.json")
+    }
 
-        fs::rename(&tmp_path, &self.config_path)
-            .context("Failed to replace config file")?;
+    /// Sessions recorded by the previous run; none if the file is missing
+    /// or unreadable, since they're only a convenience.
+    pub fn load_sessions(&self) -> Vec<SessionRecord> {
+        fs::read_to_string(self.sessions_path())
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
 
-        Ok(())
+    /// The encrypted password vault, next to the config
+    pub fn vault_path(&self) -> PathBuf {
+        self.config_path.with_file_name("vault.json")
+    }
+
+    pub fn save_sessions(&self, records: &[SessionRecord]) -> Result<()> {
+        let json = serde_json::to_string_pretty(records).context("Failed to serialize sessions")?;
+        write_private(&self.sessions_path(), &json).context("Failed to save sessions")
     }
 
     /// Add default sample servers to configuration
@@ -220,6 +431,10 @@ impl ConfigManager {
                 description: Some("Main production server".to_string()),
                 tags: vec!["production".to_string(), "web".to_string()],
                 timeout: Some(10),
+                idle_limit_minutes: None,
+                idle_action: IdleAction::Warn,
+                environment: Some(Environment::Prod),
+                protected: false,
             },
             ServerConfig {
                 name: "Development Box".to_string(),
@@ -232,6 +447,10 @@ impl ConfigManager {
                 description: Some("Development environment".to_string()),
                 tags: vec!["development".to_string(), "local".to_string()],
                 timeout: Some(5),
+                idle_limit_minutes: None,
+                idle_action: IdleAction::Warn,
+                environment: Some(Environment::Dev),
+                protected: false,
             },
             ServerConfig {
                 name: "Database Server".to_string(),
@@ -242,6 +461,10 @@ impl ConfigManager {
                 description: Some("Database server cluster".to_string()),
                 tags: vec!["database".to_string(), "production".to_string()],
                 timeout: Some(15),
+                idle_limit_minutes: None,
+                idle_action: IdleAction::Warn,
+                environment: Some(Environment::Prod),
+                protected: false,
             },
         ];
 
@@ -273,6 +496,10 @@ impl ConfigManager {
         Config {
             settings,
             servers,
+            terminal: TerminalConfig::default(),
+            credentials: CredentialsConfig::default(),
+            deep_check: DeepCheckConfig::default(),
+            keybindings: KeybindingsConfig::new(),
         }
     }
 }
@@ -293,6 +520,10 @@ mod tests {
             description: None,
             tags: vec![],
             timeout: None,
+            idle_limit_minutes: None,
+            idle_action: IdleAction::Warn,
+            environment: None,
+            protected: false,
         });
 
         let toml_str = toml::to_string(&config).unwrap();
@@ -302,6 +533,30 @@ mod tests {
         assert_eq!(config.settings.theme, parsed_config.settings.theme);
     }
 
+    #[test]
+    fn layout_settings_round_trip() {
+        let toml_str = r#"
+            [settings]
+            layout_mode = "two_panel"
+            panel_sizes = [60, 40, 0]
+
+            [settings.layout_presets.focus]
+            mode = "single_panel"
+
+            [servers]
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.settings.layout_mode, LayoutMode::TwoPanel);
+        assert_eq!(config.settings.panel_sizes, [60, 40, 0]);
+        // Sizes left out of a preset fall back to the mode's defaults
+        let focus = config.settings.layout_presets["focus"].clone().validated();
+        assert_eq!(focus, PanelLayout::for_mode(LayoutMode::SinglePanel));
+
+        let parsed: Config = toml::from_str(&toml::to_string(&config).unwrap()).unwrap();
+        assert_eq!(parsed.settings.layout_presets.len(), 1);
+        assert_eq!(parsed.settings.panel_sizes, [60, 40, 0]);
+    }
+
     #[test]
     fn test_server_conversion() {
         let server_config = ServerConfig {
@@ -313,13 +568,24 @@ mod tests {
             description: Some("test".to_string()),
             tags: vec!["test".to_string()],
             timeout: None,
+            idle_limit_minutes: None,
+            idle_action: IdleAction::Warn,
+            environment: Some(Environment::Staging),
+            protected: true,
         };
 
         let connection = ServerConnection::from(server_config.clone());
+        assert!(connection.is_protected());
         let back_to_config = ServerConfig::from(connection);
         
         assert_eq!(server_config.name, back_to_config.name);
         assert_eq!(server_config.host, back_to_config.host);
         assert_eq!(server_config.port, back_to_config.port);
+        assert_eq!((back_to_config.environment, back_to_config.protected), (Some(Environment::Staging), true));
+
+        let toml_str = toml::to_string(&back_to_config).unwrap();
+        assert!(toml_str.contains("environment = \"staging\"") && toml_str.contains("protected = true"));
+        let prod: ServerConfig = toml::from_str(&toml_str.replace("\"staging\"", "\"production\"").replace("protected = true\n", "")).unwrap();
+        assert!(ServerConnection::from(prod).is_protected());
     }
 }
\ No newline at end of file
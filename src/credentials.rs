@@ -0,0 +1,436 @@
+//! Stored passwords for password-auth servers.
+//!
+//! Off unless `[credentials] store` says otherwise. The vault keeps every
+//! secret in `vault.json`, encrypted with AES-256-GCM under a key derived
+//! from a master passphrase with Argon2id; the key only lives in memory while
+//! the vault is unlocked, and is dropped again after `lock_after_minutes`
+//! without use. The keyring backend hands secrets to the OS keyring instead,
+//! which does its own locking. Either way a password only reaches ssh through
+//! `askpass`, never its command line.
+
+use crate::config::{write_private, CredentialStore, CredentialsConfig};
+use crate::forms::InputField;
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use anyhow::{Context, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use zeroize::{Zeroize, Zeroizing};
+
+/// Service name Ghost's entries are filed under in the OS keyring
+const KEYRING_SERVICE: &str = "ghost-ssh";
+
+const VAULT_VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// `vault.json`: the Argon2 parameters and salt needed to derive the key
+/// again, and the encrypted `server id → password` map
+#[derive(Debug, Serialize, Deserialize)]
+struct VaultFile {
+    version: u32,
+    kdf: KdfParams,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct KdfParams {
+    /// Memory in KiB
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+}
+
+impl KdfParams {
+    /// Argon2's recommended defaults (19 MiB, 2 passes); tests use the
+    /// cheapest allowed so they stay fast in debug builds
+    fn current() -> Self {
+        if cfg!(test) {
+            Self { m_cost: Params::MIN_M_COST, t_cost: 1, p_cost: 1 }
+        } else {
+            Self {
+                m_cost: Params::DEFAULT_M_COST,
+                t_cost: Params::DEFAULT_T_COST,
+                p_cost: Params::DEFAULT_P_COST,
+            }
+        }
+    }
+
+    fn derive(&self, passphrase: &str, salt: &[u8]) -> Result<Zeroizing<[u8; 32]>> {
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, Some(32))
+            .map_err(|e| anyhow::anyhow!("Invalid vault key parameters: {}", e))?;
+        let mut key = Zeroizing::new([0u8; 32]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), salt, key.as_mut())
+            .map_err(|e| anyhow::anyhow!("Failed to derive the vault key: {}", e))?;
+        Ok(key)
+    }
+}
+
+/// An open vault: its key and decrypted contents
+struct Unlocked {
+    key: Zeroizing<[u8; 32]>,
+    kdf: KdfParams,
+    salt: Vec<u8>,
+    secrets: BTreeMap<String, String>,
+    last_used: Instant,
+}
+
+impl Drop for Unlocked {
+    fn drop(&mut self) {
+        for secret in self.secrets.values_mut() {
+            secret.zeroize();
+        }
+    }
+}
+
+/// Stored passwords, by server id
+pub struct Credentials {
+    config: CredentialsConfig,
+    vault_path: PathBuf,
+    unlocked: Option<Unlocked>,
+}
+
+// Never prints secrets
+impl std::fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Credentials")
+            .field("store", &self.config.store)
+            .field("unlocked", &self.is_unlocked())
+            .finish()
+    }
+}
+
+impl Default for Credentials {
+    fn default() -> Self {
+        Self::new(CredentialsConfig::default(), PathBuf::from("vault.json"))
+    }
+}
+
+impl Credentials {
+    pub fn new(config: CredentialsConfig, vault_path: PathBuf) -> Self {
+        Self { config, vault_path, unlocked: None }
+    }
+
+    pub fn config(&self) -> &CredentialsConfig {
+        &self.config
+    }
+
+    pub fn store(&self) -> CredentialStore {
+        self.config.store
+    }
+
+    /// The vault is in use but its passphrase hasn't been given yet
+    pub fn is_locked(&self) -> bool {
+        self.config.store == CredentialStore::Vault && self.unlocked.is_none()
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.unlocked.is_some()
+    }
+
+    pub fn vault_exists(&self) -> bool {
+        self.vault_path.exists()
+    }
+
+    /// Open the vault with `passphrase`, creating an empty one the first time
+    pub fn unlock(&mut self, passphrase: &str) -> Result<()> {
+        if passphrase.is_empty() {
+            anyhow::bail!("The passphrase can't be empty");
+        }
+
+        if !self.vault_exists() {
+            let kdf = KdfParams::current();
+            let mut salt = vec![0u8; SALT_LEN];
+            rand::thread_rng().fill_bytes(&mut salt);
+            self.unlocked = Some(Unlocked {
+                key: kdf.derive(passphrase, &salt)?,
+                kdf,
+                salt,
+                secrets: BTreeMap::new(),
+                last_used: Instant::now(),
+            });
+            return self.save();
+        }
+
+        let contents = fs::read_to_string(&self.vault_path).context("Failed to read the vault")?;
+        let file: VaultFile = serde_json::from_str(&contents).context("Failed to parse the vault")?;
+        if file.version != VAULT_VERSION {
+            anyhow::bail!("Unsupported vault version {}", file.version);
+        }
+        let salt = BASE64.decode(&file.salt).context("Damaged vault salt")?;
+        let nonce = BASE64.decode(&file.nonce).context("Damaged vault nonce")?;
+        let ciphertext = BASE64.decode(&file.ciphertext).context("Damaged vault contents")?;
+        if nonce.len() != NONCE_LEN {
+            anyhow::bail!("Damaged vault nonce");
+        }
+
+        let key = file.kdf.derive(passphrase, &salt)?;
+        let plaintext = Zeroizing::new(
+            Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key.as_ref()))
+                .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+                .map_err(|_| anyhow::anyhow!("Wrong passphrase (or the vault file is damaged)"))?,
+        );
+        let secrets = serde_json::from_slice(&plaintext).context("Damaged vault contents")?;
+
+        self.unlocked = Some(Unlocked { key, kdf: file.kdf, salt, secrets, last_used: Instant::now() });
+        Ok(())
+    }
+
+    /// Forget the key and decrypted secrets; false if it wasn't open
+    pub fn lock(&mut self) -> bool {
+        self.unlocked.take().is_some()
+    }
+
+    /// Lock the vault once it has gone unused for `lock_after_minutes`;
+    /// true if that just happened
+    pub fn lock_if_idle(&mut self) -> bool {
+        let limit = self.config.lock_after_minutes;
+        match &self.unlocked {
+            Some(vault) if limit > 0 && vault.last_used.elapsed() >= Duration::from_secs(limit * 60) => self.lock(),
+            _ => false,
+        }
+    }
+
+    pub fn lock_after_minutes(&self) -> u64 {
+        self.config.lock_after_minutes
+    }
+
+    /// The stored password for `server_id`, if there is one
+    pub fn get(&mut self, server_id: &str) -> Result<Option<Zeroizing<String>>> {
+        match self.config.store {
+            CredentialStore::None => Ok(None),
+            CredentialStore::Vault => {
+                let vault = self.open_vault()?;
+                Ok(vault.secrets.get(server_id).map(|s| Zeroizing::new(s.clone())))
+            }
+            CredentialStore::Keyring => match keyring_entry(server_id)?.get_password() {
+                Ok(secret) => Ok(Some(Zeroizing::new(secret))),
+                Err(keyring::Error::NoEntry) => Ok(None),
+                Err(e) => Err(e).context("Failed to read from the keyring"),
+            },
+        }
+    }
+
+    /// Store `secret` as the password for `server_id`
+    pub fn set(&mut self, server_id: &str, secret: &str) -> Result<()> {
+        match self.config.store {
+            CredentialStore::None => anyhow::bail!("Password storage is off (set [credentials] store in config.toml)"),
+            CredentialStore::Vault => {
+                let vault = self.open_vault()?;
+                if let Some(mut old) = vault.secrets.insert(server_id.to_string(), secret.to_string()) {
+                    old.zeroize();
+                }
+                self.save()
+            }
+            CredentialStore::Keyring => keyring_entry(server_id)?
+                .set_password(secret)
+                .context("Failed to write to the keyring"),
+        }
+    }
+
+    /// Drop the stored password for `server_id`; false if there was none
+    pub fn remove(&mut self, server_id: &str) -> Result<bool> {
+        match self.config.store {
+            CredentialStore::None => Ok(false),
+            CredentialStore::Vault => {
+                let vault = self.open_vault()?;
+                match vault.secrets.remove(server_id) {
+                    Some(mut old) => {
+                        old.zeroize();
+                        self.save()?;
+                        Ok(true)
+                    }
+                    None => Ok(false),
+                }
+            }
+            CredentialStore::Keyring => match keyring_entry(server_id)?.delete_password() {
+                Ok(()) => Ok(true),
+                Err(keyring::Error::NoEntry) => Ok(false),
+                Err(e) => Err(e).context("Failed to delete from the keyring"),
+            },
+        }
+    }
+
+    /// The unlocked vault, counting this as use for the idle timer
+    fn open_vault(&mut self) -> Result<&mut Unlocked> {
+        let vault = self.unlocked.as_mut().ok_or_else(|| anyhow::anyhow!("The vault is locked"))?;
+        vault.last_used = Instant::now();
+        Ok(vault)
+    }
+
+    /// Encrypt the secrets under a fresh nonce and write the vault
+    fn save(&self) -> Result<()> {
+        let vault = self.unlocked.as_ref().ok_or_else(|| anyhow::anyhow!("The vault is locked"))?;
+        let plaintext = Zeroizing::new(serde_json::to_vec(&vault.secrets).context("Failed to serialize the vault")?);
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let ciphertext = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(vault.key.as_ref()))
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_ref())
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt the vault"))?;
+
+        let file = VaultFile {
+            version: VAULT_VERSION,
+            kdf: vault.kdf,
+            salt: BASE64.encode(&vault.salt),
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        };
+        let json = serde_json::to_string_pretty(&file).context("Failed to serialize the vault")?;
+        write_private(&self.vault_path, &json).context("Failed to save the vault")
+    }
+}
+
+fn keyring_entry(server_id: &str) -> Result<keyring::Entry> {
+    keyring::Entry::new(KEYRING_SERVICE, server_id).context("Failed to open the keyring")
+}
+
+/// What the credential popup is asking for
+pub enum CredentialPromptKind {
+    /// Passphrase of an existing vault
+    Unlock,
+    /// New vault passphrase, typed twice; holds the first entry once given
+    CreateVault { first: Option<Zeroizing<String>> },
+    /// Password to store for the server (empty forgets it)
+    Password,
+    /// Password for one native login, not stored
+    LoginPassword,
+    /// Passphrase of the server's key file, for one native login
+    KeyPassphrase { key_path: String },
+}
+
+/// What to go on with once the vault is unlocked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FollowUp {
+    Connect,
+    SavePassword,
+}
+
+/// Passphrase or password popup for one server
+pub struct CredentialPrompt {
+    pub kind: CredentialPromptKind,
+    pub server_id: String,
+    pub server_name: String,
+    pub then: FollowUp,
+    pub input: InputField,
+}
+
+impl CredentialPrompt {
+    pub fn new(kind: CredentialPromptKind, server_id: String, server_name: String, then: FollowUp) -> Self {
+        let (label, placeholder) = match &kind {
+            CredentialPromptKind::Unlock => ("Vault passphrase", ""),
+            CredentialPromptKind::CreateVault { .. } => ("New vault passphrase", "protects every stored password"),
+            CredentialPromptKind::Password => ("Password", "leave empty to forget the stored one"),
+            CredentialPromptKind::LoginPassword => ("Password", ""),
+            CredentialPromptKind::KeyPassphrase { .. } => ("Key passphrase", ""),
+        };
+        let mut input = InputField::new(label, placeholder);
+        input.is_password = true;
+        input.is_focused = true;
+        Self { kind, server_id, server_name, then, input }
+    }
+
+    pub fn title(&self) -> String {
+        match &self.kind {
+            CredentialPromptKind::Unlock => " 🔒 Unlock Vault ".to_string(),
+            CredentialPromptKind::CreateVault { .. } => " 🔐 Create Vault ".to_string(),
+            CredentialPromptKind::Password => format!(" 🔑 Password for {} ", self.server_name),
+            CredentialPromptKind::LoginPassword => format!(" 🔑 Log in to {} ", self.server_name),
+            CredentialPromptKind::KeyPassphrase { .. } => " 🔑 Unlock Key ".to_string(),
+        }
+    }
+
+    /// Take the typed value, leaving the field empty
+    pub fn take_input(&mut self) -> Zeroizing<String> {
+        self.input.cursor_position = 0;
+        Zeroizing::new(std::mem::take(&mut self.input.value))
+    }
+}
+
+impl std::fmt::Debug for CredentialPrompt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CredentialPrompt")
+            .field("title", &self.title())
+            .field("server_id", &self.server_id)
+            .field("then", &self.then)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for CredentialPrompt {
+    fn drop(&mut self) {
+        self.input.value.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vault_in(dir: &std::path::Path, lock_after_minutes: u64) -> Credentials {
+        let config = CredentialsConfig { store: CredentialStore::Vault, lock_after_minutes };
+        Credentials::new(config, dir.join("vault.json"))
+    }
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ghost-credentials-{}-{}", name, uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn vault_round_trips_and_rejects_the_wrong_passphrase() {
+        let dir = scratch_dir("round-trip");
+        let mut credentials = vault_in(&dir, 15);
+        assert!(credentials.is_locked() && !credentials.vault_exists());
+        assert!(credentials.get("web").is_err());
+
+        credentials.unlock("correct horse").unwrap();
+        credentials.set("web", "s3cret-pa55").unwrap();
+        credentials.set("db", "other").unwrap();
+        assert!(credentials.remove("db").unwrap());
+        assert!(!credentials.remove("db").unwrap());
+
+        let on_disk = fs::read_to_string(dir.join("vault.json")).unwrap();
+        assert!(!on_disk.contains("s3cret") && !on_disk.contains("web"));
+
+        assert!(credentials.lock());
+        assert!(credentials.unlock("wrong horse").is_err());
+        assert!(credentials.is_locked());
+
+        let mut reopened = vault_in(&dir, 15);
+        reopened.unlock("correct horse").unwrap();
+        assert_eq!(reopened.get("web").unwrap().as_deref().map(String::as_str), Some("s3cret-pa55"));
+        assert!(reopened.get("db").unwrap().is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unused_vault_locks_itself() {
+        let dir = scratch_dir("idle");
+        let mut credentials = vault_in(&dir, 1);
+        credentials.unlock("passphrase").unwrap();
+        assert!(!credentials.lock_if_idle());
+
+        credentials.unlocked.as_mut().unwrap().last_used -= Duration::from_secs(61);
+        assert!(credentials.lock_if_idle());
+        assert!(credentials.is_locked());
+
+        // 0 never locks on its own
+        let mut credentials = vault_in(&dir, 0);
+        credentials.unlock("passphrase").unwrap();
+        credentials.unlocked.as_mut().unwrap().last_used -= Duration::from_secs(61);
+        assert!(!credentials.lock_if_idle());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,306 @@
+//! Installing a public key on servers, as ssh-copy-id does: log in the way
+//! each server is configured, append the key to ~/.ssh/authorized_keys
+//! unless it's already there, then log in again with only that key to
+//! prove it's accepted. Every server of a batch is done at once.
+
+use crate::models::{AuthMethod, ServerConnection};
+use crate::native::{self, NeedsSecret, Secrets};
+use anyhow::{anyhow, bail, Result};
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+use zeroize::Zeroizing;
+
+/// What the script prints once authorized_keys holds the key
+const ADDED: &str = "ghost-key added";
+const PRESENT: &str = "ghost-key present";
+/// Followed by the line that has the key behind options
+const RESTRICTED: &str = "ghost-key restricted";
+
+/// The key being installed, and what's needed to log in with it
+#[derive(Debug, Clone)]
+pub struct InstallKey {
+    pub name: String,
+    /// Line appended to authorized_keys
+    pub line: String,
+    /// Public key blob, to find the key in the agent
+    pub public_key: Vec<u8>,
+    pub private_path: Option<PathBuf>,
+    /// For an encrypted private key the agent doesn't hold
+    pub passphrase: Option<Zeroizing<String>>,
+}
+
+impl InstallKey {
+    /// The private key's path as a server's `key_path`, `~/…` under home
+    pub fn config_path(&self) -> Option<String> {
+        let path = self.private_path.as_ref()?;
+        let home = dirs::home_dir();
+        Some(match home.as_ref().and_then(|home| path.strip_prefix(home).ok()) {
+            Some(relative) => format!("~/{}", relative.display()),
+            None => path.display().to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstallState {
+    Running,
+    /// Key login works; `added` is false if the key was already there
+    Installed { added: bool },
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct InstallJob {
+    pub id: u64,
+    pub batch: u64,
+    pub server: ServerConnection,
+    pub key_name: String,
+    /// The key as a `key_path`, for switching the server over to it
+    pub key_path: Option<String>,
+    pub state: InstallState,
+}
+
+/// Key installs of this run, most recent last
+#[derive(Debug)]
+pub struct KeyInstalls {
+    pub jobs: Vec<InstallJob>,
+    next_id: u64,
+    next_batch: u64,
+    tx: mpsc::UnboundedSender<(u64, Result<bool, String>)>,
+    rx: mpsc::UnboundedReceiver<(u64, Result<bool, String>)>,
+}
+
+impl Default for KeyInstalls {
+    fn default() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self { jobs: Vec::new(), next_id: 0, next_batch: 0, tx, rx }
+    }
+}
+
+impl KeyInstalls {
+    /// Install `key` on every server, each logging in with its secrets
+    pub fn start(&mut self, key: &InstallKey, servers: Vec<(ServerConnection, Secrets)>) {
+        let batch = self.next_batch;
+        self.next_batch += 1;
+        for (server, secrets) in servers {
+            let id = self.next_id;
+            self.next_id += 1;
+            self.jobs.push(InstallJob {
+                id,
+                batch,
+                server: server.clone(),
+                key_name: key.name.clone(),
+                key_path: key.config_path(),
+                state: InstallState::Running,
+            });
+            let (key, tx) = (key.clone(), self.tx.clone());
+            tokio::spawn(async move {
+                let result = install(&server, &secrets, &key).await.map_err(|e| format!("{:#}", e));
+                let _ = tx.send((id, result));
+            });
+        }
+    }
+
+    /// Apply finished installs. Returns the batches that just completed.
+    pub fn poll_events(&mut self) -> Vec<u64> {
+        let mut completed = Vec::new();
+        while let Ok((id, result)) = self.rx.try_recv() {
+            let Some(job) = self.jobs.iter_mut().find(|j| j.id == id) else {
+                continue;
+            };
+            job.state = match result {
+                Ok(added) => InstallState::Installed { added },
+                Err(e) => InstallState::Failed(e),
+            };
+            let batch = job.batch;
+            if self.batch(batch).all(|j| j.state != InstallState::Running) {
+                completed.push(batch);
+            }
+        }
+        completed
+    }
+
+    fn batch(&self, batch: u64) -> impl Iterator<Item = &InstallJob> {
+        self.jobs.iter().filter(move |j| j.batch == batch)
+    }
+
+    /// Per-server outcome of a batch, for a notification
+    pub fn batch_summary(&self, batch: u64) -> String {
+        let jobs: Vec<&InstallJob> = self.batch(batch).collect();
+        let Some(first) = jobs.first() else {
+            return String::new();
+        };
+        let failed: Vec<String> = jobs
+            .iter()
+            .filter_map(|j| match &j.state {
+                InstallState::Failed(e) => Some(format!("{}: {}", j.server.name, e)),
+                _ => None,
+            })
+            .collect();
+        let mut summary = format!(
+            "{} installed on {}/{} server{}",
+            first.key_name,
+            jobs.len() - failed.len(),
+            jobs.len(),
+            if jobs.len() == 1 { "" } else { "s" }
+        );
+        for line in failed {
+            summary.push_str("\n✗ ");
+            summary.push_str(&line);
+        }
+        summary
+    }
+
+    /// Servers of `batch` that now take the key but are set up to log in
+    /// some other way, with the `key_path` to switch them to
+    pub fn switchable(&self, batch: u64) -> Vec<(ServerConnection, String)> {
+        self.batch(batch)
+            .filter(|j| matches!(j.state, InstallState::Installed { .. }))
+            .filter_map(|j| {
+                let key_path = j.key_path.clone()?;
+                let uses_key = matches!(&j.server.auth_method, AuthMethod::PublicKey { key_path: current }
+                    if Path::new(shellexpand::tilde(current).as_ref()) == Path::new(shellexpand::tilde(&key_path).as_ref()));
+                (!uses_key).then(|| (j.server.clone(), key_path))
+            })
+            .collect()
+    }
+}
+
+/// Add the key to authorized_keys on `server` and check that it logs in.
+/// Returns whether the key had to be added.
+pub async fn install(server: &ServerConnection, secrets: &Secrets, key: &InstallKey) -> Result<bool> {
+    let ssh = native::connect(server, secrets).await.map_err(|e| match e.downcast_ref::<NeedsSecret>() {
+        Some(NeedsSecret::Password) => anyhow!("No stored password to log in with - store it first"),
+        _ => e,
+    })?;
+    let output = native::run_script(&ssh, &install_script(&key.line)).await?;
+    let added = if output.contains(ADDED) {
+        true
+    } else if output.contains(PRESENT) {
+        false
+    } else if let Some((_, line)) = output.split_once(RESTRICTED) {
+        bail!(
+            "authorized_keys line {} already has this key with options in front, and sshd goes by that line - edit or remove it first",
+            line.trim()
+        );
+    } else {
+        bail!("Couldn't update ~/.ssh/authorized_keys: {}", output.trim());
+    };
+
+    let passphrase = key.passphrase.as_ref().map(|p| p.as_str());
+    native::connect_with_key(server, &key.public_key, key.private_path.as_deref(), passphrase)
+        .await
+        .map_err(|e| anyhow!("Key is in authorized_keys, but logging in with it failed: {:#}", e))?;
+    Ok(added)
+}
+
+/// Creates ~/.ssh (700) and authorized_keys (600) as needed and appends
+/// `line` unless an uncommented line already has its key. sshd goes by the
+/// first such line, so when that one has options in front (`restrict,…
+/// ssh-ed25519 …`) nothing is appended and its line number is reported
+/// instead. A last line without a newline gets one first, so the key
+/// doesn't end up glued to it.
+fn install_script(line: &str) -> String {
+    let blob = line.split_whitespace().nth(1).unwrap_or(line);
+    format!(
+        r#"umask 077
+mkdir -p ~/.ssh && chmod 700 ~/.ssh || exit 1
+keys=~/.ssh/authorized_keys
+touch "$keys" && chmod 600 "$keys" || exit 1
+match=$(awk -v key={blob} '$1 !~ /^#/ {{ for (i = 2; i <= NF; i++) if ($i == key) {{ print (i == 2 ? "plain" : "options"), NR; exit }} }}' "$keys")
+case "$match" in
+plain*) echo '{present}' ;;
+options*) echo '{restricted}' "${{match#options }}" ;;
+*)
+    if [ -s "$keys" ] && [ -n "$(tail -c 1 "$keys")" ]; then echo >> "$keys"; fi
+    printf '%s\n' {line} >> "$keys" && echo '{added}'
+    ;;
+esac
+"#,
+        blob = quote(blob),
+        line = quote(line),
+        present = PRESENT,
+        restricted = RESTRICTED,
+        added = ADDED,
+    )
+}
+
+/// Single-quote `text` for sh
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn script_adds_the_key_once_with_tight_modes() {
+        use std::io::Write;
+        use std::os::unix::fs::PermissionsExt;
+        use std::process::{Command, Stdio};
+
+        let home = std::env::temp_dir().join(format!("ghost-deploy-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&home).unwrap();
+        let run = |line: &str| {
+            let mut sh = Command::new("sh")
+                .arg("-s")
+                .env("HOME", &home)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()
+                .unwrap();
+            sh.stdin.take().unwrap().write_all(install_script(line).as_bytes()).unwrap();
+            String::from_utf8(sh.wait_with_output().unwrap().stdout).unwrap()
+        };
+        let line = "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIKz9P9d1fuyqy me's laptop";
+
+        assert_eq!(run(line).trim(), ADDED);
+        let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        let keys = home.join(".ssh/authorized_keys");
+        assert_eq!((mode(&home.join(".ssh")), mode(&keys)), (0o700, 0o600));
+        assert_eq!(run(line).trim(), PRESENT);
+
+        // A disabled copy isn't the key being installed; a restricted one
+        // would still win over an appended copy, so it's reported
+        let blob = line.split_whitespace().nth(1).unwrap();
+        std::fs::write(&keys, format!("# {line}\n")).unwrap();
+        assert_eq!(run(line).trim(), ADDED);
+        let restricted = format!("# {line}\nrestrict,command=\"echo hi\" ssh-ed25519 {blob}\n{line}\n");
+        std::fs::write(&keys, &restricted).unwrap();
+        assert_eq!(run(line).trim(), format!("{} 2", RESTRICTED));
+        assert_eq!(std::fs::read_to_string(&keys).unwrap(), restricted);
+        std::fs::write(&keys, format!("{line}\nrestrict ssh-ed25519 {blob}\n")).unwrap();
+        assert_eq!(run(line).trim(), PRESENT);
+
+        // An existing last line without a newline stays on its own line
+        std::fs::write(&keys, "ssh-rsa AAAAB3Nza old").unwrap();
+        assert_eq!(run(line).trim(), ADDED);
+        let contents = std::fs::read_to_string(&keys).unwrap();
+        assert_eq!(contents, format!("ssh-rsa AAAAB3Nza old\n{}\n", line));
+        std::fs::remove_dir_all(&home).unwrap();
+    }
+
+    #[test]
+    fn only_installed_servers_not_using_the_key_can_switch() {
+        let mut installs = KeyInstalls::default();
+        let server = |name: &str, auth_method| ServerConnection {
+            auth_method,
+            ..ServerConnection::new(name.to_string(), "example.com".to_string(), 22, "me".to_string())
+        };
+        let key_path = "~/.ssh/id_ed25519".to_string();
+        let servers = [
+            (server("web-1", AuthMethod::Password), InstallState::Installed { added: true }),
+            (server("web-2", AuthMethod::PublicKey { key_path: key_path.clone() }), InstallState::Installed { added: false }),
+            (server("web-3", AuthMethod::Agent), InstallState::Failed("refused".to_string())),
+        ];
+        for (id, (server, state)) in servers.into_iter().enumerate() {
+            let key_name = "id_ed25519".to_string();
+            installs.jobs.push(InstallJob { id: id as u64, batch: 0, server, key_name, key_path: Some(key_path.clone()), state });
+        }
+        let switchable: Vec<String> = installs.switchable(0).into_iter().map(|(s, _)| s.name).collect();
+        assert_eq!(switchable, ["web-1"]);
+        assert_eq!(installs.batch_summary(0), "id_ed25519 installed on 2/3 servers\n✗ web-3: refused");
+    }
+}
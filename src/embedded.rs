@@ -0,0 +1,370 @@
+//! Embedded terminal sessions: ssh runs under a pseudo-terminal owned by
+//! Ghost (or a shell is opened over a native connection), its output is fed
+//! through a VT100 emulator and the resulting screen is drawn in a pane
+//! next to the server list.
+
+use crate::native::{NativeShell, Session};
+use anyhow::{Context, Result};
+use crossterm::event::{KeyCode, KeyModifiers};
+use portable_pty::{native_pty_system, Child, CommandBuilder, MasterPty, PtySize};
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Lines of history kept per session
+const SCROLLBACK: usize = 1000;
+/// Native sessions have no process; they're numbered from here, above any
+/// real pid, so they can be tracked and killed like the others
+const NATIVE_PID_BASE: u32 = 0x4000_0000;
+
+static NEXT_NATIVE_PID: AtomicU32 = AtomicU32::new(NATIVE_PID_BASE);
+
+/// One ssh session running in a Ghost tab
+pub struct EmbeddedSession {
+    pub server_id: String,
+    pub server_name: String,
+    pub pid: u32,
+    parser: Arc<Mutex<vt100::Parser>>,
+    backend: Backend,
+    size: (u16, u16),
+}
+
+/// Where the session's terminal lives
+enum Backend {
+    /// ssh on a pseudo-terminal
+    Pty {
+        master: Box<dyn MasterPty + Send>,
+        writer: Box<dyn Write + Send>,
+        child: Box<dyn Child + Send + Sync>,
+    },
+    /// A shell over Ghost's own SSH client
+    Native(NativeShell),
+}
+
+impl std::fmt::Debug for EmbeddedSession {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EmbeddedSession")
+            .field("server_name", &self.server_name)
+            .field("pid", &self.pid)
+            .field("size", &self.size)
+            .finish()
+    }
+}
+
+impl EmbeddedSession {
+    /// Spawn `program args` on a new `rows` x `cols` pseudo-terminal. Output
+    /// is read on a background thread so the UI never blocks on the pty.
+    pub fn spawn(
+        server_id: String,
+        server_name: String,
+        program: &str,
+        args: &[String],
+        rows: u16,
+        cols: u16,
+    ) -> Result<Self> {
+        let (rows, cols) = (rows.max(1), cols.max(1));
+        let pair = native_pty_system()
+            .openpty(PtySize { rows, cols, pixel_width: 0, pixel_height: 0 })
+            .context("Failed to open a pseudo-terminal")?;
+
+        let mut command = CommandBuilder::new(program);
+        command.args(args);
+        if let Ok(cwd) = std::env::current_dir() {
+            command.cwd(cwd);
+        }
+        let child = pair
+            .slave
+            .spawn_command(command)
+            .with_context(|| format!("Failed to spawn {}", program))?;
+        // Only the child keeps the slave side open, so reads see EOF once it exits
+        drop(pair.slave);
+
+        let pid = child.process_id().unwrap_or_default();
+        let mut reader = pair.master.try_clone_reader().context("Failed to read from the pseudo-terminal")?;
+        let writer = pair.master.take_writer().context("Failed to write to the pseudo-terminal")?;
+
+        let parser = Arc::new(Mutex::new(vt100::Parser::new(rows, cols, SCROLLBACK)));
+        let output = Arc::clone(&parser);
+        std::thread::spawn(move || {
+            let mut buf = [0u8; 8192];
+            while let Ok(n) = reader.read(&mut buf) {
+                if n == 0 {
+                    break;
+                }
+                if let Ok(mut parser) = output.lock() {
+                    parser.process(&buf[..n]);
+                }
+            }
+        });
+
+        Ok(Self {
+            server_id,
+            server_name,
+            pid,
+            parser,
+            backend: Backend::Pty { master: pair.master, writer, child },
+            size: (rows, cols),
+        })
+    }
+
+    /// Open a shell on a `rows` x `cols` pty over the logged-in `ssh`
+    pub async fn native(server_id: String, server_name: String, ssh: Session, rows: u16, cols: u16) -> Result<Self> {
+        let (rows, cols) = (rows.max(1), cols.max(1));
+        let parser = Arc::new(Mutex::new(vt100::Parser::new(rows, cols, SCROLLBACK)));
+        let output = Arc::clone(&parser);
+        let shell = NativeShell::open(ssh, rows, cols, move |bytes| {
+            if let Ok(mut parser) = output.lock() {
+                parser.process(bytes);
+            }
+        })
+        .await?;
+
+        Ok(Self {
+            server_id,
+            server_name,
+            pid: NEXT_NATIVE_PID.fetch_add(1, Ordering::Relaxed),
+            parser,
+            backend: Backend::Native(shell),
+            size: (rows, cols),
+        })
+    }
+
+    /// The emulated screen, for drawing
+    pub fn parser(&self) -> MutexGuard<'_, vt100::Parser> {
+        self.parser.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Resize the pty and the emulator to the pane they're drawn in
+    pub fn resize(&mut self, rows: u16, cols: u16) {
+        let (rows, cols) = (rows.max(1), cols.max(1));
+        if self.size == (rows, cols) {
+            return;
+        }
+        self.size = (rows, cols);
+        match &self.backend {
+            Backend::Pty { master, .. } => {
+                let _ = master.resize(PtySize { rows, cols, pixel_width: 0, pixel_height: 0 });
+            }
+            Backend::Native(shell) => shell.resize(rows, cols),
+        }
+        self.parser().set_size(rows, cols);
+    }
+
+    /// Send a key press to the remote side
+    pub fn send_key(&mut self, code: KeyCode, modifiers: KeyModifiers) {
+        let application_cursor = self.parser().screen().application_cursor();
+        let Some(bytes) = key_to_bytes(code, modifiers, application_cursor) else {
+            return;
+        };
+        match &mut self.backend {
+            Backend::Pty { writer, .. } => {
+                let _ = writer.write_all(&bytes);
+                let _ = writer.flush();
+            }
+            Backend::Native(shell) => shell.send(bytes),
+        }
+    }
+
+    /// Exit code once the process has ended (and been reaped), or the
+    /// native shell has closed
+    pub fn try_exit(&mut self) -> Option<u32> {
+        let child = match &mut self.backend {
+            Backend::Pty { child, .. } => child,
+            Backend::Native(shell) => return shell.try_exit(),
+        };
+        match child.try_wait() {
+            Ok(Some(status)) => Some(status.exit_code()),
+            Ok(None) => None,
+            // The child is gone if it can't even be waited on
+            Err(_) => Some(1),
+        }
+    }
+
+    pub fn kill(&mut self) {
+        match &mut self.backend {
+            Backend::Pty { child, .. } => {
+                let _ = child.kill();
+            }
+            Backend::Native(shell) => shell.close(),
+        }
+    }
+
+    /// Whether this tab runs over Ghost's own SSH client
+    pub fn is_native(&self) -> bool {
+        matches!(self.backend, Backend::Native(_))
+    }
+
+    /// Window title set by the remote side, or the server name
+    pub fn title(&self) -> String {
+        let title = self.parser().screen().title().to_string();
+        if title.is_empty() {
+            self.server_name.clone()
+        } else {
+            title
+        }
+    }
+}
+
+/// Embedded sessions shown as tabs
+#[derive(Debug, Default)]
+pub struct TerminalTabs {
+    pub sessions: Vec<EmbeddedSession>,
+    pub active: usize,
+}
+
+impl TerminalTabs {
+    pub fn active_mut(&mut self) -> Option<&mut EmbeddedSession> {
+        self.sessions.get_mut(self.active)
+    }
+
+    pub fn push(&mut self, session: EmbeddedSession) {
+        self.sessions.push(session);
+        self.active = self.sessions.len() - 1;
+    }
+
+    /// Move to the next (`forward`) or previous tab, wrapping around
+    pub fn switch(&mut self, forward: bool) {
+        let count = self.sessions.len();
+        if count > 0 {
+            self.active = if forward { (self.active + 1) % count } else { (self.active + count - 1) % count };
+        }
+    }
+
+    /// Remove and return sessions whose process has ended, keeping the
+    /// active tab on the same session where possible.
+    pub fn reap(&mut self) -> Vec<(EmbeddedSession, u32)> {
+        let mut ended = Vec::new();
+        let mut i = 0;
+        while i < self.sessions.len() {
+            if let Some(code) = self.sessions[i].try_exit() {
+                ended.push((self.sessions.remove(i), code));
+                if self.active > i {
+                    self.active -= 1;
+                }
+            } else {
+                i += 1;
+            }
+        }
+        self.active = self.active.min(self.sessions.len().saturating_sub(1));
+        ended
+    }
+
+    /// Kill the session with this pid, if it's one of ours
+    pub fn kill(&mut self, pid: u32) -> bool {
+        match self.sessions.iter_mut().find(|s| s.pid == pid) {
+            Some(session) => {
+                session.kill();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Encode a key press the way a terminal would send it to the program
+/// running in it. `application_cursor` selects the `ESC O` arrow keys some
+/// full-screen programs switch to.
+pub fn key_to_bytes(code: KeyCode, modifiers: KeyModifiers, application_cursor: bool) -> Option<Vec<u8>> {
+    let arrow = |c: u8| {
+        if application_cursor {
+            vec![0x1b, b'O', c]
+        } else {
+            vec![0x1b, b'[', c]
+        }
+    };
+    let tilde = |n: &str| format!("\x1b[{}~", n).into_bytes();
+
+    let bytes = match code {
+        KeyCode::Char(c) if modifiers.contains(KeyModifiers::CONTROL) => {
+            let byte = match c.to_ascii_lowercase() {
+                c @ 'a'..='z' => c as u8 - b'a' + 1,
+                '@' | ' ' | '2' => 0,
+                '[' | '3' => 0x1b,
+                '\\' | '4' => 0x1c,
+                ']' | '5' => 0x1d,
+                '^' | '6' => 0x1e,
+                '_' | '7' | '-' => 0x1f,
+                _ => return None,
+            };
+            vec![byte]
+        }
+        KeyCode::Char(c) => {
+            let mut bytes = Vec::with_capacity(5);
+            if modifiers.contains(KeyModifiers::ALT) {
+                bytes.push(0x1b);
+            }
+            bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+            bytes
+        }
+        KeyCode::Enter => vec![b'\r'],
+        KeyCode::Backspace => vec![0x7f],
+        KeyCode::Tab => vec![b'\t'],
+        KeyCode::BackTab => b"\x1b[Z".to_vec(),
+        KeyCode::Esc => vec![0x1b],
+        KeyCode::Up => arrow(b'A'),
+        KeyCode::Down => arrow(b'B'),
+        KeyCode::Right => arrow(b'C'),
+        KeyCode::Left => arrow(b'D'),
+        KeyCode::Home => arrow(b'H'),
+        KeyCode::End => arrow(b'F'),
+        KeyCode::Insert => tilde("2"),
+        KeyCode::Delete => tilde("3"),
+        KeyCode::PageUp => tilde("5"),
+        KeyCode::PageDown => tilde("6"),
+        KeyCode::F(n @ 1..=4) => vec![0x1b, b'O', b'P' + (n - 1)],
+        KeyCode::F(n) => {
+            let code = match n {
+                5 => "15",
+                6 => "17",
+                7 => "18",
+                8 => "19",
+                9 => "20",
+                10 => "21",
+                11 => "23",
+                12 => "24",
+                _ => return None,
+            };
+            tilde(code)
+        }
+        _ => return None,
+    };
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_encoded_like_a_terminal() {
+        let none = KeyModifiers::NONE;
+        assert_eq!(key_to_bytes(KeyCode::Char('a'), none, false), Some(b"a".to_vec()));
+        assert_eq!(key_to_bytes(KeyCode::Char('c'), KeyModifiers::CONTROL, false), Some(vec![3]));
+        assert_eq!(key_to_bytes(KeyCode::Char('b'), KeyModifiers::ALT, false), Some(b"\x1bb".to_vec()));
+        assert_eq!(key_to_bytes(KeyCode::Char('é'), none, false), Some("é".as_bytes().to_vec()));
+        assert_eq!(key_to_bytes(KeyCode::Up, none, false), Some(b"\x1b[A".to_vec()));
+        assert_eq!(key_to_bytes(KeyCode::Up, none, true), Some(b"\x1bOA".to_vec()));
+        assert_eq!(key_to_bytes(KeyCode::F(2), none, false), Some(b"\x1bOQ".to_vec()));
+        assert_eq!(key_to_bytes(KeyCode::F(5), none, false), Some(b"\x1b[15~".to_vec()));
+        assert_eq!(key_to_bytes(KeyCode::Enter, none, false), Some(b"\r".to_vec()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn output_reaches_the_emulator() {
+        let args = vec!["-c".to_string(), "printf 'hello from pty'".to_string()];
+        let mut session = EmbeddedSession::spawn("id".into(), "local".into(), "sh", &args, 5, 40).unwrap();
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while session.try_exit().is_none() && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        // The reader thread may still be draining the last chunk
+        while !session.parser().screen().contents().contains("hello")
+            && std::time::Instant::now() < deadline
+        {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        assert!(session.parser().screen().contents().contains("hello from pty"));
+    }
+}
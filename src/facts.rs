@@ -0,0 +1,209 @@
+//! Host facts gathered by the deep health check: one small `sh` script run
+//! over a native login, printing `key value` lines read from /proc, `df`
+//! and `uname`. Facts a host can't report (no /proc, say) are left out
+//! rather than failing the check.
+
+use crate::config::DeepCheckConfig;
+use crate::native::{self, Session};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Prints one fact per line. Debian and Ubuntu flag a pending reboot with a
+/// file; Red Hat and friends answer through `needs-restarting`.
+const SCRIPT: &str = r#"
+echo "uptime $(cut -d' ' -f1 /proc/uptime 2>/dev/null)"
+echo "load $(cut -d' ' -f1-3 /proc/loadavg 2>/dev/null)"
+echo "cpus $(getconf _NPROCESSORS_ONLN 2>/dev/null)"
+grep -E '^(MemTotal|MemAvailable):' /proc/meminfo 2>/dev/null
+echo "disk $(df -Pk / 2>/dev/null | tail -n 1)"
+echo "kernel $(uname -r)"
+if [ -f /var/run/reboot-required ]; then
+    echo 'reboot yes'
+elif command -v needs-restarting >/dev/null 2>&1; then
+    if needs-restarting -r >/dev/null 2>&1; then echo 'reboot no'; else echo 'reboot yes'; fi
+elif command -v dpkg >/dev/null 2>&1; then
+    echo 'reboot no'
+fi
+"#;
+
+/// Used and total amount of memory or disk space, in KiB
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Usage {
+    pub used: u64,
+    pub total: u64,
+}
+
+impl Usage {
+    pub fn percent(&self) -> f32 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.used as f32 * 100.0 / self.total as f32
+        }
+    }
+}
+
+/// A fact that went over its limit in `DeepCheckConfig`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Fact {
+    Load,
+    Memory,
+    Disk,
+    Reboot,
+}
+
+/// What the deep check learned about a server
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HostFacts {
+    pub uptime: Option<Duration>,
+    /// 1, 5 and 15 minute load averages
+    pub load: Option<[f32; 3]>,
+    pub cpus: Option<u32>,
+    pub memory: Option<Usage>,
+    /// The `/` filesystem
+    pub disk: Option<Usage>,
+    pub kernel: Option<String>,
+    pub reboot_required: Option<bool>,
+    /// Facts over their limit when collected
+    pub alerts: Vec<Fact>,
+}
+
+impl HostFacts {
+    /// Run the fact script over `ssh` and flag what's over `limits`
+    pub async fn collect(ssh: &Session, limits: &DeepCheckConfig) -> Result<Self> {
+        let output = native::run_script(ssh, SCRIPT).await?;
+        let mut facts = Self::parse(&output);
+        facts.alerts = facts.over(limits);
+        Ok(facts)
+    }
+
+    fn parse(output: &str) -> Self {
+        let mut facts = Self::default();
+        let (mut mem_total, mut mem_available) = (None, None);
+        for line in output.lines() {
+            let mut words = line.split_whitespace();
+            let Some(key) = words.next() else { continue };
+            let values: Vec<&str> = words.collect();
+            let number = |i: usize| values.get(i).and_then(|v| v.parse::<u64>().ok());
+            match key {
+                "uptime" => {
+                    facts.uptime = values
+                        .first()
+                        .and_then(|v| v.parse::<f64>().ok())
+                        .and_then(|secs| Duration::try_from_secs_f64(secs).ok());
+                }
+                "load" => {
+                    let loads: Vec<f32> = values.iter().filter_map(|v| v.parse().ok()).collect();
+                    facts.load = <[f32; 3]>::try_from(loads).ok();
+                }
+                "cpus" => facts.cpus = values.first().and_then(|v| v.parse().ok()),
+                "MemTotal:" => mem_total = number(0),
+                "MemAvailable:" => mem_available = number(0),
+                // Filesystem 1024-blocks Used Available Capacity Mounted-on;
+                // like df's own percentage, reserved blocks don't count
+                "disk" => {
+                    if let (Some(used), Some(available)) = (number(2), number(3)) {
+                        facts.disk = Some(Usage { used, total: used + available });
+                    }
+                }
+                "kernel" => facts.kernel = values.first().map(|v| v.to_string()),
+                "reboot" => facts.reboot_required = values.first().map(|v| *v == "yes"),
+                _ => {}
+            }
+        }
+        if let (Some(total), Some(available)) = (mem_total, mem_available) {
+            facts.memory = Some(Usage { used: total.saturating_sub(available), total });
+        }
+        facts
+    }
+
+    /// 1-minute load average per CPU
+    pub fn load_per_cpu(&self) -> Option<f32> {
+        let load = self.load?[0];
+        Some(load / self.cpus.unwrap_or(1).max(1) as f32)
+    }
+
+    fn over(&self, limits: &DeepCheckConfig) -> Vec<Fact> {
+        let over = |value: Option<f32>, limit: f32| value.is_some_and(|value| value > limit);
+        let mut alerts = Vec::new();
+        if over(self.load_per_cpu(), limits.max_load_per_cpu) {
+            alerts.push(Fact::Load);
+        }
+        if over(self.memory.map(|m| m.percent()), limits.max_memory_percent) {
+            alerts.push(Fact::Memory);
+        }
+        if over(self.disk.map(|d| d.percent()), limits.max_disk_percent) {
+            alerts.push(Fact::Disk);
+        }
+        if limits.warn_on_reboot && self.reboot_required == Some(true) {
+            alerts.push(Fact::Reboot);
+        }
+        alerts
+    }
+
+    pub fn is_alert(&self, fact: Fact) -> bool {
+        self.alerts.contains(&fact)
+    }
+
+    /// The alerts in words, for the server's warning
+    pub fn alert_summary(&self) -> Option<String> {
+        let parts: Vec<String> = self
+            .alerts
+            .iter()
+            .map(|fact| match fact {
+                Fact::Load => format!("load {:.2} per CPU", self.load_per_cpu().unwrap_or_default()),
+                Fact::Memory => format!("memory {:.0}% used", self.memory.map(|m| m.percent()).unwrap_or_default()),
+                Fact::Disk => format!("/ {:.0}% full", self.disk.map(|d| d.percent()).unwrap_or_default()),
+                Fact::Reboot => "reboot required".to_string(),
+            })
+            .collect();
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = "\
+uptime 273845.12
+load 3.50 1.20 0.80
+cpus 2
+MemTotal:        8000000 kB
+MemAvailable:     400000 kB
+disk /dev/sda1 100000000 45000000 50000000 48% /
+kernel 6.1.0-18-amd64
+reboot yes
+";
+
+    #[test]
+    fn script_output_becomes_facts() {
+        let facts = HostFacts::parse(OUTPUT);
+        assert_eq!(facts.uptime.map(|u| u.as_secs()), Some(273845));
+        assert_eq!(facts.load, Some([3.5, 1.2, 0.8]));
+        assert_eq!(facts.load_per_cpu(), Some(1.75));
+        assert_eq!(facts.memory, Some(Usage { used: 7_600_000, total: 8_000_000 }));
+        assert_eq!(facts.disk.map(|d| d.percent().round()), Some(47.0));
+        assert_eq!(facts.kernel.as_deref(), Some("6.1.0-18-amd64"));
+        assert_eq!(facts.reboot_required, Some(true));
+
+        // A host without /proc or a known package manager reports less
+        let bare = HostFacts::parse("uptime \nload \ncpus \ndisk \nkernel 14.0-RELEASE\n");
+        assert_eq!(bare, HostFacts { kernel: Some("14.0-RELEASE".to_string()), ..HostFacts::default() });
+    }
+
+    #[test]
+    fn facts_over_their_limit_raise_alerts() {
+        let mut facts = HostFacts::parse(OUTPUT);
+        let limits = DeepCheckConfig::default();
+        facts.alerts = facts.over(&limits);
+        assert_eq!(facts.alerts, [Fact::Memory, Fact::Reboot]);
+        assert_eq!(facts.alert_summary().as_deref(), Some("memory 95% used, reboot required"));
+
+        let strict = DeepCheckConfig { max_load_per_cpu: 1.0, max_disk_percent: 40.0, warn_on_reboot: false, ..limits };
+        assert_eq!(facts.over(&strict), [Fact::Load, Fact::Memory, Fact::Disk]);
+        facts.alerts.clear();
+        assert_eq!(facts.alert_summary(), None);
+    }
+}
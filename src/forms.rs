@@ -61,6 +61,12 @@ impl InputField {
         }
     }
 
+    /// Place the cursor before the `position`th character (a clicked
+    /// column), clamped to the end of the value
+    pub fn set_cursor(&mut self, position: usize) {
+        self.cursor_position = self.value.char_indices().nth(position).map_or(self.value.len(), |(i, _)| i);
+    }
+
     pub fn move_cursor_to_start(&mut self) {
         self.cursor_position = 0;
     }
@@ -141,6 +147,14 @@ impl From<&AuthMethod> for AuthMethodSelection {
     }
 }
 
+/// A focusable element of the server form
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormTarget {
+    /// Input field by index; `fields.len()` is the tags field
+    Field(usize),
+    AuthMethod,
+}
+
 /// Server form state for adding/editing servers
 #[derive(Debug, Clone)]
 pub struct ServerForm {
@@ -268,6 +282,21 @@ impl ServerForm {
         self.update_focus();
     }
 
+    /// Focus the element at `target` (e.g. after a mouse click)
+    pub fn focus(&mut self, target: FormTarget) {
+        match target {
+            FormTarget::Field(index) => {
+                self.auth_method_focused = false;
+                self.current_field = index.min(self.fields.len());
+            }
+            FormTarget::AuthMethod => {
+                self.auth_method_focused = true;
+                self.current_field = 0;
+            }
+        }
+        self.update_focus();
+    }
+
     /// Update field focus states
     fn update_focus(&mut self) {
         for (i, field) in self.fields.iter_mut().enumerate() {
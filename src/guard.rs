@@ -0,0 +1,112 @@
+//! Typed confirmation for protected servers (`protected = true`, or
+//! `environment = "prod"`). Connecting to one, deleting it, or a transfer or
+//! key install that includes it only goes ahead once its name is typed, so
+//! a stray quick-connect digit can't open a production shell.
+
+use crate::deploy::InstallKey;
+use crate::forms::InputField;
+use crate::models::{AppMode, ServerConnection};
+use crate::native::Secrets;
+use crate::transfers::TransferKind;
+
+/// What's held back until the name is typed
+#[derive(Debug)]
+pub enum Guarded {
+    Connect(String),
+    Delete(String),
+    Transfer(TransferKind, Vec<(ServerConnection, Secrets)>),
+    InstallKey(InstallKey, Vec<(ServerConnection, Secrets)>),
+    /// Open the file browser on the server with this id
+    OpenSftp(String),
+    /// Run these failed transfer jobs again
+    Retry(Vec<u64>),
+}
+
+#[derive(Debug)]
+pub struct NameConfirm {
+    pub action: Guarded,
+    /// The protected servers among those the action touches
+    pub servers: Vec<String>,
+    pub input: InputField,
+    /// Where Esc goes back to
+    pub previous: AppMode,
+    /// Enter was pressed with the wrong text
+    pub mismatch: bool,
+}
+
+impl NameConfirm {
+    /// Hold `action` back until the `servers` it touches are confirmed
+    pub fn new(action: Guarded, servers: Vec<String>, previous: AppMode) -> Self {
+        let mut input = InputField::new("Confirm", "");
+        input.is_focused = true;
+        Self { action, servers, input, previous, mismatch: false }
+    }
+
+    /// The server's name, or with several protected servers how many there
+    /// are, as typing every name would be more chore than check
+    pub fn expected(&self) -> String {
+        match self.servers.as_slice() {
+            [name] => name.clone(),
+            servers => servers.len().to_string(),
+        }
+    }
+
+    pub fn matches(&self) -> bool {
+        self.input.value.trim() == self.expected()
+    }
+
+    /// E.g. "Connect to", for "Connect to prod-db?"
+    pub fn verb(&self) -> &'static str {
+        match &self.action {
+            Guarded::Connect(_) => "Connect to",
+            Guarded::Delete(_) => "Delete",
+            Guarded::Transfer(TransferKind::Send { .. }, _) => "Send files to",
+            Guarded::Transfer(TransferKind::Fetch { .. }, _) => "Fetch files from",
+            Guarded::InstallKey(..) => "Install a key on",
+            Guarded::OpenSftp(_) => "Browse files on",
+            Guarded::Retry(_) => "Retry transfers with",
+        }
+    }
+}
+
+/// Names of the protected servers among `servers`, sorted; empty if the
+/// action needs no confirmation
+pub fn protected_names<'a>(servers: impl IntoIterator<Item = &'a ServerConnection>) -> Vec<String> {
+    let mut names: Vec<String> = servers.into_iter().filter(|s| s.is_protected()).map(|s| s.name.clone()).collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Environment;
+
+    #[test]
+    fn asks_for_the_name_or_the_count_of_protected_servers() {
+        let server = |name: &str| ServerConnection::new(name.to_string(), "example.com".to_string(), 22, "me".to_string());
+        let prod = ServerConnection { environment: Some(Environment::Prod), ..server("prod-db") };
+        let staging = ServerConnection { environment: Some(Environment::Staging), ..server("staging") };
+        let locked = ServerConnection { protected: true, ..server("billing") };
+
+        assert!(protected_names([&staging]).is_empty());
+        let mut confirm = NameConfirm::new(Guarded::Connect(prod.id.clone()), protected_names([&prod]), AppMode::Normal);
+        confirm.input.value = "prod-d".to_string();
+        assert!(!confirm.matches());
+        confirm.input.value = "prod-db ".to_string();
+        assert!(confirm.matches());
+
+        // Retrying several jobs for one server asks for its name, not a count
+        assert_eq!(protected_names([&prod, &prod]), ["prod-db"]);
+
+        let send = TransferKind::Send { local: "a".to_string(), remote: "a".to_string() };
+        let servers = vec![prod, staging, locked];
+        let protected = protected_names(&servers);
+        let servers = servers.into_iter().map(|s| (s, Secrets::default())).collect();
+        let confirm = NameConfirm::new(Guarded::Transfer(send, servers), protected, AppMode::TransferForm);
+        assert_eq!(confirm.servers, ["billing", "prod-db"]);
+        assert_eq!(confirm.expected(), "2");
+        assert_eq!(confirm.verb(), "Send files to");
+    }
+}
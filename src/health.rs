@@ -1,5 +1,9 @@
-use crate::models::{AuthStrength, HealthStatus, ServerConnection};
-use crate::ssh::{ConnectionMode, ConnectionTestResult, SSHManager};
+use crate::askpass::Askpass;
+use crate::embedded::EmbeddedSession;
+use crate::config::{DeepCheckConfig, TerminalConfig};
+use crate::models::{AuthStrength, HealthStatus, ServerConnection, SessionKind};
+use crate::native::Secrets;
+use crate::ssh::{ConnectionMode, ConnectionTestResult, SSHManager, SessionPid};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::{mpsc, RwLock};
@@ -19,14 +23,19 @@ pub struct HealthMonitor {
 pub struct HealthUpdate {
     pub server_id: String,
     pub result: ConnectionTestResult,
+    /// Part of a refresh the user asked for, rather than a background check
+    pub requested: bool,
 }
 
 impl HealthMonitor {
-    pub fn new(check_interval_seconds: u64) -> Self {
+    pub fn new(check_interval_seconds: u64, terminal: TerminalConfig, verify_login: bool, deep_check: DeepCheckConfig) -> Self {
         let (tx, rx) = mpsc::unbounded_channel();
+        let ssh_manager = SSHManager::with_terminal_config(terminal)
+            .verify_logins(verify_login)
+            .with_deep_check(deep_check);
         
         Self {
-            ssh_manager: Arc::new(RwLock::new(SSHManager::new())),
+            ssh_manager: Arc::new(RwLock::new(ssh_manager)),
             tx,
             rx: Arc::new(RwLock::new(rx)),
             check_interval: Duration::from_secs(check_interval_seconds),
@@ -54,18 +63,25 @@ impl HealthMonitor {
                         break;
                     }
 
-                    let ssh_manager = ssh_manager.read().await;
-                    let result = ssh_manager.quick_health_check(server).await
+                    // Checked on a copy of the settings, as a login can take
+                    // a while and connecting needs the manager meanwhile.
+                    // There are no passwords here; those logins come back
+                    // as needing one
+                    let check = ssh_manager.read().await.health_check();
+                    let result = check.quick_health_check(server, &Secrets::default()).await
                         .unwrap_or_else(|e| ConnectionTestResult {
                             status: HealthStatus::Unknown,
                             auth_strength: AuthStrength::Unknown,
                             latency: None,
                             error_message: Some(format!("Health check error: {}", e)),
+                            login: None,
+                            facts: None,
                         });
 
                     let update = HealthUpdate {
                         server_id: server.id.clone(),
                         result,
+                        requested: false,
                     };
 
                     if tx.send(update).is_err() {
@@ -88,24 +104,71 @@ impl HealthMonitor {
         rx.try_recv().ok()
     }
 
-    /// Perform immediate health check on a single server
-    pub async fn check_server_now(&self, server: &ServerConnection) -> ConnectionTestResult {
-        let ssh_manager = self.ssh_manager.read().await;
-        ssh_manager.quick_health_check(server).await
-            .unwrap_or_else(|e| ConnectionTestResult {
-                status: HealthStatus::Unknown,
-                auth_strength: AuthStrength::Unknown,
-                latency: None,
-                error_message: Some(format!("Immediate check error: {}", e)),
-            })
+    /// Check `servers` right away, each on its own task and with its own
+    /// secrets. Results come back through `try_recv_update`, marked as
+    /// requested, so the UI never waits on a slow server.
+    pub async fn check_servers_now(&self, servers: Vec<(ServerConnection, Secrets)>) {
+        let check = self.ssh_manager.read().await.health_check();
+        for (server, secrets) in servers {
+            let (check, tx) = (check.clone(), self.tx.clone());
+            tokio::spawn(async move {
+                let result = check.quick_health_check(&server, &secrets).await
+                    .unwrap_or_else(|e| ConnectionTestResult {
+                        status: HealthStatus::Unknown,
+                        auth_strength: AuthStrength::Unknown,
+                        latency: None,
+                        error_message: Some(format!("Immediate check error: {}", e)),
+                        login: None,
+                        facts: None,
+                    });
+                let _ = tx.send(HealthUpdate { server_id: server.id, result, requested: true });
+            });
+        }
+    }
+
+    /// Start an embedded ssh session, see `SSHManager::spawn_embedded`
+    pub async fn spawn_embedded(
+        &self,
+        server: &ServerConnection,
+        rows: u16,
+        cols: u16,
+        askpass: Option<&Askpass>,
+    ) -> Result<EmbeddedSession, String> {
+        let mut ssh_manager = self.ssh_manager.write().await;
+        ssh_manager.spawn_embedded(server, rows, cols, askpass)
+            .map_err(|e| format!("Connection failed: {}", e))
     }
 
+    /// Open a native embedded session, see `SSHManager::open_native`. The
+    /// login runs wherever the returned future is spawned, and the manager
+    /// is only locked to record the finished session. The error is kept
+    /// intact so a `NeedsSecret` can be asked for.
+    pub fn open_native(
+        &self,
+        server: ServerConnection,
+        rows: u16,
+        cols: u16,
+        secrets: Secrets,
+    ) -> impl std::future::Future<Output = anyhow::Result<EmbeddedSession>> + Send + 'static {
+        let ssh_manager = self.ssh_manager.clone();
+        async move {
+            let session = SSHManager::open_native(&server, rows, cols, &secrets).await?;
+            ssh_manager.write().await.mark_connected(&server.id);
+            Ok(session)
+        }
+    }
 
     /// Connect to server with specific connection mode
-    /// Returns the PID of the spawned terminal process
-    pub async fn connect_to_server_with_mode(&self, server: &ServerConnection, mode: ConnectionMode) -> Result<u32, String> {
+    /// Returns the PID of the ssh (or terminal) process and how it was opened
+    pub async fn connect_to_server_with_mode(
+        &self,
+        server: &ServerConnection,
+        mode: ConnectionMode,
+        askpass: Option<&Askpass>,
+        tracked: &[u32],
+    ) -> Result<(SessionPid, SessionKind), String> {
         let mut ssh_manager = self.ssh_manager.write().await;
-        ssh_manager.connect_with_mode(server, mode).await
+        ssh_manager.connect_with_mode(server, mode, askpass, tracked).await
             .map_err(|e| format!("Connection failed: {}", e))
     }
 
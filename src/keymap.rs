@@ -0,0 +1,915 @@
+//! Keybindings: every shortcut is an [`Action`] bound to one or more key
+//! sequences per [`KeyContext`]. Defaults live here; the `[keybindings]` table
+//! in config.toml replaces the bindings of individual actions:
+//!
+//! ```toml
+//! [keybindings.normal]
+//! connect = ["enter", "o"]
+//! delete_server = "g d"        # chord: g, then d
+//! kill_all_sessions = "ctrl+k"
+//!
+//! [keybindings.sessions]
+//! kill_session = []            # unbind
+//! ```
+
+use crate::config::{KeyList, KeybindingsConfig};
+use crossterm::event::{KeyCode, KeyModifiers};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Where a binding applies. Each context has its own independent keymap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyContext {
+    /// Main server list
+    Normal,
+    Sessions,
+    Help,
+    History,
+    Analytics,
+    /// Yes/no prompts (delete server, discard changes)
+    Confirm,
+    ThemeSelector,
+    /// Embedded terminal pane; unbound keys go to the remote shell
+    Terminal,
+    /// Two-pane SFTP file browser
+    Sftp,
+    /// Queue of sends and fetches across servers
+    Transfers,
+    /// Key pairs in ~/.ssh
+    Keys,
+    /// Local security audit report
+    Audit,
+    /// Remote sshd algorithm scan
+    Algorithms,
+}
+
+impl KeyContext {
+    pub fn all() -> [KeyContext; 13] {
+        [
+            KeyContext::Normal,
+            KeyContext::Sessions,
+            KeyContext::Help,
+            KeyContext::History,
+            KeyContext::Analytics,
+            KeyContext::Confirm,
+            KeyContext::ThemeSelector,
+            KeyContext::Terminal,
+            KeyContext::Sftp,
+            KeyContext::Transfers,
+            KeyContext::Keys,
+            KeyContext::Audit,
+            KeyContext::Algorithms,
+        ]
+    }
+
+    /// Name of the context's table under `[keybindings]`
+    pub fn id(&self) -> &'static str {
+        match self {
+            KeyContext::Normal => "normal",
+            KeyContext::Sessions => "sessions",
+            KeyContext::Help => "help",
+            KeyContext::History => "history",
+            KeyContext::Analytics => "analytics",
+            KeyContext::Confirm => "confirm",
+            KeyContext::ThemeSelector => "theme_selector",
+            KeyContext::Terminal => "terminal",
+            KeyContext::Sftp => "sftp",
+            KeyContext::Transfers => "transfers",
+            KeyContext::Keys => "keys",
+            KeyContext::Audit => "audit",
+            KeyContext::Algorithms => "algorithms",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            KeyContext::Normal => "SERVER LIST",
+            KeyContext::Sessions => "SESSION MANAGER",
+            KeyContext::Help => "HELP",
+            KeyContext::History => "HISTORY",
+            KeyContext::Analytics => "ANALYTICS",
+            KeyContext::Confirm => "CONFIRMATIONS",
+            KeyContext::ThemeSelector => "THEME SELECTOR",
+            KeyContext::Terminal => "EMBEDDED TERMINAL",
+            KeyContext::Sftp => "SFTP BROWSER",
+            KeyContext::Transfers => "TRANSFER QUEUE",
+            KeyContext::Keys => "SSH KEYS",
+            KeyContext::Audit => "SECURITY AUDIT",
+            KeyContext::Algorithms => "ALGORITHM SCAN",
+        }
+    }
+
+    fn from_id(id: &str) -> Option<KeyContext> {
+        Self::all().into_iter().find(|c| c.id() == id)
+    }
+}
+
+/// Everything a key can trigger
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    ForceQuit,
+    MoveDown,
+    MoveUp,
+    Connect,
+    /// Connect to the nth (1-9) server of the filtered list
+    QuickConnect(u8),
+    AddServer,
+    EditServer,
+    DeleteServer,
+    Refresh,
+    ToggleOnlineFilter,
+    ShowHelp,
+    CommandPalette,
+    ShowHistory,
+    ShowAnalytics,
+    ShowSessions,
+    KillAllSessions,
+    ThemeSelector,
+    NextTheme,
+    CycleLayout,
+    NextLayoutPreset,
+    ShrinkPanel,
+    GrowPanel,
+    ShowTooltip,
+    ToggleTooltips,
+    KillSession,
+    RefreshSessions,
+    SessionInfo,
+    /// Show the embedded terminal pane
+    FocusTerminal,
+    NextTab,
+    PrevTab,
+    ScrollDown,
+    ScrollUp,
+    /// Leave the current view for the server list
+    Back,
+    Confirm,
+    Cancel,
+    CycleFilter,
+    Apply,
+    /// Browse the selected server's files over SFTP
+    OpenSftp,
+    SwitchPane,
+    OpenDir,
+    ParentDir,
+    /// Copy the selected entry to the other pane
+    Transfer,
+    RenameEntry,
+    MakeDir,
+    DeleteEntry,
+    /// Copy a local path to the same path on one or more servers
+    SendFiles,
+    /// Copy a remote path from one or more servers into per-server folders
+    FetchFiles,
+    ShowTransfers,
+    RetryTransfer,
+    RetryFailed,
+    ClearFinished,
+    /// Store (or forget) the selected password-auth server's password
+    SavePassword,
+    LockVault,
+    /// Key pairs in ~/.ssh and the servers using them
+    ShowKeys,
+    GenerateKey,
+    AddToAgent,
+    /// Append the selected key to authorized_keys on servers
+    InstallKey,
+    /// Scored report on password logins, weak keys, missing host key pins…
+    ShowAudit,
+    /// Read the kex, host key, cipher and MAC algorithms every server offers
+    ScanAlgorithms,
+}
+
+impl Action {
+    /// Name used in the `[keybindings]` table
+    pub fn id(&self) -> String {
+        let id = match self {
+            Action::QuickConnect(n) => return format!("quick_connect_{}", n),
+            Action::Quit => "quit",
+            Action::ForceQuit => "force_quit",
+            Action::MoveDown => "move_down",
+            Action::MoveUp => "move_up",
+            Action::Connect => "connect",
+            Action::AddServer => "add_server",
+            Action::EditServer => "edit_server",
+            Action::DeleteServer => "delete_server",
+            Action::Refresh => "refresh",
+            Action::ToggleOnlineFilter => "toggle_online_filter",
+            Action::ShowHelp => "show_help",
+            Action::CommandPalette => "command_palette",
+            Action::ShowHistory => "show_history",
+            Action::ShowAnalytics => "show_analytics",
+            Action::ShowSessions => "show_sessions",
+            Action::KillAllSessions => "kill_all_sessions",
+            Action::ThemeSelector => "theme_selector",
+            Action::NextTheme => "next_theme",
+            Action::CycleLayout => "cycle_layout",
+            Action::NextLayoutPreset => "next_layout_preset",
+            Action::ShrinkPanel => "shrink_panel",
+            Action::GrowPanel => "grow_panel",
+            Action::ShowTooltip => "show_tooltip",
+            Action::ToggleTooltips => "toggle_tooltips",
+            Action::KillSession => "kill_session",
+            Action::RefreshSessions => "refresh_sessions",
+            Action::SessionInfo => "session_info",
+            Action::FocusTerminal => "focus_terminal",
+            Action::NextTab => "next_tab",
+            Action::PrevTab => "prev_tab",
+            Action::ScrollDown => "scroll_down",
+            Action::ScrollUp => "scroll_up",
+            Action::Back => "back",
+            Action::Confirm => "confirm",
+            Action::Cancel => "cancel",
+            Action::CycleFilter => "cycle_filter",
+            Action::Apply => "apply",
+            Action::OpenSftp => "open_sftp",
+            Action::SwitchPane => "switch_pane",
+            Action::OpenDir => "open_dir",
+            Action::ParentDir => "parent_dir",
+            Action::Transfer => "transfer",
+            Action::RenameEntry => "rename_entry",
+            Action::MakeDir => "make_dir",
+            Action::DeleteEntry => "delete_entry",
+            Action::SendFiles => "send_files",
+            Action::FetchFiles => "fetch_files",
+            Action::ShowTransfers => "show_transfers",
+            Action::RetryTransfer => "retry_transfer",
+            Action::RetryFailed => "retry_failed",
+            Action::ClearFinished => "clear_finished",
+            Action::SavePassword => "save_password",
+            Action::LockVault => "lock_vault",
+            Action::ShowKeys => "show_keys",
+            Action::GenerateKey => "generate_key",
+            Action::AddToAgent => "add_to_agent",
+            Action::InstallKey => "install_key",
+            Action::ShowAudit => "show_audit",
+            Action::ScanAlgorithms => "scan_algorithms",
+        };
+        id.to_string()
+    }
+
+    pub fn description(&self) -> String {
+        let description = match self {
+            Action::QuickConnect(n) => return format!("Quick connect to server {}", n),
+            Action::Quit => "Quit application",
+            Action::ForceQuit => "Force quit",
+            Action::MoveDown => "Move selection down",
+            Action::MoveUp => "Move selection up",
+            Action::Connect => "Connect to selected server",
+            Action::AddServer => "Add new server",
+            Action::EditServer => "Edit selected server",
+            Action::DeleteServer => "Delete selected server",
+            Action::Refresh => "Refresh server status & security assessment",
+            Action::ToggleOnlineFilter => "Toggle online-only filter",
+            Action::ShowHelp => "Show this help",
+            Action::CommandPalette => "Command palette (search every action)",
+            Action::ShowHistory => "Connection history",
+            Action::ShowAnalytics => "Analytics dashboard (usage statistics)",
+            Action::ShowSessions => "Session manager (view active SSH sessions)",
+            Action::KillAllSessions => "Kill all active SSH sessions",
+            Action::ThemeSelector => "Theme selector (live preview, Enter to keep)",
+            Action::NextTheme => "Quick theme cycle",
+            Action::CycleLayout => "Cycle layout mode (Single/Two/Three panels)",
+            Action::NextLayoutPreset => "Switch to the next layout preset",
+            Action::ShrinkPanel => "Shrink left panel",
+            Action::GrowPanel => "Grow left panel",
+            Action::ShowTooltip => "Show contextual tooltip",
+            Action::ToggleTooltips => "Toggle tooltips on/off",
+            Action::KillSession => "Kill selected session",
+            Action::RefreshSessions => "Refresh sessions",
+            Action::SessionInfo => "Show session details",
+            Action::FocusTerminal => "Show embedded terminal sessions",
+            Action::NextTab => "Next terminal tab",
+            Action::PrevTab => "Previous terminal tab",
+            Action::ScrollDown => "Scroll down",
+            Action::ScrollUp => "Scroll up",
+            Action::Back => "Return to server list",
+            Action::Confirm => "Confirm",
+            Action::Cancel => "Cancel",
+            Action::CycleFilter => "Cycle dark/light filter",
+            Action::Apply => "Apply and save",
+            Action::OpenSftp => "Browse files on selected server (SFTP)",
+            Action::SwitchPane => "Switch between local and remote pane",
+            Action::OpenDir => "Open directory",
+            Action::ParentDir => "Go to parent directory",
+            Action::Transfer => "Copy to the other pane (upload/download)",
+            Action::RenameEntry => "Rename",
+            Action::MakeDir => "Create directory",
+            Action::DeleteEntry => "Delete",
+            Action::SendFiles => "Send a file or directory to servers (by name or tag)",
+            Action::FetchFiles => "Fetch a remote path from servers (by name or tag)",
+            Action::ShowTransfers => "Transfer queue (sends and fetches)",
+            Action::RetryTransfer => "Retry selected transfer (resumes where it stopped)",
+            Action::RetryFailed => "Retry all failed transfers",
+            Action::ClearFinished => "Clear finished transfers",
+            Action::SavePassword => "Store or forget the selected server's password",
+            Action::LockVault => "Lock the password vault",
+            Action::ShowKeys => "SSH keys (fingerprints, agent, which servers use them)",
+            Action::GenerateKey => "Generate a new ed25519 key",
+            Action::AddToAgent => "Add selected key to the SSH agent",
+            Action::InstallKey => "Install selected key on servers (by name or tag)",
+            Action::ShowAudit => "Security audit of servers and keys",
+            Action::ScanAlgorithms => "Scan the algorithms every server's sshd offers",
+        };
+        description.to_string()
+    }
+
+    /// Heading the action is grouped under in the help popup
+    pub fn category(&self) -> &'static str {
+        match self {
+            Action::MoveDown | Action::MoveUp | Action::Connect | Action::QuickConnect(_) => "NAVIGATION",
+            Action::AddServer
+            | Action::EditServer
+            | Action::DeleteServer
+            | Action::Refresh
+            | Action::OpenSftp
+            | Action::SavePassword
+            | Action::LockVault
+            | Action::GenerateKey
+            | Action::AddToAgent
+            | Action::InstallKey => "SERVER MANAGEMENT",
+            Action::SwitchPane
+            | Action::OpenDir
+            | Action::ParentDir
+            | Action::Transfer
+            | Action::RenameEntry
+            | Action::MakeDir
+            | Action::DeleteEntry
+            | Action::SendFiles
+            | Action::FetchFiles
+            | Action::RetryTransfer
+            | Action::RetryFailed
+            | Action::ClearFinished => "FILES",
+            Action::ToggleOnlineFilter
+            | Action::ShowHistory
+            | Action::ShowAnalytics
+            | Action::ShowSessions
+            | Action::ShowTransfers
+            | Action::ShowKeys
+            | Action::ShowAudit
+            | Action::ScanAlgorithms => {
+                "FILTERING & VIEWS"
+            }
+            Action::KillAllSessions
+            | Action::KillSession
+            | Action::RefreshSessions
+            | Action::SessionInfo
+            | Action::FocusTerminal
+            | Action::NextTab
+            | Action::PrevTab => "SESSION MANAGEMENT",
+            Action::ThemeSelector
+            | Action::NextTheme
+            | Action::CycleLayout
+            | Action::NextLayoutPreset
+            | Action::ShrinkPanel
+            | Action::GrowPanel
+            | Action::CycleFilter => "THEMES & LAYOUT",
+            Action::ShowTooltip | Action::ToggleTooltips | Action::ShowHelp | Action::CommandPalette => {
+                "TOOLTIPS & HELP"
+            }
+            _ => "GENERAL",
+        }
+    }
+}
+
+/// Default bindings of a context, in the order they're listed in help.
+fn default_bindings(context: KeyContext) -> Vec<(Action, Vec<&'static str>)> {
+    const DIGITS: [&str; 9] = ["1", "2", "3", "4", "5", "6", "7", "8", "9"];
+
+    match context {
+        KeyContext::Normal => {
+            let mut bindings = vec![
+                (Action::MoveDown, vec!["j", "down"]),
+                (Action::MoveUp, vec!["k", "up"]),
+                (Action::Connect, vec!["enter"]),
+            ];
+            bindings.extend((1..=9).map(|n| (Action::QuickConnect(n), vec![DIGITS[n as usize - 1]])));
+            bindings.extend([
+                (Action::AddServer, vec!["a"]),
+                (Action::EditServer, vec!["e"]),
+                (Action::DeleteServer, vec!["d"]),
+                (Action::Refresh, vec!["r"]),
+                (Action::OpenSftp, vec!["F"]),
+                (Action::SavePassword, vec!["P"]),
+                (Action::LockVault, vec!["ctrl+l"]),
+                (Action::SendFiles, vec!["U"]),
+                (Action::FetchFiles, vec!["D"]),
+                (Action::ToggleOnlineFilter, vec!["f"]),
+                (Action::ShowSessions, vec!["S"]),
+                (Action::ShowAnalytics, vec!["A"]),
+                (Action::ShowHistory, vec!["H"]),
+                (Action::ShowTransfers, vec!["Q"]),
+                (Action::ShowKeys, vec!["K"]),
+                (Action::ShowAudit, vec!["!"]),
+                (Action::KillAllSessions, vec!["ctrl+x"]),
+                (Action::FocusTerminal, vec!["tab"]),
+                (Action::ThemeSelector, vec!["t"]),
+                (Action::NextTheme, vec!["T"]),
+                (Action::CycleLayout, vec!["l"]),
+                (Action::NextLayoutPreset, vec!["L"]),
+                (Action::ShrinkPanel, vec!["["]),
+                (Action::GrowPanel, vec!["]"]),
+                (Action::ShowTooltip, vec!["?"]),
+                (Action::ToggleTooltips, vec!["f2"]),
+                (Action::ShowHelp, vec!["h", "f1"]),
+                (Action::CommandPalette, vec!["ctrl+p"]),
+                (Action::Quit, vec!["q", "esc"]),
+                (Action::ForceQuit, vec!["ctrl+c"]),
+            ]);
+            bindings
+        }
+        KeyContext::Sessions => vec![
+            (Action::MoveDown, vec!["j", "down"]),
+            (Action::MoveUp, vec!["k", "up"]),
+            (Action::KillSession, vec!["d"]),
+            (Action::RefreshSessions, vec!["r"]),
+            (Action::SessionInfo, vec!["enter"]),
+            (Action::Back, vec!["S", "q", "esc"]),
+        ],
+        KeyContext::Help => vec![
+            (Action::ScrollDown, vec!["j", "down"]),
+            (Action::ScrollUp, vec!["k", "up"]),
+            (Action::Back, vec!["h", "q", "esc"]),
+        ],
+        KeyContext::History => vec![(Action::Back, vec!["H", "q", "esc"])],
+        KeyContext::Analytics => vec![(Action::Back, vec!["A", "q", "esc"])],
+        KeyContext::Confirm => vec![
+            (Action::Confirm, vec!["y", "Y"]),
+            (Action::Cancel, vec!["n", "N", "esc"]),
+        ],
+        KeyContext::ThemeSelector => vec![
+            (Action::MoveDown, vec!["j", "down"]),
+            (Action::MoveUp, vec!["k", "up"]),
+            (Action::CycleFilter, vec!["tab", "f"]),
+            (Action::Apply, vec!["enter"]),
+            (Action::Cancel, vec!["esc", "q", "t"]),
+        ],
+        // Everything else is typed into the session, so only combinations a
+        // shell is unlikely to need
+        KeyContext::Terminal => vec![
+            // Most terminals send Ctrl+] as the same byte as Ctrl+5
+            (Action::Back, vec!["ctrl+]", "ctrl+5"]),
+            (Action::NextTab, vec!["ctrl+pagedown"]),
+            (Action::PrevTab, vec!["ctrl+pageup"]),
+        ],
+        // Function keys follow the classic two-pane file managers
+        KeyContext::Sftp => vec![
+            (Action::MoveDown, vec!["j", "down"]),
+            (Action::MoveUp, vec!["k", "up"]),
+            (Action::SwitchPane, vec!["tab"]),
+            (Action::OpenDir, vec!["enter", "right", "l"]),
+            (Action::ParentDir, vec!["backspace", "left", "h"]),
+            (Action::Transfer, vec!["c", "f5"]),
+            (Action::RenameEntry, vec!["r", "f6"]),
+            (Action::MakeDir, vec!["m", "f7"]),
+            (Action::DeleteEntry, vec!["d", "delete", "f8"]),
+            (Action::Refresh, vec!["ctrl+r"]),
+            (Action::Back, vec!["q", "esc"]),
+        ],
+        KeyContext::Transfers => vec![
+            (Action::MoveDown, vec!["j", "down"]),
+            (Action::MoveUp, vec!["k", "up"]),
+            (Action::RetryTransfer, vec!["r"]),
+            (Action::RetryFailed, vec!["R"]),
+            (Action::ClearFinished, vec!["c"]),
+            (Action::Back, vec!["Q", "q", "esc"]),
+        ],
+        KeyContext::Keys => vec![
+            (Action::MoveDown, vec!["j", "down"]),
+            (Action::MoveUp, vec!["k", "up"]),
+            (Action::GenerateKey, vec!["g"]),
+            (Action::AddToAgent, vec!["a"]),
+            (Action::InstallKey, vec!["i"]),
+            (Action::Refresh, vec!["r"]),
+            (Action::Back, vec!["K", "q", "esc"]),
+        ],
+        KeyContext::Audit => vec![
+            (Action::MoveDown, vec!["j", "down"]),
+            (Action::MoveUp, vec!["k", "up"]),
+            (Action::Refresh, vec!["r"]),
+            (Action::ScanAlgorithms, vec!["s"]),
+            (Action::Back, vec!["!", "q", "esc"]),
+        ],
+        KeyContext::Algorithms => vec![
+            (Action::MoveDown, vec!["j", "down"]),
+            (Action::MoveUp, vec!["k", "up"]),
+            (Action::ScanAlgorithms, vec!["r"]),
+            (Action::ShowAudit, vec!["!"]),
+            (Action::Back, vec!["q", "esc"]),
+        ],
+    }
+}
+
+/// A single key press with its modifiers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    /// Normalise a key event. Shift is folded into the character for
+    /// `Char`/`BackTab` keys, so `H` matches whether or not the terminal
+    /// reports the shift modifier alongside it.
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let mut modifiers = modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        if matches!(code, KeyCode::Char(_) | KeyCode::BackTab) {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Self { code, modifiers }
+    }
+
+    /// Parse `"ctrl+x"`, `"shift+tab"`, `"f1"`, `"H"`, `"enter"`, …
+    pub fn parse(text: &str) -> Result<Self, String> {
+        // "+" on its own (or as the last part, "ctrl++") is the plus key
+        let (prefix, key) = match text.strip_suffix("++") {
+            Some(prefix) => (Some(prefix), "+"),
+            None if text == "+" => (None, "+"),
+            None => match text.rsplit_once('+') {
+                Some((prefix, key)) => (Some(prefix), key),
+                None => (None, text),
+            },
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for part in prefix.into_iter().flat_map(|p| p.split('+')) {
+            modifiers |= match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier '{}' in '{}'", part, text)),
+            };
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => {
+                if modifiers.contains(KeyModifiers::SHIFT) && c.is_ascii_alphabetic() {
+                    KeyCode::Char(c.to_ascii_uppercase())
+                } else {
+                    KeyCode::Char(c)
+                }
+            }
+            _ => match key.to_ascii_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "space" => KeyCode::Char(' '),
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n) if (1..=24).contains(&n) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key '{}'", key)),
+                },
+            },
+        };
+
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::Enter => write!(f, "Enter"),
+            KeyCode::Esc => write!(f, "Esc"),
+            KeyCode::Tab => write!(f, "Tab"),
+            KeyCode::BackTab => write!(f, "Shift+Tab"),
+            KeyCode::Backspace => write!(f, "Backspace"),
+            KeyCode::Delete => write!(f, "Del"),
+            KeyCode::Insert => write!(f, "Ins"),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            KeyCode::Home => write!(f, "Home"),
+            KeyCode::End => write!(f, "End"),
+            KeyCode::PageUp => write!(f, "PgUp"),
+            KeyCode::PageDown => write!(f, "PgDn"),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+/// Parse a whitespace-separated chord such as `"g g"` or `"ctrl+w j"`.
+pub fn parse_sequence(text: &str) -> Result<Vec<KeyBinding>, String> {
+    let keys = text
+        .split_whitespace()
+        .map(KeyBinding::parse)
+        .collect::<Result<Vec<_>, _>>()?;
+    if keys.is_empty() {
+        return Err("empty key sequence".to_string());
+    }
+    Ok(keys)
+}
+
+pub fn format_sequence(keys: &[KeyBinding]) -> String {
+    keys.iter().map(|k| k.to_string()).collect::<Vec<_>>().join(" ")
+}
+
+/// A problem in the `[keybindings]` table, reported at startup
+#[derive(Debug, Clone)]
+pub struct KeymapError {
+    /// Dotted path of the offending entry, e.g. `keybindings.normal.quit`
+    pub key: String,
+    pub message: String,
+}
+
+impl fmt::Display for KeymapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}`: {}", self.key, self.message)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Binding {
+    keys: Vec<KeyBinding>,
+    action: Action,
+    /// Came from config.toml rather than the defaults
+    user: bool,
+}
+
+/// A user's replacement bindings for one action, with its config path
+type Override = (Vec<Vec<KeyBinding>>, String);
+
+/// Result of feeding the keys pressed so far to the keymap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyLookup {
+    Action(Action),
+    /// The keys are the start of a longer chord; wait for the next key
+    Pending,
+    Unbound,
+}
+
+/// The active bindings of every context
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<KeyContext, Vec<Binding>>,
+    /// Actions per context in help order
+    actions: HashMap<KeyContext, Vec<Action>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::from_config(&KeybindingsConfig::new()).0
+    }
+}
+
+impl Keymap {
+    /// Build the keymap from the defaults overridden by `config`. Invalid
+    /// entries are skipped and conflicting bindings dropped, with every
+    /// problem reported back; user bindings win over default ones.
+    pub fn from_config(config: &KeybindingsConfig) -> (Self, Vec<KeymapError>) {
+        let mut errors = Vec::new();
+        // (context, action id) → (key sequences, config path for error messages)
+        let mut overrides: HashMap<(KeyContext, String), Override> = HashMap::new();
+
+        for (context_id, table) in config {
+            let Some(context) = KeyContext::from_id(context_id) else {
+                errors.push(KeymapError {
+                    key: format!("keybindings.{}", context_id),
+                    message: format!(
+                        "unknown context (expected one of: {})",
+                        KeyContext::all().map(|c| c.id()).join(", ")
+                    ),
+                });
+                continue;
+            };
+            let known: Vec<String> = default_bindings(context).iter().map(|(a, _)| a.id()).collect();
+
+            for (action_id, keys) in table {
+                let path = format!("keybindings.{}.{}", context_id, action_id);
+                if !known.contains(action_id) {
+                    errors.push(KeymapError {
+                        key: path,
+                        message: format!("no action '{}' in the {} context", action_id, context_id),
+                    });
+                    continue;
+                }
+                let texts: Vec<&String> = match keys {
+                    KeyList::One(text) => vec![text],
+                    KeyList::Many(texts) => texts.iter().collect(),
+                };
+                match texts.into_iter().map(|t| parse_sequence(t)).collect::<Result<Vec<_>, _>>() {
+                    Ok(sequences) => {
+                        overrides.insert((context, action_id.clone()), (sequences, path));
+                    }
+                    Err(message) => errors.push(KeymapError { key: path, message }),
+                }
+            }
+        }
+
+        let mut bindings = HashMap::new();
+        let mut actions = HashMap::new();
+        for context in KeyContext::all() {
+            let defaults = default_bindings(context);
+            let mut list: Vec<Binding> = Vec::new();
+            let mut paths: HashMap<Action, String> = HashMap::new();
+
+            for (action, keys) in &defaults {
+                match overrides.remove(&(context, action.id())) {
+                    Some((sequences, path)) => {
+                        paths.insert(*action, path);
+                        list.extend(sequences.into_iter().map(|keys| Binding { keys, action: *action, user: true }));
+                    }
+                    None => list.extend(keys.iter().map(|k| Binding {
+                        keys: parse_sequence(k).expect("default keybinding must parse"),
+                        action: *action,
+                        user: false,
+                    })),
+                }
+            }
+
+            // User bindings go first so they claim their keys over defaults
+            list.sort_by_key(|b| !b.user);
+            let mut accepted: Vec<Binding> = Vec::new();
+            for binding in list {
+                let clash = accepted.iter().find(|other| {
+                    let n = other.keys.len().min(binding.keys.len());
+                    other.keys[..n] == binding.keys[..n]
+                });
+                match clash {
+                    Some(other) if other.action == binding.action && other.keys == binding.keys => {}
+                    Some(other) => {
+                        let kind = if other.keys.len() == binding.keys.len() {
+                            "is also bound to"
+                        } else {
+                            "overlaps with the chord of"
+                        };
+                        errors.push(KeymapError {
+                            key: paths
+                                .get(&binding.action)
+                                .or_else(|| paths.get(&other.action))
+                                .cloned()
+                                .unwrap_or_else(|| format!("keybindings.{}.{}", context.id(), binding.action.id())),
+                            message: format!(
+                                "'{}' {} '{}' ({}); ignoring it for '{}'",
+                                format_sequence(&binding.keys),
+                                kind,
+                                other.action.id(),
+                                format_sequence(&other.keys),
+                                binding.action.id(),
+                            ),
+                        });
+                    }
+                    None => accepted.push(binding),
+                }
+            }
+
+            bindings.insert(context, accepted);
+            actions.insert(context, defaults.into_iter().map(|(a, _)| a).collect());
+        }
+
+        (Self { bindings, actions }, errors)
+    }
+
+    /// Look up the keys pressed so far (a single key, or a partial chord)
+    pub fn lookup(&self, context: KeyContext, keys: &[KeyBinding]) -> KeyLookup {
+        let mut pending = false;
+        for binding in self.bindings.get(&context).into_iter().flatten() {
+            if binding.keys == keys {
+                return KeyLookup::Action(binding.action);
+            }
+            if binding.keys.starts_with(keys) {
+                pending = true;
+            }
+        }
+        if pending {
+            KeyLookup::Pending
+        } else {
+            KeyLookup::Unbound
+        }
+    }
+
+    /// Actions available in `context`, in help order
+    pub fn actions(&self, context: KeyContext) -> &[Action] {
+        self.actions.get(&context).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Every key sequence bound to `action`, formatted for display
+    pub fn keys_for(&self, context: KeyContext, action: Action) -> Vec<String> {
+        let mut keys: Vec<(usize, String)> = self
+            .bindings
+            .get(&context)
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .filter(|(_, b)| b.action == action)
+            .map(|(i, b)| (i, format_sequence(&b.keys)))
+            .collect();
+        keys.sort_by_key(|(i, _)| *i);
+        keys.into_iter().map(|(_, k)| k).collect()
+    }
+
+    /// The first key for `action`, for short hints in the footer
+    pub fn hint(&self, context: KeyContext, action: Action) -> String {
+        self.keys_for(context, action)
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| "unbound".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(c: char) -> KeyBinding {
+        KeyBinding::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    fn config(toml_str: &str) -> KeybindingsConfig {
+        #[derive(serde::Deserialize)]
+        struct Wrapper {
+            keybindings: KeybindingsConfig,
+        }
+        toml::from_str::<Wrapper>(toml_str).unwrap().keybindings
+    }
+
+    #[test]
+    fn parses_modifiers_and_named_keys() {
+        assert_eq!(
+            KeyBinding::parse("ctrl+x").unwrap(),
+            KeyBinding::new(KeyCode::Char('x'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(KeyBinding::parse("shift+h").unwrap(), key('H'));
+        assert_eq!(
+            KeyBinding::parse("shift+tab").unwrap(),
+            KeyBinding::new(KeyCode::BackTab, KeyModifiers::SHIFT)
+        );
+        assert_eq!(KeyBinding::parse("F1").unwrap().code, KeyCode::F(1));
+        assert_eq!(KeyBinding::parse("+").unwrap(), key('+'));
+        assert!(KeyBinding::parse("hyper+x").is_err());
+        assert!(KeyBinding::parse("nope").is_err());
+    }
+
+    #[test]
+    fn defaults_match_the_builtin_shortcuts() {
+        let keymap = Keymap::default();
+        assert_eq!(keymap.lookup(KeyContext::Normal, &[key('a')]), KeyLookup::Action(Action::AddServer));
+        // Terminals report uppercase letters with SHIFT set
+        assert_eq!(
+            keymap.lookup(KeyContext::Normal, &[KeyBinding::new(KeyCode::Char('S'), KeyModifiers::SHIFT)]),
+            KeyLookup::Action(Action::ShowSessions)
+        );
+        assert_eq!(keymap.lookup(KeyContext::Sessions, &[key('d')]), KeyLookup::Action(Action::KillSession));
+        assert_eq!(keymap.lookup(KeyContext::Normal, &[key('3')]), KeyLookup::Action(Action::QuickConnect(3)));
+    }
+
+    #[test]
+    fn chords_and_overrides() {
+        let (keymap, errors) = Keymap::from_config(&config(
+            "[keybindings.normal]\ndelete_server = \"g d\"\nconnect = [\"enter\", \"o\"]",
+        ));
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(keymap.lookup(KeyContext::Normal, &[key('g')]), KeyLookup::Pending);
+        assert_eq!(
+            keymap.lookup(KeyContext::Normal, &[key('g'), key('d')]),
+            KeyLookup::Action(Action::DeleteServer)
+        );
+        // The old binding is replaced, not kept alongside
+        assert_eq!(keymap.lookup(KeyContext::Normal, &[key('d')]), KeyLookup::Unbound);
+        assert_eq!(keymap.keys_for(KeyContext::Normal, Action::Connect), vec!["Enter", "o"]);
+    }
+
+    #[test]
+    fn conflicts_and_bad_entries_are_reported() {
+        let (keymap, errors) = Keymap::from_config(&config(
+            "[keybindings.normal]\nquit = \"d\"\nadd_server = \"r r\"\nteleport = \"x\"\n\
+             [keybindings.sessions]\nback = \"ctrl+nope\"",
+        ));
+        let keys: Vec<&str> = errors.iter().map(|e| e.key.as_str()).collect();
+        assert!(keys.contains(&"keybindings.normal.quit"), "{:?}", keys);
+        assert!(keys.contains(&"keybindings.normal.add_server"), "{:?}", keys);
+        assert!(keys.contains(&"keybindings.normal.teleport"), "{:?}", keys);
+        assert!(keys.contains(&"keybindings.sessions.back"), "{:?}", keys);
+
+        // The user's binding wins over the default it collides with
+        assert_eq!(keymap.lookup(KeyContext::Normal, &[key('d')]), KeyLookup::Action(Action::Quit));
+        assert_eq!(keymap.lookup(KeyContext::Normal, &[key('r'), key('r')]), KeyLookup::Action(Action::AddServer));
+    }
+}
@@ -1,4 +1,9 @@
+pub mod algorithms;
+pub mod askpass;
+pub mod audit;
 pub mod config;
+pub mod deploy;
+pub mod credentials;
 pub mod models;
 pub mod ssh;
 pub mod health;
@@ -6,3 +11,15 @@ pub mod colors;
 pub mod forms;
 pub mod ui;
 pub mod themes;
+pub mod background;
+pub mod embedded;
+pub mod facts;
+pub mod guard;
+pub mod keymap;
+pub mod keys;
+pub mod native;
+pub mod palette;
+pub mod process;
+pub mod sftp;
+pub mod transfers;
+pub mod wrapper;
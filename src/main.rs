@@ -1,15 +1,32 @@
+mod algorithms;
 mod app;
-mod colors;
+mod askpass;
+mod audit;
+mod background;
 mod config;
+mod credentials;
+mod deploy;
+mod embedded;
+mod facts;
 mod forms;
+mod guard;
 mod health;
+mod keymap;
+mod keys;
 mod models;
+mod native;
+mod palette;
+mod process;
+mod sftp;
 mod ssh;
 mod themes;
+mod transfers;
 mod ui;
+mod wrapper;
 
 use app::App;
 use clap::Parser;
+use std::path::PathBuf;
 use std::time::Duration;
 use ssh::ConnectionMode;
 
@@ -29,17 +46,66 @@ struct Args {
     /// Force direct connection in current terminal (shorthand for --connection-mode direct)
     #[arg(long, conflicts_with = "connection_mode")]
     direct: bool,
+
+    /// Run SSH in tabs inside Ghost (shorthand for --connection-mode embedded)
+    #[arg(long, conflicts_with_all = ["connection_mode", "direct", "new_terminal"])]
+    embedded: bool,
+
+    /// Connect with Ghost's own SSH client, in tabs inside Ghost (shorthand
+    /// for --connection-mode native)
+    #[arg(long, conflicts_with_all = ["connection_mode", "direct", "new_terminal", "embedded"])]
+    native: bool,
+
+    /// Audit the saved servers and the keys in ~/.ssh, print the report and
+    /// exit - with status 1 if anything high severity turned up
+    #[arg(long)]
+    audit: bool,
+
+    /// Print the audit report as JSON
+    #[arg(long, requires = "audit")]
+    json: bool,
+
+    /// Internal: report this process's pid on a socket, then run the command
+    /// after `--` (used to track ssh inside terminal windows)
+    #[arg(long = "report-pid", hide = true, requires = "command")]
+    report_pid: Option<PathBuf>,
+
+    #[arg(last = true, hide = true)]
+    command: Vec<String>,
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    // ssh runs us as its askpass helper (`ghost <prompt>`) while a stored
+    // password is on offer
+    #[cfg(unix)]
+    if let Some(socket) = std::env::var_os(askpass::SOCKET_ENV) {
+        let prompt = std::env::args().nth(1).unwrap_or_default();
+        return askpass::run(std::path::Path::new(&socket), &prompt);
+    }
+
     let args = Args::parse();
+
+    if let Some(socket) = &args.report_pid {
+        return wrapper::run(socket, &args.command);
+    }
+
+    if args.audit {
+        if audit::run_cli(args.json)? {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
     
     // Determine the connection mode from arguments
     let connection_mode = if args.new_terminal {
         ConnectionMode::NewTerminal
     } else if args.direct {
         ConnectionMode::Direct
+    } else if args.embedded {
+        ConnectionMode::Embedded
+    } else if args.native {
+        ConnectionMode::Native
     } else {
         args.connection_mode
     };
@@ -1,10 +1,24 @@
 use chrono::{DateTime, Utc};
+use ratatui::layout::{Direction, Rect};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::time::Duration;
 
-use crate::forms::ServerForm;
-use crate::themes::ThemeManager;
+use crate::algorithms::AlgorithmScan;
+use crate::audit::AuditReport;
+use crate::embedded::TerminalTabs;
+use crate::forms::{FormTarget, ServerForm};
+use crate::keymap::{KeyBinding, Keymap};
+use crate::palette::PaletteState;
+use crate::config::SessionRecord;
+use crate::credentials::{CredentialPrompt, Credentials};
+use crate::facts::HostFacts;
+use crate::guard::NameConfirm;
+use crate::process::{self, Activity, ProcessState, ResourceUsage, Terminations};
+use crate::sftp::SftpBrowser;
+use crate::keys::KeyManager;
+use crate::transfers::{TransferForm, TransferQueue};
+use crate::themes::{ThemeManager, ThemeVariant};
 
 /// Represents the health status of a server
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
@@ -45,6 +59,7 @@ impl HealthStatus {
 /// NOT a security audit of the remote host. It cannot tell you whether the
 /// server itself is patched, hardened, or compromised; it only surfaces which
 /// auth method you've configured, so weaker choices (password) stand out.
+/// `audit` also weighs keys, root logins and host key pins - still locally.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub enum AuthStrength {
     /// Public-key file (`-i <key>`)
@@ -82,6 +97,26 @@ impl AuthStrength {
     }
 }
 
+/// Outcome of logging in during a health check (`verify_login`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum LoginCheck {
+    /// Logged in with the configured method
+    Verified,
+    /// Not tried: it takes a password or key passphrase Ghost doesn't have
+    NeedsSecret,
+    /// The server is up but refused the login
+    Failed(String),
+}
+
+impl LoginCheck {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LoginCheck::Verified => "VERIFIED",
+            LoginCheck::NeedsSecret => "NEEDS SECRET",
+            LoginCheck::Failed(_) => "FAILED",
+        }
+    }
+}
 
 /// Connection statistics for monitoring
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -95,7 +130,10 @@ pub struct ConnectionStats {
     pub total_session_duration: Duration,
     pub average_session_duration: Duration,
     pub peak_usage_hour: Option<u8>, // 0-23 hour of day
-    
+    /// Result of the last login attempted by a health check, if any
+    pub login: Option<LoginCheck>,
+    /// What the last deep check found out about the host
+    pub facts: Option<HostFacts>,
 }
 
 impl Default for ConnectionStats {
@@ -110,6 +148,8 @@ impl Default for ConnectionStats {
             total_session_duration: Duration::from_secs(0),
             average_session_duration: Duration::from_secs(0),
             peak_usage_hour: None,
+            login: None,
+            facts: None,
         }
     }
 }
@@ -127,6 +167,16 @@ pub struct ServerConnection {
     pub tags: Vec<String>,
     pub created_at: DateTime<Utc>,
     pub last_modified: DateTime<Utc>,
+    /// Minutes a session may sit idle before `idle_action` kicks in
+    pub idle_limit_minutes: Option<u64>,
+    #[serde(default)]
+    pub idle_action: IdleAction,
+    #[serde(default)]
+    pub environment: Option<Environment>,
+    /// Needs its name typed to connect, delete or include in a transfer;
+    /// prod servers always do
+    #[serde(default)]
+    pub protected: bool,
     
     // Status information (not persisted, computed at runtime)
     #[serde(skip)]
@@ -145,6 +195,63 @@ pub struct ServerConnection {
     pub active_sessions: Vec<SessionInfo>,
 }
 
+/// What to do with a session idle for longer than its server's limit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdleAction {
+    /// Show a notice, once per idle stretch
+    #[default]
+    Warn,
+    /// Kill the session
+    Terminate,
+}
+
+/// Which environment a server belongs to, shown on its row. Prod servers are
+/// protected: connecting to or deleting one asks for its name first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Environment {
+    #[serde(alias = "production")]
+    Prod,
+    Staging,
+    #[serde(alias = "development")]
+    Dev,
+}
+
+impl Environment {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Environment::Prod => "PROD",
+            Environment::Staging => "STAGING",
+            Environment::Dev => "DEV",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Environment::Prod => "PRODUCTION",
+            Environment::Staging => "STAGING",
+            Environment::Dev => "DEVELOPMENT",
+        }
+    }
+}
+
+/// Where a session's ssh process runs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionKind {
+    /// A terminal window, or the suspended TUI's own terminal
+    External,
+    /// A tab of the embedded terminal pane
+    Embedded,
+    /// A tmux pane, by pane id (`%12`); killed with `tmux kill-pane`
+    Tmux(String),
+    /// A zellij pane, by name. zellij can't close a pane other than the
+    /// focused one, so these are ended by pid and the pane is left for the
+    /// user to close.
+    Zellij(String),
+}
+
 /// Information about an active SSH session
 #[derive(Debug, Clone)]
 pub struct SessionInfo {
@@ -153,18 +260,86 @@ pub struct SessionInfo {
     pub window_title: String,
     pub server_name: String,
     pub is_idle: bool,
+    pub kind: SessionKind,
+    /// Start time of the process, to tell it apart from a later process
+    /// with the same pid
+    pub process_start: Option<u64>,
+    /// As of the last session poll
+    pub process_state: ProcessState,
+    pub usage: Option<ResourceUsage>,
+    /// Last time the process used CPU or its terminal
+    pub last_activity: DateTime<Utc>,
+    activity: Activity,
+    /// The idle limit notice was shown for the current idle stretch
+    pub idle_warned: bool,
 }
 
 impl SessionInfo {
-    pub fn new(pid: u32, window_title: String, server_name: String, _server_id: String) -> Self {
+    pub fn new(pid: u32, window_title: String, server_name: String, _server_id: String, kind: SessionKind) -> Self {
         Self {
             pid,
             started_at: Utc::now(),
             window_title,
             server_name,
             is_idle: false,
+            kind,
+            process_start: process::start_time(pid),
+            process_state: ProcessState::Running,
+            usage: None,
+            last_activity: Utc::now(),
+            activity: Activity::default(),
+            idle_warned: false,
         }
     }
+
+    /// Record the latest signs of life at `now` and update `is_idle`: idle
+    /// once nothing has changed for `idle_after`. Where the platform shows
+    /// no activity at all, sessions never count as idle.
+    pub fn observe_activity(&mut self, activity: Activity, now: DateTime<Utc>, idle_after: Duration) {
+        if activity != self.activity {
+            self.activity = activity;
+            self.last_activity = now;
+            self.idle_warned = false;
+        }
+        self.is_idle = activity != Activity::default() && self.idle_for(now) >= idle_after;
+    }
+
+    /// The record kept in `sessions.json`
+    pub fn to_record(&self, server_id: &str) -> SessionRecord {
+        SessionRecord {
+            pid: self.pid,
+            server_id: server_id.to_string(),
+            started_at: self.started_at,
+            process_start: self.process_start,
+            window_title: self.window_title.clone(),
+            kind: self.kind.clone(),
+        }
+    }
+
+    /// A session from a previous run, if its process is still the one that
+    /// was recorded. Without a start time to compare, the pid alone can't
+    /// prove that, so such records are never adopted.
+    pub fn adopt(record: &SessionRecord, server_name: &str) -> Option<Self> {
+        if record.process_start.is_none()
+            || !process::is_alive(record.pid)
+            || process::start_time(record.pid) != record.process_start
+        {
+            return None;
+        }
+        let mut session = Self::new(
+            record.pid,
+            record.window_title.clone(),
+            server_name.to_string(),
+            record.server_id.clone(),
+            record.kind.clone(),
+        );
+        session.started_at = record.started_at;
+        Some(session)
+    }
+
+    pub fn idle_for(&self, now: DateTime<Utc>) -> Duration {
+        now.signed_duration_since(self.last_activity).to_std().unwrap_or_default()
+    }
     
     pub fn duration(&self) -> Duration {
         Utc::now().signed_duration_since(self.started_at).to_std().unwrap_or_default()
@@ -185,6 +360,16 @@ impl SessionInfo {
             format!("{}s", seconds)
         }
     }
+
+    /// CPU time and memory used so far, e.g. `CPU 1.2s · 5.3 MB`
+    pub fn format_usage(&self) -> Option<String> {
+        let usage = self.usage?;
+        let mut text = format!("CPU {:.1}s", usage.cpu_time.as_secs_f64());
+        if let Some(bytes) = usage.memory_bytes {
+            text.push_str(&format!(" · {:.1} MB", bytes as f64 / (1024.0 * 1024.0)));
+        }
+        Some(text)
+    }
 }
 
 /// Connection history entry
@@ -252,6 +437,10 @@ impl ServerConnection {
             tags: Vec::new(),
             created_at: now,
             last_modified: now,
+            idle_limit_minutes: None,
+            idle_action: IdleAction::default(),
+            environment: None,
+            protected: false,
             health_status: HealthStatus::Unknown,
             auth_strength: AuthStrength::Unknown,
             stats: ConnectionStats::default(),
@@ -260,6 +449,10 @@ impl ServerConnection {
         }
     }
     
+    pub fn is_protected(&self) -> bool {
+        self.protected || self.environment == Some(Environment::Prod)
+    }
+
     pub fn connection_string(&self) -> String {
         format!("{}@{}:{}", self.username, self.host, self.port)
     }
@@ -281,12 +474,13 @@ impl ServerConnection {
     }
     
     /// Add an active session
-    pub fn add_session(&mut self, pid: u32, window_title: String) {
+    pub fn add_session(&mut self, pid: u32, window_title: String, kind: SessionKind) {
         self.active_sessions.push(SessionInfo::new(
             pid, 
             window_title, 
             self.name.clone(), 
-            self.id.clone()
+            self.id.clone(),
+            kind,
         ));
     }
     
@@ -378,10 +572,43 @@ impl ServerManager {
             .map(|conn| conn.session_count())
             .sum();
     }
+
+    /// Records for every session that can outlive Ghost. Embedded tabs
+    /// can't: their ssh goes away with the pty.
+    pub fn session_records(&self) -> Vec<SessionRecord> {
+        let mut records: Vec<SessionRecord> = self
+            .connections
+            .iter()
+            .flat_map(|(id, conn)| conn.active_sessions.iter().map(move |s| (id, s)))
+            .filter(|(_, session)| session.kind != SessionKind::Embedded)
+            .map(|(id, session)| session.to_record(id))
+            .collect();
+        records.sort_by_key(|record| record.pid);
+        records
+    }
+
+    /// Track the recorded sessions that are still running; returns how many
+    pub fn adopt_sessions(&mut self, records: &[SessionRecord]) -> usize {
+        let mut adopted = 0;
+        for record in records {
+            let Some(conn) = self.connections.get_mut(&record.server_id) else { continue };
+            if conn.active_sessions.iter().any(|s| s.pid == record.pid) {
+                continue;
+            }
+            if let Some(session) = SessionInfo::adopt(record, &conn.name) {
+                conn.active_sessions.push(session);
+                adopted += 1;
+            }
+        }
+        self.update_session_count();
+        adopted
+    }
 }
 
 /// Layout configurations for the UI
-#[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::enum_variant_names)] // "Panel" is the meaningful part of each name
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum LayoutMode {
     /// Two panels: server list and details
     TwoPanel,
@@ -391,15 +618,20 @@ pub enum LayoutMode {
     SinglePanel,
 }
 
-/// Panel sizing configuration
-#[derive(Debug, Clone)]
+/// Panel sizing configuration, also the shape of a `[settings.layout_presets.<name>]` table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PanelLayout {
     pub mode: LayoutMode,
     /// Panel size percentages [left, center, right] (0-100)
     /// For TwoPanel: [server_list, details, 0]
     /// For ThreePanel: [server_list, details, metrics]
     /// For SinglePanel: [100, 0, 0]
+    #[serde(default)]
     pub panel_sizes: [u16; 3],
+    /// Step down to fewer or stacked panels when the terminal is too small
+    /// for `mode`. Cleared by an explicit choice (`cycle_layout`, a preset).
+    #[serde(skip)]
+    pub auto: bool,
 }
 
 impl Default for PanelLayout {
@@ -407,11 +639,81 @@ impl Default for PanelLayout {
         Self {
             mode: LayoutMode::ThreePanel,
             panel_sizes: [50, 25, 25], // Default: 50% server list, 25% details, 25% metrics
+            auto: true,
         }
     }
 }
 
+/// Below this width an automatic layout drops the metrics panel
+pub const COLLAPSE_METRICS_WIDTH: u16 = 120;
+/// Below this width an automatic layout shows only the server list
+pub const SINGLE_PANEL_WIDTH: u16 = 80;
+/// Narrow terminals at least this tall stack the list above the details
+pub const STACKED_MIN_HEIGHT: u16 = 30;
+
 impl PanelLayout {
+    /// A mode with its default panel sizes
+    pub fn for_mode(mode: LayoutMode) -> Self {
+        let panel_sizes = match mode {
+            LayoutMode::SinglePanel => [100, 0, 0],
+            LayoutMode::TwoPanel => [70, 30, 0],
+            LayoutMode::ThreePanel => [50, 25, 25],
+        };
+        Self { mode, panel_sizes, auto: false }
+    }
+
+    /// The layout itself if its sizes fit the mode, otherwise the mode's
+    /// defaults. Sizes loaded from the config may be hand-edited or missing.
+    pub fn validated(self) -> Self {
+        // Widened so hand-edited sizes can't overflow the sum
+        let [left, center, right] = self.panel_sizes.map(u32::from);
+        let fits = match self.mode {
+            LayoutMode::SinglePanel => left == 100,
+            LayoutMode::TwoPanel => left >= 20 && center >= 10 && left + center == 100 && right == 0,
+            LayoutMode::ThreePanel => {
+                left >= 20 && center >= 10 && right >= 10 && left + center + right == 100
+            }
+        };
+        if fits {
+            self
+        } else {
+            Self { auto: self.auto, ..Self::for_mode(self.mode) }
+        }
+    }
+
+    /// The layout to draw in a `width` x `height` area and the direction to
+    /// split it in. Fixed layouts are drawn as they are; automatic ones step
+    /// down at the width breakpoints, and stack vertically when the terminal
+    /// is narrow but tall (cells are about twice as tall as they are wide).
+    pub fn effective(&self, width: u16, height: u16) -> (PanelLayout, Direction) {
+        if !self.auto || width >= COLLAPSE_METRICS_WIDTH || self.mode == LayoutMode::SinglePanel {
+            return (self.clone(), Direction::Horizontal);
+        }
+
+        let tall = height >= STACKED_MIN_HEIGHT && height * 2 >= width;
+        if tall {
+            let stacked = PanelLayout { mode: LayoutMode::TwoPanel, panel_sizes: [60, 40, 0], auto: true };
+            return (stacked, Direction::Vertical);
+        }
+        if width < SINGLE_PANEL_WIDTH {
+            return (PanelLayout { auto: true, ..Self::for_mode(LayoutMode::SinglePanel) }, Direction::Horizontal);
+        }
+
+        // Collapse the metrics panel, keeping the list's width
+        let left = self.panel_sizes[0].clamp(20, 80);
+        let two = PanelLayout { mode: LayoutMode::TwoPanel, panel_sizes: [left, 100 - left, 0], auto: true };
+        (two, Direction::Horizontal)
+    }
+
+    /// Name shown in popups and the metrics panel
+    pub fn label(&self) -> String {
+        if self.auto {
+            format!("Auto ({:?})", self.mode)
+        } else {
+            format!("{:?}", self.mode)
+        }
+    }
+
     /// Get constraints for ratatui layout based on current configuration
     pub fn get_constraints(&self) -> Vec<ratatui::layout::Constraint> {
         use ratatui::layout::Constraint;
@@ -430,18 +732,19 @@ impl PanelLayout {
     }
     
     /// Toggle between layout modes
+    /// Auto → TwoPanel → ThreePanel → SinglePanel → Auto; every step but
+    /// Auto is a fixed layout that ignores the terminal size.
     pub fn cycle_layout(&mut self) {
-        self.mode = match self.mode {
-            LayoutMode::TwoPanel => LayoutMode::ThreePanel,
-            LayoutMode::ThreePanel => LayoutMode::SinglePanel,
-            LayoutMode::SinglePanel => LayoutMode::TwoPanel,
-        };
+        if self.auto {
+            *self = Self::for_mode(LayoutMode::TwoPanel);
+            return;
+        }
         
         // Update panel sizes for the new mode
-        self.panel_sizes = match self.mode {
-            LayoutMode::SinglePanel => [100, 0, 0],
-            LayoutMode::TwoPanel => [70, 30, 0],
-            LayoutMode::ThreePanel => [50, 25, 25],
+        *self = match self.mode {
+            LayoutMode::TwoPanel => Self::for_mode(LayoutMode::ThreePanel),
+            LayoutMode::ThreePanel => Self::for_mode(LayoutMode::SinglePanel),
+            LayoutMode::SinglePanel => Self::default(),
         };
     }
     
@@ -464,6 +767,67 @@ impl PanelLayout {
             LayoutMode::SinglePanel => {}, // No resizing in single panel
         }
     }
+
+    /// Move the border after panel `border` (0 = between list and details,
+    /// 1 = between details and metrics) to `percent` of the total width.
+    /// Used for mouse dragging; panels keep a minimum width.
+    pub fn drag_border(&mut self, border: usize, percent: u16) {
+        match (&self.mode, border) {
+            (LayoutMode::TwoPanel, 0) => {
+                let left = percent.clamp(20, 80);
+                self.panel_sizes = [left, 100 - left, 0];
+            }
+            (LayoutMode::ThreePanel, 0) => {
+                // Keep the metrics panel where it is and trade width with details
+                let right = self.panel_sizes[2];
+                let left = percent.clamp(20, 100 - right - 10);
+                self.panel_sizes = [left, 100 - left - right, right];
+            }
+            (LayoutMode::ThreePanel, 1) => {
+                let left = self.panel_sizes[0];
+                let center = percent.saturating_sub(left).clamp(10, 100 - left - 10);
+                self.panel_sizes = [left, center, 100 - left - center];
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A rendered list whose rows can be clicked
+#[derive(Debug, Clone, Copy)]
+pub struct ListArea {
+    /// Inside of the list's border
+    pub area: Rect,
+    /// Lines per item
+    pub item_height: u16,
+    /// Index of the first item shown, once the list has scrolled
+    pub offset: usize,
+}
+
+impl ListArea {
+    /// Index of the item drawn at the given screen cell, if any
+    pub fn index_at(&self, column: u16, row: u16) -> Option<usize> {
+        rect_contains(self.area, column, row)
+            .then(|| self.offset + ((row - self.area.y) / self.item_height.max(1)) as usize)
+    }
+}
+
+/// Whether the screen cell (`column`, `row`) lies inside `rect`
+pub fn rect_contains(rect: Rect, column: u16, row: u16) -> bool {
+    column >= rect.x && column < rect.x + rect.width && row >= rect.y && row < rect.y + rect.height
+}
+
+/// Where things were drawn in the last frame, so mouse events can be mapped
+/// back to the element under the pointer. Rebuilt on every render.
+#[derive(Debug, Clone, Default)]
+pub struct HitAreas {
+    pub server_list: Option<ListArea>,
+    pub session_list: Option<ListArea>,
+    /// Area of the side-by-side main panels
+    pub main_panels: Option<Rect>,
+    /// Columns of the draggable borders between main panels, left to right
+    pub panel_borders: Vec<u16>,
+    pub form_fields: Vec<(FormTarget, Rect)>,
 }
 
 /// Application modes for different UI states
@@ -481,6 +845,27 @@ pub enum AppMode {
     History,
     Analytics,
     Sessions,
+    /// Ctrl+P command palette over the server list
+    CommandPalette,
+    /// Embedded terminal tabs, with keys going to the active session
+    Terminal,
+    /// Two-pane SFTP file browser for `AppState::sftp`
+    Sftp,
+    /// Send/fetch popup for `AppState::transfer_form`
+    TransferForm,
+    /// Queue of sends and fetches
+    Transfers,
+    /// SSH key manager for `AppState::keys`
+    Keys,
+    /// Security audit report in `AppState::audit`
+    Audit,
+    /// Algorithms the servers' sshd offer, from `AppState::algorithm_scan`
+    Algorithms,
+    /// Typing a protected server's name, for `AppState::name_confirm`
+    ConfirmName,
+    /// Vault passphrase or server password popup for
+    /// `AppState::credential_prompt`
+    CredentialPrompt,
 }
 
 /// Context for different loading operations
@@ -543,6 +928,42 @@ impl Default for PerformanceMetrics {
     }
 }
 
+/// Which themes the theme selector lists
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ThemeFilter {
+    #[default]
+    All,
+    Dark,
+    Light,
+}
+
+impl ThemeFilter {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThemeFilter::All => "All",
+            ThemeFilter::Dark => "Dark",
+            ThemeFilter::Light => "Light",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            ThemeFilter::All => ThemeFilter::Dark,
+            ThemeFilter::Dark => ThemeFilter::Light,
+            ThemeFilter::Light => ThemeFilter::All,
+        }
+    }
+}
+
+/// State of the theme selector popup
+#[derive(Debug, Clone, Default)]
+pub struct ThemeSelectorState {
+    pub selected_index: usize,
+    pub filter: ThemeFilter,
+    /// Theme that was active when the selector opened; restored on cancel
+    pub original: Option<ThemeVariant>,
+}
+
 /// Global application state
 #[derive(Debug)]
 pub struct AppState {
@@ -559,7 +980,41 @@ pub struct AppState {
     pub session_filter: String,
     pub theme_manager: ThemeManager,
     pub show_theme_selector: bool,
+    pub theme_selector: ThemeSelectorState,
+    pub keymap: Keymap,
+    /// Keys of a chord typed so far (e.g. the first `g` of `g g`)
+    pub pending_keys: Vec<KeyBinding>,
+    pub help_scroll: u16,
+    /// First item shown by the server and session lists, kept between
+    /// frames so they only scroll when the selection leaves the view
+    pub server_list_offset: usize,
+    pub session_list_offset: usize,
+    pub palette: PaletteState,
+    pub hit_areas: HitAreas,
     pub layout: PanelLayout,
+    /// Named layouts from the config, by name
+    pub layout_presets: BTreeMap<String, PanelLayout>,
+    /// Sessions running in the embedded terminal pane
+    pub terminals: TerminalTabs,
+    /// Killed sessions that may still need a SIGKILL
+    pub terminations: Terminations,
+    /// File browser of the last server opened with SFTP; kept while the
+    /// server list is shown so transfers carry on
+    pub sftp: Option<SftpBrowser>,
+    /// Sends and fetches across servers, with the form that starts them
+    pub transfers: TransferQueue,
+    pub transfer_form: Option<TransferForm>,
+    /// Key pairs in ~/.ssh, loaded when the key manager is opened
+    pub keys: Option<KeyManager>,
+    /// Last local security audit, with the finding selected in its view
+    pub audit: Option<AuditReport>,
+    pub audit_selected: usize,
+    /// Last scan of what the servers' sshd offer
+    pub algorithm_scan: AlgorithmScan,
+    /// Stored passwords (vault or OS keyring)
+    pub credentials: Credentials,
+    pub credential_prompt: Option<CredentialPrompt>,
+    pub name_confirm: Option<NameConfirm>,
     pub show_tooltips: bool,
     pub current_tooltip: Option<TooltipInfo>,
     pub tooltip_shown_at: Option<DateTime<Utc>>,
@@ -582,6 +1037,20 @@ impl AppState {
         }
     }
     
+    /// Themes listed by the theme selector under its current light/dark filter
+    pub fn theme_selector_entries(&self) -> Vec<ThemeVariant> {
+        let manager = &self.theme_manager;
+        manager
+            .available_variants()
+            .into_iter()
+            .filter(|v| match self.theme_selector.filter {
+                ThemeFilter::All => true,
+                ThemeFilter::Dark => manager.variant_is_dark(v),
+                ThemeFilter::Light => !manager.variant_is_dark(v),
+            })
+            .collect()
+    }
+
     /// Get all active sessions across all servers
     pub fn get_all_sessions(&self) -> Vec<&SessionInfo> {
         self.server_manager.connections.values()
@@ -603,7 +1072,7 @@ impl AppState {
         }
         
         // Sort by start time (newest first)
-        sessions.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+        sessions.sort_by_key(|s| std::cmp::Reverse(s.started_at));
         sessions
     }
     
@@ -730,44 +1199,40 @@ impl AppState {
 
     /// Kill session by PID
     pub fn kill_session(&mut self, pid: u32) -> Result<(), String> {
-        #[cfg(unix)]
-        {
+        // Embedded sessions are our own children; the tab closes on the next tick
+        if self.terminals.kill(pid) {
+            return Ok(());
+        }
+
+        let pane = self.get_all_sessions().into_iter().find(|s| s.pid == pid).and_then(|s| match &s.kind {
+            SessionKind::Tmux(pane) => Some(pane.clone()),
+            _ => None,
+        });
+        if let Some(pane) = pane {
             use std::process::Command;
-            match Command::new("kill").arg("-TERM").arg(pid.to_string()).output() {
-                Ok(output) => {
-                    if output.status.success() {
-                        // Remove session from tracking
-                        for conn in self.server_manager.connections.values_mut() {
-                            conn.active_sessions.retain(|s| s.pid != pid);
-                        }
-                        self.server_manager.update_session_count();
-                        Ok(())
-                    } else {
-                        Err(format!("Failed to kill session PID {}", pid))
+            return match Command::new("tmux").args(["kill-pane", "-t", &pane]).output() {
+                Ok(output) if output.status.success() => {
+                    for conn in self.server_manager.connections.values_mut() {
+                        conn.active_sessions.retain(|s| s.pid != pid);
                     }
+                    self.server_manager.update_session_count();
+                    Ok(())
                 }
-                Err(e) => Err(format!("Error killing session: {}", e)),
-            }
+                Ok(output) => Err(format!("tmux: {}", String::from_utf8_lossy(&output.stderr).trim())),
+                Err(e) => Err(format!("Error running tmux: {}", e)),
+            };
         }
-        
-        #[cfg(windows)]
-        {
-            use std::process::Command;
-            match Command::new("taskkill").args(["/F", "/PID", &pid.to_string()]).output() {
-                Ok(output) => {
-                    if output.status.success() {
-                        // Remove session from tracking
-                        for conn in self.server_manager.connections.values_mut() {
-                            conn.active_sessions.retain(|s| s.pid != pid);
-                        }
-                        self.server_manager.update_session_count();
-                        Ok(())
-                    } else {
-                        Err(format!("Failed to kill session PID {}", pid))
-                    }
+
+        match self.terminations.terminate(pid) {
+            Ok(()) => {
+                // Remove session from tracking
+                for conn in self.server_manager.connections.values_mut() {
+                    conn.active_sessions.retain(|s| s.pid != pid);
                 }
-                Err(e) => Err(format!("Error killing session: {}", e)),
+                self.server_manager.update_session_count();
+                Ok(())
             }
+            Err(e) => Err(format!("Failed to kill session PID {}: {}", pid, e)),
         }
     }
 }
@@ -788,7 +1253,28 @@ impl Default for AppState {
             session_filter: String::new(),
             theme_manager: ThemeManager::default(),
             show_theme_selector: false,
+            theme_selector: ThemeSelectorState::default(),
+            keymap: Keymap::default(),
+            pending_keys: Vec::new(),
+            help_scroll: 0,
+            server_list_offset: 0,
+            session_list_offset: 0,
+            palette: PaletteState::default(),
+            hit_areas: HitAreas::default(),
             layout: PanelLayout::default(),
+            layout_presets: BTreeMap::new(),
+            terminals: TerminalTabs::default(),
+            terminations: Terminations::default(),
+            sftp: None,
+            transfers: TransferQueue::default(),
+            keys: None,
+            audit: None,
+            audit_selected: 0,
+            algorithm_scan: AlgorithmScan::default(),
+            transfer_form: None,
+            credentials: Credentials::default(),
+            credential_prompt: None,
+            name_confirm: None,
             show_tooltips: true, // Enable tooltips by default
             current_tooltip: None,
             tooltip_shown_at: None,
@@ -800,3 +1286,109 @@ impl Default for AppState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clicks_map_to_items_past_the_scroll_offset() {
+        let list = ListArea { area: Rect::new(1, 1, 30, 10), item_height: 2, offset: 0 };
+        assert_eq!(list.index_at(5, 1), Some(0));
+        assert_eq!(list.index_at(5, 4), Some(1));
+        assert_eq!(list.index_at(5, 11), None);
+
+        let scrolled = ListArea { offset: 7, ..list };
+        assert_eq!(scrolled.index_at(5, 1), Some(7));
+        assert_eq!(scrolled.index_at(5, 10), Some(11));
+    }
+
+    #[test]
+    fn oversized_panel_sizes_are_rejected() {
+        // 65535 + 65535 + 102 wraps to exactly 100 in u16
+        let layout = PanelLayout { mode: LayoutMode::ThreePanel, panel_sizes: [65535, 65535, 102], auto: false };
+        assert_eq!(layout.validated(), PanelLayout::for_mode(LayoutMode::ThreePanel));
+    }
+
+    #[test]
+    fn auto_layout_follows_breakpoints() {
+        let layout = PanelLayout::default();
+        let mode_at = |w, h| {
+            let (effective, direction) = layout.effective(w, h);
+            (effective.mode, direction)
+        };
+
+        assert_eq!(mode_at(160, 40), (LayoutMode::ThreePanel, Direction::Horizontal));
+        assert_eq!(mode_at(100, 30), (LayoutMode::TwoPanel, Direction::Horizontal));
+        assert_eq!(mode_at(70, 24), (LayoutMode::SinglePanel, Direction::Horizontal));
+        assert_eq!(mode_at(70, 50), (LayoutMode::TwoPanel, Direction::Vertical));
+        assert_eq!(layout.effective(100, 30).0.panel_sizes, [50, 50, 0]);
+
+        // An explicit choice is drawn as is, whatever the size
+        let fixed = PanelLayout::for_mode(LayoutMode::ThreePanel);
+        assert_eq!(fixed.effective(70, 24), (fixed.clone(), Direction::Horizontal));
+    }
+
+    #[test]
+    fn cycling_passes_through_auto() {
+        let mut layout = PanelLayout::default();
+        let mut seen = Vec::new();
+        for _ in 0..4 {
+            layout.cycle_layout();
+            seen.push(layout.label());
+        }
+        assert_eq!(seen, ["TwoPanel", "ThreePanel", "SinglePanel", "Auto (ThreePanel)"]);
+    }
+
+    #[test]
+    fn sessions_go_idle_when_nothing_changes() {
+        let mut session = SessionInfo::new(1, "t".into(), "prod".into(), "id".into(), SessionKind::External);
+        let start = session.last_activity;
+        let idle_after = Duration::from_secs(600);
+        let busy = Activity { cpu_time: Some(Duration::from_millis(30)), tty_used: None };
+
+        session.observe_activity(busy, start, idle_after);
+        session.observe_activity(busy, start + chrono::Duration::minutes(5), idle_after);
+        assert!(!session.is_idle);
+        session.observe_activity(busy, start + chrono::Duration::minutes(10), idle_after);
+        assert!(session.is_idle);
+
+        session.idle_warned = true;
+        let busier = Activity { cpu_time: Some(Duration::from_millis(40)), ..busy };
+        session.observe_activity(busier, start + chrono::Duration::minutes(11), idle_after);
+        assert!(!session.is_idle && !session.idle_warned);
+
+        // Nothing observable (other platforms): never idle
+        let mut blind = SessionInfo::new(2, "t".into(), "prod".into(), "id".into(), SessionKind::External);
+        blind.observe_activity(Activity::default(), start + chrono::Duration::hours(5), idle_after);
+        assert!(!blind.is_idle);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn only_the_recorded_process_is_readopted() {
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        let mut manager = ServerManager::default();
+        let server = ServerConnection::new("prod".into(), "prod.example.com".into(), 22, "root".into());
+        let id = server.id.clone();
+        manager.connections.insert(id.clone(), server);
+
+        let mut session = SessionInfo::new(child.id(), "t".into(), "prod".into(), id.clone(), SessionKind::External);
+        session.started_at -= chrono::Duration::hours(2);
+        let record = session.to_record(&id);
+        assert!(record.process_start.is_some());
+
+        // Same pid, different process
+        let reused = SessionRecord { process_start: record.process_start.map(|t| t + 1), ..record.clone() };
+        let unknown_server = SessionRecord { server_id: "gone".into(), ..record.clone() };
+        let no_start_time = SessionRecord { process_start: None, ..record.clone() };
+        assert_eq!(manager.adopt_sessions(&[reused, unknown_server, no_start_time]), 0);
+
+        assert_eq!(manager.adopt_sessions(std::slice::from_ref(&record)), 1);
+        assert_eq!(manager.session_records(), vec![record]);
+        assert!(manager.connections[&id].active_sessions[0].duration() >= Duration::from_secs(7200));
+
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
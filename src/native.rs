@@ -0,0 +1,530 @@
+//! Ghost's own SSH client: connecting and logging in with russh instead of
+//! running `ssh`. The file browser and login-verifying health checks always
+//! go this way; terminal tabs do with `--connection-mode native`, or when
+//! there's no OpenSSH client installed.
+//!
+//! Host keys are only trusted if ~/.ssh/known_hosts already has them. A
+//! password or key passphrase that only the user knows is never guessed at:
+//! logging in fails with `NeedsSecret` so the caller can ask for it.
+
+use crate::models::{AuthMethod, ServerConnection};
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use russh::client::{self, KeyboardInteractiveAuthResponse, Prompt};
+use russh::{ChannelMsg, Disconnect};
+use russh_keys::agent::client::AgentClient;
+use russh_keys::{key, PublicKeyBase64};
+use sha1::Sha1;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use zeroize::Zeroizing;
+
+/// Give up on a server that doesn't complete the handshake and login in time
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+/// Keys tried after the agent and the server's configured key
+const DEFAULT_KEYS: [&str; 3] = ["~/.ssh/id_ed25519", "~/.ssh/id_ecdsa", "~/.ssh/id_rsa"];
+/// Keyboard-interactive rounds answered before giving up on a server that
+/// keeps asking
+const MAX_INTERACTIVE_ROUNDS: usize = 5;
+/// Exit code reported when the connection drops without one, as ssh does
+const CONNECTION_LOST: u32 = 255;
+/// Output kept from a script run by `run_script`
+const SCRIPT_OUTPUT_LIMIT: usize = 64 * 1024;
+
+/// Trusts a server only if its key is already in ~/.ssh/known_hosts, i.e.
+/// it was verified by connecting with ssh before
+pub struct KnownHosts {
+    host: String,
+    port: u16,
+}
+
+#[async_trait]
+impl client::Handler for KnownHosts {
+    type Error = anyhow::Error;
+
+    async fn check_server_key(self, server_public_key: &key::PublicKey) -> Result<(Self, bool), Self::Error> {
+        let Some(path) = dirs::home_dir().map(|home| home.join(".ssh").join("known_hosts")) else {
+            return Ok((self, false));
+        };
+        match check_known_hosts(&path, &self.host, self.port, &server_public_key.public_key_bytes()) {
+            HostKeyStatus::Known => Ok((self, true)),
+            HostKeyStatus::Unknown => Ok((self, false)),
+            HostKeyStatus::Changed { line } => Err(russh::Error::from(russh_keys::Error::KeyChanged { line }).into()),
+            HostKeyStatus::Revoked { line } => Err(anyhow!(
+                "Host key of {} is marked @revoked in known_hosts line {} - refusing to connect",
+                self.host,
+                line
+            )),
+        }
+    }
+}
+
+/// What known_hosts says about a server's key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HostKeyStatus {
+    /// One of the host's lines records exactly this key
+    Known,
+    /// No line records a key of this type for the host
+    Unknown,
+    /// The line records a different key of the same type
+    Changed { line: usize },
+    /// An `@revoked` line lists exactly this key
+    Revoked { line: usize },
+}
+
+/// Look `blob` (the offered key) up in the known_hosts file at `path`.
+/// Every line for the host is checked, since a server usually has one per
+/// key type; only a line of the same type with another key counts as a
+/// change, and a key listed on an `@revoked` line is refused whatever else
+/// says. Hashed (`|1|salt|hash`) host names are matched too.
+fn check_known_hosts(path: &Path, host: &str, port: u16, blob: &[u8]) -> HostKeyStatus {
+    let Ok(contents) = std::fs::read_to_string(path) else {
+        return HostKeyStatus::Unknown;
+    };
+    let algorithm = blob_algorithm(blob);
+
+    let (mut known, mut changed) = (false, None);
+    for entry in host_entries(&contents, host, port) {
+        let Ok(recorded) = STANDARD.decode(entry.key) else {
+            continue;
+        };
+        if entry.revoked {
+            if recorded == blob {
+                return HostKeyStatus::Revoked { line: entry.line };
+            }
+        } else if recorded == blob {
+            known = true;
+        } else if changed.is_none() && Some(entry.algorithm) == algorithm {
+            changed = Some(entry.line);
+        }
+    }
+    match changed {
+        _ if known => HostKeyStatus::Known,
+        Some(line) => HostKeyStatus::Changed { line },
+        None => HostKeyStatus::Unknown,
+    }
+}
+
+/// Whether the known_hosts file at `path` pins a key for the host, i.e.
+/// whether logging in natively would check its key rather than refuse it
+/// as unknown
+pub fn host_key_pinned(path: &Path, host: &str, port: u16) -> bool {
+    std::fs::read_to_string(path)
+        .map(|contents| host_entries(&contents, host, port).any(|entry| !entry.revoked))
+        .unwrap_or(false)
+}
+
+/// A known_hosts line naming the host being looked up
+struct HostEntry<'a> {
+    /// 1-based, for messages
+    line: usize,
+    revoked: bool,
+    algorithm: &'a str,
+    /// Base64 key blob
+    key: &'a str,
+}
+
+/// The lines of known_hosts `contents` for `host` on `port`
+fn host_entries<'a>(contents: &'a str, host: &str, port: u16) -> impl Iterator<Item = HostEntry<'a>> {
+    let host_port = if port == 22 { host.to_string() } else { format!("[{}]:{}", host, port) };
+    contents.lines().enumerate().filter_map(move |(index, line)| {
+        let mut fields = line.split_whitespace().peekable();
+        let revoked = fields.next_if_eq(&"@revoked").is_some();
+        let (Some(hosts), Some(algorithm), Some(key)) = (fields.next(), fields.next(), fields.next()) else {
+            return None;
+        };
+        // Comments and @cert-authority lines never pin a key
+        if hosts.starts_with('#') || hosts.starts_with('@') || !hosts_match(hosts, &host_port) {
+            return None;
+        }
+        Some(HostEntry { line: index + 1, revoked, algorithm, key })
+    })
+}
+
+/// Whether a known_hosts host field (comma-separated names, plain or
+/// hashed) names `host_port`
+fn hosts_match(hosts: &str, host_port: &str) -> bool {
+    hosts.split(',').any(|entry| match entry.strip_prefix("|1|").and_then(|hashed| hashed.split_once('|')) {
+        Some((salt, hash)) => {
+            let (Ok(salt), Ok(hash)) = (STANDARD.decode(salt), STANDARD.decode(hash)) else {
+                return false;
+            };
+            Hmac::<Sha1>::new_from_slice(&salt)
+                .map(|mac| mac.chain_update(host_port).verify_slice(&hash).is_ok())
+                .unwrap_or(false)
+        }
+        None => entry == host_port,
+    })
+}
+
+/// The algorithm name a public key blob starts with
+fn blob_algorithm(blob: &[u8]) -> Option<&str> {
+    let len = u32::from_be_bytes(blob.get(..4)?.try_into().ok()?) as usize;
+    std::str::from_utf8(blob.get(4..4 + len)?).ok()
+}
+
+/// A logged-in connection
+pub type Session = client::Handle<KnownHosts>;
+
+/// What the user typed (or the credential store had) for one login
+#[derive(Clone, Default)]
+pub struct Secrets {
+    /// Answer to password and hidden keyboard-interactive prompts
+    pub password: Option<Zeroizing<String>>,
+    /// Unlocks the server's configured key file
+    pub passphrase: Option<Zeroizing<String>>,
+}
+
+impl std::fmt::Debug for Secrets {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Secrets")
+            .field("password", &self.password.is_some())
+            .field("passphrase", &self.passphrase.is_some())
+            .finish()
+    }
+}
+
+/// Logging in needs something only the user can provide. Returned inside
+/// the `anyhow::Error`, so callers can `downcast_ref` it and ask.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NeedsSecret {
+    /// The server wants a password
+    Password,
+    /// The key file at this path is protected by a passphrase
+    Passphrase(String),
+}
+
+impl std::fmt::Display for NeedsSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NeedsSecret::Password => write!(f, "The server asks for a password"),
+            NeedsSecret::Passphrase(path) => write!(f, "{} is protected by a passphrase", path),
+        }
+    }
+}
+
+impl std::error::Error for NeedsSecret {}
+
+/// Connect to `server` and log in with its configured method
+pub async fn connect(server: &ServerConnection, secrets: &Secrets) -> Result<Session> {
+    tokio::time::timeout(CONNECT_TIMEOUT, open(server, secrets))
+        .await
+        .map_err(|_| anyhow!("Timed out connecting to {}:{}", server.host, server.port))?
+}
+
+async fn open(server: &ServerConnection, secrets: &Secrets) -> Result<Session> {
+    let mut ssh = handshake(server).await?;
+    let accepted = match &server.auth_method {
+        AuthMethod::Password => {
+            let password = secrets.password.as_ref().ok_or(NeedsSecret::Password)?;
+            ssh.authenticate_password(server.username.as_str(), password.as_str()).await?
+        }
+        AuthMethod::Interactive => {
+            keyboard_interactive(&mut ssh, &server.username, secrets.password.as_ref().map(|p| p.as_str())).await?
+        }
+        AuthMethod::Agent | AuthMethod::PublicKey { .. } => authenticate_with_keys(&mut ssh, server, secrets).await?,
+    };
+    if !accepted {
+        bail!("Authentication failed for {}@{}", server.username, server.host);
+    }
+    Ok(ssh)
+}
+
+/// Connect to `server` and log in with one particular key and nothing
+/// else: the agent's copy if it holds `public_key` (a key blob), otherwise
+/// the private key at `key_path`, opened with `passphrase`
+pub async fn connect_with_key(
+    server: &ServerConnection,
+    public_key: &[u8],
+    key_path: Option<&Path>,
+    passphrase: Option<&str>,
+) -> Result<Session> {
+    let login = async {
+        let mut ssh = handshake(server).await?;
+        let user = server.username.as_str();
+        let mut accepted = None;
+        if let Ok(mut agent) = AgentClient::connect_env().await {
+            let identities = agent.request_identities().await.unwrap_or_default();
+            if let Some(identity) = identities.into_iter().find(|k| k.public_key_bytes() == public_key) {
+                accepted = Some(ssh.authenticate_future(user, identity, agent).await.1?);
+            }
+        }
+        let accepted = match (accepted, key_path) {
+            (Some(accepted), _) => accepted,
+            (None, Some(path)) => {
+                let key = russh_keys::load_secret_key(path, passphrase)
+                    .with_context(|| format!("Failed to load {}", path.display()))?;
+                ssh.authenticate_publickey(user, Arc::new(key)).await?
+            }
+            (None, None) => bail!("Neither the agent nor a key file has the private key"),
+        };
+        if !accepted {
+            bail!("{}@{} doesn't accept the key", server.username, server.host);
+        }
+        Ok(ssh)
+    };
+    tokio::time::timeout(CONNECT_TIMEOUT, login)
+        .await
+        .map_err(|_| anyhow!("Timed out connecting to {}:{}", server.host, server.port))?
+}
+
+/// Connect and check the host key, ready to log in
+async fn handshake(server: &ServerConnection) -> Result<Session> {
+    let handler = KnownHosts { host: server.host.clone(), port: server.port };
+    client::connect(Arc::new(client::Config::default()), (server.host.as_str(), server.port), handler)
+        .await
+        .map_err(|e| match e.downcast_ref::<russh::Error>() {
+            Some(russh::Error::UnknownKey) => anyhow!(
+                "{} is not in known_hosts - connect with ssh once to verify its host key",
+                server.host
+            ),
+            Some(russh::Error::Keys(russh_keys::Error::KeyChanged { line })) => anyhow!(
+                "Host key of {} does not match known_hosts line {} - refusing to connect",
+                server.host,
+                line
+            ),
+            Some(_) => anyhow!("Failed to connect to {}:{}: {}", server.host, server.port, e),
+            // Refused by `check_server_key`, which says why
+            None => e,
+        })
+}
+
+/// Try the ssh agent's identities, then the server's key file, then the
+/// usual default keys. An encrypted key file is only opened for the
+/// server's own key, with `secrets.passphrase`; without one, and with
+/// nothing else accepted, the passphrase is asked for.
+async fn authenticate_with_keys(ssh: &mut Session, server: &ServerConnection, secrets: &Secrets) -> Result<bool> {
+    let user = server.username.as_str();
+    if let Ok(mut agent) = AgentClient::connect_env().await {
+        for identity in agent.request_identities().await.unwrap_or_default() {
+            let (returned, accepted) = ssh.authenticate_future(user, identity, agent).await;
+            agent = returned;
+            if accepted.unwrap_or(false) {
+                return Ok(true);
+            }
+        }
+    }
+
+    let configured = match &server.auth_method {
+        AuthMethod::PublicKey { key_path } => Some(key_path.as_str()),
+        _ => None,
+    };
+    let mut locked_key = None;
+    let paths = configured.into_iter().chain(DEFAULT_KEYS.into_iter().filter(|path| Some(*path) != configured));
+    for path in paths {
+        let expanded = shellexpand::tilde(path);
+        let key = match russh_keys::load_secret_key(expanded.as_ref(), None) {
+            Ok(key) => key,
+            Err(russh_keys::Error::KeyIsEncrypted) if Some(path) == configured => match &secrets.passphrase {
+                Some(passphrase) => russh_keys::load_secret_key(expanded.as_ref(), Some(passphrase.as_str()))
+                    .map_err(|_| anyhow!("Wrong passphrase for {}", path))?,
+                None => {
+                    locked_key = Some(path.to_string());
+                    continue;
+                }
+            },
+            Err(_) => continue,
+        };
+        if ssh.authenticate_publickey(user, Arc::new(key)).await? {
+            return Ok(true);
+        }
+    }
+
+    match locked_key {
+        Some(path) => Err(NeedsSecret::Passphrase(path).into()),
+        None => Ok(false),
+    }
+}
+
+/// Keyboard-interactive login, typically PAM asking for the password
+async fn keyboard_interactive(ssh: &mut Session, user: &str, password: Option<&str>) -> Result<bool> {
+    let mut response = ssh.authenticate_keyboard_interactive_start(user, None).await?;
+    for _ in 0..MAX_INTERACTIVE_ROUNDS {
+        match response {
+            KeyboardInteractiveAuthResponse::Success => return Ok(true),
+            KeyboardInteractiveAuthResponse::Failure => return Ok(false),
+            KeyboardInteractiveAuthResponse::InfoRequest { prompts, .. } => {
+                let answers = answer_prompts(&prompts, password)?;
+                response = ssh.authenticate_keyboard_interactive_respond(answers).await?;
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// Hidden prompts are answered with the password. Visible ones are
+/// questions that can't be answered ahead of time, so they need ssh.
+fn answer_prompts(prompts: &[Prompt], password: Option<&str>) -> Result<Vec<String>> {
+    prompts
+        .iter()
+        .map(|prompt| match (prompt.echo, password) {
+            (false, Some(password)) => Ok(password.to_string()),
+            (false, None) => Err(NeedsSecret::Password.into()),
+            (true, _) => Err(anyhow!("The server asks \"{}\" - connect with ssh to answer it", prompt.prompt.trim())),
+        })
+        .collect()
+}
+
+/// Run `script` with the remote `sh` (whatever the login shell is) and
+/// return what it printed on stdout
+pub async fn run_script(ssh: &Session, script: &str) -> Result<String> {
+    let mut channel = ssh.channel_open_session().await.context("Failed to open an SSH channel")?;
+    channel.exec(true, "sh -s").await.context("Server refused to run a command")?;
+    channel.data(script.as_bytes()).await.context("Failed to send the script")?;
+    channel.eof().await.context("Failed to send the script")?;
+
+    let mut output = Vec::new();
+    while let Some(message) = channel.wait().await {
+        match message {
+            ChannelMsg::Data { data } if output.len() < SCRIPT_OUTPUT_LIMIT => output.extend_from_slice(&data),
+            ChannelMsg::Eof | ChannelMsg::Close => break,
+            _ => {}
+        }
+    }
+    Ok(String::from_utf8_lossy(&output).into_owned())
+}
+
+enum Input {
+    Data(Vec<u8>),
+    Resize(u16, u16),
+    Close,
+}
+
+/// An interactive shell over a native connection. A background task feeds
+/// the output to the tab's emulator and forwards keys and resizes; the
+/// connection closes when the shell exits or the tab is closed.
+pub struct NativeShell {
+    input: mpsc::UnboundedSender<Input>,
+    exit: Arc<Mutex<Option<u32>>>,
+}
+
+impl NativeShell {
+    /// Start a shell on a `rows` x `cols` pty, writing its output to `output`
+    pub async fn open(ssh: Session, rows: u16, cols: u16, output: impl Fn(&[u8]) + Send + 'static) -> Result<Self> {
+        let mut channel = ssh.channel_open_session().await.context("Failed to open an SSH channel")?;
+        channel
+            .request_pty(false, "xterm-256color", cols.into(), rows.into(), 0, 0, &[])
+            .await
+            .context("Server refused a pseudo-terminal")?;
+        channel.request_shell(false).await.context("Server refused a shell")?;
+
+        let (input, mut keys) = mpsc::unbounded_channel();
+        let exit = Arc::new(Mutex::new(None));
+        let status = Arc::clone(&exit);
+        tokio::spawn(async move {
+            let mut code = None;
+            loop {
+                tokio::select! {
+                    message = channel.wait() => match message {
+                        Some(ChannelMsg::Data { data }) | Some(ChannelMsg::ExtendedData { data, .. }) => output(&data),
+                        Some(ChannelMsg::ExitStatus { exit_status }) => code = Some(exit_status),
+                        Some(ChannelMsg::Close) | None => break,
+                        Some(_) => {}
+                    },
+                    key = keys.recv() => match key {
+                        Some(Input::Data(bytes)) => {
+                            if channel.data(&bytes[..]).await.is_err() {
+                                break;
+                            }
+                        }
+                        Some(Input::Resize(rows, cols)) => {
+                            let _ = channel.window_change(cols.into(), rows.into(), 0, 0).await;
+                        }
+                        Some(Input::Close) | None => {
+                            let _ = channel.close().await;
+                            break;
+                        }
+                    },
+                }
+            }
+            let _ = ssh.disconnect(Disconnect::ByApplication, "", "en").await;
+            *status.lock().unwrap_or_else(|e| e.into_inner()) = Some(code.unwrap_or(CONNECTION_LOST));
+        });
+
+        Ok(Self { input, exit })
+    }
+
+    pub fn send(&self, bytes: Vec<u8>) {
+        let _ = self.input.send(Input::Data(bytes));
+    }
+
+    pub fn resize(&self, rows: u16, cols: u16) {
+        let _ = self.input.send(Input::Resize(rows, cols));
+    }
+
+    pub fn close(&self) {
+        let _ = self.input.send(Input::Close);
+    }
+
+    /// Exit code once the shell has ended and the connection is closed
+    pub fn try_exit(&self) -> Option<u32> {
+        *self.exit.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prompt(text: &str, echo: bool) -> Prompt {
+        Prompt { prompt: text.to_string(), echo }
+    }
+
+    #[test]
+    fn hidden_prompts_get_the_password_and_others_need_ssh() {
+        assert_eq!(answer_prompts(&[], None).unwrap(), Vec::<String>::new());
+        assert_eq!(answer_prompts(&[prompt("Password: ", false)], Some("hunter2")).unwrap(), ["hunter2"]);
+
+        let missing = answer_prompts(&[prompt("Password: ", false)], None).unwrap_err();
+        assert_eq!(missing.downcast_ref::<NeedsSecret>(), Some(&NeedsSecret::Password));
+
+        let question = answer_prompts(&[prompt("Your favourite colour? ", true)], Some("hunter2")).unwrap_err();
+        assert!(question.downcast_ref::<NeedsSecret>().is_none());
+        assert!(question.to_string().contains("Your favourite colour?"));
+    }
+
+    #[test]
+    fn every_known_hosts_line_for_the_host_is_checked() {
+        let dir = std::env::temp_dir().join(format!("ghost-known-hosts-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("known_hosts");
+
+        let ecdsa = STANDARD.encode(b"\0\0\0\x13ecdsa-sha2-nistp256-key");
+        let ed25519 = STANDARD.encode(b"\0\0\0\x0bssh-ed25519-key-one");
+        let other_ed25519 = b"\0\0\0\x0bssh-ed25519-key-two";
+
+        // A hashed entry for web-1, as `ssh-keygen -H` writes them
+        let salt = b"0123456789abcdefghij";
+        let hash = Hmac::<Sha1>::new_from_slice(salt).unwrap().chain_update("web-1").finalize().into_bytes();
+        let hashed = format!("|1|{}|{}", STANDARD.encode(salt), STANDARD.encode(hash));
+
+        std::fs::write(
+            &path,
+            format!(
+                "# comment\nweb-1,10.0.0.1 ecdsa-sha2-nistp256 {ecdsa}\n{hashed} ssh-ed25519 {ed25519}\n[db]:2222 ssh-ed25519 {ed25519}\n\
+                 @revoked old-web ssh-ed25519 {ed25519}\nold-web ssh-ed25519 {ed25519}\n"
+            ),
+        )
+        .unwrap();
+
+        let offered = STANDARD.decode(&ed25519).unwrap();
+        assert_eq!(check_known_hosts(&path, "web-1", 22, &offered), HostKeyStatus::Known);
+        assert_eq!(check_known_hosts(&path, "db", 2222, &offered), HostKeyStatus::Known);
+        assert_eq!(check_known_hosts(&path, "10.0.0.1", 22, &offered), HostKeyStatus::Unknown);
+        assert_eq!(check_known_hosts(&path, "web-1", 22, other_ed25519), HostKeyStatus::Changed { line: 3 });
+        assert_eq!(check_known_hosts(&path, "db", 22, &offered), HostKeyStatus::Unknown);
+        // Revoked even though a later line pins it; other keys aren't
+        assert_eq!(check_known_hosts(&path, "old-web", 22, &offered), HostKeyStatus::Revoked { line: 5 });
+        assert_eq!(check_known_hosts(&path, "old-web", 22, other_ed25519), HostKeyStatus::Changed { line: 6 });
+        assert!(host_key_pinned(&path, "web-1", 22));
+        assert!(!host_key_pinned(&path, "db", 22));
+        assert_eq!(check_known_hosts(&dir.join("missing"), "web-1", 22, &offered), HostKeyStatus::Unknown);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,191 @@
+//! Command palette (Ctrl+P): every keymap action plus per-server and
+//! per-theme commands, searchable with fuzzy matching.
+
+use crate::keymap::{Action, KeyContext};
+use crate::models::AppState;
+use crate::themes::ThemeVariant;
+
+/// What running a palette entry does
+#[derive(Debug, Clone, PartialEq)]
+pub enum PaletteCommand {
+    Action(Action),
+    Connect(String),
+    Edit(String),
+    SetTheme(ThemeVariant),
+    SetLayout(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct PaletteEntry {
+    pub command: PaletteCommand,
+    pub label: String,
+    /// Keybinding that runs the same thing, if any
+    pub keys: Option<String>,
+}
+
+/// An entry that matched the query, with the matched character positions
+/// of its label for highlighting
+#[derive(Debug, Clone)]
+pub struct PaletteMatch {
+    pub entry: PaletteEntry,
+    pub score: i64,
+    pub positions: Vec<usize>,
+}
+
+/// Palette input state
+#[derive(Debug, Clone, Default)]
+pub struct PaletteState {
+    pub query: String,
+    pub selected_index: usize,
+}
+
+/// Server-list actions that make sense to run from the palette. Plain
+/// navigation and per-slot quick connects are left out: "Connect to …"
+/// entries cover the latter by name.
+fn palette_actions(state: &AppState) -> impl Iterator<Item = Action> + '_ {
+    state
+        .keymap
+        .actions(KeyContext::Normal)
+        .iter()
+        .copied()
+        .filter(|a| {
+            !matches!(
+                a,
+                Action::MoveDown | Action::MoveUp | Action::QuickConnect(_) | Action::CommandPalette
+            )
+        })
+}
+
+/// Every command the palette offers, in display order for an empty query.
+pub fn build_entries(state: &AppState) -> Vec<PaletteEntry> {
+    let keymap = &state.keymap;
+    let mut entries: Vec<PaletteEntry> = palette_actions(state)
+        .map(|action| PaletteEntry {
+            command: PaletteCommand::Action(action),
+            label: action.description(),
+            keys: keymap.keys_for(KeyContext::Normal, action).into_iter().next(),
+        })
+        .collect();
+
+    // Servers in list order so quick-connect digits line up with the list
+    let servers = state.server_manager.filtered_connections();
+    for (i, server) in servers.iter().enumerate() {
+        let quick = if i < 9 {
+            keymap
+                .keys_for(KeyContext::Normal, Action::QuickConnect(i as u8 + 1))
+                .into_iter()
+                .next()
+        } else {
+            None
+        };
+        entries.push(PaletteEntry {
+            command: PaletteCommand::Connect(server.id.clone()),
+            label: format!("Connect to {} ({}@{})", server.name, server.username, server.host),
+            keys: quick,
+        });
+    }
+    for server in &servers {
+        entries.push(PaletteEntry {
+            command: PaletteCommand::Edit(server.id.clone()),
+            label: format!("Edit {}", server.name),
+            keys: None,
+        });
+    }
+
+    for variant in state.theme_manager.available_variants() {
+        entries.push(PaletteEntry {
+            label: format!("Theme: {}", variant.name()),
+            command: PaletteCommand::SetTheme(variant),
+            keys: None,
+        });
+    }
+
+    for name in state.layout_presets.keys() {
+        entries.push(PaletteEntry {
+            command: PaletteCommand::SetLayout(name.clone()),
+            label: format!("Layout: {}", name),
+            keys: None,
+        });
+    }
+
+    entries
+}
+
+/// Entries matching `query`, best first. An empty query keeps every entry in
+/// its original order.
+pub fn filter_entries(entries: Vec<PaletteEntry>, query: &str) -> Vec<PaletteMatch> {
+    let mut matches: Vec<PaletteMatch> = entries
+        .into_iter()
+        .filter_map(|entry| {
+            let (score, positions) = fuzzy_match(query, &entry.label)?;
+            Some(PaletteMatch { entry, score, positions })
+        })
+        .collect();
+    // Stable sort: equal scores keep the display order
+    matches.sort_by_key(|m| std::cmp::Reverse(m.score));
+    matches
+}
+
+/// Case-insensitive subsequence match of `query` against `text`. Returns a
+/// score (higher is better) and the char positions matched, or `None` when
+/// some query character can't be found in order.
+///
+/// Matches are greedy from the left; consecutive characters and characters
+/// at the start of a word score extra, gaps and late starts cost a little.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    let chars: Vec<char> = text.chars().collect();
+
+    let mut positions = Vec::with_capacity(query.len());
+    let mut score = 0i64;
+    let mut next = 0;
+    for q in &query {
+        let offset = chars[next..]
+            .iter()
+            .position(|c| c.to_lowercase().eq(std::iter::once(*q)))?;
+        let pos = next + offset;
+
+        score += 1;
+        if positions.last().is_some_and(|&last| last + 1 == pos) {
+            score += 5;
+        }
+        if pos == 0 || !chars[pos - 1].is_alphanumeric() {
+            score += 3;
+        }
+        score -= offset.min(5) as i64;
+
+        positions.push(pos);
+        next = pos + 1;
+    }
+
+    if let Some(&first) = positions.first() {
+        score -= first.min(10) as i64 / 2;
+    }
+    Some((score, positions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_match_is_an_ordered_subsequence() {
+        assert!(fuzzy_match("cnp", "Connect to prod").is_some());
+        assert!(fuzzy_match("CONN", "Connect to prod").is_some());
+        assert!(fuzzy_match("pc", "Connect to prod").is_none());
+        assert_eq!(fuzzy_match("", "anything").map(|(_, p)| p), Some(vec![]));
+        assert_eq!(fuzzy_match("ctp", "Connect to prod").unwrap().1, vec![0, 6, 11]);
+    }
+
+    #[test]
+    fn word_starts_and_runs_rank_higher() {
+        let score = |q: &str, t: &str| fuzzy_match(q, t).unwrap().0;
+        // "th" at the start of "Theme" beats "th" scattered through a word
+        assert!(score("th", "Theme: Nord") > score("th", "Toggle online-only filter with"));
+        assert!(score("del", "Delete selected server") > score("del", "Add new server (details)"));
+    }
+}
@@ -0,0 +1,407 @@
+//! Process management for session tracking: finding, inspecting and ending
+//! processes that aren't necessarily Ghost's own children, without spawning
+//! `kill`/`tasklist` for it. Linux reads `/proc` and signals with
+//! `libc::kill`; macOS can also tell when a process started, other unix
+//! systems only whether it exists, and Windows uses the process handle APIs.
+
+use std::io;
+use std::time::{Duration, Instant, SystemTime};
+
+/// How long a process gets to exit after SIGTERM before it is sent SIGKILL
+pub const KILL_GRACE: Duration = Duration::from_secs(3);
+
+/// Scheduling state of a live process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessState {
+    /// Running or waiting (for the network, most of the time)
+    Running,
+    /// Suspended, e.g. by Ctrl+Z or SIGSTOP
+    Stopped,
+    /// Exited, but not yet reaped by its parent
+    Zombie,
+}
+
+impl ProcessState {
+    pub fn label(self) -> &'static str {
+        match self {
+            ProcessState::Running => "running",
+            ProcessState::Stopped => "stopped",
+            ProcessState::Zombie => "zombie",
+        }
+    }
+}
+
+/// CPU time and memory a process has used so far
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceUsage {
+    /// User plus system time
+    pub cpu_time: Duration,
+    /// Resident memory, where the platform reports it
+    pub memory_bytes: Option<u64>,
+}
+
+/// What could be found out about a process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProcessInfo {
+    pub state: ProcessState,
+    pub usage: Option<ResourceUsage>,
+}
+
+/// Signs of life of a session's process: the session is idle while these
+/// stay the same
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Activity {
+    pub cpu_time: Option<Duration>,
+    /// Last read or write on the process's terminal
+    pub tty_used: Option<SystemTime>,
+}
+
+impl Activity {
+    pub fn of(pid: u32, usage: Option<ResourceUsage>) -> Self {
+        Self { cpu_time: usage.map(|u| u.cpu_time), tty_used: tty_used(pid) }
+    }
+}
+
+/// When the terminal on `pid`'s stdin was last read or written. The kernel
+/// keeps these times on the device node (coarsely, to a few seconds), which
+/// is also how `w` works out idle times.
+#[cfg(target_os = "linux")]
+pub fn tty_used(pid: u32) -> Option<SystemTime> {
+    let tty = std::fs::read_link(format!("/proc/{}/fd/0", pid)).ok()?;
+    if !tty.starts_with("/dev/pts") && !tty.to_string_lossy().starts_with("/dev/tty") {
+        return None;
+    }
+    let meta = std::fs::metadata(tty).ok()?;
+    match (meta.accessed().ok(), meta.modified().ok()) {
+        (Some(a), Some(m)) => Some(a.max(m)),
+        (a, m) => a.or(m),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn tty_used(_pid: u32) -> Option<SystemTime> {
+    None
+}
+
+/// State and usage of `pid`, or `None` once it no longer exists
+#[cfg(target_os = "linux")]
+pub fn inspect(pid: u32) -> Option<ProcessInfo> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    Some(parse_stat(&stat, clock_ticks(), page_size()))
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+pub fn inspect(pid: u32) -> Option<ProcessInfo> {
+    // Signal 0 only checks that the process exists; EPERM means it does but
+    // belongs to someone else
+    let alive = unsafe { libc::kill(pid as libc::pid_t, 0) } == 0
+        || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM);
+    alive.then_some(ProcessInfo { state: ProcessState::Running, usage: None })
+}
+
+#[cfg(windows)]
+pub fn inspect(pid: u32) -> Option<ProcessInfo> {
+    use windows_sys::Win32::Foundation::{CloseHandle, FILETIME};
+    use windows_sys::Win32::System::Threading::{
+        GetExitCodeProcess, GetProcessTimes, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    const STILL_ACTIVE: u32 = 259;
+
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if handle == 0 {
+            return None;
+        }
+        let mut code = 0u32;
+        let alive = GetExitCodeProcess(handle, &mut code) != 0 && code == STILL_ACTIVE;
+        let zero = FILETIME { dwLowDateTime: 0, dwHighDateTime: 0 };
+        let (mut created, mut exited, mut kernel, mut user) = (zero, zero, zero, zero);
+        let usage = (GetProcessTimes(handle, &mut created, &mut exited, &mut kernel, &mut user) != 0).then(|| {
+            // FILETIME counts 100ns intervals
+            let ticks = |t: FILETIME| ((t.dwHighDateTime as u64) << 32) | t.dwLowDateTime as u64;
+            ResourceUsage {
+                cpu_time: Duration::from_nanos((ticks(kernel) + ticks(user)) * 100),
+                memory_bytes: None,
+            }
+        });
+        CloseHandle(handle);
+        alive.then_some(ProcessInfo { state: ProcessState::Running, usage })
+    }
+}
+
+/// Whether `pid` is still a live (non-zombie) process
+pub fn is_alive(pid: u32) -> bool {
+    inspect(pid).is_some_and(|info| info.state != ProcessState::Zombie)
+}
+
+/// Ask `pid` to exit (SIGTERM). On Windows there is no polite request for a
+/// console program, so the process is terminated outright.
+#[cfg(unix)]
+pub fn terminate(pid: u32) -> io::Result<()> {
+    send_signal(pid, libc::SIGTERM)
+}
+
+/// End `pid` immediately (SIGKILL)
+#[cfg(unix)]
+pub fn force_kill(pid: u32) -> io::Result<()> {
+    send_signal(pid, libc::SIGKILL)
+}
+
+#[cfg(unix)]
+fn send_signal(pid: u32, signal: libc::c_int) -> io::Result<()> {
+    // pid 0 and negative pids address whole process groups
+    let pid = libc::pid_t::try_from(pid)
+        .ok()
+        .filter(|pid| *pid > 0)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid pid"))?;
+    if unsafe { libc::kill(pid, signal) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(windows)]
+pub fn terminate(pid: u32) -> io::Result<()> {
+    force_kill(pid)
+}
+
+#[cfg(windows)]
+pub fn force_kill(pid: u32) -> io::Result<()> {
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::Threading::{OpenProcess, TerminateProcess, PROCESS_TERMINATE};
+
+    unsafe {
+        let handle = OpenProcess(PROCESS_TERMINATE, 0, pid);
+        if handle == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let ok = TerminateProcess(handle, 1) != 0;
+        let error = io::Error::last_os_error();
+        CloseHandle(handle);
+        if ok {
+            Ok(())
+        } else {
+            Err(error)
+        }
+    }
+}
+
+/// Processes that were asked to exit and are force-killed if they're still
+/// around after `KILL_GRACE`
+#[derive(Debug, Default)]
+pub struct Terminations {
+    /// (pid, start time where known, when it was asked to exit)
+    pending: Vec<(u32, Option<u64>, Instant)>,
+}
+
+impl Terminations {
+    /// Send `pid` SIGTERM and remember to follow up
+    pub fn terminate(&mut self, pid: u32) -> io::Result<()> {
+        let started = start_time(pid);
+        terminate(pid)?;
+        self.pending.push((pid, started, Instant::now()));
+        Ok(())
+    }
+
+    /// SIGKILL whatever outlived its grace period, and forget processes
+    /// that have exited. Returns the pids that had to be killed.
+    pub fn escalate(&mut self) -> Vec<u32> {
+        let mut killed = Vec::new();
+        self.pending.retain(|&(pid, started, asked)| {
+            // A different start time means the pid now belongs to someone else
+            if !is_alive(pid) || start_time(pid) != started {
+                return false;
+            }
+            if asked.elapsed() < KILL_GRACE {
+                return true;
+            }
+            if force_kill(pid).is_ok() {
+                killed.push(pid);
+            }
+            false
+        });
+        killed
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+/// The most recently started process whose command line is exactly `argv`,
+/// skipping the pids in `exclude` (sessions already being tracked).
+#[cfg(target_os = "linux")]
+pub fn find_by_cmdline(argv: &[String], exclude: &[u32]) -> Option<u32> {
+    let entries = std::fs::read_dir("/proc").ok()?;
+    entries
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<u32>().ok())
+        .filter(|pid| !exclude.contains(pid))
+        .filter(|pid| read_cmdline(*pid).is_some_and(|cmdline| cmdline == argv))
+        .max_by_key(|pid| start_time(*pid).unwrap_or_default())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn find_by_cmdline(_argv: &[String], _exclude: &[u32]) -> Option<u32> {
+    None
+}
+
+/// Arguments of a running process
+#[cfg(target_os = "linux")]
+pub fn read_cmdline(pid: u32) -> Option<Vec<String>> {
+    let raw = std::fs::read(format!("/proc/{}/cmdline", pid)).ok()?;
+    Some(
+        raw.split(|b| *b == 0)
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| String::from_utf8_lossy(arg).into_owned())
+            .collect(),
+    )
+}
+
+/// Start time of a process in clock ticks since boot (`/proc/<pid>/stat`
+/// field 22). Together with the pid it identifies a process even after the
+/// pid has been reused.
+#[cfg(target_os = "linux")]
+pub fn start_time(pid: u32) -> Option<u64> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    stat_fields(&stat).get(19)?.parse().ok()
+}
+
+/// Start time of a process in microseconds since the epoch, from the BSD
+/// info `proc_pidinfo` returns
+#[cfg(target_os = "macos")]
+pub fn start_time(pid: u32) -> Option<u64> {
+    let mut info: libc::proc_bsdinfo = unsafe { std::mem::zeroed() };
+    let size = std::mem::size_of::<libc::proc_bsdinfo>() as libc::c_int;
+    // SAFETY: `info` is a writable proc_bsdinfo of exactly `size` bytes
+    let written = unsafe {
+        libc::proc_pidinfo(pid as libc::c_int, libc::PROC_PIDTBSDINFO, 0, (&mut info as *mut libc::proc_bsdinfo).cast(), size)
+    };
+    (written == size).then(|| info.pbi_start_tvsec * 1_000_000 + info.pbi_start_tvusec)
+}
+
+/// Creation time of a process in 100ns intervals since 1601
+#[cfg(windows)]
+pub fn start_time(pid: u32) -> Option<u64> {
+    use windows_sys::Win32::Foundation::{CloseHandle, FILETIME};
+    use windows_sys::Win32::System::Threading::{GetProcessTimes, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION};
+
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if handle == 0 {
+            return None;
+        }
+        let zero = FILETIME { dwLowDateTime: 0, dwHighDateTime: 0 };
+        let (mut created, mut exited, mut kernel, mut user) = (zero, zero, zero, zero);
+        let ok = GetProcessTimes(handle, &mut created, &mut exited, &mut kernel, &mut user) != 0;
+        CloseHandle(handle);
+        ok.then(|| ((created.dwHighDateTime as u64) << 32) | created.dwLowDateTime as u64)
+    }
+}
+
+/// Other unix systems don't say; sessions recorded there are never
+/// reattached, as a bare pid may have been reused
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+pub fn start_time(_pid: u32) -> Option<u64> {
+    None
+}
+
+/// State, CPU time (fields 14 and 15, in clock ticks) and resident set size
+/// (field 24, in pages) from a `/proc/<pid>/stat` line
+#[cfg(target_os = "linux")]
+fn parse_stat(stat: &str, ticks_per_second: u64, page_size: u64) -> ProcessInfo {
+    let fields = stat_fields(stat);
+    let state = match fields.first().copied() {
+        Some("T" | "t") => ProcessState::Stopped,
+        Some("Z" | "X" | "x") => ProcessState::Zombie,
+        _ => ProcessState::Running,
+    };
+    let number = |i: usize| fields.get(i).and_then(|f| f.parse::<u64>().ok());
+    let usage = match (number(11), number(12)) {
+        (Some(utime), Some(stime)) if ticks_per_second > 0 => Some(ResourceUsage {
+            cpu_time: Duration::from_millis((utime + stime) * 1000 / ticks_per_second),
+            memory_bytes: number(21).map(|pages| pages * page_size),
+        }),
+        _ => None,
+    };
+    ProcessInfo { state, usage }
+}
+
+#[cfg(target_os = "linux")]
+fn clock_ticks() -> u64 {
+    u64::try_from(unsafe { libc::sysconf(libc::_SC_CLK_TCK) }).unwrap_or(100)
+}
+
+#[cfg(target_os = "linux")]
+fn page_size() -> u64 {
+    u64::try_from(unsafe { libc::sysconf(libc::_SC_PAGESIZE) }).unwrap_or(4096)
+}
+
+/// Fields of a `/proc/<pid>/stat` line after the `(comm)` field, which may
+/// itself contain spaces and parentheses. Index 0 is the state (field 3).
+#[cfg(target_os = "linux")]
+pub fn stat_fields(stat: &str) -> Vec<&str> {
+    match stat.rfind(')') {
+        Some(end) => stat[end + 1..].split_whitespace().collect(),
+        None => Vec::new(),
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stat_fields_skip_the_command_name() {
+        let stat = "4242 (ssh (prod) x) S 1 4242 4242 0 -1 4194560 0 0 0 0 1 2 0 0 20 0 1 0 98765 0 1";
+        let fields = stat_fields(stat);
+        assert_eq!(fields[0], "S");
+        assert_eq!(fields[19], "98765");
+
+        let info = parse_stat(stat, 100, 4096);
+        assert_eq!(info.state, ProcessState::Running);
+        // utime 1 + stime 2 ticks, rss 1 page
+        assert_eq!(info.usage, Some(ResourceUsage { cpu_time: Duration::from_millis(30), memory_bytes: Some(4096) }));
+        assert_eq!(parse_stat("1 (x) Z 0", 100, 4096).state, ProcessState::Zombie);
+        assert_eq!(parse_stat("1 (x) T 0", 100, 4096).state, ProcessState::Stopped);
+    }
+
+    #[test]
+    fn stubborn_processes_are_killed_after_the_grace_period() {
+        // A shell that ignores SIGTERM
+        let mut child = std::process::Command::new("sh").args(["-c", "trap '' TERM; exec sleep 30"]).spawn().unwrap();
+        let pid = child.id();
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(is_alive(pid));
+
+        let mut terminations = Terminations::default();
+        terminations.terminate(pid).unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(terminations.escalate().is_empty());
+        assert_eq!(inspect(pid).map(|info| info.state), Some(ProcessState::Running));
+
+        // Pretend the grace period is over
+        terminations.pending[0].2 -= KILL_GRACE;
+        assert_eq!(terminations.escalate(), vec![pid]);
+        assert!(terminations.is_empty());
+        let _ = child.wait();
+        assert!(!is_alive(pid));
+    }
+
+    #[test]
+    fn finds_processes_by_exact_cmdline() {
+        let mut child = std::process::Command::new("sleep").arg("30.123").spawn().unwrap();
+        let argv = vec!["sleep".to_string(), "30.123".to_string()];
+        // procfs shows the new command line once exec has happened
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        let mut found = None;
+        while found.is_none() && std::time::Instant::now() < deadline {
+            found = find_by_cmdline(&argv, &[]);
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        assert_eq!(found, Some(child.id()));
+        assert_eq!(find_by_cmdline(&argv, &[child.id()]), None);
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
@@ -0,0 +1,1108 @@
+//! SFTP file browser: a native connection (see `native`) to the selected
+//! server with the sftp subsystem on top, shown as a local and a remote pane side by
+//! side. Listings and file operations are short round trips awaited in
+//! place; uploads and downloads run as background tasks that report their
+//! progress over a channel drained on every tick.
+
+use crate::forms::InputField;
+use crate::models::ServerConnection;
+use crate::native::{self, Secrets};
+use anyhow::{anyhow, bail, Context, Result};
+use russh_sftp::client::SftpSession;
+use russh_sftp::protocol::OpenFlags;
+use std::collections::HashSet;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc;
+
+/// Bytes read and written per step of a transfer
+const CHUNK_SIZE: usize = 32 * 1024;
+/// Finished transfers kept in the list below the panes
+const FINISHED_KEPT: usize = 4;
+
+/// An open SFTP session. Clones share the connection, which stays up until
+/// the last clone (including those held by running transfers) is dropped.
+#[derive(Clone)]
+pub struct SftpClient {
+    session: Arc<SftpSession>,
+    _ssh: Option<Arc<native::Session>>,
+}
+
+impl std::fmt::Debug for SftpClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SftpClient").finish_non_exhaustive()
+    }
+}
+
+impl SftpClient {
+    /// Log in to `server` and start the sftp subsystem
+    pub async fn connect(server: &ServerConnection, secrets: &Secrets) -> Result<Self> {
+        let ssh = native::connect(server, secrets).await?;
+        let channel = ssh.channel_open_session().await.context("Failed to open an SSH channel")?;
+        channel
+            .request_subsystem(true, "sftp")
+            .await
+            .context("Server refused the sftp subsystem")?;
+        let session = SftpSession::new(channel.into_stream()).await.context("Failed to start SFTP")?;
+        Ok(Self { session: Arc::new(session), _ssh: Some(Arc::new(ssh)) })
+    }
+
+    /// Run over an already established sftp stream, such as an in-memory one
+    #[cfg(test)]
+    pub async fn from_stream<S>(stream: S) -> Result<Self>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let session = SftpSession::new(stream).await.context("Failed to start SFTP")?;
+        Ok(Self { session: Arc::new(session), _ssh: None })
+    }
+
+    /// Directory the server starts the session in
+    pub async fn home(&self) -> Result<String> {
+        self.session.canonicalize(".").await.context("Failed to resolve the remote home directory")
+    }
+
+    pub async fn list(&self, path: &str) -> Result<Vec<FileEntry>> {
+        let dir = self.session.read_dir(path).await.with_context(|| format!("Failed to list {}", path))?;
+        let mut entries = Vec::new();
+        for entry in dir {
+            let name = entry.file_name();
+            let file_type = entry.file_type();
+            let mut is_dir = file_type.is_dir();
+            // Links are listed as links; follow them to tell directories apart
+            if file_type.is_symlink() {
+                if let Ok(target) = self.session.metadata(Side::Remote.join(path, &name)).await {
+                    is_dir = target.is_dir();
+                }
+            }
+            entries.push(FileEntry { name, is_dir, is_link: file_type.is_symlink(), size: entry.metadata().len() });
+        }
+        sort_entries(&mut entries);
+        Ok(entries)
+    }
+
+    pub async fn mkdir(&self, path: &str) -> Result<()> {
+        self.session.create_dir(path).await.with_context(|| format!("Failed to create {}", path))
+    }
+
+    pub async fn rename(&self, from: &str, to: &str) -> Result<()> {
+        self.session.rename(from, to).await.with_context(|| format!("Failed to rename {}", from))
+    }
+
+    /// Delete a file, or a directory with everything in it
+    pub async fn remove(&self, path: &str, is_dir: bool) -> Result<()> {
+        if !is_dir {
+            return self.session.remove_file(path).await.with_context(|| format!("Failed to delete {}", path));
+        }
+        let mut dirs = vec![path.to_string()];
+        let mut pending = vec![path.to_string()];
+        let mut files = Vec::new();
+        while let Some(dir) = pending.pop() {
+            for entry in self.list(&dir).await? {
+                let child = Side::Remote.join(&dir, &entry.name);
+                // Links are removed, never the directories they point to
+                if entry.is_dir && !entry.is_link {
+                    dirs.push(child.clone());
+                    pending.push(child);
+                } else {
+                    files.push(child);
+                }
+            }
+        }
+        for file in files {
+            self.session.remove_file(&file).await.with_context(|| format!("Failed to delete {}", file))?;
+        }
+        // Children were found after their parents, so delete back to front
+        for dir in dirs.iter().rev() {
+            self.session.remove_dir(dir).await.with_context(|| format!("Failed to delete {}", dir))?;
+        }
+        Ok(())
+    }
+
+    /// Files and directories to copy for downloading `from` to the local `to`
+    async fn plan_download(&self, from: &str, to: &str) -> Result<TransferPlan> {
+        let mut plan = TransferPlan::default();
+        let meta = self.session.metadata(from).await.with_context(|| format!("Failed to stat {}", from))?;
+        if !meta.is_dir() {
+            plan.files.push((from.to_string(), to.to_string(), meta.len()));
+            return Ok(plan);
+        }
+        let mut pending = vec![(from.to_string(), to.to_string())];
+        let mut visited = HashSet::new();
+        while let Some((src, dst)) = pending.pop() {
+            // Links to directories are followed, but a link back up the tree
+            // would go round forever: each directory is copied only once
+            let real = self.session.canonicalize(src.as_str()).await.unwrap_or_else(|_| src.clone());
+            if !visited.insert(real) {
+                continue;
+            }
+            plan.dirs.push(dst.clone());
+            for entry in self.list(&src).await? {
+                let (child_src, child_dst) = (Side::Remote.join(&src, &entry.name), Side::Local.join(&dst, &entry.name));
+                if entry.is_dir {
+                    pending.push((child_src, child_dst));
+                } else {
+                    plan.files.push((child_src, child_dst, entry.size));
+                }
+            }
+        }
+        Ok(plan)
+    }
+}
+
+/// One line of a pane
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileEntry {
+    pub name: String,
+    /// Also set for links to directories
+    pub is_dir: bool,
+    pub is_link: bool,
+    pub size: u64,
+}
+
+/// Directories first, then case-insensitively by name
+fn sort_entries(entries: &mut [FileEntry]) {
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase())));
+}
+
+pub fn list_local(path: &str) -> Result<Vec<FileEntry>> {
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(path).with_context(|| format!("Failed to list {}", path))? {
+        let entry = entry?;
+        // Follows links, so a link to a directory can be entered
+        let Ok(meta) = std::fs::metadata(entry.path()) else {
+            continue;
+        };
+        entries.push(FileEntry {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            is_dir: meta.is_dir(),
+            is_link: entry.file_type().is_ok_and(|t| t.is_symlink()),
+            size: meta.len(),
+        });
+    }
+    sort_entries(&mut entries);
+    Ok(entries)
+}
+
+/// "12.3 KB" style sizes for the panes and transfer list
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Local,
+    Remote,
+}
+
+impl Side {
+    pub fn other(self) -> Side {
+        match self {
+            Side::Local => Side::Remote,
+            Side::Remote => Side::Local,
+        }
+    }
+
+    /// `dir`/`name` in this side's path syntax; remote paths always use `/`
+    pub fn join(self, dir: &str, name: &str) -> String {
+        match self {
+            Side::Local => Path::new(dir).join(name).to_string_lossy().into_owned(),
+            Side::Remote if dir.ends_with('/') => format!("{}{}", dir, name),
+            Side::Remote => format!("{}/{}", dir, name),
+        }
+    }
+
+    /// Parent directory and the name of `path` within it, or None at the root
+    pub fn split(self, path: &str) -> Option<(String, String)> {
+        match self {
+            Side::Local => {
+                let path = Path::new(path);
+                let name = path.file_name()?.to_string_lossy().into_owned();
+                Some((path.parent()?.to_string_lossy().into_owned(), name))
+            }
+            Side::Remote => {
+                let trimmed = path.trim_end_matches('/');
+                let (parent, name) = trimmed.rsplit_once('/')?;
+                let parent = if parent.is_empty() { "/" } else { parent };
+                (!name.is_empty()).then(|| (parent.to_string(), name.to_string()))
+            }
+        }
+    }
+}
+
+/// A directory listing with a cursor
+#[derive(Debug, Clone, Default)]
+pub struct Pane {
+    pub path: String,
+    pub entries: Vec<FileEntry>,
+    pub selected: usize,
+}
+
+impl Pane {
+    pub fn selected_entry(&self) -> Option<&FileEntry> {
+        self.entries.get(self.selected)
+    }
+
+    fn set_entries(&mut self, entries: Vec<FileEntry>) {
+        self.entries = entries;
+        self.selected = self.selected.min(self.entries.len().saturating_sub(1));
+    }
+
+    fn select_name(&mut self, name: &str) {
+        if let Some(i) = self.entries.iter().position(|e| e.name == name) {
+            self.selected = i;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Upload,
+    Download,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransferStatus {
+    Running,
+    Done,
+    Failed(String),
+}
+
+/// A file or directory being copied between the panes
+#[derive(Debug, Clone)]
+pub struct Transfer {
+    pub id: u64,
+    pub direction: Direction,
+    pub name: String,
+    pub done: u64,
+    pub total: u64,
+    pub status: TransferStatus,
+}
+
+impl Transfer {
+    pub fn progress(&self) -> f32 {
+        match self.status {
+            TransferStatus::Done => 1.0,
+            _ if self.total == 0 => 0.0,
+            _ => (self.done as f64 / self.total as f64).min(1.0) as f32,
+        }
+    }
+}
+
+/// Directories to create (parents first) and files to copy as
+/// (source, destination, size)
+#[derive(Debug, Default)]
+struct TransferPlan {
+    dirs: Vec<String>,
+    files: Vec<(String, String, u64)>,
+}
+
+fn plan_upload(from: &str, to: &str) -> Result<TransferPlan> {
+    let mut plan = TransferPlan::default();
+    let mut pending = vec![(PathBuf::from(from), to.to_string())];
+    let mut visited = HashSet::new();
+    while let Some((src, dst)) = pending.pop() {
+        let meta = std::fs::metadata(&src).with_context(|| format!("Failed to read {}", src.display()))?;
+        if meta.is_dir() {
+            // As for downloads, every directory once, however it's linked
+            if !visited.insert(std::fs::canonicalize(&src).unwrap_or_else(|_| src.clone())) {
+                continue;
+            }
+            plan.dirs.push(dst.clone());
+            for entry in std::fs::read_dir(&src)? {
+                let entry = entry?;
+                let name = entry.file_name().to_string_lossy().into_owned();
+                pending.push((entry.path(), Side::Remote.join(&dst, &name)));
+            }
+        } else {
+            plan.files.push((src.to_string_lossy().into_owned(), dst, meta.len()));
+        }
+    }
+    Ok(plan)
+}
+
+/// Sent from the connect, transfer and delete tasks back to the browser
+#[derive(Debug)]
+pub enum SftpEvent {
+    Connected(Box<Result<(SftpClient, String, Vec<FileEntry>)>>),
+    Progress { id: u64, done: u64, total: u64 },
+    Finished { id: u64, error: Option<String> },
+    Removed { error: Option<String> },
+}
+
+/// Copy `from` to `to`, a single file or a whole directory tree, calling
+/// `report` with (bytes done, total bytes) after each chunk. With `resume`,
+/// files already complete at the destination are skipped and shorter ones
+/// continued from where they stopped, so a failed transfer can be retried
+/// without starting over.
+pub async fn transfer(
+    client: &SftpClient,
+    direction: Direction,
+    from: &str,
+    to: &str,
+    resume: bool,
+    report: &mut impl FnMut(u64, u64),
+) -> Result<()> {
+    let plan = match direction {
+        Direction::Upload => plan_upload(from, to)?,
+        Direction::Download => client.plan_download(from, to).await?,
+    };
+    let total = plan.files.iter().map(|(_, _, size)| size).sum();
+    let mut done = 0;
+    report(done, total);
+
+    for dir in &plan.dirs {
+        match direction {
+            Direction::Upload => {
+                if !client.session.try_exists(dir.as_str()).await? {
+                    client.mkdir(dir).await?;
+                }
+            }
+            Direction::Download => {
+                tokio::fs::create_dir_all(dir).await.with_context(|| format!("Failed to create {}", dir))?;
+            }
+        }
+    }
+
+    for (src, dst, size) in &plan.files {
+        let existing = match (resume, direction) {
+            (false, _) => None,
+            (true, Direction::Upload) => client.session.metadata(dst.as_str()).await.ok().map(|m| m.len()),
+            (true, Direction::Download) => tokio::fs::metadata(dst).await.ok().map(|m| m.len()),
+        };
+        // A longer file at the destination isn't ours to append to
+        let offset = match existing {
+            Some(len) if len == *size => {
+                done += size;
+                report(done, total);
+                continue;
+            }
+            Some(len) if len < *size => len,
+            _ => 0,
+        };
+        done += offset;
+
+        let mut step = |bytes: u64| {
+            done += bytes;
+            report(done, total);
+        };
+        match direction {
+            Direction::Upload => {
+                let mut reader = tokio::fs::File::open(src).await.with_context(|| format!("Failed to open {}", src))?;
+                let mut writer = if offset > 0 {
+                    client.session.open_with_flags(dst.as_str(), OpenFlags::WRITE).await
+                } else {
+                    client.session.create(dst.as_str()).await
+                }
+                .with_context(|| format!("Failed to create {}", dst))?;
+                reader.seek(SeekFrom::Start(offset)).await?;
+                writer.seek(SeekFrom::Start(offset)).await?;
+                copy(reader, writer, &mut step).await
+            }
+            Direction::Download => {
+                let mut reader = client.session.open(src.as_str()).await.with_context(|| format!("Failed to open {}", src))?;
+                let mut writer = if offset > 0 {
+                    tokio::fs::OpenOptions::new().write(true).open(dst).await
+                } else {
+                    tokio::fs::File::create(dst).await
+                }
+                .with_context(|| format!("Failed to create {}", dst))?;
+                reader.seek(SeekFrom::Start(offset)).await?;
+                writer.seek(SeekFrom::Start(offset)).await?;
+                copy(reader, writer, &mut step).await
+            }
+        }
+        .with_context(|| format!("Failed to copy {}", src))?;
+    }
+    Ok(())
+}
+
+async fn copy<R, W>(mut reader: R, mut writer: W, report: &mut impl FnMut(u64)) -> Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut buf = vec![0; CHUNK_SIZE];
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        writer.write_all(&buf[..n]).await?;
+        report(n as u64);
+    }
+    // Closes the remote handle, or flushes the local file
+    writer.shutdown().await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptKind {
+    Rename,
+    MakeDir,
+    /// Answered with y/n rather than typed text
+    Delete,
+}
+
+/// Question shown over the browser before a rename, mkdir or delete
+#[derive(Debug, Clone)]
+pub struct SftpPrompt {
+    pub kind: PromptKind,
+    pub side: Side,
+    pub input: InputField,
+    /// Entry the rename or delete applies to
+    pub target: Option<FileEntry>,
+}
+
+/// State of the two-pane browser for one server
+#[derive(Debug)]
+pub struct SftpBrowser {
+    pub server_id: String,
+    pub server_name: String,
+    pub client: Option<SftpClient>,
+    /// Why connecting failed; None while connecting or once connected
+    pub error: Option<String>,
+    pub local: Pane,
+    pub remote: Pane,
+    pub focus: Side,
+    pub transfers: Vec<Transfer>,
+    pub prompt: Option<SftpPrompt>,
+    next_id: u64,
+    /// Remote deletes still running
+    removing: usize,
+    tx: mpsc::UnboundedSender<SftpEvent>,
+    rx: mpsc::UnboundedReceiver<SftpEvent>,
+}
+
+impl SftpBrowser {
+    /// Show the local working directory right away and connect in the
+    /// background; the remote pane fills in when the login completes
+    pub fn open(server: &ServerConnection, secrets: Secrets) -> Self {
+        let browser = Self::new(server.id.clone(), server.name.clone());
+        let server = server.clone();
+        let tx = browser.tx.clone();
+        tokio::spawn(async move {
+            let connected = async {
+                let client = SftpClient::connect(&server, &secrets).await?;
+                let home = client.home().await?;
+                let entries = client.list(&home).await?;
+                Ok((client, home, entries))
+            }
+            .await;
+            let _ = tx.send(SftpEvent::Connected(Box::new(connected)));
+        });
+        browser
+    }
+
+    fn new(server_id: String, server_name: String) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let path = std::env::current_dir()
+            .or_else(|_| dirs::home_dir().context("no home directory"))
+            .map(|p| p.to_string_lossy().into_owned())
+            .unwrap_or_else(|_| "/".to_string());
+        let mut local = Pane { path, ..Pane::default() };
+        local.set_entries(list_local(&local.path).unwrap_or_default());
+        Self {
+            server_id,
+            server_name,
+            client: None,
+            error: None,
+            local,
+            remote: Pane::default(),
+            focus: Side::Local,
+            transfers: Vec::new(),
+            prompt: None,
+            next_id: 0,
+            removing: 0,
+            tx,
+            rx,
+        }
+    }
+
+    /// A browser on an existing client, starting in `local_dir` and `remote_dir`
+    #[cfg(test)]
+    pub async fn with_client(client: SftpClient, local_dir: &str, remote_dir: &str) -> Result<Self> {
+        let mut browser = Self::new(String::new(), String::new());
+        browser.local = Pane { path: local_dir.to_string(), ..Pane::default() };
+        browser.remote = Pane { path: remote_dir.to_string(), ..Pane::default() };
+        browser.client = Some(client);
+        browser.reload(Side::Local).await?;
+        browser.reload(Side::Remote).await?;
+        Ok(browser)
+    }
+
+    pub fn pane(&self, side: Side) -> &Pane {
+        match side {
+            Side::Local => &self.local,
+            Side::Remote => &self.remote,
+        }
+    }
+
+    fn pane_mut(&mut self, side: Side) -> &mut Pane {
+        match side {
+            Side::Local => &mut self.local,
+            Side::Remote => &mut self.remote,
+        }
+    }
+
+    fn client(&self) -> Result<SftpClient> {
+        match (&self.client, &self.error) {
+            (Some(client), _) => Ok(client.clone()),
+            (None, Some(error)) => Err(anyhow!("Not connected: {}", error)),
+            (None, None) => Err(anyhow!("Still connecting to {}", self.server_name)),
+        }
+    }
+
+    /// Whether transfers or remote deletes are still running
+    pub fn is_busy(&self) -> bool {
+        self.removing > 0 || self.transfers.iter().any(|t| t.status == TransferStatus::Running)
+    }
+
+    /// Apply whatever the background tasks sent since the last tick.
+    /// Returns why remote deletes failed, for the caller to show.
+    pub async fn poll_events(&mut self) -> Vec<String> {
+        let mut failures = Vec::new();
+        while let Ok(event) = self.rx.try_recv() {
+            match event {
+                SftpEvent::Connected(result) => match *result {
+                    Ok((client, home, entries)) => {
+                        self.client = Some(client);
+                        self.remote = Pane { path: home, ..Pane::default() };
+                        self.remote.set_entries(entries);
+                    }
+                    Err(e) => self.error = Some(e.to_string()),
+                },
+                SftpEvent::Progress { id, done, total } => {
+                    if let Some(transfer) = self.transfers.iter_mut().find(|t| t.id == id) {
+                        transfer.done = done;
+                        transfer.total = total;
+                    }
+                }
+                SftpEvent::Finished { id, error } => {
+                    let Some(transfer) = self.transfers.iter_mut().find(|t| t.id == id) else {
+                        continue;
+                    };
+                    transfer.status = match error {
+                        Some(e) => TransferStatus::Failed(e),
+                        None => TransferStatus::Done,
+                    };
+                    // Show the new files in the pane they were copied to
+                    let side = match transfer.direction {
+                        Direction::Upload => Side::Remote,
+                        Direction::Download => Side::Local,
+                    };
+                    let _ = self.reload(side).await;
+                }
+                SftpEvent::Removed { error } => {
+                    self.removing = self.removing.saturating_sub(1);
+                    failures.extend(error);
+                    let _ = self.reload(Side::Remote).await;
+                }
+            }
+        }
+        failures
+    }
+
+    /// Re-read the directory shown on `side`
+    pub async fn reload(&mut self, side: Side) -> Result<()> {
+        let path = self.pane(side).path.clone();
+        let entries = match side {
+            Side::Local => list_local(&path)?,
+            Side::Remote => self.client()?.list(&path).await?,
+        };
+        self.pane_mut(side).set_entries(entries);
+        Ok(())
+    }
+
+    pub fn move_selection(&mut self, down: bool) {
+        let pane = self.pane_mut(self.focus);
+        if down {
+            if pane.selected + 1 < pane.entries.len() {
+                pane.selected += 1;
+            }
+        } else {
+            pane.selected = pane.selected.saturating_sub(1);
+        }
+    }
+
+    pub fn switch_pane(&mut self) {
+        self.focus = self.focus.other();
+    }
+
+    /// Change the focused pane to `path`, staying put if it can't be listed
+    async fn change_dir(&mut self, path: String, select: Option<&str>) -> Result<()> {
+        let side = self.focus;
+        let previous = std::mem::replace(&mut self.pane_mut(side).path, path);
+        if let Err(e) = self.reload(side).await {
+            self.pane_mut(side).path = previous;
+            return Err(e);
+        }
+        let pane = self.pane_mut(side);
+        pane.selected = 0;
+        if let Some(name) = select {
+            pane.select_name(name);
+        }
+        Ok(())
+    }
+
+    /// Enter the selected directory
+    pub async fn open_selected(&mut self) -> Result<()> {
+        let side = self.focus;
+        let pane = self.pane(side);
+        let Some(entry) = pane.selected_entry().filter(|e| e.is_dir) else {
+            return Ok(());
+        };
+        let path = side.join(&pane.path, &entry.name);
+        self.change_dir(path, None).await
+    }
+
+    /// Go up one directory, keeping the one we came from selected
+    pub async fn parent(&mut self) -> Result<()> {
+        let side = self.focus;
+        let Some((parent, name)) = side.split(&self.pane(side).path) else {
+            return Ok(());
+        };
+        self.change_dir(parent, Some(&name)).await
+    }
+
+    /// Copy the selected entry into the directory shown in the other pane
+    pub fn start_transfer(&mut self) -> Result<()> {
+        let client = self.client()?;
+        let side = self.focus;
+        let Some(entry) = self.pane(side).selected_entry().cloned() else {
+            return Ok(());
+        };
+        let from = side.join(&self.pane(side).path, &entry.name);
+        let to = side.other().join(&self.pane(side.other()).path, &entry.name);
+        let direction = match side {
+            Side::Local => Direction::Upload,
+            Side::Remote => Direction::Download,
+        };
+
+        // Drop the oldest finished transfers so the list stays short
+        let finished = self.transfers.iter().filter(|t| t.status != TransferStatus::Running).count();
+        let mut excess = finished.saturating_sub(FINISHED_KEPT - 1);
+        self.transfers.retain(|t| {
+            let drop = excess > 0 && t.status != TransferStatus::Running;
+            excess -= drop as usize;
+            !drop
+        });
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.transfers.push(Transfer {
+            id,
+            direction,
+            name: entry.name,
+            done: 0,
+            total: entry.size,
+            status: TransferStatus::Running,
+        });
+        let tx = self.tx.clone();
+        tokio::spawn(async move {
+            let mut report = |done, total| {
+                let _ = tx.send(SftpEvent::Progress { id, done, total });
+            };
+            let error = transfer(&client, direction, &from, &to, false, &mut report)
+                .await
+                .err()
+                .map(|e| format!("{:#}", e));
+            let _ = tx.send(SftpEvent::Finished { id, error });
+        });
+        Ok(())
+    }
+
+    /// Ask for the details of a rename, mkdir or delete in the focused pane
+    pub fn start_prompt(&mut self, kind: PromptKind) {
+        let side = self.focus;
+        let target = self.pane(side).selected_entry().cloned();
+        if kind != PromptKind::MakeDir && target.is_none() {
+            return;
+        }
+        let mut input = match kind {
+            PromptKind::Rename => InputField::new("New name", ""),
+            PromptKind::MakeDir => InputField::new("Directory name", "new-folder"),
+            PromptKind::Delete => InputField::new("Delete", ""),
+        };
+        if let (PromptKind::Rename, Some(entry)) = (kind, &target) {
+            input.value = entry.name.clone();
+            input.move_cursor_to_end();
+        }
+        input.is_focused = true;
+        self.prompt = Some(SftpPrompt { kind, side, input, target });
+    }
+
+    /// Carry out the open prompt and close it. Remote deletes can take a
+    /// while on big trees, so they run in the background and end in a
+    /// `Removed` event.
+    pub async fn submit_prompt(&mut self) -> Result<()> {
+        let Some(prompt) = self.prompt.take() else {
+            return Ok(());
+        };
+        let side = prompt.side;
+        let dir = self.pane(side).path.clone();
+        let name = prompt.input.value.trim().to_string();
+        if prompt.kind != PromptKind::Delete && (name.is_empty() || name.contains('/')) {
+            bail!("'{}' is not a valid name", name);
+        }
+
+        match (prompt.kind, &prompt.target) {
+            (PromptKind::MakeDir, _) => {
+                let path = side.join(&dir, &name);
+                match side {
+                    Side::Local => std::fs::create_dir(&path).with_context(|| format!("Failed to create {}", path))?,
+                    Side::Remote => self.client()?.mkdir(&path).await?,
+                }
+                self.reload(side).await?;
+                self.pane_mut(side).select_name(&name);
+            }
+            (PromptKind::Rename, Some(entry)) => {
+                let (from, to) = (side.join(&dir, &entry.name), side.join(&dir, &name));
+                match side {
+                    Side::Local => std::fs::rename(&from, &to).with_context(|| format!("Failed to rename {}", from))?,
+                    Side::Remote => self.client()?.rename(&from, &to).await?,
+                }
+                self.reload(side).await?;
+                self.pane_mut(side).select_name(&name);
+            }
+            (PromptKind::Delete, Some(entry)) => {
+                let path = side.join(&dir, &entry.name);
+                match side {
+                    Side::Local if entry.is_dir && !entry.is_link => {
+                        std::fs::remove_dir_all(&path).with_context(|| format!("Failed to delete {}", path))?
+                    }
+                    Side::Local => std::fs::remove_file(&path).with_context(|| format!("Failed to delete {}", path))?,
+                    Side::Remote => {
+                        let client = self.client()?;
+                        let is_dir = entry.is_dir && !entry.is_link;
+                        let tx = self.tx.clone();
+                        self.removing += 1;
+                        tokio::spawn(async move {
+                            let error = client.remove(&path, is_dir).await.err().map(|e| format!("{:#}", e));
+                            let _ = tx.send(SftpEvent::Removed { error });
+                        });
+                        return Ok(());
+                    }
+                }
+                self.reload(side).await?;
+            }
+            (_, None) => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use russh_sftp::protocol::{
+        Attrs, Data, File, FileAttributes, Handle, Name, OpenFlags, Status, StatusCode, Version,
+    };
+    use std::collections::HashMap;
+    use std::io::{Read, Seek, SeekFrom, Write};
+    use std::time::Duration;
+
+    /// Minimal SFTP server serving a local directory as "/", standing in for
+    /// sshd's sftp subsystem
+    struct StandIn {
+        root: PathBuf,
+        handles: HashMap<String, Open>,
+        next: u32,
+    }
+
+    enum Open {
+        File(std::fs::File),
+        /// Listing not sent yet, then None so the next read reports EOF
+        Dir(Option<Vec<File>>),
+    }
+
+    impl StandIn {
+        fn real(&self, path: &str) -> PathBuf {
+            self.root.join(path.trim_start_matches('/'))
+        }
+
+        fn handle(&mut self, open: Open) -> String {
+            self.next += 1;
+            let handle = self.next.to_string();
+            self.handles.insert(handle.clone(), open);
+            handle
+        }
+
+        fn ok(id: u32) -> Status {
+            Status { id, status_code: StatusCode::Ok, error_message: "Ok".to_string(), language_tag: "en-US".to_string() }
+        }
+    }
+
+    fn status(e: std::io::Error) -> StatusCode {
+        match e.kind() {
+            std::io::ErrorKind::NotFound => StatusCode::NoSuchFile,
+            std::io::ErrorKind::PermissionDenied => StatusCode::PermissionDenied,
+            _ => StatusCode::Failure,
+        }
+    }
+
+    impl russh_sftp::server::Handler for StandIn {
+        type Error = StatusCode;
+
+        fn unimplemented(&self) -> Self::Error {
+            StatusCode::OpUnsupported
+        }
+
+        async fn init(&mut self, _version: u32, _extensions: HashMap<String, String>) -> Result<Version, Self::Error> {
+            Ok(Version::new())
+        }
+
+        async fn realpath(&mut self, id: u32, path: String) -> Result<Name, Self::Error> {
+            let root = std::fs::canonicalize(&self.root).map_err(status)?;
+            let real = std::fs::canonicalize(self.real(&path)).map_err(status)?;
+            let inside = real.strip_prefix(&root).map_err(|_| StatusCode::NoSuchFile)?;
+            Ok(Name { id, files: vec![File::dummy(format!("/{}", inside.to_string_lossy()))] })
+        }
+
+        async fn stat(&mut self, id: u32, path: String) -> Result<Attrs, Self::Error> {
+            let meta = std::fs::metadata(self.real(&path)).map_err(status)?;
+            Ok(Attrs { id, attrs: FileAttributes::from(&meta) })
+        }
+
+        async fn lstat(&mut self, id: u32, path: String) -> Result<Attrs, Self::Error> {
+            self.stat(id, path).await
+        }
+
+        async fn opendir(&mut self, id: u32, path: String) -> Result<Handle, Self::Error> {
+            let mut files = Vec::new();
+            for entry in std::fs::read_dir(self.real(&path)).map_err(status)? {
+                let entry = entry.map_err(status)?;
+                let meta = entry.metadata().map_err(status)?;
+                files.push(File::new(entry.file_name().to_string_lossy(), FileAttributes::from(&meta)));
+            }
+            let handle = self.handle(Open::Dir(Some(files)));
+            Ok(Handle { id, handle })
+        }
+
+        async fn readdir(&mut self, id: u32, handle: String) -> Result<Name, Self::Error> {
+            match self.handles.get_mut(&handle) {
+                Some(Open::Dir(files)) => files.take().map(|files| Name { id, files }).ok_or(StatusCode::Eof),
+                _ => Err(StatusCode::Failure),
+            }
+        }
+
+        async fn open(&mut self, id: u32, filename: String, pflags: OpenFlags, _attrs: FileAttributes) -> Result<Handle, Self::Error> {
+            let file = std::fs::OpenOptions::from(pflags).open(self.real(&filename)).map_err(status)?;
+            let handle = self.handle(Open::File(file));
+            Ok(Handle { id, handle })
+        }
+
+        async fn read(&mut self, id: u32, handle: String, offset: u64, len: u32) -> Result<Data, Self::Error> {
+            let Some(Open::File(file)) = self.handles.get_mut(&handle) else {
+                return Err(StatusCode::Failure);
+            };
+            let mut data = vec![0; len as usize];
+            file.seek(SeekFrom::Start(offset)).map_err(status)?;
+            let n = file.read(&mut data).map_err(status)?;
+            if n == 0 {
+                return Err(StatusCode::Eof);
+            }
+            data.truncate(n);
+            Ok(Data { id, data })
+        }
+
+        async fn write(&mut self, id: u32, handle: String, offset: u64, data: Vec<u8>) -> Result<Status, Self::Error> {
+            let Some(Open::File(file)) = self.handles.get_mut(&handle) else {
+                return Err(StatusCode::Failure);
+            };
+            file.seek(SeekFrom::Start(offset)).map_err(status)?;
+            file.write_all(&data).map_err(status)?;
+            Ok(Self::ok(id))
+        }
+
+        async fn close(&mut self, id: u32, handle: String) -> Result<Status, Self::Error> {
+            self.handles.remove(&handle);
+            Ok(Self::ok(id))
+        }
+
+        async fn mkdir(&mut self, id: u32, path: String, _attrs: FileAttributes) -> Result<Status, Self::Error> {
+            std::fs::create_dir(self.real(&path)).map_err(status)?;
+            Ok(Self::ok(id))
+        }
+
+        async fn rmdir(&mut self, id: u32, path: String) -> Result<Status, Self::Error> {
+            std::fs::remove_dir(self.real(&path)).map_err(status)?;
+            Ok(Self::ok(id))
+        }
+
+        async fn remove(&mut self, id: u32, filename: String) -> Result<Status, Self::Error> {
+            std::fs::remove_file(self.real(&filename)).map_err(status)?;
+            Ok(Self::ok(id))
+        }
+
+        async fn rename(&mut self, id: u32, oldpath: String, newpath: String) -> Result<Status, Self::Error> {
+            std::fs::rename(self.real(&oldpath), self.real(&newpath)).map_err(status)?;
+            Ok(Self::ok(id))
+        }
+    }
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ghost-sftp-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    async fn stand_in(root: &Path) -> SftpClient {
+        let (client_end, server_end) = tokio::io::duplex(64 * 1024);
+        let handler = StandIn { root: root.to_path_buf(), handles: HashMap::new(), next: 0 };
+        russh_sftp::server::run(server_end, handler).await;
+        SftpClient::from_stream(client_end).await.unwrap()
+    }
+
+    async fn wait_for_transfers(browser: &mut SftpBrowser) {
+        for _ in 0..200 {
+            browser.poll_events().await;
+            if !browser.is_busy() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("transfers did not finish: {:?}", browser.transfers);
+    }
+
+    fn names(pane: &Pane) -> Vec<&str> {
+        pane.entries.iter().map(|e| e.name.as_str()).collect()
+    }
+
+    #[test]
+    fn remote_paths_split_and_join_with_slashes() {
+        assert_eq!(Side::Remote.join("/", "etc"), "/etc");
+        assert_eq!(Side::Remote.join("/home/me", "notes"), "/home/me/notes");
+        assert_eq!(Side::Remote.split("/home/me"), Some(("/home".to_string(), "me".to_string())));
+        assert_eq!(Side::Remote.split("/etc"), Some(("/".to_string(), "etc".to_string())));
+        assert_eq!(Side::Remote.split("/"), None);
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+    }
+
+    #[tokio::test]
+    async fn browses_and_transfers_against_a_stand_in_server() {
+        let local = scratch_dir("local");
+        let remote = scratch_dir("remote");
+        std::fs::create_dir(local.join("project")).unwrap();
+        std::fs::write(local.join("project/main.rs"), vec![b'x'; 100_000]).unwrap();
+        std::fs::write(remote.join("log.txt"), "remote log").unwrap();
+        std::fs::create_dir(remote.join("srv")).unwrap();
+
+        let client = stand_in(&remote).await;
+        let mut browser = SftpBrowser::with_client(client, &local.to_string_lossy(), "/").await.unwrap();
+        assert_eq!(names(&browser.remote), ["srv", "log.txt"]);
+
+        // Upload a directory tree from the local pane
+        browser.start_transfer().unwrap();
+        wait_for_transfers(&mut browser).await;
+        assert_eq!(browser.transfers[0].status, TransferStatus::Done);
+        assert_eq!(browser.transfers[0].done, 100_000);
+        assert_eq!(std::fs::read(remote.join("project/main.rs")).unwrap().len(), 100_000);
+        assert_eq!(names(&browser.remote), ["project", "srv", "log.txt"]);
+
+        // Download a file from the remote pane
+        browser.switch_pane();
+        browser.remote.select_name("log.txt");
+        browser.start_transfer().unwrap();
+        wait_for_transfers(&mut browser).await;
+        assert_eq!(std::fs::read_to_string(local.join("log.txt")).unwrap(), "remote log");
+
+        // mkdir, enter it and come back
+        browser.start_prompt(PromptKind::MakeDir);
+        browser.prompt.as_mut().unwrap().input.value = "backups".to_string();
+        browser.submit_prompt().await.unwrap();
+        assert!(remote.join("backups").is_dir());
+        assert_eq!(browser.remote.selected_entry().unwrap().name, "backups");
+        browser.open_selected().await.unwrap();
+        assert_eq!(browser.remote.path, "/backups");
+        browser.parent().await.unwrap();
+        assert_eq!(browser.remote.path, "/");
+        assert_eq!(browser.remote.selected_entry().unwrap().name, "backups");
+
+        // Rename, then delete the uploaded tree recursively
+        browser.remote.select_name("log.txt");
+        browser.start_prompt(PromptKind::Rename);
+        browser.prompt.as_mut().unwrap().input.value = "old.txt".to_string();
+        browser.submit_prompt().await.unwrap();
+        assert!(remote.join("old.txt").is_file());
+
+        browser.remote.select_name("project");
+        browser.start_prompt(PromptKind::Delete);
+        browser.submit_prompt().await.unwrap();
+        wait_for_transfers(&mut browser).await;
+        assert!(!remote.join("project").exists());
+        assert_eq!(names(&browser.remote), ["backups", "srv", "old.txt"]);
+
+        let _ = std::fs::remove_dir_all(&local);
+        let _ = std::fs::remove_dir_all(&remote);
+    }
+
+    #[tokio::test]
+    async fn resumed_transfers_continue_partial_files() {
+        let local = scratch_dir("resume-local");
+        let remote = scratch_dir("resume-remote");
+        std::fs::create_dir(local.join("site")).unwrap();
+        std::fs::write(local.join("site/index.html"), "<html>hello</html>").unwrap();
+        std::fs::write(local.join("site/app.js"), "let x = 1;").unwrap();
+        // A previous attempt finished one file and died halfway through the
+        // other; the partial copy is marked so a full resend would show
+        std::fs::create_dir(remote.join("site")).unwrap();
+        std::fs::write(remote.join("site/index.html"), "<HTML>hel").unwrap();
+        std::fs::write(remote.join("site/app.js"), "let x = 1;").unwrap();
+
+        let client = stand_in(&remote).await;
+        let mut reports = Vec::new();
+        let from = local.join("site").to_string_lossy().into_owned();
+        transfer(&client, Direction::Upload, &from, "/site", true, &mut |done, total| reports.push((done, total)))
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(remote.join("site/index.html")).unwrap(), "<HTML>hello</html>");
+        assert_eq!(std::fs::read_to_string(remote.join("site/app.js")).unwrap(), "let x = 1;");
+        assert_eq!(reports.first(), Some(&(0, 28)));
+        assert_eq!(reports.last(), Some(&(28, 28)));
+
+        let _ = std::fs::remove_dir_all(&local);
+        let _ = std::fs::remove_dir_all(&remote);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn linked_directory_loops_are_copied_once() {
+        let local = scratch_dir("loop-local");
+        let remote = scratch_dir("loop-remote");
+        for side in [&local, &remote] {
+            std::fs::create_dir(side.join("tree")).unwrap();
+            std::fs::write(side.join("tree/file.txt"), "data").unwrap();
+            std::os::unix::fs::symlink(side.join("tree"), side.join("tree/again")).unwrap();
+        }
+
+        let client = stand_in(&remote).await;
+        let from = local.join("tree").to_string_lossy().into_owned();
+        transfer(&client, Direction::Upload, &from, "/up", false, &mut |_, _| {}).await.unwrap();
+        assert_eq!(std::fs::read_to_string(remote.join("up/file.txt")).unwrap(), "data");
+        assert!(!remote.join("up/again").exists());
+
+        let to = local.join("down").to_string_lossy().into_owned();
+        transfer(&client, Direction::Download, "/tree", &to, false, &mut |_, _| {}).await.unwrap();
+        assert_eq!(std::fs::read_to_string(local.join("down/file.txt")).unwrap(), "data");
+        assert!(!local.join("down/again").exists());
+
+        let _ = std::fs::remove_dir_all(&local);
+        let _ = std::fs::remove_dir_all(&remote);
+    }
+}
@@ -1,4 +1,9 @@
-use crate::models::{AuthStrength, HealthStatus, ServerConnection};
+use crate::askpass::Askpass;
+use crate::config::{DeepCheckConfig, MultiplexerTarget, TerminalConfig};
+use crate::embedded::EmbeddedSession;
+use crate::facts::HostFacts;
+use crate::models::{AuthStrength, HealthStatus, LoginCheck, ServerConnection, SessionKind};
+use crate::native::{self, NeedsSecret, Secrets};
 use anyhow::{Context, Result};
 use chrono::Utc;
 use std::collections::HashMap;
@@ -21,6 +26,10 @@ pub enum AvailableTerminal {
     Wezterm,
     Ghostty,
     Warp,
+    /// New window or split in the tmux session Ghost runs in
+    Tmux,
+    /// New pane or tab in the zellij session Ghost runs in
+    Zellij,
     None, // Fallback to direct mode
 }
 
@@ -30,9 +39,43 @@ pub enum ConnectionMode {
     Auto,        // Try new terminal, fallback to direct
     NewTerminal, // Force new terminal (fail if none available)
     Direct,      // Always use current direct approach
+    Embedded,    // Run ssh in a tab inside Ghost
+    Tmux,        // Force a tmux window/split (Ghost must run inside tmux)
+    Zellij,      // Force a zellij tab/pane (Ghost must run inside zellij)
+    Native,      // Log in with Ghost's own SSH client, in a tab inside Ghost
+}
+
+/// Whether an OpenSSH client is installed; without one, Ghost connects
+/// with its own
+pub fn openssh_available() -> bool {
+    which::which("ssh").is_ok()
 }
 
 impl AvailableTerminal {
+    /// Every launchable terminal, in detection order after the multiplexers
+    pub fn all() -> [AvailableTerminal; 13] {
+        [
+            AvailableTerminal::Tmux,
+            AvailableTerminal::Zellij,
+            AvailableTerminal::Ghostty,
+            AvailableTerminal::Alacritty,
+            AvailableTerminal::Kitty,
+            AvailableTerminal::Wezterm,
+            AvailableTerminal::GnomeTerminal,
+            AvailableTerminal::Konsole,
+            AvailableTerminal::XfceTerminal,
+            AvailableTerminal::XTerm,
+            AvailableTerminal::MacTerminal,
+            AvailableTerminal::WindowsTerminal,
+            AvailableTerminal::Warp,
+        ]
+    }
+
+    /// The built-in terminal whose command is `name` (as used by `preferred`)
+    pub fn from_command_name(name: &str) -> Option<AvailableTerminal> {
+        Self::all().into_iter().find(|t| t.command_name() == Some(name))
+    }
+
     /// Get the command name for this terminal
     pub fn command_name(&self) -> Option<&'static str> {
         match self {
@@ -47,6 +90,8 @@ impl AvailableTerminal {
             AvailableTerminal::Wezterm => Some("wezterm"),
             AvailableTerminal::Ghostty => Some("ghostty"),
             AvailableTerminal::Warp => Some("warp"),
+            AvailableTerminal::Tmux => Some("tmux"),
+            AvailableTerminal::Zellij => Some("zellij"),
             AvailableTerminal::None => None,
         }
     }
@@ -68,6 +113,14 @@ impl AvailableTerminal {
                     }
                     return false;
                 }
+
+                // Multiplexers only make sense from inside one of their sessions
+                if *self == AvailableTerminal::Tmux && std::env::var_os("TMUX").is_none() {
+                    return false;
+                }
+                if *self == AvailableTerminal::Zellij && std::env::var_os("ZELLIJ").is_none() {
+                    return false;
+                }
                 
                 // Check if command exists in PATH using cross-platform approach
                 #[cfg(unix)]
@@ -154,11 +207,213 @@ impl AvailableTerminal {
                 );
                 Some(vec!["-e".to_string(), script])
             }
+            // Launched by `SSHManager::launch_in_multiplexer`, which needs
+            // the pane's details back rather than a detached window
+            AvailableTerminal::Tmux | AvailableTerminal::Zellij => None,
             AvailableTerminal::None => None,
         }
     }
 }
 
+/// What `connect_with_mode` opens a new-terminal connection with
+#[derive(Debug, Clone, PartialEq)]
+enum Launcher {
+    Builtin(AvailableTerminal),
+    /// A `[terminal.templates]` entry
+    Template(Vec<String>),
+}
+
+/// Problems with the `[terminal]` section, reported at startup
+pub fn terminal_config_errors(config: &TerminalConfig) -> Vec<String> {
+    let mut errors = Vec::new();
+    for (name, template) in &config.templates {
+        if let Some(problem) = template_problem(template) {
+            errors.push(format!("terminal.templates.{}: {}", name, problem));
+        }
+    }
+    if let Some(preferred) = &config.preferred {
+        if !config.templates.contains_key(preferred) && AvailableTerminal::from_command_name(preferred).is_none() {
+            let builtins: Vec<&str> = AvailableTerminal::all().iter().filter_map(|t| t.command_name()).collect();
+            errors.push(format!(
+                "terminal.preferred: unknown terminal '{}' (built-ins: {}; or a name from [terminal.templates])",
+                preferred,
+                builtins.join(", ")
+            ));
+        }
+    }
+    errors
+}
+
+/// What's wrong with a launch template, if it can't be used
+fn template_problem(template: &[String]) -> Option<&'static str> {
+    if template.first().map_or(true, |program| program.is_empty() || program == "{argv}") {
+        Some("the first element must be the terminal program")
+    } else if !template.iter().any(|arg| arg == "{argv}") {
+        Some("needs an \"{argv}\" element for the ssh command")
+    } else {
+        None
+    }
+}
+
+/// Expand a launch template: an element that is exactly `{argv}` becomes
+/// `program` and its arguments as separate elements, and `{title}` is
+/// replaced inside any element. Nothing is passed through a shell.
+fn expand_template(template: &[String], title: &str, program: &str, args: &[String]) -> Vec<String> {
+    let mut argv = Vec::with_capacity(template.len() + args.len() + 1);
+    for element in template {
+        if element == "{argv}" {
+            argv.push(program.to_string());
+            argv.extend_from_slice(args);
+        } else {
+            argv.push(element.replace("{title}", title));
+        }
+    }
+    argv
+}
+
+/// Spawn a terminal detached from Ghost's stdio and return its pid
+async fn spawn_detached(argv: &[String]) -> Result<u32> {
+    use std::process::Stdio;
+    let (program, args) = argv.split_first().ok_or_else(|| anyhow::anyhow!("Empty terminal command"))?;
+    let child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to spawn terminal process '{}'", program))?;
+
+    let pid = child.id();
+
+    // Reap the terminal in the background when it eventually exits. This lets
+    // the window run independently of Ghost without leaking a zombie process
+    // (the previous `mem::forget` left the child unwaited-for).
+    std::thread::spawn(move || {
+        let mut child = child;
+        let _ = child.wait();
+    });
+
+    // Small delay to ensure the terminal has time to launch.
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+    Ok(pid)
+}
+
+/// Format tmux prints for a new pane: its id and the pid of the command in it
+const TMUX_PANE_FORMAT: &str = "#{pane_id} #{pane_pid}";
+
+/// `tmux` arguments that run `program args` in a new window named `title`
+/// (or a split of the current one) and print `TMUX_PANE_FORMAT`. With more
+/// than one argument after `--` tmux execs the argv itself, so the pane pid
+/// is ssh's own.
+fn tmux_args(target: MultiplexerTarget, title: &str, program: &str, args: &[String]) -> Vec<String> {
+    let mut v: Vec<String> = match target {
+        MultiplexerTarget::Window => vec!["new-window".into(), "-n".into(), title.into()],
+        MultiplexerTarget::Split => vec!["split-window".into(), "-h".into()],
+    };
+    v.extend(["-P".into(), "-F".into(), TMUX_PANE_FORMAT.into(), "--".into(), program.into()]);
+    v.extend_from_slice(args);
+    v
+}
+
+/// Parse tmux's `%12 4242` reply into (pane id, pid)
+fn parse_tmux_pane(output: &str) -> Option<(String, u32)> {
+    let mut fields = output.split_whitespace();
+    let pane = fields.next().filter(|p| p.starts_with('%'))?;
+    let pid = fields.next()?.parse().ok()?;
+    Some((pane.to_string(), pid))
+}
+
+/// `zellij run` arguments for a pane named `title` that closes when the
+/// command exits
+fn zellij_run_args(title: &str, program: &str, args: &[String]) -> Vec<String> {
+    let mut v: Vec<String> = vec![
+        "run".into(),
+        "--name".into(),
+        title.into(),
+        "--close-on-exit".into(),
+        "--".into(),
+        program.into(),
+    ];
+    v.extend_from_slice(args);
+    v
+}
+
+/// The pid to track a launched session by
+#[derive(Debug)]
+pub enum SessionPid {
+    Known(u32),
+    /// Still being looked for; `None` once it's given up on, leaving the
+    /// session untracked
+    Pending(tokio::task::JoinHandle<Option<u32>>),
+}
+
+/// An ssh command about to be handed to a terminal or multiplexer that
+/// doesn't tell us what it started. Where possible ssh runs behind the
+/// pid-reporting wrapper (see `wrapper`); otherwise its pid is looked up by
+/// command line once it's running.
+struct TrackedSsh {
+    ssh_argv: Vec<String>,
+    program: String,
+    args: Vec<String>,
+    #[cfg(unix)]
+    listener: Option<crate::wrapper::PidListener>,
+}
+
+impl TrackedSsh {
+    fn new(server: &ServerConnection, askpass: Option<&Askpass>) -> Self {
+        let mut ssh_argv = vec!["ssh".to_string()];
+        ssh_argv.extend(build_ssh_args(server));
+        let (program, args) = ssh_command(server, askpass);
+
+        #[cfg(unix)]
+        {
+            let listener = crate::wrapper::PidListener::bind().ok();
+            if let Some((program, args)) = listener.as_ref().and_then(|l| l.wrap(&program, &args)) {
+                return Self { ssh_argv, program, args, listener };
+            }
+        }
+        Self {
+            ssh_argv,
+            program,
+            args,
+            #[cfg(unix)]
+            listener: None,
+        }
+    }
+
+    /// Look for the pid of ssh on a task of its own, as it can take a few
+    /// seconds to start. `fallback` is used when it isn't found.
+    fn resolve_later(self, tracked: &[u32], fallback: Option<u32>) -> SessionPid {
+        let tracked = tracked.to_vec();
+        SessionPid::Pending(tokio::spawn(async move { self.resolve(&tracked).await.or(fallback) }))
+    }
+
+    /// The pid of ssh itself, once it has started. Pids in `tracked` belong
+    /// to sessions already being tracked, so an identical ssh to the same
+    /// server is never mistaken for this one.
+    async fn resolve(self, tracked: &[u32]) -> Option<u32> {
+        #[cfg(unix)]
+        if let Some(listener) = &self.listener {
+            if let Some(pid) = listener.wait(crate::wrapper::REPORT_TIMEOUT).await {
+                return Some(pid);
+            }
+        }
+
+        // The wrapper never checked in (or wasn't used): look for ssh itself
+        let deadline = Instant::now() + Duration::from_secs(1);
+        loop {
+            if let Some(pid) = crate::process::find_by_cmdline(&self.ssh_argv, tracked) {
+                return Some(pid);
+            }
+            if !cfg!(target_os = "linux") || Instant::now() >= deadline {
+                return None;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+}
+
 /// Build the argument vector passed to `ssh` (everything after the `ssh`
 /// program name) as separate argv elements — never assembled into a shell
 /// string. Shared by both the direct and new-terminal launch paths.
@@ -198,6 +453,16 @@ fn build_ssh_args(server: &ServerConnection) -> Vec<String> {
     args
 }
 
+/// What runs ssh for `server`: `ssh` itself, or `env` pointing it at the
+/// askpass helper when a stored password is on offer. `env` execs ssh, so the
+/// pid and the command line ssh ends up with are the same either way.
+fn ssh_command(server: &ServerConnection, askpass: Option<&Askpass>) -> (String, Vec<String>) {
+    let args = build_ssh_args(server);
+    askpass
+        .and_then(|askpass| askpass.wrap("ssh", &args))
+        .unwrap_or_else(|| ("ssh".to_string(), args))
+}
+
 /// POSIX-shell single-quote escaping: wrap in '...' and escape embedded quotes.
 fn shell_quote(s: &str) -> String {
     let mut out = String::with_capacity(s.len() + 2);
@@ -220,6 +485,13 @@ fn applescript_escape(s: &str) -> String {
 
 /// Detect the best available terminal emulator
 pub fn detect_available_terminal() -> AvailableTerminal {
+    // Inside a multiplexer, keep connections next to Ghost
+    for multiplexer in [AvailableTerminal::Tmux, AvailableTerminal::Zellij] {
+        if multiplexer.is_available() {
+            return multiplexer;
+        }
+    }
+
     // Check environment variables first for better detection
     if let Ok(term_program) = std::env::var("TERM_PROGRAM") {
         match term_program.as_str() {
@@ -266,9 +538,25 @@ pub fn detect_available_terminal() -> AvailableTerminal {
     AvailableTerminal::None
 }
 
+/// Give up on host facts from a server that's slow to report them
+const FACTS_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// SSH connection manager
 pub struct SSHManager {
     connections: HashMap<String, bool>, // Simple connection tracking for now
+    terminal: TerminalConfig,
+    health: HealthCheck,
+}
+
+/// How servers are health checked. Owns nothing but settings, so a check
+/// (which may log in and take a while) runs on a copy instead of holding
+/// the manager that connections need.
+#[derive(Debug, Clone, Default)]
+pub struct HealthCheck {
+    /// Log in during health checks instead of only opening the port
+    verify_login: bool,
+    /// Also collect host facts while logged in
+    deep_check: DeepCheckConfig,
 }
 
 impl Default for SSHManager {
@@ -279,65 +567,46 @@ impl Default for SSHManager {
 
 impl SSHManager {
     pub fn new() -> Self {
+        Self::with_terminal_config(TerminalConfig::default())
+    }
+
+    pub fn with_terminal_config(terminal: TerminalConfig) -> Self {
         Self {
             connections: HashMap::new(),
+            terminal,
+            health: HealthCheck::default(),
         }
     }
 
-    /// Perform a simple connectivity test with security assessment
-    pub async fn quick_health_check(&self, server: &ServerConnection) -> Result<ConnectionTestResult> {
-        let start_time = Instant::now();
-        let address = format!("{}:{}", server.host, server.port);
-        
-        let result = timeout(
-            Duration::from_secs(5), // Quick timeout for health checks
-            TcpStream::connect(&address)
-        ).await;
-
-        let latency = start_time.elapsed();
+    /// Make health checks of reachable servers also log in (natively)
+    pub fn verify_logins(mut self, verify: bool) -> Self {
+        self.health.verify_login = verify;
+        self
+    }
 
-        match result {
-            Ok(Ok(_)) => {
-                // Reachable — surface the configured auth method as a hint.
-                let auth_strength = self.assess_auth_strength(server);
-                Ok(ConnectionTestResult {
-                    status: HealthStatus::Online,
-                    auth_strength,
-                    latency: Some(latency),
-                    error_message: None,
-                })
-            },
-            Ok(Err(e)) => Ok(ConnectionTestResult {
-                status: HealthStatus::Offline,
-                auth_strength: AuthStrength::Unknown,
-                latency: Some(latency),
-                error_message: Some(format!("Connection failed: {}", e)),
-            }),
-            Err(_) => Ok(ConnectionTestResult {
-                status: HealthStatus::Offline,
-                auth_strength: AuthStrength::Unknown,
-                latency: Some(latency),
-                error_message: Some("Connection timeout".to_string()),
-            }),
-        }
+    /// Make health checks collect host facts when `deep_check.enabled`,
+    /// which implies logging in
+    pub fn with_deep_check(mut self, deep_check: DeepCheckConfig) -> Self {
+        self.health.deep_check = deep_check;
+        self
     }
 
-    /// Map the configured auth method to an at-a-glance strength hint.
-    ///
-    /// This is a reflection of LOCAL config only — it does not (and cannot, from
-    /// a plain TCP connect) audit the remote host's actual security posture. Its
-    /// only job is to make weaker auth choices (password) visually stand out.
-    fn assess_auth_strength(&self, server: &ServerConnection) -> AuthStrength {
-        match &server.auth_method {
-            crate::models::AuthMethod::PublicKey { .. } => AuthStrength::Key,
-            crate::models::AuthMethod::Agent => AuthStrength::Agent,
-            crate::models::AuthMethod::Password => AuthStrength::Password,
-            crate::models::AuthMethod::Interactive => AuthStrength::Interactive,
-        }
+    /// The health check settings, to check servers without holding on to
+    /// the manager
+    pub fn health_check(&self) -> HealthCheck {
+        self.health.clone()
     }
 
-    /// Connect to a server with a specific connection mode
-    pub async fn connect_with_mode(&mut self, server: &ServerConnection, mode: ConnectionMode) -> Result<u32> {
+    /// Connect to a server with a specific connection mode, answering its
+    /// password prompt from `askpass` if given. `tracked` holds the pids of
+    /// sessions already tracked, which the new session can't be.
+    pub async fn connect_with_mode(
+        &mut self,
+        server: &ServerConnection,
+        mode: ConnectionMode,
+        askpass: Option<&Askpass>,
+        tracked: &[u32],
+    ) -> Result<(SessionPid, SessionKind)> {
         // We deliberately do NOT pre-gate on a raw TCP reachability probe.
         // Hosts behind a bastion/ProxyJump, with port-knocking, or that drop
         // port scans are perfectly connectable via ssh even when a direct TCP
@@ -348,24 +617,169 @@ impl SSHManager {
         match mode {
             ConnectionMode::Auto => {
                 // Try a new terminal first, fall back to direct if none available.
-                let available_terminal = detect_available_terminal();
-                if available_terminal != AvailableTerminal::None {
-                    self.launch_ssh_in_new_terminal(server, available_terminal).await
-                } else {
-                    self.launch_ssh_session(server).await
+                match self.choose_launcher() {
+                    Some(launcher) => self.launch_in_terminal(server, launcher, askpass, tracked).await,
+                    None => self.launch_ssh_session(server, askpass).await.map(|pid| (SessionPid::Known(pid), SessionKind::External)),
                 }
             }
             ConnectionMode::NewTerminal => {
-                let available_terminal = detect_available_terminal();
-                if available_terminal != AvailableTerminal::None {
-                    self.launch_ssh_in_new_terminal(server, available_terminal).await
+                if let Some(launcher) = self.choose_launcher() {
+                    self.launch_in_terminal(server, launcher, askpass, tracked).await
                 } else {
                     Err(anyhow::anyhow!("No terminal emulator available for new terminal mode. Available terminals: Ghostty, Alacritty, Kitty, Wezterm, GNOME Terminal, Konsole, XFCE Terminal, XTerm"))
                 }
             }
-            ConnectionMode::Direct => self.launch_ssh_session(server).await,
+            ConnectionMode::Direct => self.launch_ssh_session(server, askpass).await.map(|pid| (SessionPid::Known(pid), SessionKind::External)),
+            ConnectionMode::Tmux | ConnectionMode::Zellij => {
+                let (multiplexer, env) = if mode == ConnectionMode::Tmux {
+                    (AvailableTerminal::Tmux, "TMUX")
+                } else {
+                    (AvailableTerminal::Zellij, "ZELLIJ")
+                };
+                if multiplexer.is_available() {
+                    self.launch_in_multiplexer(server, multiplexer, askpass, tracked).await
+                } else {
+                    Err(anyhow::anyhow!(
+                        "Not running inside {} (${} is unset) or it isn't on PATH",
+                        multiplexer.command_name().unwrap_or_default(),
+                        env
+                    ))
+                }
+            }
+            ConnectionMode::Embedded => Err(anyhow::anyhow!(
+                "Embedded sessions are owned by the UI; use spawn_embedded"
+            )),
+            ConnectionMode::Native => Err(anyhow::anyhow!(
+                "Native sessions are owned by the UI; use open_native"
+            )),
         }
     }
+
+    /// The pinned terminal if it can be used, otherwise the first one
+    /// detected; `None` when there's nothing to open a window with.
+    fn choose_launcher(&self) -> Option<Launcher> {
+        if let Some(preferred) = &self.terminal.preferred {
+            if let Some(template) = self.terminal.templates.get(preferred) {
+                if template_problem(template).is_none() {
+                    return Some(Launcher::Template(template.clone()));
+                }
+            }
+            // An unknown, missing or invalid preferred terminal was reported
+            // at startup; fall back to detection rather than failing to connect
+            if let Some(terminal) = AvailableTerminal::from_command_name(preferred).filter(|t| t.is_available()) {
+                return Some(Launcher::Builtin(terminal));
+            }
+        }
+        match detect_available_terminal() {
+            AvailableTerminal::None => None,
+            terminal => Some(Launcher::Builtin(terminal)),
+        }
+    }
+
+    /// Launch in a terminal emulator window, a multiplexer pane or a
+    /// user-defined template
+    async fn launch_in_terminal(
+        &self,
+        server: &ServerConnection,
+        launcher: Launcher,
+        askpass: Option<&Askpass>,
+        tracked: &[u32],
+    ) -> Result<(SessionPid, SessionKind)> {
+        if let Launcher::Builtin(terminal @ (AvailableTerminal::Tmux | AvailableTerminal::Zellij)) = launcher {
+            return self.launch_in_multiplexer(server, terminal, askpass, tracked).await;
+        }
+        let ssh = TrackedSsh::new(server, askpass);
+        let terminal_pid = match launcher {
+            Launcher::Builtin(terminal) => self.launch_ssh_in_new_terminal(terminal, &ssh.program, &ssh.args).await?,
+            Launcher::Template(template) => {
+                let title = format!("Ghost SSH: {}", server.name);
+                spawn_detached(&expand_template(&template, &title, &ssh.program, &ssh.args)).await?
+            }
+        };
+        // Many terminals exit right after handing the window to a server
+        // process, so their pid is only a last resort
+        Ok((ssh.resolve_later(tracked, Some(terminal_pid)), SessionKind::External))
+    }
+
+    /// Open ssh in a tmux window/split or zellij tab/pane named after the
+    /// server, and find out which pid to track.
+    async fn launch_in_multiplexer(
+        &self,
+        server: &ServerConnection,
+        multiplexer: AvailableTerminal,
+        askpass: Option<&Askpass>,
+        tracked: &[u32],
+    ) -> Result<(SessionPid, SessionKind)> {
+        let target = self.terminal.multiplexer;
+
+        if multiplexer == AvailableTerminal::Tmux {
+            let (program, args) = ssh_command(server, askpass);
+            let output = Command::new("tmux")
+                .args(tmux_args(target, &server.name, &program, &args))
+                .output()
+                .context("Failed to run tmux")?;
+            if !output.status.success() {
+                anyhow::bail!("tmux: {}", String::from_utf8_lossy(&output.stderr).trim());
+            }
+            let (pane, pid) = parse_tmux_pane(&String::from_utf8_lossy(&output.stdout))
+                .ok_or_else(|| anyhow::anyhow!("tmux didn't report the new pane"))?;
+            return Ok((SessionPid::Known(pid), SessionKind::Tmux(pane)));
+        }
+
+        if target == MultiplexerTarget::Window {
+            let status = Command::new("zellij")
+                .args(["action", "new-tab", "--name", &server.name])
+                .status()
+                .context("Failed to run zellij")?;
+            if !status.success() {
+                anyhow::bail!("zellij couldn't open a new tab");
+            }
+        }
+
+        let ssh = TrackedSsh::new(server, askpass);
+        let status = Command::new("zellij")
+            .args(zellij_run_args(&server.name, &ssh.program, &ssh.args))
+            .status()
+            .context("Failed to run zellij")?;
+        if !status.success() {
+            anyhow::bail!("zellij couldn't open a new pane");
+        }
+
+        // zellij doesn't report what it started; the pane is open either way
+        Ok((ssh.resolve_later(tracked, None), SessionKind::Zellij(server.name.clone())))
+    }
+
+    /// Start ssh on a pseudo-terminal for an embedded tab of `rows` x `cols`.
+    pub fn spawn_embedded(
+        &mut self,
+        server: &ServerConnection,
+        rows: u16,
+        cols: u16,
+        askpass: Option<&Askpass>,
+    ) -> Result<EmbeddedSession> {
+        self.connections.insert(server.id.clone(), true);
+        let (program, args) = ssh_command(server, askpass);
+        EmbeddedSession::spawn(server.id.clone(), server.name.clone(), &program, &args, rows, cols)
+    }
+
+    /// Log in with Ghost's own SSH client and open a shell for an embedded
+    /// tab of `rows` x `cols`. Fails with `NeedsSecret` when the login
+    /// takes a password or passphrase missing from `secrets`. Needs no
+    /// manager, so the slow part runs without holding one.
+    pub async fn open_native(
+        server: &ServerConnection,
+        rows: u16,
+        cols: u16,
+        secrets: &Secrets,
+    ) -> Result<EmbeddedSession> {
+        let ssh = native::connect(server, secrets).await?;
+        EmbeddedSession::native(server.id.clone(), server.name.clone(), ssh, rows, cols).await
+    }
+
+    /// Record a session opened outside the manager, see `open_native`
+    pub fn mark_connected(&mut self, server_id: &str) {
+        self.connections.insert(server_id.to_string(), true);
+    }
     
     /// Launch SSH session in a new terminal window.
     ///
@@ -373,50 +787,28 @@ impl SSHManager {
     /// (`build_ssh_args`) and wrapped into the terminal's launch form via
     /// `wrap_command`, so no untrusted server field is ever interpolated into a
     /// shell command string.
-    async fn launch_ssh_in_new_terminal(&self, server: &ServerConnection, terminal: AvailableTerminal) -> Result<u32> {
+    async fn launch_ssh_in_new_terminal(&self, terminal: AvailableTerminal, program: &str, args: &[String]) -> Result<u32> {
         let cmd_name = terminal
             .command_name()
             .ok_or_else(|| anyhow::anyhow!("Invalid terminal type"))?;
 
-        let ssh_args = build_ssh_args(server);
         let terminal_args = terminal
-            .wrap_command("ssh", &ssh_args)
+            .wrap_command(program, args)
             .ok_or_else(|| anyhow::anyhow!("Cannot generate SSH command for this terminal"))?;
 
-        let mut terminal_cmd = Command::new(cmd_name);
-        terminal_cmd.args(&terminal_args);
-
         // Spawn the terminal detached so its I/O can't interfere with the TUI.
-        use std::process::Stdio;
-        let child = terminal_cmd
-            .stdin(Stdio::null())
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .spawn()
-            .context("Failed to spawn terminal process")?;
-
-        let pid = child.id();
-
-        // Reap the terminal in the background when it eventually exits. This lets
-        // the window run independently of Ghost without leaking a zombie process
-        // (the previous `mem::forget` left the child unwaited-for).
-        std::thread::spawn(move || {
-            let mut child = child;
-            let _ = child.wait();
-        });
-
-        // Small delay to ensure the terminal has time to launch.
-        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
-
-        Ok(pid)
+        let mut argv = vec![cmd_name.to_string()];
+        argv.extend(terminal_args);
+        spawn_detached(&argv).await
     }
     
     /// Launch SSH session directly in the current terminal.
-    async fn launch_ssh_session(&self, server: &ServerConnection) -> Result<u32> {
+    async fn launch_ssh_session(&self, server: &ServerConnection, askpass: Option<&Askpass>) -> Result<u32> {
         // Same discrete-argv construction as the new-terminal path: ssh receives
         // each option as its own argument, so nothing is shell-interpreted.
-        let mut ssh_cmd = Command::new("ssh");
-        ssh_cmd.args(build_ssh_args(server));
+        let (program, args) = ssh_command(server, askpass);
+        let mut ssh_cmd = Command::new(program);
+        ssh_cmd.args(args);
 
         // Execute SSH directly in the current terminal
         self.execute_ssh_direct(ssh_cmd, server).await
@@ -424,11 +816,14 @@ impl SSHManager {
     
     /// Execute SSH directly in the current terminal
     async fn execute_ssh_direct(&self, mut ssh_cmd: std::process::Command, server: &ServerConnection) -> Result<u32> {
+        use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
         use crossterm::terminal::{disable_raw_mode, enable_raw_mode, LeaveAlternateScreen, EnterAlternateScreen};
         use crossterm::ExecutableCommand;
         use std::io::stdout;
         
-        // Suspend Ghost's TUI - disable raw mode and leave alternate screen
+        // Suspend Ghost's TUI - release the mouse, disable raw mode and leave
+        // alternate screen
+        let _ = stdout().execute(DisableMouseCapture);
         if disable_raw_mode().is_err() {
             eprintln!("Warning: Failed to disable raw mode");
         }
@@ -505,6 +900,7 @@ impl SSHManager {
         if enable_raw_mode().is_err() {
             eprintln!("Warning: Failed to enable raw mode");
         }
+        let _ = stdout().execute(EnableMouseCapture);
         
         // Force terminal to clear and prepare for Ghost's redraw
         use crossterm::terminal::Clear;
@@ -521,6 +917,95 @@ impl SSHManager {
 
 }
 
+impl HealthCheck {
+    /// Perform a simple connectivity test with security assessment. With
+    /// `verify_login` or the deep check, a reachable server is also logged
+    /// in to, using `secrets` where it needs a password or passphrase.
+    pub async fn quick_health_check(&self, server: &ServerConnection, secrets: &Secrets) -> Result<ConnectionTestResult> {
+        let start_time = Instant::now();
+        let address = format!("{}:{}", server.host, server.port);
+        
+        let result = timeout(
+            Duration::from_secs(5), // Quick timeout for health checks
+            TcpStream::connect(&address)
+        ).await;
+
+        let latency = start_time.elapsed();
+
+        match result {
+            Ok(Ok(_)) => {
+                // Reachable — surface the configured auth method as a hint.
+                let auth_strength = self.assess_auth_strength(server);
+                let (login, facts) = if self.verify_login || self.deep_check.enabled {
+                    let (login, facts) = self.log_in(server, secrets).await;
+                    (Some(login), facts)
+                } else {
+                    (None, None)
+                };
+                let (status, error_message) = match (&login, facts.as_ref().and_then(HostFacts::alert_summary)) {
+                    (Some(LoginCheck::Failed(reason)), _) => (HealthStatus::Warning, Some(format!("Login failed: {}", reason))),
+                    (_, Some(alerts)) => (HealthStatus::Warning, Some(format!("Host: {}", alerts))),
+                    _ => (HealthStatus::Online, None),
+                };
+                Ok(ConnectionTestResult {
+                    status,
+                    auth_strength,
+                    latency: Some(latency),
+                    error_message,
+                    login,
+                    facts,
+                })
+            },
+            Ok(Err(e)) => Ok(ConnectionTestResult {
+                status: HealthStatus::Offline,
+                auth_strength: AuthStrength::Unknown,
+                latency: Some(latency),
+                error_message: Some(format!("Connection failed: {}", e)),
+                login: None,
+                facts: None,
+            }),
+            Err(_) => Ok(ConnectionTestResult {
+                status: HealthStatus::Offline,
+                auth_strength: AuthStrength::Unknown,
+                latency: Some(latency),
+                error_message: Some("Connection timeout".to_string()),
+                login: None,
+                facts: None,
+            }),
+        }
+    }
+
+    /// Log in natively, collect host facts if the deep check is on, and
+    /// log out again. Facts a server won't give are just missing.
+    async fn log_in(&self, server: &ServerConnection, secrets: &Secrets) -> (LoginCheck, Option<HostFacts>) {
+        let ssh = match native::connect(server, secrets).await {
+            Ok(ssh) => ssh,
+            Err(e) if e.is::<NeedsSecret>() => return (LoginCheck::NeedsSecret, None),
+            Err(e) => return (LoginCheck::Failed(format!("{:#}", e)), None),
+        };
+        let facts = match self.deep_check.enabled {
+            true => timeout(FACTS_TIMEOUT, HostFacts::collect(&ssh, &self.deep_check)).await.ok().and_then(Result::ok),
+            false => None,
+        };
+        let _ = ssh.disconnect(russh::Disconnect::ByApplication, "", "en").await;
+        (LoginCheck::Verified, facts)
+    }
+
+    /// Map the configured auth method to an at-a-glance strength hint.
+    ///
+    /// This is a reflection of LOCAL config only — it does not (and cannot, from
+    /// a plain TCP connect) audit the remote host's actual security posture. Its
+    /// only job is to make weaker auth choices (password) visually stand out.
+    fn assess_auth_strength(&self, server: &ServerConnection) -> AuthStrength {
+        match &server.auth_method {
+            crate::models::AuthMethod::PublicKey { .. } => AuthStrength::Key,
+            crate::models::AuthMethod::Agent => AuthStrength::Agent,
+            crate::models::AuthMethod::Password => AuthStrength::Password,
+            crate::models::AuthMethod::Interactive => AuthStrength::Interactive,
+        }
+    }
+}
+
 /// Result of a connection test
 #[derive(Debug, Clone)]
 pub struct ConnectionTestResult {
@@ -528,9 +1013,14 @@ pub struct ConnectionTestResult {
     pub auth_strength: AuthStrength,
     pub latency: Option<Duration>,
     pub error_message: Option<String>,
+    /// Set when the check also tried to log in
+    pub login: Option<LoginCheck>,
+    /// Set when the deep check logged in and collected them
+    pub facts: Option<HostFacts>,
 }
 
 
+
 impl ConnectionTestResult {
     pub fn update_server_stats(&self, server: &mut ServerConnection) {
         server.health_status = self.status.clone();
@@ -541,6 +1031,8 @@ impl ConnectionTestResult {
         // Update connection stats
         server.stats.latency = self.latency;
         server.stats.last_connected = Some(Utc::now());
+        server.stats.login = self.login.clone();
+        server.stats.facts = self.facts.clone();
         
         match self.status {
             HealthStatus::Online => {
@@ -630,6 +1122,58 @@ mod tests {
         assert!(wrapped[1].contains("\\\""));
     }
 
+    #[test]
+    fn multiplexer_commands_keep_argv_intact() {
+        let args = vec!["-p".to_string(), "2222".to_string(), "user@host; rm -rf ~".to_string()];
+
+        let window = tmux_args(MultiplexerTarget::Window, "prod", "ssh", &args);
+        assert_eq!(&window[..3], ["new-window", "-n", "prod"]);
+        assert_eq!(window[window.len() - 4..], ["ssh", "-p", "2222", "user@host; rm -rf ~"]);
+        let split = tmux_args(MultiplexerTarget::Split, "prod", "ssh", &args);
+        assert_eq!(&split[..2], ["split-window", "-h"]);
+
+        assert_eq!(parse_tmux_pane("%12 4242\n"), Some(("%12".to_string(), 4242)));
+        assert_eq!(parse_tmux_pane("garbage"), None);
+
+        let zellij = zellij_run_args("prod", "ssh", &args);
+        assert_eq!(zellij[..4], ["run", "--name", "prod", "--close-on-exit"]);
+        assert_eq!(zellij[4..], ["--", "ssh", "-p", "2222", "user@host; rm -rf ~"]);
+    }
+
+    #[test]
+    fn templates_expand_without_a_shell() {
+        let template: Vec<String> = ["foot", "--title", "{title}", "--", "{argv}"].map(String::from).to_vec();
+        let args = vec!["-p".to_string(), "2222".to_string(), "$(whoami)@host".to_string()];
+        assert_eq!(
+            expand_template(&template, "Ghost SSH: prod", "ssh", &args),
+            ["foot", "--title", "Ghost SSH: prod", "--", "ssh", "-p", "2222", "$(whoami)@host"]
+        );
+    }
+
+    #[test]
+    fn terminal_config_is_validated() {
+        let mut config = TerminalConfig::default();
+        config.templates.insert("foot".into(), vec!["foot".into(), "--".into(), "{argv}".into()]);
+        config.templates.insert("st".into(), vec!["st".into(), "-e".into()]);
+        config.templates.insert("empty".into(), vec![]);
+        config.preferred = Some("foot".into());
+        let errors = terminal_config_errors(&config);
+        assert_eq!(errors.len(), 2);
+        assert!(errors.iter().any(|e| e.starts_with("terminal.templates.st:")));
+        assert!(errors.iter().any(|e| e.starts_with("terminal.templates.empty:")));
+
+        // An invalid preferred template is never launched
+        config.preferred = Some("st".into());
+        let manager = SSHManager::with_terminal_config(config.clone());
+        assert!(!matches!(manager.choose_launcher(), Some(Launcher::Template(_))));
+
+        config.templates.clear();
+        config.preferred = Some("kitty".into());
+        assert!(terminal_config_errors(&config).is_empty());
+        config.preferred = Some("urxvt".into());
+        assert_eq!(terminal_config_errors(&config).len(), 1);
+    }
+
     #[test]
     fn shell_quote_neutralizes_metacharacters() {
         assert_eq!(shell_quote("a b"), "'a b'");
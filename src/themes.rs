@@ -1,9 +1,13 @@
+use crate::background::Background;
 use ratatui::style::Color;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 /// Available theme variants
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
-#[derive(Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
 pub enum ThemeVariant {
     #[default]
     TokyoNightDark,
@@ -18,9 +22,14 @@ pub enum ThemeVariant {
     CatppuccinDark,
     OneDark,
     Ayu,
+    /// Follow the terminal background: resolves to the configured light or
+    /// dark theme (`theme = "auto"`).
+    Auto,
+    /// A user-defined theme loaded from `~/.config/ghost/themes/*.toml`,
+    /// identified by its name.
+    Custom(String),
 }
 
-
 impl ThemeVariant {
     pub fn all() -> Vec<ThemeVariant> {
         vec![
@@ -39,7 +48,7 @@ impl ThemeVariant {
         ]
     }
 
-    pub fn name(&self) -> &'static str {
+    pub fn name(&self) -> &str {
         match self {
             ThemeVariant::TokyoNightDark => "Tokyo Night (Dark)",
             ThemeVariant::TokyoNightLight => "Tokyo Night (Light)",
@@ -53,21 +62,71 @@ impl ThemeVariant {
             ThemeVariant::CatppuccinDark => "Catppuccin",
             ThemeVariant::OneDark => "One Dark",
             ThemeVariant::Ayu => "Ayu",
+            ThemeVariant::Auto => "Auto (terminal background)",
+            ThemeVariant::Custom(name) => name,
         }
     }
 
+    /// Stable identifier used in config files (`theme = "GruvboxDark"`).
+    /// Custom themes are identified by their name.
+    pub fn id(&self) -> &str {
+        match self {
+            ThemeVariant::TokyoNightDark => "TokyoNightDark",
+            ThemeVariant::TokyoNightLight => "TokyoNightLight",
+            ThemeVariant::DraculaDark => "DraculaDark",
+            ThemeVariant::GruvboxDark => "GruvboxDark",
+            ThemeVariant::GruvboxLight => "GruvboxLight",
+            ThemeVariant::NordDark => "NordDark",
+            ThemeVariant::SolarizedDark => "SolarizedDark",
+            ThemeVariant::SolarizedLight => "SolarizedLight",
+            ThemeVariant::MonokaiDark => "MonokaiDark",
+            ThemeVariant::CatppuccinDark => "CatppuccinDark",
+            ThemeVariant::OneDark => "OneDark",
+            ThemeVariant::Ayu => "Ayu",
+            ThemeVariant::Auto => "auto",
+            ThemeVariant::Custom(name) => name,
+        }
+    }
+
+    /// Look up a built-in variant by its config identifier (case-insensitive).
+    pub fn builtin_from_id(id: &str) -> Option<ThemeVariant> {
+        Self::all()
+            .into_iter()
+            .find(|v| v.id().eq_ignore_ascii_case(id))
+    }
+
+    /// Whether this is a dark theme. Custom themes and `Auto` can't be answered
+    /// from the variant alone — ask `ThemeManager::variant_is_dark` for those.
     #[allow(dead_code)] // part of the theme API; not yet wired into the UI
     pub fn is_dark(&self) -> bool {
-        match self {
-            ThemeVariant::TokyoNightLight | ThemeVariant::GruvboxLight | ThemeVariant::SolarizedLight => false,
-            _ => true,
+        !matches!(
+            self,
+            ThemeVariant::TokyoNightLight | ThemeVariant::GruvboxLight | ThemeVariant::SolarizedLight
+        )
+    }
+}
+
+// Serialized as a plain string so existing configs (`theme = "NordDark"`) keep
+// loading; any name that isn't a built-in refers to a custom theme.
+impl Serialize for ThemeVariant {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.id())
+    }
+}
+
+impl<'de> Deserialize<'de> for ThemeVariant {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = String::deserialize(deserializer)?;
+        if id.eq_ignore_ascii_case(ThemeVariant::Auto.id()) {
+            return Ok(ThemeVariant::Auto);
         }
+        Ok(ThemeVariant::builtin_from_id(&id).unwrap_or(ThemeVariant::Custom(id)))
     }
 }
 
 /// Comprehensive theme structure with all colors needed for the app.
-// Several colors aren't consumed yet — the UI still uses the hardcoded
-// `TokyoNight` palette while the dynamic theme system is being wired in.
+// A few colors (cursor, selection, ...) aren't drawn by any widget yet but are
+// part of the palette so custom themes can already set them.
 #[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct Theme {
@@ -113,7 +172,19 @@ pub struct Theme {
 }
 
 impl Theme {
-    pub fn from_variant(variant: ThemeVariant) -> Self {
+    /// Every color field name, as used in custom theme files.
+    pub const FIELD_NAMES: [&'static str; 28] = [
+        "bg", "bg_dark", "bg_highlight", "bg_popup", "fg", "fg_dark", "comment",
+        "border", "border_highlight", "cursor",
+        "theme_primary", "theme_secondary",
+        "status_online", "status_offline", "status_connecting", "status_warning", "status_unknown",
+        "red", "orange", "yellow", "green", "cyan", "blue", "purple", "pink",
+        "terminal_black", "selection", "match_highlight",
+    ];
+
+    /// Built-in palette for `variant`. Custom variants have no built-in
+    /// palette and resolve to the default theme; `ThemeManager` handles them.
+    pub fn from_variant(variant: &ThemeVariant) -> Self {
         match variant {
             ThemeVariant::TokyoNightDark => Self::tokyo_night_dark(),
             ThemeVariant::TokyoNightLight => Self::tokyo_night_light(),
@@ -127,9 +198,45 @@ impl Theme {
             ThemeVariant::CatppuccinDark => Self::catppuccin_dark(),
             ThemeVariant::OneDark => Self::one_dark(),
             ThemeVariant::Ayu => Self::ayu(),
+            ThemeVariant::Auto | ThemeVariant::Custom(_) => Self::from_variant(&ThemeVariant::default()),
         }
     }
 
+    /// Mutable access to a color field by its config name.
+    fn color_mut(&mut self, key: &str) -> Option<&mut Color> {
+        Some(match key {
+            "bg" => &mut self.bg,
+            "bg_dark" => &mut self.bg_dark,
+            "bg_highlight" => &mut self.bg_highlight,
+            "bg_popup" => &mut self.bg_popup,
+            "fg" => &mut self.fg,
+            "fg_dark" => &mut self.fg_dark,
+            "comment" => &mut self.comment,
+            "border" => &mut self.border,
+            "border_highlight" => &mut self.border_highlight,
+            "cursor" => &mut self.cursor,
+            "theme_primary" => &mut self.theme_primary,
+            "theme_secondary" => &mut self.theme_secondary,
+            "status_online" => &mut self.status_online,
+            "status_offline" => &mut self.status_offline,
+            "status_connecting" => &mut self.status_connecting,
+            "status_warning" => &mut self.status_warning,
+            "status_unknown" => &mut self.status_unknown,
+            "red" => &mut self.red,
+            "orange" => &mut self.orange,
+            "yellow" => &mut self.yellow,
+            "green" => &mut self.green,
+            "cyan" => &mut self.cyan,
+            "blue" => &mut self.blue,
+            "purple" => &mut self.purple,
+            "pink" => &mut self.pink,
+            "terminal_black" => &mut self.terminal_black,
+            "selection" => &mut self.selection,
+            "match_highlight" => &mut self.match_highlight,
+            _ => return None,
+        })
+    }
+
     fn tokyo_night_dark() -> Self {
         Self {
             bg: Color::Rgb(26, 27, 38),
@@ -587,19 +694,205 @@ impl Theme {
     }
 }
 
+/// A user-defined theme loaded from a TOML file in the themes directory.
+#[derive(Debug, Clone)]
+pub struct CustomTheme {
+    pub name: String,
+    pub theme: Theme,
+    pub dark: bool,
+}
+
+/// A problem encountered while loading a custom theme file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThemeLoadError {
+    pub path: PathBuf,
+    /// The offending key, when the error is tied to one.
+    pub key: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for ThemeLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let file = self
+            .path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.path.display().to_string());
+        match &self.key {
+            Some(key) => write!(f, "{}: `{}`: {}", file, key, self.message),
+            None => write!(f, "{}: {}", file, self.message),
+        }
+    }
+}
+
+/// Load every `*.toml` theme in `dir`, in file-name order.
+///
+/// A missing directory simply means no custom themes. Files that fail to
+/// parse are skipped and reported; one bad file never prevents the others
+/// from loading.
+pub fn load_custom_themes(dir: &Path) -> (Vec<CustomTheme>, Vec<ThemeLoadError>) {
+    let mut themes = Vec::new();
+    let mut errors = Vec::new();
+
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return (themes, errors),
+    };
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().map(|ext| ext == "toml").unwrap_or(false))
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        let result = fs::read_to_string(&path)
+            .map_err(|e| ThemeLoadError {
+                path: path.clone(),
+                key: None,
+                message: format!("failed to read file: {}", e),
+            })
+            .and_then(|contents| parse_custom_theme(&path, &contents));
+
+        match result {
+            Ok(theme) => {
+                if themes.iter().any(|t: &CustomTheme| t.name == theme.name) {
+                    errors.push(ThemeLoadError {
+                        path: path.clone(),
+                        key: Some("name".to_string()),
+                        message: format!("duplicate theme name '{}'", theme.name),
+                    });
+                } else {
+                    themes.push(theme);
+                }
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+
+    (themes, errors)
+}
+
+/// Parse a single custom theme file.
+///
+/// ```toml
+/// name = "Midnight"          # optional, defaults to the file name
+/// base = "TokyoNightDark"    # optional, supplies any color not listed
+/// dark = true                # optional, defaults to the base's darkness
+///
+/// [colors]
+/// bg = "#101018"             # hex or a named color ("black", "lightblue")
+/// theme_primary = "cyan"
+/// ```
+pub fn parse_custom_theme(path: &Path, contents: &str) -> Result<CustomTheme, ThemeLoadError> {
+    let error = |key: Option<&str>, message: String| ThemeLoadError {
+        path: path.to_path_buf(),
+        key: key.map(str::to_string),
+        message,
+    };
+
+    let table: toml::Table = contents
+        .parse()
+        .map_err(|e: toml::de::Error| error(None, format!("invalid TOML: {}", e.message())))?;
+
+    let name = match table.get("name") {
+        Some(value) => value
+            .as_str()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| error(Some("name"), "expected a non-empty string".to_string()))?,
+        None => path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+    };
+
+    if ThemeVariant::builtin_from_id(&name).is_some() || name.eq_ignore_ascii_case(ThemeVariant::Auto.id()) {
+        return Err(error(
+            Some("name"),
+            format!("'{}' is the name of a built-in theme", name),
+        ));
+    }
+
+    let base = match table.get("base") {
+        Some(value) => {
+            let id = value
+                .as_str()
+                .ok_or_else(|| error(Some("base"), "expected a theme name".to_string()))?;
+            ThemeVariant::builtin_from_id(id).ok_or_else(|| {
+                error(Some("base"), format!("unknown built-in theme '{}'", id))
+            })?
+        }
+        None => ThemeVariant::default(),
+    };
+
+    let dark = match table.get("dark") {
+        Some(value) => value
+            .as_bool()
+            .ok_or_else(|| error(Some("dark"), "expected true or false".to_string()))?,
+        None => base.is_dark(),
+    };
+
+    for key in table.keys() {
+        if !matches!(key.as_str(), "name" | "base" | "dark" | "colors") {
+            return Err(error(Some(key), "unknown key".to_string()));
+        }
+    }
+
+    let mut theme = Theme::from_variant(&base);
+    if let Some(colors) = table.get("colors") {
+        let colors = colors
+            .as_table()
+            .ok_or_else(|| error(Some("colors"), "expected a table".to_string()))?;
+
+        for (key, value) in colors {
+            let qualified = format!("colors.{}", key);
+            let slot = theme.color_mut(key).ok_or_else(|| {
+                error(
+                    Some(&qualified),
+                    format!("unknown color (expected one of: {})", Theme::FIELD_NAMES.join(", ")),
+                )
+            })?;
+            let text = value
+                .as_str()
+                .ok_or_else(|| error(Some(&qualified), "expected a color string".to_string()))?;
+            *slot = Color::from_str(text.trim()).map_err(|_| {
+                error(
+                    Some(&qualified),
+                    format!("invalid color '{}' (use \"#rrggbb\" or a color name)", text),
+                )
+            })?;
+        }
+    }
+
+    Ok(CustomTheme {
+        name,
+        theme,
+        dark,
+    })
+}
+
 /// Theme manager for the application
 #[derive(Debug, Clone)]
 pub struct ThemeManager {
     current_theme: Theme,
     current_variant: ThemeVariant,
+    custom_themes: Vec<CustomTheme>,
+    /// Themes `Auto` chooses between, as (light, dark)
+    auto_themes: (ThemeVariant, ThemeVariant),
+    /// Detected terminal background; `None` when it couldn't be determined
+    background: Option<Background>,
 }
 
 impl Default for ThemeManager {
     fn default() -> Self {
         let variant = ThemeVariant::default();
         Self {
-            current_theme: Theme::from_variant(variant),
+            current_theme: Theme::from_variant(&variant),
             current_variant: variant,
+            custom_themes: Vec::new(),
+            auto_themes: (ThemeVariant::TokyoNightLight, ThemeVariant::TokyoNightDark),
+            background: None,
         }
     }
 }
@@ -607,10 +900,9 @@ impl Default for ThemeManager {
 impl ThemeManager {
     #[allow(dead_code)] // pairs with Default; kept for explicit construction
     pub fn new(variant: ThemeVariant) -> Self {
-        Self {
-            current_theme: Theme::from_variant(variant),
-            current_variant: variant,
-        }
+        let mut manager = Self::default();
+        manager.set_theme(variant);
+        manager
     }
 
     pub fn current_theme(&self) -> &Theme {
@@ -618,41 +910,234 @@ impl ThemeManager {
     }
 
     pub fn current_variant(&self) -> ThemeVariant {
-        self.current_variant
+        self.current_variant.clone()
+    }
+
+    /// Replace the set of loaded custom themes. The current theme is
+    /// re-resolved so edits to an active custom theme take effect.
+    pub fn set_custom_themes(&mut self, themes: Vec<CustomTheme>) {
+        self.custom_themes = themes;
+        let current = self.current_variant.clone();
+        self.set_theme(current);
+    }
+
+    /// Configure what `ThemeVariant::Auto` resolves to. Without a detected
+    /// background, auto falls back to the dark theme.
+    pub fn set_auto_themes(&mut self, light: ThemeVariant, dark: ThemeVariant, background: Option<Background>) {
+        self.auto_themes = (light, dark);
+        self.background = background;
+        let current = self.current_variant.clone();
+        self.set_theme(current);
     }
 
+    /// The concrete theme `variant` stands for: the light or dark pick for
+    /// `Auto`, the variant itself otherwise.
+    pub fn effective_variant(&self, variant: &ThemeVariant) -> ThemeVariant {
+        match variant {
+            ThemeVariant::Auto => {
+                let (light, dark) = &self.auto_themes;
+                let pick = match self.background {
+                    Some(Background::Light) => light,
+                    _ => dark,
+                };
+                // Auto pointing at itself or at a missing custom theme would
+                // otherwise never settle on a palette
+                if *pick == ThemeVariant::Auto || !self.has_variant(pick) {
+                    ThemeVariant::default()
+                } else {
+                    pick.clone()
+                }
+            }
+            other => other.clone(),
+        }
+    }
+
+    /// Whether `variant` can be displayed: always true for built-ins, and true
+    /// for custom themes that were loaded successfully.
+    pub fn has_variant(&self, variant: &ThemeVariant) -> bool {
+        match variant {
+            ThemeVariant::Custom(name) => self.custom_themes.iter().any(|t| &t.name == name),
+            _ => true,
+        }
+    }
+
+    /// Auto, the built-in variants, then loaded custom themes.
+    pub fn available_variants(&self) -> Vec<ThemeVariant> {
+        let mut variants = vec![ThemeVariant::Auto];
+        variants.extend(ThemeVariant::all());
+        variants.extend(
+            self.custom_themes
+                .iter()
+                .map(|t| ThemeVariant::Custom(t.name.clone())),
+        );
+        variants
+    }
+
+    /// Switch to `variant`. An unknown custom theme falls back to the default.
     pub fn set_theme(&mut self, variant: ThemeVariant) {
+        let variant = if self.has_variant(&variant) {
+            variant
+        } else {
+            ThemeVariant::default()
+        };
+        self.current_theme = self.resolve(&variant);
         self.current_variant = variant;
-        self.current_theme = Theme::from_variant(variant);
+    }
+
+    /// Palette for any available variant, without switching to it.
+    pub fn resolve(&self, variant: &ThemeVariant) -> Theme {
+        match &self.effective_variant(variant) {
+            ThemeVariant::Custom(name) => self
+                .custom_themes
+                .iter()
+                .find(|t| &t.name == name)
+                .map(|t| t.theme.clone())
+                .unwrap_or_else(|| Theme::from_variant(&ThemeVariant::default())),
+            builtin => Theme::from_variant(builtin),
+        }
     }
 
     pub fn next_theme(&mut self) {
-        let variants = ThemeVariant::all();
+        let variants = self.available_variants();
         let current_index = variants
             .iter()
-            .position(|&v| v == self.current_variant)
+            .position(|v| *v == self.current_variant)
             .unwrap_or(0);
         let next_index = (current_index + 1) % variants.len();
-        self.set_theme(variants[next_index]);
+        self.set_theme(variants[next_index].clone());
     }
 
     #[allow(dead_code)] // pairs with next_theme(); not yet bound to a key
     pub fn previous_theme(&mut self) {
-        let variants = ThemeVariant::all();
+        let variants = self.available_variants();
         let current_index = variants
             .iter()
-            .position(|&v| v == self.current_variant)
+            .position(|v| *v == self.current_variant)
             .unwrap_or(0);
         let prev_index = if current_index == 0 {
             variants.len() - 1
         } else {
             current_index - 1
         };
-        self.set_theme(variants[prev_index]);
+        self.set_theme(variants[prev_index].clone());
+    }
+
+    /// Darkness of any available variant, including custom themes.
+    pub fn variant_is_dark(&self, variant: &ThemeVariant) -> bool {
+        match &self.effective_variant(variant) {
+            ThemeVariant::Custom(name) => self
+                .custom_themes
+                .iter()
+                .find(|t| &t.name == name)
+                .map(|t| t.dark)
+                .unwrap_or(true),
+            builtin => builtin.is_dark(),
+        }
     }
 
     #[allow(dead_code)] // exposed for future light/dark-aware rendering
     pub fn is_dark(&self) -> bool {
-        self.current_variant.is_dark()
+        self.variant_is_dark(&self.current_variant)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(contents: &str) -> Result<CustomTheme, ThemeLoadError> {
+        parse_custom_theme(Path::new("/themes/midnight.toml"), contents)
+    }
+
+    #[test]
+    fn builtin_ids_round_trip_through_config() {
+        #[derive(Serialize, Deserialize)]
+        struct Wrapper {
+            theme: ThemeVariant,
+        }
+
+        for variant in ThemeVariant::all() {
+            let toml_str = toml::to_string(&Wrapper { theme: variant.clone() }).unwrap();
+            let parsed: Wrapper = toml::from_str(&toml_str).unwrap();
+            assert_eq!(parsed.theme, variant);
+        }
+
+        let parsed: Wrapper = toml::from_str("theme = \"Midnight\"").unwrap();
+        assert_eq!(parsed.theme, ThemeVariant::Custom("Midnight".to_string()));
+    }
+
+    #[test]
+    fn missing_colors_fall_back_to_base() {
+        let theme = parse(
+            r##"
+            base = "GruvboxLight"
+            [colors]
+            bg = "#102030"
+            fg = "light blue"
+            "##,
+        )
+        .unwrap();
+
+        let base = Theme::from_variant(&ThemeVariant::GruvboxLight);
+        assert_eq!(theme.name, "midnight");
+        assert_eq!(theme.theme.bg, Color::Rgb(0x10, 0x20, 0x30));
+        assert_eq!(theme.theme.fg, Color::LightBlue);
+        assert_eq!(theme.theme.border, base.border);
+        assert!(!theme.dark, "darkness defaults to the base theme's");
+    }
+
+    #[test]
+    fn errors_name_the_offending_key() {
+        let err = parse("[colors]\nbg = \"#12345\"").unwrap_err();
+        assert_eq!(err.key.as_deref(), Some("colors.bg"));
+        assert!(err.to_string().starts_with("midnight.toml: `colors.bg`:"));
+
+        let err = parse("[colors]\nbackground = \"red\"").unwrap_err();
+        assert_eq!(err.key.as_deref(), Some("colors.background"));
+
+        let err = parse("base = \"NotATheme\"").unwrap_err();
+        assert_eq!(err.key.as_deref(), Some("base"));
+    }
+
+    #[test]
+    fn custom_themes_join_the_cycle() {
+        let mut manager = ThemeManager::default();
+        manager.set_custom_themes(vec![parse("name = \"Midnight\"").unwrap()]);
+        manager.set_theme(ThemeVariant::Ayu);
+        manager.next_theme();
+        assert_eq!(manager.current_variant(), ThemeVariant::Custom("Midnight".to_string()));
+        manager.next_theme();
+        assert_eq!(manager.current_variant(), ThemeVariant::Auto);
+        manager.next_theme();
+        assert_eq!(manager.current_variant(), ThemeVariant::TokyoNightDark);
+
+        // Unknown custom themes fall back to the default rather than erroring.
+        manager.set_theme(ThemeVariant::Custom("Gone".to_string()));
+        assert_eq!(manager.current_variant(), ThemeVariant::default());
+    }
+
+    #[test]
+    fn auto_follows_the_terminal_background() {
+        let mut manager = ThemeManager::default();
+        manager.set_theme(ThemeVariant::Auto);
+        manager.set_auto_themes(ThemeVariant::GruvboxLight, ThemeVariant::NordDark, Some(Background::Light));
+        assert_eq!(manager.current_variant(), ThemeVariant::Auto);
+        assert_eq!(manager.effective_variant(&ThemeVariant::Auto), ThemeVariant::GruvboxLight);
+        assert!(!manager.is_dark());
+
+        manager.set_auto_themes(ThemeVariant::GruvboxLight, ThemeVariant::NordDark, Some(Background::Dark));
+        assert_eq!(manager.effective_variant(&ThemeVariant::Auto), ThemeVariant::NordDark);
+
+        // Undetectable backgrounds count as dark
+        manager.set_auto_themes(ThemeVariant::GruvboxLight, ThemeVariant::NordDark, None);
+        assert_eq!(manager.effective_variant(&ThemeVariant::Auto), ThemeVariant::NordDark);
+
+        #[derive(Serialize, Deserialize)]
+        struct Wrapper {
+            theme: ThemeVariant,
+        }
+        let parsed: Wrapper = toml::from_str("theme = \"auto\"").unwrap();
+        assert_eq!(parsed.theme, ThemeVariant::Auto);
+        assert_eq!(toml::to_string(&parsed).unwrap().trim(), "theme = \"auto\"");
+    }
+}
@@ -0,0 +1,523 @@
+//! Quick transfers: send a local file or directory to the same path on one
+//! or many servers, or fetch a remote path from each of them into its own
+//! local directory. Every server is a job in a queue that runs a few at a
+//! time over its own SFTP connection; failed jobs can be retried and pick up
+//! where they stopped.
+
+use crate::forms::InputField;
+use crate::models::ServerConnection;
+use crate::native::Secrets;
+use crate::sftp::{self, Direction, Side, SftpClient};
+use anyhow::{anyhow, bail, Result};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+
+/// Jobs transferring at the same time; the rest wait in the queue
+const MAX_PARALLEL: usize = 4;
+/// Where fetched files go when no directory is given
+const DEFAULT_FETCH_DIR: &str = "ghost-fetch";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransferKind {
+    /// `local` to `remote` on every server
+    Send { local: String, remote: String },
+    /// `remote` on every server to `<local_dir>/<server name>/`
+    Fetch { remote: String, local_dir: String },
+}
+
+impl TransferKind {
+    /// Send `path` to the same path on the servers. `~/…` means the home
+    /// directory on both ends; other relative paths are taken from the
+    /// current directory locally and the home directory remotely, like scp.
+    pub fn send(path: &str) -> Result<Self> {
+        let path = path.trim();
+        let (local, remote) = match path.strip_prefix("~/") {
+            Some(rest) if !rest.is_empty() => {
+                let home = dirs::home_dir().ok_or_else(|| anyhow!("No home directory to expand ~ in"))?;
+                (home.join(rest), rest.to_string())
+            }
+            _ if path.is_empty() || path == "~" => bail!("Enter the file or directory to send"),
+            _ => (PathBuf::from(path), path.to_string()),
+        };
+        let local = if local.is_absolute() { local } else { std::env::current_dir()?.join(local) };
+        if !local.exists() {
+            bail!("{} does not exist", local.display());
+        }
+        Ok(TransferKind::Send { local: local.to_string_lossy().into_owned(), remote })
+    }
+
+    pub fn fetch(path: &str, local_dir: &str) -> Result<Self> {
+        let path = path.trim();
+        // The remote home is where SFTP resolves relative paths anyway
+        let remote = path.strip_prefix("~/").unwrap_or(path);
+        if Side::Remote.split(&format!("/{}", remote)).is_none() {
+            bail!("Enter the remote file or directory to fetch");
+        }
+        let local_dir = match local_dir.trim() {
+            "" => DEFAULT_FETCH_DIR,
+            dir => dir,
+        };
+        Ok(TransferKind::Fetch {
+            remote: remote.to_string(),
+            local_dir: shellexpand::tilde(local_dir).into_owned(),
+        })
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            TransferKind::Send { local, .. } => format!("Send {}", local),
+            TransferKind::Fetch { remote, .. } => format!("Fetch {}", remote),
+        }
+    }
+
+    /// Source and destination of the copy for `server`
+    fn endpoints(&self, server: &ServerConnection) -> (Direction, String, String) {
+        match self {
+            TransferKind::Send { local, remote } => (Direction::Upload, local.clone(), remote.clone()),
+            TransferKind::Fetch { remote, local_dir } => {
+                let name = Side::Remote
+                    .split(&format!("/{}", remote))
+                    .map(|(_, name)| name)
+                    .unwrap_or_default();
+                let to = Path::new(local_dir).join(host_dir(server)).join(name);
+                (Direction::Download, remote.clone(), to.to_string_lossy().into_owned())
+            }
+        }
+    }
+}
+
+/// Directory name for a server's fetched files
+fn host_dir(server: &ServerConnection) -> String {
+    server.name.replace(['/', '\\'], "_")
+}
+
+/// Servers named by `targets`: comma-separated server names, or `tag:<tag>`
+/// for every server carrying that tag (in name order)
+pub fn resolve_targets<'a>(
+    targets: &str,
+    connections: impl IntoIterator<Item = &'a ServerConnection>,
+) -> Result<Vec<ServerConnection>> {
+    let mut connections: Vec<&ServerConnection> = connections.into_iter().collect();
+    connections.sort_by(|a, b| a.name.cmp(&b.name));
+    let mut servers: Vec<ServerConnection> = Vec::new();
+    for target in targets.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        let matched: Vec<&ServerConnection> = match target.strip_prefix("tag:") {
+            Some(tag) => connections.iter().copied().filter(|c| c.tags.iter().any(|t| t == tag.trim())).collect(),
+            None => connections.iter().copied().filter(|c| c.name == target).collect(),
+        };
+        if matched.is_empty() {
+            bail!("No server matches '{}'", target);
+        }
+        for server in matched {
+            if !servers.iter().any(|s| s.id == server.id) {
+                servers.push(server.clone());
+            }
+        }
+    }
+    if servers.is_empty() {
+        bail!("Enter a server name or tag:<tag>");
+    }
+    Ok(servers)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobState {
+    Queued,
+    Running,
+    Done,
+    Failed(String),
+}
+
+/// One server's part of a send or fetch
+#[derive(Debug, Clone)]
+pub struct TransferJob {
+    pub id: u64,
+    /// Jobs queued together share a batch, which is summarised once all of
+    /// them have finished
+    pub batch: u64,
+    pub server: ServerConnection,
+    /// Stored password for the server, kept for retries
+    pub secrets: Secrets,
+    pub kind: TransferKind,
+    pub state: JobState,
+    pub done: u64,
+    pub total: u64,
+    /// Times the job has been started
+    pub attempts: u32,
+}
+
+impl TransferJob {
+    pub fn progress(&self) -> f32 {
+        match self.state {
+            JobState::Done => 1.0,
+            _ if self.total == 0 => 0.0,
+            _ => (self.done as f64 / self.total as f64).min(1.0) as f32,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self.state, JobState::Done | JobState::Failed(_))
+    }
+}
+
+#[derive(Debug)]
+enum QueueEvent {
+    Progress { id: u64, done: u64, total: u64 },
+    Finished { id: u64, error: Option<String> },
+}
+
+/// Every send and fetch of this run, most recent last
+#[derive(Debug)]
+pub struct TransferQueue {
+    pub jobs: Vec<TransferJob>,
+    pub selected: usize,
+    next_id: u64,
+    next_batch: u64,
+    tx: mpsc::UnboundedSender<QueueEvent>,
+    rx: mpsc::UnboundedReceiver<QueueEvent>,
+}
+
+impl Default for TransferQueue {
+    fn default() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self { jobs: Vec::new(), selected: 0, next_id: 0, next_batch: 0, tx, rx }
+    }
+}
+
+impl TransferQueue {
+    /// Queue `kind` for each server, logging in with its secrets, and start
+    /// as many as may run
+    pub fn enqueue(&mut self, servers: Vec<(ServerConnection, Secrets)>, kind: TransferKind) {
+        let batch = self.next_batch;
+        self.next_batch += 1;
+        for (server, secrets) in servers {
+            self.jobs.push(TransferJob {
+                id: self.next_id,
+                batch,
+                server,
+                secrets,
+                kind: kind.clone(),
+                state: JobState::Queued,
+                done: 0,
+                total: 0,
+                attempts: 0,
+            });
+            self.next_id += 1;
+        }
+        self.pump();
+    }
+
+    /// Start queued jobs while there's room
+    fn pump(&mut self) {
+        let running = self.jobs.iter().filter(|j| j.state == JobState::Running).count();
+        let room = MAX_PARALLEL.saturating_sub(running);
+        for job in self.jobs.iter_mut().filter(|j| j.state == JobState::Queued).take(room) {
+            // Retries continue the files the failed attempt left behind
+            let resume = job.attempts > 0;
+            job.attempts += 1;
+            job.state = JobState::Running;
+
+            let (id, server, secrets, tx) = (job.id, job.server.clone(), job.secrets.clone(), self.tx.clone());
+            let (direction, from, to) = job.kind.endpoints(&server);
+            tokio::spawn(async move {
+                let result = async {
+                    if direction == Direction::Download {
+                        if let Some(parent) = Path::new(&to).parent() {
+                            tokio::fs::create_dir_all(parent).await?;
+                        }
+                    }
+                    let client = SftpClient::connect(&server, &secrets).await?;
+                    let mut report = |done, total| {
+                        let _ = tx.send(QueueEvent::Progress { id, done, total });
+                    };
+                    sftp::transfer(&client, direction, &from, &to, resume, &mut report).await
+                }
+                .await;
+                let error = result.err().map(|e| format!("{:#}", e));
+                let _ = tx.send(QueueEvent::Finished { id, error });
+            });
+        }
+    }
+
+    /// Apply progress from the running jobs and start the next ones.
+    /// Returns the batches whose last job just finished.
+    pub fn poll_events(&mut self) -> Vec<u64> {
+        let mut completed = Vec::new();
+        while let Ok(event) = self.rx.try_recv() {
+            match event {
+                QueueEvent::Progress { id, done, total } => {
+                    if let Some(job) = self.jobs.iter_mut().find(|j| j.id == id) {
+                        job.done = done;
+                        job.total = total;
+                    }
+                }
+                QueueEvent::Finished { id, error } => {
+                    let Some(job) = self.jobs.iter_mut().find(|j| j.id == id) else {
+                        continue;
+                    };
+                    job.state = match error {
+                        Some(e) => JobState::Failed(e),
+                        None => JobState::Done,
+                    };
+                    let batch = job.batch;
+                    if self.jobs.iter().filter(|j| j.batch == batch).all(TransferJob::is_finished) {
+                        completed.push(batch);
+                    }
+                }
+            }
+        }
+        self.pump();
+        completed
+    }
+
+    /// Per-server outcome of a batch, e.g. for a notification
+    pub fn batch_summary(&self, batch: u64) -> String {
+        let jobs: Vec<&TransferJob> = self.jobs.iter().filter(|j| j.batch == batch).collect();
+        let Some(first) = jobs.first() else {
+            return String::new();
+        };
+        let failed: Vec<String> = jobs
+            .iter()
+            .filter_map(|j| match &j.state {
+                JobState::Failed(e) => Some(format!("{}: {}", j.server.name, e)),
+                _ => None,
+            })
+            .collect();
+        let mut summary = format!(
+            "{}: {}/{} server{} done",
+            first.kind.label(),
+            jobs.len() - failed.len(),
+            jobs.len(),
+            if jobs.len() == 1 { "" } else { "s" }
+        );
+        for line in failed {
+            summary.push_str("\n✗ ");
+            summary.push_str(&line);
+        }
+        summary
+    }
+
+    /// Queue a failed job again
+    pub fn retry(&mut self, id: u64) -> bool {
+        let Some(job) = self.jobs.iter_mut().find(|j| j.id == id && matches!(j.state, JobState::Failed(_))) else {
+            return false;
+        };
+        job.state = JobState::Queued;
+        self.pump();
+        true
+    }
+
+    /// The jobs that failed, e.g. to retry them all
+    pub fn failed(&self) -> Vec<&TransferJob> {
+        self.jobs.iter().filter(|j| matches!(j.state, JobState::Failed(_))).collect()
+    }
+
+    /// Drop finished jobs, except those of batches still running, which
+    /// their summary counts
+    pub fn clear_finished(&mut self) {
+        let running: HashSet<u64> = self.jobs.iter().filter(|j| !j.is_finished()).map(|j| j.batch).collect();
+        self.jobs.retain(|j| !j.is_finished() || running.contains(&j.batch));
+        self.selected = self.selected.min(self.jobs.len().saturating_sub(1));
+    }
+
+    pub fn selected_job(&self) -> Option<&TransferJob> {
+        self.jobs.get(self.selected)
+    }
+
+    pub fn move_selection(&mut self, down: bool) {
+        if down {
+            if self.selected + 1 < self.jobs.len() {
+                self.selected += 1;
+            }
+        } else {
+            self.selected = self.selected.saturating_sub(1);
+        }
+    }
+
+    pub fn count(&self, state: fn(&JobState) -> bool) -> usize {
+        self.jobs.iter().filter(|j| state(&j.state)).count()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferMode {
+    Send,
+    Fetch,
+}
+
+const PATH_FIELD: usize = 0;
+const TARGETS_FIELD: usize = 1;
+const LOCAL_DIR_FIELD: usize = 2;
+
+/// The popup asking what to send or fetch and from which servers
+#[derive(Debug, Clone)]
+pub struct TransferForm {
+    pub mode: TransferMode,
+    pub fields: Vec<InputField>,
+    pub focused: usize,
+}
+
+impl TransferForm {
+    /// A form targeting `targets` (usually the selected server's name)
+    pub fn new(mode: TransferMode, targets: &str) -> Self {
+        let mut fields = match mode {
+            TransferMode::Send => vec![
+                InputField::new("Local file or directory", "~/notes.txt"),
+                InputField::new("Servers (names or tag:<tag>)", "web1, tag:prod"),
+            ],
+            TransferMode::Fetch => vec![
+                InputField::new("Remote file or directory", "/var/log/syslog"),
+                InputField::new("Servers (names or tag:<tag>)", "web1, tag:prod"),
+                InputField::new("Local directory (one folder per server)", DEFAULT_FETCH_DIR),
+            ],
+        };
+        fields[TARGETS_FIELD].value = targets.to_string();
+        fields[TARGETS_FIELD].move_cursor_to_end();
+        fields[PATH_FIELD].is_focused = true;
+        Self { mode, fields, focused: PATH_FIELD }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self.mode {
+            TransferMode::Send => " 📤 Send to Servers ",
+            TransferMode::Fetch => " 📥 Fetch from Servers ",
+        }
+    }
+
+    pub fn focused_field_mut(&mut self) -> &mut InputField {
+        &mut self.fields[self.focused]
+    }
+
+    pub fn next_field(&mut self) {
+        self.focus((self.focused + 1) % self.fields.len());
+    }
+
+    pub fn previous_field(&mut self) {
+        self.focus((self.focused + self.fields.len() - 1) % self.fields.len());
+    }
+
+    fn focus(&mut self, index: usize) {
+        self.fields[self.focused].is_focused = false;
+        self.focused = index;
+        self.fields[index].is_focused = true;
+    }
+
+    /// The transfer described by the form and the servers it runs on
+    pub fn parse<'a>(
+        &self,
+        connections: impl IntoIterator<Item = &'a ServerConnection>,
+    ) -> Result<(TransferKind, Vec<ServerConnection>)> {
+        let path = &self.fields[PATH_FIELD].value;
+        let kind = match self.mode {
+            TransferMode::Send => TransferKind::send(path)?,
+            TransferMode::Fetch => TransferKind::fetch(path, &self.fields[LOCAL_DIR_FIELD].value)?,
+        };
+        let servers = resolve_targets(&self.fields[TARGETS_FIELD].value, connections)?;
+        Ok((kind, servers))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(name: &str, tags: &[&str]) -> ServerConnection {
+        let mut server = ServerConnection::new(name.to_string(), format!("{}.example.com", name), 22, "deploy".to_string());
+        server.tags = tags.iter().map(|t| t.to_string()).collect();
+        server
+    }
+
+    #[test]
+    fn targets_resolve_by_name_and_tag_without_duplicates() {
+        let servers = [server("web1", &["prod", "web"]), server("web2", &["web"]), server("db1", &["prod"])];
+        let names = |targets: &str| -> Vec<String> {
+            resolve_targets(targets, &servers).unwrap().into_iter().map(|s| s.name).collect()
+        };
+        assert_eq!(names("db1"), ["db1"]);
+        assert_eq!(names("tag:web"), ["web1", "web2"]);
+        assert_eq!(names("tag:prod, web1, tag:web"), ["db1", "web1", "web2"]);
+        assert!(resolve_targets("web3", &servers).is_err());
+        assert!(resolve_targets("tag:staging", &servers).is_err());
+        assert!(resolve_targets(" , ", &servers).is_err());
+    }
+
+    #[test]
+    fn fetched_files_land_in_a_folder_per_server() {
+        let kind = TransferKind::fetch("~/logs/app.log", "/tmp/pulled").unwrap();
+        assert_eq!(kind, TransferKind::Fetch { remote: "logs/app.log".to_string(), local_dir: "/tmp/pulled".to_string() });
+        let (direction, from, to) = kind.endpoints(&server("web/1", &[]));
+        assert_eq!(direction, Direction::Download);
+        assert_eq!(from, "logs/app.log");
+        assert_eq!(PathBuf::from(to), Path::new("/tmp/pulled").join("web_1").join("app.log"));
+
+        assert!(TransferKind::fetch("/", "").is_err());
+        assert!(matches!(TransferKind::fetch("/etc/hosts", " ").unwrap(), TransferKind::Fetch { local_dir, .. } if local_dir == DEFAULT_FETCH_DIR));
+    }
+
+    /// Poll until every job has finished, checking no more than
+    /// MAX_PARALLEL ever run at once; returns the batches reported complete
+    async fn run_queue(queue: &mut TransferQueue) -> Vec<u64> {
+        let mut completed = Vec::new();
+        for _ in 0..500 {
+            assert!(queue.count(|s| *s == JobState::Running) <= MAX_PARALLEL);
+            if queue.jobs.iter().all(TransferJob::is_finished) {
+                return completed;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            completed.extend(queue.poll_events());
+        }
+        panic!("jobs did not finish: {:?}", queue.jobs);
+    }
+
+    #[tokio::test]
+    async fn queued_jobs_run_a_few_at_a_time_and_retry() {
+        // Nothing listens on port 1, so every job fails at once
+        let servers: Vec<(ServerConnection, Secrets)> = (0..6)
+            .map(|i| {
+                let server = ServerConnection::new(format!("s{}", i), "127.0.0.1".to_string(), 1, "deploy".to_string());
+                (server, Secrets::default())
+            })
+            .collect();
+        let dir = std::env::temp_dir().join(format!("ghost-queue-{}", std::process::id()));
+        let mut queue = TransferQueue::default();
+        queue.enqueue(servers, TransferKind::fetch("app.log", &dir.to_string_lossy()).unwrap());
+        assert_eq!(queue.count(|s| *s == JobState::Running), MAX_PARALLEL);
+        assert_eq!(queue.count(|s| *s == JobState::Queued), 2);
+
+        assert_eq!(run_queue(&mut queue).await, [0]);
+        assert_eq!(queue.failed().len(), 6);
+        assert!(queue.jobs.iter().all(|j| j.attempts == 1));
+        let summary = queue.batch_summary(0);
+        assert!(summary.starts_with("Fetch app.log: 0/6 servers done"), "{}", summary);
+        assert_eq!(summary.matches("\n✗ ").count(), 6);
+
+        // A retry runs the job again, resuming, in the same batch; clearing
+        // meanwhile keeps the batch whole
+        let first = queue.jobs[0].id;
+        assert!(queue.retry(first));
+        assert!(!queue.retry(first));
+        assert_eq!(queue.jobs[0].state, JobState::Running);
+        assert_eq!(queue.jobs[0].attempts, 2);
+        queue.clear_finished();
+        assert_eq!(queue.jobs.len(), 6);
+        assert_eq!(run_queue(&mut queue).await, [0]);
+        assert!(queue.batch_summary(0).starts_with("Fetch app.log: 0/6 servers done"));
+
+        queue.clear_finished();
+        assert!(queue.jobs.is_empty());
+        assert_eq!(queue.batch_summary(0), "");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn sends_go_to_the_same_path() {
+        let dir = std::env::temp_dir().join(format!("ghost-send-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.to_string_lossy().into_owned();
+        assert_eq!(TransferKind::send(&path).unwrap(), TransferKind::Send { local: path.clone(), remote: path.clone() });
+        assert!(TransferKind::send(&format!("{}/missing", path)).is_err());
+        assert!(TransferKind::send("~").is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,17 +1,30 @@
-use crate::colors::TokyoNight;
-use crate::models::{AppMode, AppState, AuthStrength, HealthStatus};
+use crate::algorithms::ScanState;
+use crate::audit::{Severity, SubjectKind};
+use crate::credentials::{CredentialPromptKind, FollowUp};
+use crate::facts::{Fact, HostFacts};
+use crate::keymap::{format_sequence, Action, KeyContext, Keymap};
+use crate::deploy::InstallState;
+use crate::keys::{KeyManager, KeyProblem, KeyPromptKind};
+use crate::forms::FormTarget;
+use crate::models::{AppMode, AppState, AuthStrength, Environment, HealthStatus, HitAreas, ListArea, LoginCheck, SessionKind, SINGLE_PANEL_WIDTH};
+use crate::palette;
+use crate::process::ProcessState;
+use crate::sftp::{self, Direction as TransferDirection, PromptKind, SftpBrowser, SftpPrompt, Side, TransferStatus};
+use crate::transfers::{JobState, TransferKind};
+use crate::themes::{Theme, ThemeVariant};
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{
-        Block, Borders, Clear, List, ListItem, Paragraph, Wrap,
+        Block, Borders, Clear, List, ListItem, ListState, Paragraph, Tabs, Widget, Wrap,
     },
     Frame,
 };
 
 pub fn ui(f: &mut Frame, app_state: &mut AppState) {
     let size = f.size();
+    app_state.hit_areas = HitAreas::default();
     
 
     // Create main layout
@@ -33,7 +46,14 @@ pub fn ui(f: &mut Frame, app_state: &mut AppState) {
         AppMode::Help => render_help_popup(f, size, app_state),
         AppMode::History => render_history_popup(f, size, app_state),
         AppMode::Analytics => render_analytics_dashboard(f, main_chunks[1], app_state),
-        AppMode::Sessions => render_sessions_view(f, main_chunks[1], app_state),
+        AppMode::Sessions => {
+            let list_area = render_sessions_view(f, main_chunks[1], app_state);
+            app_state.hit_areas.session_list = Some(ListArea {
+                area: bordered_inner(list_area),
+                item_height: SESSION_ITEM_HEIGHT,
+                offset: app_state.session_list_offset,
+            });
+        }
         AppMode::ConfirmDelete(id) => render_confirm_delete_popup(f, size, app_state, &id),
         AppMode::Connecting(id) => render_connecting_popup(f, size, app_state, &id),
         AppMode::Loading(context) => {
@@ -42,18 +62,53 @@ pub fn ui(f: &mut Frame, app_state: &mut AppState) {
         }
         AppMode::AddServer | AppMode::EditServer(_) => {
             render_main_view(f, main_chunks[1], app_state);
-            render_server_form_popup(f, size, app_state);
+            app_state.hit_areas.form_fields = render_server_form_popup(f, size, app_state);
+        }
+        AppMode::CommandPalette => {
+            render_main_view(f, main_chunks[1], app_state);
+            render_command_palette(f, size, app_state);
         }
         AppMode::ConfirmDiscard => {
             render_main_view(f, main_chunks[1], app_state);
             render_server_form_popup(f, size, app_state);
-            render_confirm_discard_popup(f, size);
+            render_confirm_discard_popup(f, size, app_state);
+        }
+        AppMode::Terminal => render_terminal_view(f, main_chunks[1], app_state),
+        AppMode::Sftp => render_sftp_view(f, main_chunks[1], app_state),
+        AppMode::Transfers => render_transfers_view(f, main_chunks[1], app_state),
+        AppMode::Keys => render_keys_view(f, main_chunks[1], app_state),
+        AppMode::Audit => render_audit_view(f, main_chunks[1], app_state),
+        AppMode::Algorithms => render_algorithms_view(f, main_chunks[1], app_state),
+        AppMode::TransferForm => {
+            render_main_view(f, main_chunks[1], app_state);
+            render_transfer_form_popup(f, size, app_state);
+        }
+        AppMode::CredentialPrompt => {
+            render_main_view(f, main_chunks[1], app_state);
+            render_credential_prompt(f, size, app_state);
+        }
+        AppMode::ConfirmName => {
+            match app_state.name_confirm.as_ref().map(|c| &c.previous) {
+                Some(AppMode::Keys) => render_keys_view(f, main_chunks[1], app_state),
+                Some(AppMode::TransferForm) => {
+                    render_main_view(f, main_chunks[1], app_state);
+                    render_transfer_form_popup(f, size, app_state);
+                }
+                _ => render_main_view(f, main_chunks[1], app_state),
+            }
+            render_name_confirm(f, size, app_state);
         }
     }
 
     // Render footer
     render_footer(f, main_chunks[2], app_state);
 
+    // The theme selector floats above every view so the preview recolours
+    // whatever is on screen
+    if app_state.show_theme_selector {
+        render_theme_selector_popup(f, size, app_state);
+    }
+
     // Render general popup if needed
     if app_state.show_popup {
         render_message_popup(f, size, app_state);
@@ -96,12 +151,18 @@ fn render_header(f: &mut Frame, area: Rect, app_state: &AppState) {
 }
 
 fn render_main_view(f: &mut Frame, area: Rect, app_state: &mut AppState) {
+    let (layout, direction) = app_state.layout.effective(area.width, area.height);
     let chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints(app_state.layout.get_constraints())
+        .direction(direction)
+        .constraints(layout.get_constraints())
         .split(area);
 
-    match app_state.layout.mode {
+    app_state.hit_areas.main_panels = Some(area);
+    // Borders can only be dragged when the panels are drawn as configured
+    if layout == app_state.layout {
+        app_state.hit_areas.panel_borders = chunks.iter().skip(1).map(|c| c.x).collect();
+    }
+    match layout.mode {
         crate::models::LayoutMode::SinglePanel => {
             // Only render server list in full width
             render_server_list(f, chunks[0], app_state);
@@ -118,9 +179,915 @@ fn render_main_view(f: &mut Frame, area: Rect, app_state: &mut AppState) {
             render_metrics_panel(f, chunks[2], app_state);
         }
     }
+    app_state.hit_areas.server_list = Some(ListArea {
+        area: bordered_inner(chunks[0]),
+        item_height: SERVER_ITEM_HEIGHT,
+        offset: app_state.server_list_offset,
+    });
+}
+
+/// Embedded terminal tabs beside a narrow server list. The active session is
+/// resized to the pane, so the remote side always matches what's drawn.
+fn render_terminal_view(f: &mut Frame, area: Rect, app_state: &mut AppState) {
+    let pane = if area.width >= SINGLE_PANEL_WIDTH {
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(30), Constraint::Percentage(70)])
+            .split(area);
+        render_server_list(f, chunks[0], app_state);
+        chunks[1]
+    } else {
+        area
+    };
+
+    let theme = app_state.theme_manager.current_theme().clone();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(0)])
+        .split(pane);
+
+    let tabs = &mut app_state.terminals;
+    let titles: Vec<Line> = tabs
+        .sessions
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            // Tabs on Ghost's own SSH client are marked, they don't run ssh
+            let marker = if s.is_native() { "⚡" } else { "" };
+            Line::from(format!("{}:{}{}", i + 1, marker, s.server_name))
+        })
+        .collect();
+    let tab_bar = Tabs::new(titles)
+        .select(tabs.active)
+        .style(Style::default().fg(theme.comment).bg(theme.bg))
+        .highlight_style(Style::default().fg(theme.theme_primary).add_modifier(Modifier::BOLD));
+    f.render_widget(tab_bar, chunks[0]);
+
+    let Some(session) = tabs.active_mut() else {
+        let empty = Paragraph::new("No embedded sessions - start Ghost with --embedded to open connections in tabs")
+            .style(Style::default().fg(theme.comment).bg(theme.bg))
+            .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(theme.border)));
+        f.render_widget(empty, chunks[1]);
+        return;
+    };
+
+    let block = Block::default()
+        .title(format!(" {} ", session.title()))
+        .title_style(Style::default().fg(theme.theme_primary).add_modifier(Modifier::BOLD))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border_highlight))
+        .style(Style::default().bg(theme.bg));
+    let inner = block.inner(chunks[1]);
+    f.render_widget(block, chunks[1]);
+
+    session.resize(inner.height, inner.width);
+    let parser = session.parser();
+    let screen = parser.screen();
+    f.render_widget(TerminalScreen { screen, fg: theme.fg, bg: theme.bg }, inner);
+    if !screen.hide_cursor() {
+        let (row, col) = screen.cursor_position();
+        f.set_cursor(inner.x + col.min(inner.width.saturating_sub(1)), inner.y + row.min(inner.height.saturating_sub(1)));
+    }
+}
+
+/// Local and remote panes side by side, with transfers listed below
+fn render_sftp_view(f: &mut Frame, area: Rect, app_state: &AppState) {
+    let Some(browser) = app_state.sftp.as_ref() else {
+        return;
+    };
+    let transfer_rows = browser.transfers.len() as u16;
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(if transfer_rows > 0 { transfer_rows + 2 } else { 0 }),
+        ])
+        .split(area);
+    let panes = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(chunks[0]);
+
+    render_sftp_pane(f, panes[0], app_state, browser, Side::Local);
+    render_sftp_pane(f, panes[1], app_state, browser, Side::Remote);
+    if transfer_rows > 0 {
+        render_sftp_transfers(f, chunks[1], app_state, browser);
+    }
+    if let Some(prompt) = &browser.prompt {
+        render_sftp_prompt(f, area, app_state, prompt);
+    }
+}
+
+fn render_sftp_pane(f: &mut Frame, area: Rect, app_state: &AppState, browser: &SftpBrowser, side: Side) {
+    let theme = app_state.theme_manager.current_theme();
+    let pane = browser.pane(side);
+    let focused = browser.focus == side;
+    let label = match side {
+        Side::Local => "💻 Local".to_string(),
+        Side::Remote => format!("🌐 {}", browser.server_name),
+    };
+    let block = Block::default()
+        .title(format!(" {}: {} ", label, pane.path))
+        .title_style(if focused {
+            Style::default().fg(theme.theme_primary).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(theme.comment)
+        })
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(if focused { theme.border_highlight } else { theme.border }))
+        .style(Style::default().bg(theme.bg));
+
+    if side == Side::Remote && browser.client.is_none() {
+        let (text, color) = match &browser.error {
+            Some(error) => (format!("❌ {}", error), theme.red),
+            None => (format!("{} Connecting to {}...", app_state.get_globe_char(), browser.server_name), theme.comment),
+        };
+        let status = Paragraph::new(text)
+            .style(Style::default().fg(color))
+            .wrap(Wrap { trim: true })
+            .block(block);
+        f.render_widget(status, area);
+        return;
+    }
+
+    // Icon and a space take three columns, sizes are right-aligned in ten
+    let name_width = block.inner(area).width.saturating_sub(13) as usize;
+    let items: Vec<ListItem> = pane
+        .entries
+        .iter()
+        .map(|entry| {
+            let (icon, color) = if entry.is_dir { ("📁", theme.blue) } else { ("📄", theme.fg) };
+            let mut name = entry.name.clone();
+            if entry.is_dir {
+                name.push('/');
+            }
+            let name: String = name.chars().take(name_width).collect();
+            let size = if entry.is_dir { String::new() } else { sftp::format_size(entry.size) };
+            ListItem::new(Line::from(vec![
+                Span::raw(format!("{} ", icon)),
+                Span::styled(format!("{:<width$}", name, width = name_width), Style::default().fg(color)),
+                Span::styled(format!("{:>10}", size), Style::default().fg(theme.comment)),
+            ]))
+        })
+        .collect();
+
+    let highlight = if focused {
+        Style::default().bg(theme.bg_highlight).fg(theme.theme_secondary).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().bg(theme.bg_highlight)
+    };
+    let list = List::new(items).block(block).highlight_style(highlight);
+    let mut state = ListState::default();
+    state.select((!pane.entries.is_empty()).then_some(pane.selected));
+    f.render_stateful_widget(list, area, &mut state);
+}
+
+fn render_sftp_transfers(f: &mut Frame, area: Rect, app_state: &AppState, browser: &SftpBrowser) {
+    let theme = app_state.theme_manager.current_theme();
+    let lines: Vec<Line> = browser
+        .transfers
+        .iter()
+        .map(|transfer| {
+            let arrow = match transfer.direction {
+                TransferDirection::Upload => "⬆",
+                TransferDirection::Download => "⬇",
+            };
+            let (status, color) = match &transfer.status {
+                TransferStatus::Running => (format!("{:>3.0}%", transfer.progress() * 100.0), theme.cyan),
+                TransferStatus::Done => ("done".to_string(), theme.green),
+                TransferStatus::Failed(error) => (format!("failed: {}", error), theme.red),
+            };
+            Line::from(vec![
+                Span::styled(format!(" {} ", arrow), Style::default().fg(theme.theme_primary)),
+                Span::styled(format!("{:<24} ", transfer.name.chars().take(24).collect::<String>()), Style::default().fg(theme.fg)),
+                Span::styled(create_progress_bar(transfer.progress(), 20), Style::default().fg(color)),
+                Span::styled(
+                    format!(" {}/{} ", sftp::format_size(transfer.done), sftp::format_size(transfer.total)),
+                    Style::default().fg(theme.comment),
+                ),
+                Span::styled(status, Style::default().fg(color)),
+            ])
+        })
+        .collect();
+
+    let transfers = Paragraph::new(lines).block(
+        Block::default()
+            .title(" Transfers ")
+            .title_style(Style::default().fg(theme.theme_secondary).add_modifier(Modifier::BOLD))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border))
+            .style(Style::default().bg(theme.bg)),
+    );
+    f.render_widget(transfers, area);
+}
+
+fn render_sftp_prompt(f: &mut Frame, area: Rect, app_state: &AppState, prompt: &SftpPrompt) {
+    let theme = app_state.theme_manager.current_theme();
+    let popup_area = centered_rect(50, 20, area);
+    f.render_widget(Clear, popup_area);
+
+    if prompt.kind == PromptKind::Delete {
+        let target = prompt.target.as_ref().map(|e| e.name.as_str()).unwrap_or_default();
+        let what = match prompt.target.as_ref() {
+            Some(entry) if entry.is_dir && !entry.is_link => "directory and everything in it",
+            _ => "file",
+        };
+        let text = vec![
+            Line::from(""),
+            Line::from(vec![
+                Span::raw(format!("Delete {} \"", what)),
+                Span::styled(target, Style::default().fg(theme.cyan)),
+                Span::raw("\"?"),
+            ]),
+            Line::from(""),
+            Line::from(Span::styled("y: Yes | any other key: No", Style::default().fg(theme.comment))),
+        ];
+        let confirm = Paragraph::new(text)
+            .block(
+                Block::default()
+                    .title(" Confirm Delete ")
+                    .title_style(Style::default().fg(theme.red).add_modifier(Modifier::BOLD))
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(theme.red))
+                    .style(Style::default().bg(theme.bg_popup)),
+            )
+            .style(Style::default().fg(theme.fg))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+        f.render_widget(confirm, popup_area);
+        return;
+    }
+
+    let title = match prompt.kind {
+        PromptKind::Rename => " Rename ",
+        _ => " New Directory ",
+    };
+    let block = Block::default()
+        .title(title)
+        .title_style(Style::default().fg(theme.theme_primary).add_modifier(Modifier::BOLD))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.theme_primary))
+        .style(Style::default().bg(theme.bg_popup));
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+    let field_area = Rect { height: inner.height.min(3), ..inner };
+    render_input_field(f, field_area, &prompt.input, true, theme);
+}
+
+/// Per-server jobs of every send and fetch, newest at the bottom
+fn render_transfers_view(f: &mut Frame, area: Rect, app_state: &AppState) {
+    let theme = app_state.theme_manager.current_theme();
+    let queue = &app_state.transfers;
+
+    if queue.jobs.is_empty() {
+        let empty = Paragraph::new(format!(
+            "No transfers yet - press {} on the server list to send files, {} to fetch them",
+            app_state.keymap.hint(KeyContext::Normal, Action::SendFiles),
+            app_state.keymap.hint(KeyContext::Normal, Action::FetchFiles),
+        ))
+        .style(Style::default().fg(theme.comment))
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .title(" 📦 Transfer Queue ")
+                .title_style(Style::default().fg(theme.theme_secondary).add_modifier(Modifier::BOLD))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border))
+                .style(Style::default().bg(theme.bg)),
+        );
+        f.render_widget(empty, area);
+        return;
+    }
+
+    let items: Vec<ListItem> = queue
+        .jobs
+        .iter()
+        .map(|job| {
+            let (symbol, color, status) = match &job.state {
+                JobState::Queued => ("…", theme.comment, "queued".to_string()),
+                JobState::Running => ("⚡", theme.cyan, format!("{:>3.0}%", job.progress() * 100.0)),
+                JobState::Done => ("✓", theme.green, "done".to_string()),
+                JobState::Failed(error) => ("✗", theme.red, format!("failed: {}", error)),
+            };
+            let (arrow, path) = match &job.kind {
+                TransferKind::Send { remote, .. } => ("⬆", remote),
+                TransferKind::Fetch { remote, .. } => ("⬇", remote),
+            };
+            let attempts = if job.attempts > 1 { format!(" (attempt {})", job.attempts) } else { String::new() };
+            ListItem::new(vec![
+                Line::from(vec![
+                    Span::styled(format!(" {} ", symbol), Style::default().fg(color).add_modifier(Modifier::BOLD)),
+                    Span::styled(format!("{:<16} ", job.server.name), Style::default().fg(theme.fg).add_modifier(Modifier::BOLD)),
+                    Span::styled(format!("{} {}", arrow, path), Style::default().fg(theme.comment)),
+                ]),
+                Line::from(vec![
+                    Span::raw("    "),
+                    Span::styled(create_progress_bar(job.progress(), 24), Style::default().fg(color)),
+                    Span::styled(
+                        format!(" {}/{} ", sftp::format_size(job.done), sftp::format_size(job.total)),
+                        Style::default().fg(theme.comment),
+                    ),
+                    Span::styled(status, Style::default().fg(color)),
+                    Span::styled(attempts, Style::default().fg(theme.orange)),
+                ]),
+            ])
+        })
+        .collect();
+
+    let title = format!(
+        " 📦 Transfer Queue [{} running · {} queued · {} done · {} failed] ",
+        queue.count(|s| *s == JobState::Running),
+        queue.count(|s| *s == JobState::Queued),
+        queue.count(|s| *s == JobState::Done),
+        queue.count(|s| matches!(s, JobState::Failed(_))),
+    );
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(title)
+                .title_style(Style::default().fg(theme.theme_secondary).add_modifier(Modifier::BOLD))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border))
+                .style(Style::default().bg(theme.bg)),
+        )
+        .highlight_style(Style::default().bg(theme.bg_highlight));
+    let mut state = ListState::default();
+    state.select(Some(queue.selected));
+    f.render_stateful_widget(list, area, &mut state);
+}
+
+/// Key pairs in ~/.ssh, with the servers whose configured key ssh can't use
+/// listed underneath
+fn render_keys_view(f: &mut Frame, area: Rect, app_state: &AppState) {
+    let Some(keys) = app_state.keys.as_ref() else {
+        return;
+    };
+    let theme = app_state.theme_manager.current_theme();
+    let block = |title: String, color| {
+        Block::default()
+            .title(title)
+            .title_style(Style::default().fg(color).add_modifier(Modifier::BOLD))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border))
+            .style(Style::default().bg(theme.bg))
+    };
+
+    let issues_height = if keys.issues.is_empty() { 0 } else { keys.issues.len().min(6) as u16 + 2 };
+    let installs = &keys.installs.jobs;
+    let installs_height = if installs.is_empty() { 0 } else { installs.len().min(6) as u16 + 2 };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(4), Constraint::Length(issues_height), Constraint::Length(installs_height)])
+        .split(area);
+
+    let agent = if !keys.agent {
+        "agent not running".to_string()
+    } else {
+        format!("{} in agent", keys.keys.iter().filter(|k| k.in_agent).count())
+    };
+    let title = format!(" 🔑 SSH Keys [{} · {} keys · {}] ", keys.dir.display(), keys.keys.len(), agent);
+
+    if keys.keys.is_empty() {
+        let empty = Paragraph::new(format!(
+            "No keys in {} - press {} to generate one",
+            keys.dir.display(),
+            app_state.keymap.hint(KeyContext::Keys, Action::GenerateKey),
+        ))
+        .style(Style::default().fg(theme.comment))
+        .alignment(Alignment::Center)
+        .block(block(title, theme.theme_secondary));
+        f.render_widget(empty, chunks[0]);
+    } else {
+        let items: Vec<ListItem> = keys.keys.iter().map(|key| {
+            let bits = key.bits.map(|b| format!(" {}", b)).unwrap_or_default();
+            let (lock, lock_color) = match (&key.private_path, key.encrypted) {
+                (None, _) => ("public key only", theme.comment),
+                (_, Some(true)) => ("🔒 passphrase", theme.green),
+                _ => ("no passphrase", theme.orange),
+            };
+            let mut first = vec![
+                Span::styled(" 🔑 ", Style::default().fg(theme.theme_primary)),
+                Span::styled(format!("{:<20} ", key.name), Style::default().fg(theme.fg).add_modifier(Modifier::BOLD)),
+                Span::styled(format!("{:<14} ", format!("{}{}", key.kind(), bits)), Style::default().fg(theme.cyan)),
+                Span::styled(lock, Style::default().fg(lock_color)),
+            ];
+            if key.in_agent {
+                first.push(Span::styled("  ✓ in agent", Style::default().fg(theme.green)));
+            }
+            if key.too_open {
+                first.push(Span::styled("  ⚠ readable by others", Style::default().fg(theme.red)));
+            }
+            let mut second = vec![
+                Span::raw("    "),
+                Span::styled(key.fingerprint.clone().unwrap_or_else(|| "no public key".to_string()), Style::default().fg(theme.comment)),
+            ];
+            if !key.comment.is_empty() {
+                second.push(Span::styled(format!("  {}", key.comment), Style::default().fg(theme.fg)));
+            }
+            if !key.used_by.is_empty() {
+                second.push(Span::styled(format!("  · used by {}", key.used_by.join(", ")), Style::default().fg(theme.theme_secondary)));
+            }
+            ListItem::new(vec![Line::from(first), Line::from(second)])
+        }).collect();
+        let list = List::new(items)
+            .block(block(title, theme.theme_secondary))
+            .highlight_style(Style::default().bg(theme.bg_highlight));
+        let mut state = ListState::default();
+        state.select(Some(keys.selected));
+        f.render_stateful_widget(list, chunks[0], &mut state);
+    }
+
+    if !keys.issues.is_empty() {
+        let lines: Vec<Line> = keys.issues.iter().map(|issue| {
+            let problem = match issue.problem {
+                KeyProblem::Missing => "doesn't exist",
+                KeyProblem::TooOpen => "is readable by others - ssh won't use it (chmod 600)",
+            };
+            Line::from(vec![
+                Span::styled(" ⚠ ", Style::default().fg(theme.red)),
+                Span::styled(format!("{}: ", issue.server_name), Style::default().fg(theme.fg).add_modifier(Modifier::BOLD)),
+                Span::styled(format!("{} {}", issue.key_path, problem), Style::default().fg(theme.orange)),
+            ])
+        }).collect();
+        let issues = Paragraph::new(lines).block(block(format!(" ⚠ Servers With Unusable Keys [{}] ", keys.issues.len()), theme.red));
+        f.render_widget(issues, chunks[1]);
+    }
+
+    // The most recent installs
+    if !installs.is_empty() {
+        let lines: Vec<Line> = installs.iter().skip(installs.len().saturating_sub(6)).map(|job| {
+            let (symbol, color, status) = match &job.state {
+                InstallState::Running => ("⚡", theme.cyan, "installing…".to_string()),
+                InstallState::Installed { added: true } => ("✓", theme.green, "added, key login works".to_string()),
+                InstallState::Installed { added: false } => ("✓", theme.green, "already there, key login works".to_string()),
+                InstallState::Failed(e) => ("✗", theme.red, e.clone()),
+            };
+            Line::from(vec![
+                Span::styled(format!(" {} ", symbol), Style::default().fg(color).add_modifier(Modifier::BOLD)),
+                Span::styled(format!("{:<16} ", job.server.name), Style::default().fg(theme.fg).add_modifier(Modifier::BOLD)),
+                Span::styled(format!("{}: ", job.key_name), Style::default().fg(theme.comment)),
+                Span::styled(status, Style::default().fg(color)),
+            ])
+        }).collect();
+        let running = installs.iter().filter(|j| j.state == InstallState::Running).count();
+        let installs = Paragraph::new(lines).block(block(format!(" 📤 Key Installs [{} running] ", running), theme.theme_secondary));
+        f.render_widget(installs, chunks[2]);
+    }
+
+    if keys.prompt.is_some() {
+        render_keys_prompt(f, area, app_state, keys);
+    }
+}
+
+fn render_keys_prompt(f: &mut Frame, area: Rect, app_state: &AppState, keys: &KeyManager) {
+    let Some(prompt) = keys.prompt.as_ref() else {
+        return;
+    };
+    let theme = app_state.theme_manager.current_theme();
+    let popup_area = centered_rect(50, 20, area);
+    f.render_widget(Clear, popup_area);
+
+    if let KeyPromptKind::SwitchAuth { servers, key_name, .. } = &prompt.kind {
+        let text = vec![
+            Line::from(""),
+            Line::from(vec![
+                Span::raw("Log in to "),
+                Span::styled(servers.join(", "), Style::default().fg(theme.cyan)),
+                Span::raw(" with "),
+                Span::styled(key_name.as_str(), Style::default().fg(theme.cyan)),
+                Span::raw(" from now on?"),
+            ]),
+            Line::from(""),
+            Line::from(Span::styled("y: Yes | any other key: No", Style::default().fg(theme.comment))),
+        ];
+        let confirm = Paragraph::new(text)
+            .block(
+                Block::default()
+                    .title(prompt.title())
+                    .title_style(Style::default().fg(theme.theme_primary).add_modifier(Modifier::BOLD))
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(theme.theme_primary))
+                    .style(Style::default().bg(theme.bg_popup)),
+            )
+            .style(Style::default().fg(theme.fg))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+        f.render_widget(confirm, popup_area);
+        return;
+    }
+
+    let block = Block::default()
+        .title(prompt.title())
+        .title_style(Style::default().fg(theme.theme_primary).add_modifier(Modifier::BOLD))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.theme_primary))
+        .style(Style::default().bg(theme.bg_popup));
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+    let field_area = Rect { height: inner.height.min(3), ..inner };
+    render_input_field(f, field_area, &prompt.input, true, theme);
+}
+
+fn severity_color(severity: Severity, theme: &Theme) -> Color {
+    match severity {
+        Severity::High => theme.red,
+        Severity::Medium => theme.orange,
+        Severity::Low => theme.yellow,
+    }
+}
+
+/// Fleet summary of an algorithm scan over its servers, with the selected
+/// server's algorithms by category, weak ones marked
+fn render_algorithms_view(f: &mut Frame, area: Rect, app_state: &AppState) {
+    let scan = &app_state.algorithm_scan;
+    let theme = app_state.theme_manager.current_theme();
+    let block = |title: String, color| {
+        Block::default()
+            .title(title)
+            .title_style(Style::default().fg(color).add_modifier(Modifier::BOLD))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border))
+            .style(Style::default().bg(theme.bg))
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(5), Constraint::Min(6)])
+        .split(area);
+
+    let summary = scan.summary();
+    let mut counts = vec![Span::styled(format!(" {} scanned", summary.scanned), Style::default().fg(theme.fg))];
+    if summary.running > 0 {
+        counts.push(Span::styled(format!(" · {} scanning…", summary.running), Style::default().fg(theme.cyan)));
+    }
+    if summary.failed > 0 {
+        counts.push(Span::styled(format!(" · {} unreachable", summary.failed), Style::default().fg(theme.comment)));
+    }
+    let weak_color = if summary.weak_servers > 0 { theme.orange } else { theme.green };
+    counts.push(Span::styled(
+        format!(" · {} offer weak algorithms", summary.weak_servers),
+        Style::default().fg(weak_color).add_modifier(Modifier::BOLD),
+    ));
+    let mut common = vec![Span::styled(" Most common: ", Style::default().fg(theme.comment))];
+    if summary.common.is_empty() {
+        common.push(Span::styled("none", Style::default().fg(theme.green)));
+    }
+    for (i, (name, severity, count)) in summary.common.iter().take(8).enumerate() {
+        if i > 0 {
+            common.push(Span::raw(", "));
+        }
+        common.push(Span::styled(format!("{} ×{}", name, count), Style::default().fg(severity_color(*severity, theme))));
+    }
+    let lines = vec![Line::from(counts), Line::from(common), Line::from(Span::styled(
+        " Read from each sshd's KEXINIT, before any login",
+        Style::default().fg(theme.comment),
+    ))];
+    let summary = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(block(format!(" 🛡 Algorithm Scan [{} servers] ", scan.servers.len()), theme.theme_primary));
+    f.render_widget(summary, chunks[0]);
+
+    let panes = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+        .split(chunks[1]);
+
+    let items: Vec<ListItem> = scan.servers.iter().map(|server| {
+        let (symbol, color, status) = match &server.state {
+            ScanState::Running => ("⚡".to_string(), theme.cyan, "scanning…".to_string()),
+            ScanState::Failed(_) => ("✗".to_string(), theme.comment, "unreachable".to_string()),
+            ScanState::Done(offer) => {
+                let software = offer.banner.splitn(3, '-').nth(2).unwrap_or_default().to_string();
+                match offer.worst() {
+                    Some(severity) => (severity.as_str().to_string(), severity_color(severity, theme), software),
+                    None => ("✓".to_string(), theme.green, software),
+                }
+            }
+        };
+        ListItem::new(Line::from(vec![
+            Span::styled(format!(" {:<7}", symbol), Style::default().fg(color).add_modifier(Modifier::BOLD)),
+            Span::styled(format!("{:<18} ", server.name), Style::default().fg(theme.fg).add_modifier(Modifier::BOLD)),
+            Span::styled(status, Style::default().fg(theme.comment)),
+        ]))
+    }).collect();
+    let list = List::new(items)
+        .block(block(" Servers ".to_string(), theme.theme_secondary))
+        .highlight_style(Style::default().bg(theme.bg_highlight));
+    let mut state = ListState::default();
+    state.select(Some(scan.selected));
+    f.render_stateful_widget(list, panes[0], &mut state);
+
+    let Some(server) = scan.selected_scan() else {
+        let empty = Paragraph::new("No servers to scan")
+            .style(Style::default().fg(theme.comment))
+            .alignment(Alignment::Center)
+            .block(block(" Algorithms ".to_string(), theme.theme_secondary));
+        f.render_widget(empty, panes[1]);
+        return;
+    };
+    let title = format!(" {} ({}:{}) ", server.name, server.host, server.port);
+    let offer = match &server.state {
+        ScanState::Done(offer) => offer,
+        state => {
+            let (text, color) = match state {
+                ScanState::Failed(e) => (e.clone(), theme.red),
+                _ => ("Scanning…".to_string(), theme.cyan),
+            };
+            let message = Paragraph::new(text).style(Style::default().fg(color)).wrap(Wrap { trim: true }).block(block(title, theme.theme_secondary));
+            f.render_widget(message, panes[1]);
+            return;
+        }
+    };
+    let details = block(format!("{}· {} ", title, offer.banner), theme.theme_secondary);
+    let inner = details.inner(panes[1]);
+    f.render_widget(details, panes[1]);
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(inner);
+    let cells: Vec<Rect> = rows
+        .iter()
+        .flat_map(|row| {
+            Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .split(*row)
+                .to_vec()
+        })
+        .collect();
+    for ((category, algorithms), cell) in offer.sections().into_iter().zip(cells) {
+        let mut lines = vec![Line::from(Span::styled(
+            format!(" {}", category.title()),
+            Style::default().fg(theme.theme_primary).add_modifier(Modifier::BOLD),
+        ))];
+        for algorithm in algorithms {
+            lines.push(match algorithm.weakness {
+                Some((severity, reason)) => Line::from(vec![
+                    Span::styled(format!(" ✗ {}", algorithm.name), Style::default().fg(severity_color(severity, theme))),
+                    Span::styled(format!("  {}", reason), Style::default().fg(theme.comment)),
+                ]),
+                None => Line::from(Span::styled(format!("   {}", algorithm.name), Style::default().fg(theme.fg))),
+            });
+        }
+        f.render_widget(Paragraph::new(lines), cell);
+    }
+}
+
+/// Audit score with the findings, most severe first
+fn render_audit_view(f: &mut Frame, area: Rect, app_state: &AppState) {
+    let Some(report) = app_state.audit.as_ref() else {
+        return;
+    };
+    let theme = app_state.theme_manager.current_theme();
+    let block = |title: String, color| {
+        Block::default()
+            .title(title)
+            .title_style(Style::default().fg(color).add_modifier(Modifier::BOLD))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border))
+            .style(Style::default().bg(theme.bg))
+    };
+    let severity_color = |severity| severity_color(severity, theme);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(5), Constraint::Min(3)])
+        .split(area);
+
+    let score_color = match report.score {
+        90.. => theme.green,
+        70..=89 => theme.yellow,
+        _ => theme.red,
+    };
+    let mut counts = vec![Span::raw(" ")];
+    for severity in [Severity::High, Severity::Medium, Severity::Low] {
+        counts.push(Span::styled(
+            format!("{} {}   ", report.count(severity), severity.as_str().to_lowercase()),
+            Style::default().fg(severity_color(severity)).add_modifier(Modifier::BOLD),
+        ));
+    }
+    let summary = vec![
+        Line::from(vec![
+            Span::styled(format!(" {}/100 ", report.score), Style::default().fg(score_color).add_modifier(Modifier::BOLD)),
+            Span::styled(format!("grade {}", report.grade), Style::default().fg(score_color)),
+            Span::styled(
+                format!("   {} servers · {} keys checked", report.servers_checked, report.keys_checked),
+                Style::default().fg(theme.comment),
+            ),
+        ]),
+        Line::from(counts),
+        Line::from(Span::styled(
+            format!(" Local config, key files and known_hosts only · {}", report.generated_at.with_timezone(&chrono::Local).format("%H:%M:%S")),
+            Style::default().fg(theme.comment),
+        )),
+    ];
+    f.render_widget(Paragraph::new(summary).block(block(" 🛡 Security Audit ".to_string(), theme.theme_primary)), chunks[0]);
+
+    let title = format!(" Findings [{}] ", report.findings.len());
+    if report.findings.is_empty() {
+        let empty = Paragraph::new("Nothing to report")
+            .style(Style::default().fg(theme.green))
+            .alignment(Alignment::Center)
+            .block(block(title, theme.theme_secondary));
+        f.render_widget(empty, chunks[1]);
+        return;
+    }
+    let items: Vec<ListItem> = report.findings.iter().map(|finding| {
+        let color = severity_color(finding.severity);
+        let kind = match finding.kind {
+            SubjectKind::Server => "🖥",
+            SubjectKind::Key => "🔑",
+        };
+        ListItem::new(Line::from(vec![
+            Span::styled(format!(" {:<7}", finding.severity.as_str()), Style::default().fg(color).add_modifier(Modifier::BOLD)),
+            Span::raw(format!("{} ", kind)),
+            Span::styled(format!("{:<24} ", finding.subject), Style::default().fg(theme.fg).add_modifier(Modifier::BOLD)),
+            Span::styled(finding.message.clone(), Style::default().fg(color)),
+        ]))
+    }).collect();
+    let list = List::new(items)
+        .block(block(title, theme.theme_secondary))
+        .highlight_style(Style::default().bg(theme.bg_highlight));
+    let mut state = ListState::default();
+    state.select(Some(app_state.audit_selected));
+    f.render_stateful_widget(list, chunks[1], &mut state);
+}
+
+fn render_transfer_form_popup(f: &mut Frame, area: Rect, app_state: &AppState) {
+    let Some(form) = app_state.transfer_form.as_ref() else {
+        return;
+    };
+    let theme = app_state.theme_manager.current_theme();
+    let height = form.fields.len() as u16 * 3 + 2;
+    let popup_area = centered_rect(60, 100, area);
+    let popup_area = Rect {
+        y: popup_area.y + popup_area.height.saturating_sub(height) / 2,
+        height: height.min(popup_area.height),
+        ..popup_area
+    };
+    f.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .title(form.title())
+        .title_style(Style::default().fg(theme.theme_primary).add_modifier(Modifier::BOLD))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.theme_primary))
+        .style(Style::default().bg(theme.bg_popup));
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(form.fields.iter().map(|_| Constraint::Length(3)).collect::<Vec<_>>())
+        .split(inner);
+    for (i, field) in form.fields.iter().enumerate() {
+        render_input_field(f, rows[i], field, i == form.focused, theme);
+    }
+}
+
+/// Vault passphrase or server password, masked
+fn render_credential_prompt(f: &mut Frame, area: Rect, app_state: &AppState) {
+    let Some(prompt) = app_state.credential_prompt.as_ref() else {
+        return;
+    };
+    let theme = app_state.theme_manager.current_theme();
+    let popup_area = centered_rect(50, 100, area);
+    let popup_area = Rect {
+        y: popup_area.y + popup_area.height.saturating_sub(6) / 2,
+        height: popup_area.height.min(6),
+        ..popup_area
+    };
+    f.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .title(prompt.title())
+        .title_style(Style::default().fg(theme.theme_primary).add_modifier(Modifier::BOLD))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.theme_primary))
+        .style(Style::default().bg(theme.bg_popup));
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+
+    let reason = match (&prompt.kind, prompt.then) {
+        (CredentialPromptKind::Password, _) => format!("{} will log in with it from now on", prompt.server_name),
+        (CredentialPromptKind::LoginPassword, _) => format!("{} asks for a password - used once, not stored", prompt.server_name),
+        (CredentialPromptKind::KeyPassphrase { key_path }, _) => format!("{} - used once, not stored", key_path),
+        (_, FollowUp::Connect) => format!("Holds the stored password for {}", prompt.server_name),
+        (_, FollowUp::SavePassword) => format!("Needed to store a password for {}", prompt.server_name),
+    };
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Length(3)])
+        .split(inner);
+    f.render_widget(
+        Paragraph::new(reason).style(Style::default().fg(theme.comment)).alignment(Alignment::Center),
+        rows[0],
+    );
+    render_input_field(f, rows[1], &prompt.input, true, theme);
+}
+
+/// Asks for a protected server's name (or the number of them) before a
+/// connect, delete, transfer or key install goes ahead
+fn render_name_confirm(f: &mut Frame, area: Rect, app_state: &AppState) {
+    let Some(confirm) = app_state.name_confirm.as_ref() else {
+        return;
+    };
+    let theme = app_state.theme_manager.current_theme();
+    let popup_area = centered_rect(50, 100, area);
+    let popup_area = Rect {
+        y: popup_area.y + popup_area.height.saturating_sub(9) / 2,
+        height: popup_area.height.min(9),
+        ..popup_area
+    };
+    f.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .title(" ⚠ Protected Server ")
+        .title_style(Style::default().fg(theme.red).add_modifier(Modifier::BOLD))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.red))
+        .style(Style::default().bg(theme.bg_popup));
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Length(1), Constraint::Length(1), Constraint::Length(3), Constraint::Length(1)])
+        .split(inner);
+    let question = Line::from(vec![
+        Span::raw(format!("{} ", confirm.verb())),
+        Span::styled(confirm.servers.join(", "), Style::default().fg(theme.red).add_modifier(Modifier::BOLD)),
+        Span::raw("?"),
+    ]);
+    f.render_widget(Paragraph::new(question).alignment(Alignment::Center), rows[0]);
+    let expected = confirm.expected();
+    let hint = if confirm.servers.len() == 1 {
+        format!("Type {} to confirm", expected)
+    } else {
+        format!("Type {} (the number of protected servers) to confirm", expected)
+    };
+    f.render_widget(
+        Paragraph::new(hint).style(Style::default().fg(theme.comment)).alignment(Alignment::Center),
+        rows[2],
+    );
+    render_input_field(f, rows[3], &confirm.input, true, theme);
+    if confirm.mismatch {
+        f.render_widget(
+            Paragraph::new(format!("Doesn't match {}", expected)).style(Style::default().fg(theme.red)).alignment(Alignment::Center),
+            rows[4],
+        );
+    }
+}
+
+/// Draws a VT100 screen cell by cell; default colours follow the theme
+struct TerminalScreen<'a> {
+    screen: &'a vt100::Screen,
+    fg: Color,
+    bg: Color,
+}
+
+impl Widget for TerminalScreen<'_> {
+    fn render(self, area: Rect, buf: &mut ratatui::buffer::Buffer) {
+        let vt_color = |color: vt100::Color, default: Color| match color {
+            vt100::Color::Default => default,
+            vt100::Color::Idx(i) => Color::Indexed(i),
+            vt100::Color::Rgb(r, g, b) => Color::Rgb(r, g, b),
+        };
+
+        for row in 0..area.height {
+            for col in 0..area.width {
+                let Some(cell) = self.screen.cell(row, col) else {
+                    continue;
+                };
+                // The left half of a wide character covers this cell
+                if cell.is_wide_continuation() {
+                    continue;
+                }
+
+                let mut style = Style::default()
+                    .fg(vt_color(cell.fgcolor(), self.fg))
+                    .bg(vt_color(cell.bgcolor(), self.bg));
+                if cell.bold() {
+                    style = style.add_modifier(Modifier::BOLD);
+                }
+                if cell.italic() {
+                    style = style.add_modifier(Modifier::ITALIC);
+                }
+                if cell.underline() {
+                    style = style.add_modifier(Modifier::UNDERLINED);
+                }
+                if cell.inverse() {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+
+                let contents = cell.contents();
+                let symbol = if contents.is_empty() { " " } else { contents.as_str() };
+                buf.get_mut(area.x + col, area.y + row).set_symbol(symbol).set_style(style);
+            }
+        }
+    }
 }
 
 fn render_server_list(f: &mut Frame, area: Rect, app_state: &mut AppState) {
+    let theme = app_state.theme_manager.current_theme();
     let connections = app_state.server_manager.filtered_connections();
     
     let items: Vec<ListItem> = connections
@@ -129,26 +1096,26 @@ fn render_server_list(f: &mut Frame, area: Rect, app_state: &mut AppState) {
         .map(|(i, conn)| {
             let style = if i == app_state.server_manager.selected_index {
                 Style::default()
-                    .bg(TokyoNight::BG_HIGHLIGHT)
-                    .fg(TokyoNight::THEME_GREEN)
+                    .bg(theme.bg_highlight)
+                    .fg(theme.theme_secondary)
                     .add_modifier(Modifier::BOLD)
             } else {
-                Style::default().fg(TokyoNight::FG)
+                Style::default().fg(theme.fg)
             };
 
             let health_color = match conn.health_status {
-                HealthStatus::Online => TokyoNight::STATUS_ONLINE,
-                HealthStatus::Offline => TokyoNight::STATUS_OFFLINE,
-                HealthStatus::Connecting => TokyoNight::STATUS_CONNECTING,
-                HealthStatus::Warning => TokyoNight::STATUS_WARNING,
-                HealthStatus::Unknown => TokyoNight::STATUS_UNKNOWN,
+                HealthStatus::Online => theme.status_online,
+                HealthStatus::Offline => theme.status_offline,
+                HealthStatus::Connecting => theme.status_connecting,
+                HealthStatus::Warning => theme.status_warning,
+                HealthStatus::Unknown => theme.status_unknown,
             };
 
             let auth_color = match conn.auth_strength {
-                AuthStrength::Key | AuthStrength::Agent => TokyoNight::GREEN,
-                AuthStrength::Password => TokyoNight::ORANGE,
-                AuthStrength::Interactive => TokyoNight::COMMENT,
-                AuthStrength::Unknown => TokyoNight::COMMENT,
+                AuthStrength::Key | AuthStrength::Agent => theme.green,
+                AuthStrength::Password => theme.orange,
+                AuthStrength::Interactive => theme.comment,
+                AuthStrength::Unknown => theme.comment,
             };
 
             // Use spinning globe for connecting servers, otherwise use normal symbol
@@ -174,16 +1141,28 @@ fn render_server_list(f: &mut Frame, area: Rect, app_state: &mut AppState) {
                 "  ".to_string()
             };
             
+            // Protected servers stand out in red, whatever their environment
+            let badge = match (conn.environment, conn.is_protected()) {
+                (Some(environment), protected) => Some((environment.label(), environment_color(environment, protected, theme))),
+                (None, true) => Some(("PROTECTED", theme.red)),
+                (None, false) => None,
+            };
+            let name_style = if conn.is_protected() { style.fg(theme.red) } else { style };
+
             let content = vec![
                 Line::from(vec![
-                    Span::styled(quick_num.clone(), Style::default().fg(TokyoNight::COMMENT)),
+                    Span::styled(quick_num.clone(), Style::default().fg(theme.comment)),
                     Span::styled(health_symbol, Style::default().fg(health_color)),
                     Span::raw(" "),
                     Span::styled(conn.auth_strength.symbol(), Style::default().fg(auth_color)),
                     Span::raw(" "),
-                    Span::styled(&conn.name, style),
+                    Span::styled(&conn.name, name_style),
+                    match badge {
+                        Some((label, color)) => Span::styled(format!(" [{}]", label), Style::default().fg(color).add_modifier(Modifier::BOLD)),
+                        None => Span::raw(""),
+                    },
                     if conn.has_active_sessions() {
-                        Span::styled(session_indicator, Style::default().fg(TokyoNight::GREEN).add_modifier(Modifier::BOLD))
+                        Span::styled(session_indicator, Style::default().fg(theme.green).add_modifier(Modifier::BOLD))
                     } else {
                         Span::raw("")
                     },
@@ -191,7 +1170,7 @@ fn render_server_list(f: &mut Frame, area: Rect, app_state: &mut AppState) {
                 Line::from(vec![
                     Span::raw("     "),
                     Span::styled(connection_string, 
-                        Style::default().fg(TokyoNight::COMMENT)),
+                        Style::default().fg(theme.comment)),
                 ]),
             ];
 
@@ -209,14 +1188,28 @@ fn render_server_list(f: &mut Frame, area: Rect, app_state: &mut AppState) {
         .block(
             Block::default()
                 .title(title)
-                .title_style(Style::default().fg(TokyoNight::THEME_GREEN).add_modifier(Modifier::BOLD))
+                .title_style(Style::default().fg(theme.theme_secondary).add_modifier(Modifier::BOLD))
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(TokyoNight::BORDER))
-                .style(Style::default().bg(TokyoNight::BG)),
+                .border_style(Style::default().fg(theme.border))
+                .style(Style::default().bg(theme.bg)),
         )
-        .style(Style::default().fg(TokyoNight::FG));
+        .style(Style::default().fg(theme.fg));
+
+    // Rendered with the selection so the list scrolls to keep it in view
+    let mut state = ListState::default()
+        .with_offset(app_state.server_list_offset)
+        .with_selected(Some(app_state.server_manager.selected_index));
+    f.render_stateful_widget(list, area, &mut state);
+    app_state.server_list_offset = state.offset();
+}
 
-    f.render_widget(list, area);
+fn environment_color(environment: Environment, protected: bool, theme: &Theme) -> Color {
+    match environment {
+        _ if protected => theme.red,
+        Environment::Prod => theme.red,
+        Environment::Staging => theme.orange,
+        Environment::Dev => theme.green,
+    }
 }
 
 fn render_metrics_panel(f: &mut Frame, area: Rect, app_state: &AppState) {
@@ -249,7 +1242,7 @@ fn render_metrics_panel(f: &mut Frame, area: Rect, app_state: &AppState) {
         ]),
         Line::from(vec![
             Span::styled("Layout: ", Style::default().fg(theme.comment)),
-            Span::styled(format!("{:?}", app_state.layout.mode), Style::default().fg(theme.fg)),
+            Span::styled(app_state.layout.label(), Style::default().fg(theme.fg)),
         ]),
         Line::from(""),
         Line::from(vec![
@@ -285,7 +1278,7 @@ fn render_metrics_panel(f: &mut Frame, area: Rect, app_state: &AppState) {
         Line::from(""),
         Line::from(vec![
             Span::styled("Theme: ", Style::default().fg(theme.comment)),
-            Span::styled(format!("{:?}", app_state.theme_manager.current_variant()), Style::default().fg(theme.fg)),
+            Span::styled(theme_label(app_state), Style::default().fg(theme.fg)),
         ]),
         Line::from(vec![
             Span::styled("History: ", Style::default().fg(theme.comment)),
@@ -293,6 +1286,19 @@ fn render_metrics_panel(f: &mut Frame, area: Rect, app_state: &AppState) {
                 Style::default().fg(theme.fg)),
         ]),
     ];
+    let mut stats_text = stats_text;
+
+    // Host facts of the selected server, from the last deep check
+    let connections = app_state.server_manager.filtered_connections();
+    if let Some(connection) = connections.get(app_state.server_manager.selected_index) {
+        if let Some(facts) = &connection.stats.facts {
+            stats_text.push(Line::from(""));
+            stats_text.push(Line::from(vec![
+                Span::styled(format!("🖥 {}", connection.name), Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD)),
+            ]));
+            stats_text.extend(host_fact_lines(facts, theme));
+        }
+    }
 
     let stats = Paragraph::new(stats_text)
         .block(
@@ -309,95 +1315,133 @@ fn render_metrics_panel(f: &mut Frame, area: Rect, app_state: &AppState) {
 }
 
 fn render_details_panel(f: &mut Frame, area: Rect, app_state: &AppState) {
+    let theme = app_state.theme_manager.current_theme();
     let connections = app_state.server_manager.filtered_connections();
     
     if let Some(connection) = connections.get(app_state.server_manager.selected_index) {
         let details = vec![
             Line::from(vec![
-                Span::styled("Name: ", Style::default().fg(TokyoNight::CYAN).add_modifier(Modifier::BOLD)),
-                Span::styled(&connection.name, Style::default().fg(TokyoNight::FG)),
+                Span::styled("Name: ", Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD)),
+                Span::styled(&connection.name, Style::default().fg(theme.fg)),
             ]),
             Line::from(vec![]),
             Line::from(vec![
-                Span::styled("Host: ", Style::default().fg(TokyoNight::CYAN).add_modifier(Modifier::BOLD)),
-                Span::styled(&connection.host, Style::default().fg(TokyoNight::FG)),
+                Span::styled("Host: ", Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD)),
+                Span::styled(&connection.host, Style::default().fg(theme.fg)),
             ]),
             Line::from(vec![
-                Span::styled("Port: ", Style::default().fg(TokyoNight::CYAN).add_modifier(Modifier::BOLD)),
-                Span::styled(connection.port.to_string(), Style::default().fg(TokyoNight::FG)),
+                Span::styled("Port: ", Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD)),
+                Span::styled(connection.port.to_string(), Style::default().fg(theme.fg)),
             ]),
             Line::from(vec![
-                Span::styled("User: ", Style::default().fg(TokyoNight::CYAN).add_modifier(Modifier::BOLD)),
-                Span::styled(&connection.username, Style::default().fg(TokyoNight::FG)),
+                Span::styled("User: ", Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD)),
+                Span::styled(&connection.username, Style::default().fg(theme.fg)),
             ]),
             Line::from(vec![]),
             Line::from(vec![
-                Span::styled("Status: ", Style::default().fg(TokyoNight::CYAN).add_modifier(Modifier::BOLD)),
+                Span::styled("Status: ", Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD)),
                 Span::styled(connection.health_status.symbol(), 
-                    Style::default().fg(get_health_color(&connection.health_status))),
+                    Style::default().fg(get_health_color(&connection.health_status, theme))),
                 Span::raw(" "),
                 Span::styled(connection.health_status.as_str(), 
-                    Style::default().fg(get_health_color(&connection.health_status))),
+                    Style::default().fg(get_health_color(&connection.health_status, theme))),
             ]),
             Line::from(vec![
-                Span::styled("Auth: ", Style::default().fg(TokyoNight::CYAN).add_modifier(Modifier::BOLD)),
+                Span::styled("Auth: ", Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD)),
                 Span::styled(connection.auth_strength.symbol(),
-                    Style::default().fg(get_auth_color(&connection.auth_strength))),
+                    Style::default().fg(get_auth_color(&connection.auth_strength, theme))),
                 Span::raw(" "),
                 Span::styled(connection.auth_strength.as_str(),
-                    Style::default().fg(get_auth_color(&connection.auth_strength))),
+                    Style::default().fg(get_auth_color(&connection.auth_strength, theme))),
             ]),
             Line::from(vec![]),
             Line::from(vec![
-                Span::styled("Created: ", Style::default().fg(TokyoNight::CYAN).add_modifier(Modifier::BOLD)),
+                Span::styled("Created: ", Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD)),
                 Span::styled(
                     connection.created_at.format("%Y-%m-%d %H:%M").to_string(),
-                    Style::default().fg(TokyoNight::COMMENT)
+                    Style::default().fg(theme.comment)
                 ),
             ]),
             Line::from(vec![]),
             Line::from(vec![
-                Span::styled("Latency: ", Style::default().fg(TokyoNight::CYAN).add_modifier(Modifier::BOLD)),
+                Span::styled("Latency: ", Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD)),
                 if let Some(latency) = connection.stats.latency {
-                    Span::styled(format!("{}ms", latency.as_millis()), Style::default().fg(TokyoNight::GREEN))
+                    Span::styled(format!("{}ms", latency.as_millis()), Style::default().fg(theme.green))
                 } else {
-                    Span::styled("N/A", Style::default().fg(TokyoNight::COMMENT))
+                    Span::styled("N/A", Style::default().fg(theme.comment))
                 },
                 Span::raw(" "),
                 Span::styled(render_latency_sparkline(&connection.stats.latency_history), 
-                    Style::default().fg(TokyoNight::BLUE)),
+                    Style::default().fg(theme.blue)),
             ]),
             Line::from(vec![
-                Span::styled("Connections: ", Style::default().fg(TokyoNight::CYAN).add_modifier(Modifier::BOLD)),
+                Span::styled("Connections: ", Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD)),
                 Span::styled(connection.stats.connection_count.to_string(), 
-                    Style::default().fg(TokyoNight::FG)),
+                    Style::default().fg(theme.fg)),
                 if connection.stats.failed_attempts > 0 {
                     Span::styled(format!(" ({} failed)", connection.stats.failed_attempts), 
-                        Style::default().fg(TokyoNight::RED))
+                        Style::default().fg(theme.red))
                 } else {
                     Span::raw("")
                 },
             ]),
             Line::from(vec![]),
             Line::from(vec![
-                Span::styled("Sessions: ", Style::default().fg(TokyoNight::CYAN).add_modifier(Modifier::BOLD)),
+                Span::styled("Sessions: ", Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD)),
                 if connection.has_active_sessions() {
                     Span::styled(format!("{} active", connection.session_count()), 
-                        Style::default().fg(TokyoNight::GREEN).add_modifier(Modifier::BOLD))
+                        Style::default().fg(theme.green).add_modifier(Modifier::BOLD))
                 } else {
-                    Span::styled("None", Style::default().fg(TokyoNight::COMMENT))
+                    Span::styled("None", Style::default().fg(theme.comment))
                 },
             ]),
         ];
         
         let mut details = details;
 
+        // Whether the last health check could log in (`verify_login`)
+        if let Some(login) = &connection.stats.login {
+            let color = match login {
+                LoginCheck::Verified => theme.green,
+                LoginCheck::NeedsSecret => theme.yellow,
+                LoginCheck::Failed(_) => theme.red,
+            };
+            details.push(Line::from(vec![
+                Span::styled("Login: ", Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD)),
+                Span::styled(login.as_str(), Style::default().fg(color)),
+            ]));
+        }
+
+        // Who the host is, from the last deep check; the numbers are in the
+        // metrics panel
+        if let Some(facts) = &connection.stats.facts {
+            if let Some(kernel) = &facts.kernel {
+                details.push(Line::from(vec![
+                    Span::styled("Kernel: ", Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD)),
+                    Span::styled(kernel.clone(), Style::default().fg(theme.fg)),
+                ]));
+            }
+            if let Some(uptime) = facts.uptime {
+                details.push(Line::from(vec![
+                    Span::styled("Uptime: ", Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD)),
+                    Span::styled(format_uptime(uptime), Style::default().fg(theme.fg)),
+                ]));
+            }
+            if let Some(reboot) = facts.reboot_required {
+                let (text, color) = if reboot { ("required", theme.red) } else { ("not needed", theme.green) };
+                details.push(Line::from(vec![
+                    Span::styled("Reboot: ", Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD)),
+                    Span::styled(text, Style::default().fg(color)),
+                ]));
+            }
+        }
+
         // Surface the latest health-check error, if the last check failed.
         if let Some(err) = &connection.last_error {
             details.push(Line::from(vec![]));
             details.push(Line::from(vec![
-                Span::styled("⚠ Error: ", Style::default().fg(TokyoNight::RED).add_modifier(Modifier::BOLD)),
-                Span::styled(err.clone(), Style::default().fg(TokyoNight::RED)),
+                Span::styled("⚠ Error: ", Style::default().fg(theme.red).add_modifier(Modifier::BOLD)),
+                Span::styled(err.clone(), Style::default().fg(theme.red)),
             ]));
         }
 
@@ -409,10 +1453,10 @@ fn render_details_panel(f: &mut Frame, area: Rect, app_state: &AppState) {
                     details.push(Line::from(vec![
                         Span::raw("  "),
                         Span::styled(format!("PID {}: ", session.pid), 
-                            Style::default().fg(TokyoNight::COMMENT)),
+                            Style::default().fg(theme.comment)),
                         Span::styled(
                             session.started_at.format("%H:%M:%S").to_string(),
-                            Style::default().fg(TokyoNight::FG)
+                            Style::default().fg(theme.fg)
                         ),
                     ]));
                 }
@@ -421,7 +1465,7 @@ fn render_details_panel(f: &mut Frame, area: Rect, app_state: &AppState) {
                 details.push(Line::from(vec![
                     Span::raw("  "),
                     Span::styled(format!("... and {} more", connection.active_sessions.len() - 3),
-                        Style::default().fg(TokyoNight::COMMENT)),
+                        Style::default().fg(theme.comment)),
                 ]));
             }
         }
@@ -430,10 +1474,10 @@ fn render_details_panel(f: &mut Frame, area: Rect, app_state: &AppState) {
             .block(
                 Block::default()
                     .title(" Details ")
-                    .title_style(Style::default().fg(TokyoNight::THEME_GREEN).add_modifier(Modifier::BOLD))
+                    .title_style(Style::default().fg(theme.theme_secondary).add_modifier(Modifier::BOLD))
                     .borders(Borders::ALL)
-                    .border_style(Style::default().fg(TokyoNight::BORDER))
-                    .style(Style::default().bg(TokyoNight::BG)),
+                    .border_style(Style::default().fg(theme.border))
+                    .style(Style::default().bg(theme.bg)),
             )
             .wrap(Wrap { trim: true });
 
@@ -443,122 +1487,307 @@ fn render_details_panel(f: &mut Frame, area: Rect, app_state: &AppState) {
             .block(
                 Block::default()
                     .title(" Details ")
-                    .title_style(Style::default().fg(TokyoNight::THEME_GREEN).add_modifier(Modifier::BOLD))
+                    .title_style(Style::default().fg(theme.theme_secondary).add_modifier(Modifier::BOLD))
                     .borders(Borders::ALL)
-                    .border_style(Style::default().fg(TokyoNight::BORDER))
-                    .style(Style::default().bg(TokyoNight::BG)),
+                    .border_style(Style::default().fg(theme.border))
+                    .style(Style::default().bg(theme.bg)),
             )
-            .style(Style::default().fg(TokyoNight::COMMENT))
+            .style(Style::default().fg(theme.comment))
             .alignment(Alignment::Center);
 
         f.render_widget(no_selection, area);
     }
 }
 
+/// Name of the active theme; for auto, also the theme it resolved to.
+fn theme_label(app_state: &AppState) -> String {
+    let manager = &app_state.theme_manager;
+    match manager.current_variant() {
+        ThemeVariant::Auto => format!("Auto: {}", manager.effective_variant(&ThemeVariant::Auto).name()),
+        variant => variant.name().to_string(),
+    }
+}
+
 fn render_footer(f: &mut Frame, area: Rect, app_state: &AppState) {
-    let keybindings = match app_state.mode {
-        AppMode::Normal => "j/k: Navigate | Enter/1-9: Connect | a: Add | e: Edit | d: Delete | r: Refresh & Security Check | f: Filter | S: Sessions | A: Analytics | H: History | t/T: Themes | l: Layout | [/]: Resize | ?: Tips | h: Help | Ctrl+X: Kill All | q: Quit",
-        AppMode::Help => "Press h, q, or Esc to return",
-        AppMode::History => "Press H, q, or Esc to return",
-        AppMode::Analytics => "Press A, q, or Esc to return",
-        AppMode::Sessions => "j/k: Navigate | d: Kill | r: Refresh | Enter: Info | S/q/Esc: Return",
-        AppMode::ConfirmDelete(_) => "y: Confirm | n: Cancel",
-        AppMode::ConfirmDiscard => "y: Discard changes | n: Keep editing",
-        AppMode::Connecting(_) => "Esc: Cancel connection",
-        _ => "Esc: Return to main view",
+    let theme = app_state.theme_manager.current_theme();
+    let keymap = &app_state.keymap;
+    let keybindings = if !app_state.pending_keys.is_empty() {
+        format!("{} …  (waiting for the rest of the chord)", format_sequence(&app_state.pending_keys))
+    } else if app_state.show_theme_selector {
+        footer_hints(keymap, KeyContext::ThemeSelector, &[
+            (&[Action::MoveDown, Action::MoveUp], "Preview"),
+            (&[Action::CycleFilter], "Filter dark/light"),
+            (&[Action::Apply], "Apply"),
+            (&[Action::Cancel], "Revert"),
+        ])
+    } else {
+        match app_state.mode {
+            AppMode::Normal => footer_hints(keymap, KeyContext::Normal, &[
+                (&[Action::MoveDown, Action::MoveUp], "Navigate"),
+                (&[Action::Connect], "Connect"),
+                (&quick_connect_actions(), "Quick connect"),
+                (&[Action::AddServer], "Add"),
+                (&[Action::EditServer], "Edit"),
+                (&[Action::DeleteServer], "Delete"),
+                (&[Action::Refresh], "Refresh & Security Check"),
+                (&[Action::ToggleOnlineFilter], "Filter"),
+                (&[Action::ShowSessions], "Sessions"),
+                (&[Action::ShowAnalytics], "Analytics"),
+                (&[Action::ShowHistory], "History"),
+                (&[Action::ThemeSelector, Action::NextTheme], "Themes"),
+                (&[Action::CycleLayout], "Layout"),
+                (&[Action::ShrinkPanel, Action::GrowPanel], "Resize"),
+                (&[Action::ShowTooltip], "Tips"),
+                (&[Action::ShowHelp], "Help"),
+                (&[Action::CommandPalette], "Commands"),
+                (&[Action::KillAllSessions], "Kill All"),
+                (&[Action::FocusTerminal], "Terminal tabs"),
+                (&[Action::Quit], "Quit"),
+            ]),
+            AppMode::Help => footer_hints(keymap, KeyContext::Help, &[
+                (&[Action::ScrollDown, Action::ScrollUp], "Scroll"),
+                (&[Action::Back], "Return"),
+            ]),
+            AppMode::History => footer_hints(keymap, KeyContext::History, &[(&[Action::Back], "Return")]),
+            AppMode::Analytics => footer_hints(keymap, KeyContext::Analytics, &[(&[Action::Back], "Return")]),
+            AppMode::Sessions => footer_hints(keymap, KeyContext::Sessions, &[
+                (&[Action::MoveDown, Action::MoveUp], "Navigate"),
+                (&[Action::KillSession], "Kill"),
+                (&[Action::RefreshSessions], "Refresh"),
+                (&[Action::SessionInfo], "Info"),
+                (&[Action::Back], "Return"),
+            ]),
+            AppMode::ConfirmDelete(_) => footer_hints(keymap, KeyContext::Confirm, &[
+                (&[Action::Confirm], "Confirm"),
+                (&[Action::Cancel], "Cancel"),
+            ]),
+            AppMode::ConfirmDiscard => footer_hints(keymap, KeyContext::Confirm, &[
+                (&[Action::Confirm], "Discard changes"),
+                (&[Action::Cancel], "Keep editing"),
+            ]),
+            AppMode::CommandPalette => {
+                "Type to search | ↑/↓: Select | Enter: Run | Ctrl+U: Clear | Esc: Close".to_string()
+            }
+            AppMode::Terminal => footer_hints(keymap, KeyContext::Terminal, &[
+                (&[Action::Back], "Server list (session keeps running)"),
+                (&[Action::NextTab, Action::PrevTab], "Switch tab"),
+            ]) + " | Other keys go to the session",
+            AppMode::Sftp if app_state.sftp.as_ref().is_some_and(|b| b.prompt.is_some()) => {
+                "Enter: Confirm | Esc: Cancel".to_string()
+            }
+            AppMode::Sftp => footer_hints(keymap, KeyContext::Sftp, &[
+                (&[Action::MoveDown, Action::MoveUp], "Navigate"),
+                (&[Action::SwitchPane], "Switch pane"),
+                (&[Action::OpenDir], "Open"),
+                (&[Action::ParentDir], "Up"),
+                (&[Action::Transfer], "Copy"),
+                (&[Action::RenameEntry], "Rename"),
+                (&[Action::MakeDir], "Mkdir"),
+                (&[Action::DeleteEntry], "Delete"),
+                (&[Action::Refresh], "Reload"),
+                (&[Action::Back], "Server list (transfers keep running)"),
+            ]),
+            AppMode::Transfers => footer_hints(keymap, KeyContext::Transfers, &[
+                (&[Action::MoveDown, Action::MoveUp], "Navigate"),
+                (&[Action::RetryTransfer], "Retry"),
+                (&[Action::RetryFailed], "Retry all failed"),
+                (&[Action::ClearFinished], "Clear finished"),
+                (&[Action::Back], "Return (transfers keep running)"),
+            ]),
+            AppMode::Keys if app_state.keys.as_ref().is_some_and(|k| k.prompt.is_some()) => {
+                match app_state.keys.as_ref().and_then(|k| k.prompt.as_ref()).map(|p| &p.kind) {
+                    Some(KeyPromptKind::SwitchAuth { .. }) => "y: Switch | any other key: Keep as is".to_string(),
+                    _ => "Enter: Next | Esc: Cancel".to_string(),
+                }
+            }
+            AppMode::Keys => footer_hints(keymap, KeyContext::Keys, &[
+                (&[Action::MoveDown, Action::MoveUp], "Navigate"),
+                (&[Action::GenerateKey], "Generate ed25519"),
+                (&[Action::AddToAgent], "Add to agent"),
+                (&[Action::InstallKey], "Install on servers"),
+                (&[Action::Refresh], "Rescan"),
+                (&[Action::Back], "Return"),
+            ]),
+            AppMode::Audit => footer_hints(keymap, KeyContext::Audit, &[
+                (&[Action::MoveDown, Action::MoveUp], "Navigate"),
+                (&[Action::Refresh], "Re-run"),
+                (&[Action::ScanAlgorithms], "Scan server algorithms"),
+                (&[Action::Back], "Return"),
+            ]),
+            AppMode::Algorithms => footer_hints(keymap, KeyContext::Algorithms, &[
+                (&[Action::MoveDown, Action::MoveUp], "Navigate"),
+                (&[Action::ScanAlgorithms], "Rescan"),
+                (&[Action::ShowAudit], "Local audit"),
+                (&[Action::Back], "Return"),
+            ]),
+            AppMode::TransferForm => "Tab/↑/↓: Next field | Enter: Start | Esc: Cancel".to_string(),
+            AppMode::ConfirmName => "Enter: Confirm | Esc: Cancel".to_string(),
+            AppMode::CredentialPrompt => match app_state.credential_prompt.as_ref().map(|p| (&p.kind, p.then)) {
+                Some((CredentialPromptKind::Password, _)) => "Enter: Store (empty forgets it) | Esc: Cancel".to_string(),
+                Some((CredentialPromptKind::LoginPassword | CredentialPromptKind::KeyPassphrase { .. }, _)) => {
+                    "Enter: Log in | Esc: Cancel".to_string()
+                }
+                Some((_, FollowUp::Connect)) => "Enter: Unlock | Esc: Connect without it".to_string(),
+                _ => "Enter: Unlock | Esc: Cancel".to_string(),
+            },
+            AppMode::Connecting(_) => "Esc: Cancel connection".to_string(),
+            _ => "Esc: Return to main view".to_string(),
+        }
     };
 
     let footer = Paragraph::new(keybindings)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(TokyoNight::BORDER))
-                .style(Style::default().bg(TokyoNight::BG)),
+                .border_style(Style::default().fg(theme.border))
+                .style(Style::default().bg(theme.bg)),
         )
-        .style(Style::default().fg(TokyoNight::COMMENT))
+        .style(Style::default().fg(theme.comment))
         .alignment(Alignment::Center);
 
     f.render_widget(footer, area);
 }
 
-fn render_help_popup(f: &mut Frame, area: Rect, _app_state: &AppState) {
+fn render_help_popup(f: &mut Frame, area: Rect, app_state: &mut AppState) {
+    let theme = app_state.theme_manager.current_theme();
+    let keymap = &app_state.keymap;
     let popup_area = centered_rect(60, 70, area);
-    
-    let help_text = vec![
+    let heading = |text: &str| Line::from(Span::styled(text.to_string(), Style::default().fg(theme.theme_primary)));
+
+    let mut help_text = vec![
         Line::from(Span::styled("👻 GHOST SSH Manager - Help", 
-            Style::default().fg(TokyoNight::THEME_GREEN).add_modifier(Modifier::BOLD))),
-        Line::from(""),
-        Line::from("NAVIGATION:"),
-        Line::from("  j/k or ↑/↓     Navigate server list"),
-        Line::from("  Enter          Connect to selected server / Dismiss popup"),
-        Line::from("  1-9            Quick connect to server 1-9"),
+            Style::default().fg(theme.theme_secondary).add_modifier(Modifier::BOLD))),
+    ];
+
+    // Server list shortcuts grouped by category, then one section per view.
+    // Everything here comes from the active keymap, so remapped keys show up.
+    let mut sections: Vec<(String, Vec<Action>)> = Vec::new();
+    for &action in keymap.actions(KeyContext::Normal) {
+        if matches!(action, Action::QuickConnect(n) if n > 1) {
+            continue; // listed once together with quick_connect_1
+        }
+        match sections.iter_mut().find(|(title, _)| title == action.category()) {
+            Some((_, actions)) => actions.push(action),
+            None => sections.push((action.category().to_string(), vec![action])),
+        }
+    }
+    let sections = sections.into_iter().map(|(title, actions)| (title, KeyContext::Normal, actions)).chain(
+        KeyContext::all()
+            .into_iter()
+            .filter(|c| *c != KeyContext::Normal)
+            .map(|c| (c.title().to_string(), c, keymap.actions(c).to_vec())),
+    );
+
+    for (title, context, actions) in sections {
+        help_text.push(Line::from(""));
+        help_text.push(heading(&format!("{}:", title)));
+        for action in actions {
+            let (keys, description) = match action {
+                Action::QuickConnect(_) => (
+                    keys_label(keymap, context, &quick_connect_actions()),
+                    "Quick connect to server 1-9".to_string(),
+                ),
+                _ => (keys_label(keymap, context, &[action]), action.description()),
+            };
+            let id = match action {
+                Action::QuickConnect(_) => "quick_connect_1…9".to_string(),
+                _ => action.id(),
+            };
+            help_text.push(Line::from(vec![
+                Span::raw(format!("  {:<14} {}", keys, description)),
+                Span::styled(format!("  {}", id), Style::default().fg(theme.comment)),
+            ]));
+        }
+    }
+
+    help_text.extend([
         Line::from(""),
-        Line::from("SERVER MANAGEMENT:"),
-        Line::from("  a              Add new server"),
-        Line::from("  e              Edit selected server"),
-        Line::from("  d              Delete selected server"),
-        Line::from("  r              Refresh server status & security assessment"),
+        heading("SERVER FORM:"),
+        Line::from("  Tab/Shift+Tab  Next/previous field"),
+        Line::from("  Enter          Save server"),
+        Line::from("  Esc            Cancel (asks before discarding changes)"),
         Line::from(""),
-        Line::from("CONNECTION MODES:"),
+        heading("CONNECTION MODES:"),
         Line::from("  CLI: --new-terminal     Force new terminal window"),
-        Line::from("  CLI: --direct          Force current terminal (Warp compatible)"),
-        Line::from("  CLI: --connection-mode  auto/new-terminal/direct"),
-        Line::from(""),
-        Line::from("FILTERING & VIEWS:"),
-        Line::from("  f              Toggle online-only filter"),
-        Line::from("  S              Session manager (view active SSH sessions)"),
-        Line::from("  A              Analytics dashboard (usage statistics)"),
-        Line::from("  H              Connection history"),
-        Line::from(""),
-        Line::from("SESSION MANAGEMENT:"),
-        Line::from("  Ctrl+X         Kill all active SSH sessions"),
+        Line::from("  CLI: --direct           Force current terminal (Warp compatible)"),
+        Line::from("  CLI: --embedded         ssh in tabs inside Ghost"),
+        Line::from("  CLI: --native           Tabs with Ghost's own SSH client"),
+        Line::from("  CLI: --connection-mode  auto/new-terminal/direct/embedded/"),
+        Line::from("                          tmux/zellij/native"),
         Line::from(""),
-        Line::from("THEMES & LAYOUT:"),
-        Line::from("  t              Toggle theme selector"),
-        Line::from("  T              Quick theme cycle"),
-        Line::from("  l              Cycle layout mode (Single/Two/Three panels)"),
-        Line::from("  [ / ]          Resize panels (decrease/increase left panel)"),
-        Line::from(""),
-        Line::from("TOOLTIPS & HELP:"),
-        Line::from("  ?              Show contextual tooltip"),
-        Line::from("  F2             Toggle tooltips on/off"),
-        Line::from("  h or F1        Show this help"),
-        Line::from(""),
-        Line::from("SECURITY STATUS:"),
+        heading("SECURITY STATUS:"),
         Line::from("  🛡️ SECURE       SSH keys, non-standard ports"),
         Line::from("  ⚠️ VULNERABLE   Password auth on port 22"),
         Line::from("  ? UNKNOWN       Assessment pending/failed"),
         Line::from(""),
-        Line::from("TERMINAL SUPPORT:"),
+        heading("TERMINAL SUPPORT:"),
         Line::from("  ✅ Ghostty, Alacritty, Kitty, Wezterm, GNOME, Konsole"),
         Line::from("  ⚠️ Warp Terminal (direct mode only)"),
         Line::from(""),
-        Line::from("GENERAL:"),
-        Line::from("  q              Quit application"),
-        Line::from("  Esc            Dismiss popup / Quit application"),
-        Line::from("  Ctrl+C         Force quit"),
+        Line::from("Keys can be remapped in the [keybindings] section of config.toml"),
         Line::from(""),
-        Line::from(Span::styled("Press h, q, or Esc to return", 
-            Style::default().fg(TokyoNight::CYAN))),
-    ];
+        Line::from(Span::styled(
+            format!(
+                "{}: Scroll | {}: Return",
+                keys_label(keymap, KeyContext::Help, &[Action::ScrollDown, Action::ScrollUp]),
+                keys_label(keymap, KeyContext::Help, &[Action::Back]),
+            ),
+            Style::default().fg(theme.cyan),
+        )),
+    ]);
+
+    // Keep the last page in view rather than scrolling into blank space
+    let visible = popup_area.height.saturating_sub(2);
+    let max_scroll = (help_text.len() as u16).saturating_sub(visible);
+    app_state.help_scroll = app_state.help_scroll.min(max_scroll);
 
     f.render_widget(Clear, popup_area);
     let help = Paragraph::new(help_text)
         .block(
             Block::default()
                 .title(" Help ")
-                .title_style(Style::default().fg(TokyoNight::THEME_GREEN).add_modifier(Modifier::BOLD))
+                .title_style(Style::default().fg(theme.theme_secondary).add_modifier(Modifier::BOLD))
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(TokyoNight::BORDER_HIGHLIGHT))
-                .style(Style::default().bg(TokyoNight::BG_POPUP)),
+                .border_style(Style::default().fg(theme.border_highlight))
+                .style(Style::default().bg(theme.bg_popup)),
         )
-        .style(Style::default().fg(TokyoNight::FG))
-        .wrap(Wrap { trim: true });
+        .style(Style::default().fg(theme.fg))
+        .scroll((app_state.help_scroll, 0));
 
     f.render_widget(help, popup_area);
 }
 
+fn quick_connect_actions() -> Vec<Action> {
+    (1..=9).map(Action::QuickConnect).collect()
+}
+
+/// Keys bound to `actions`, e.g. `j/↓`. The default quick-connect digits are
+/// shortened to `1-9`.
+fn keys_label(keymap: &Keymap, context: KeyContext, actions: &[Action]) -> String {
+    let keys: Vec<String> = actions
+        .iter()
+        .flat_map(|&action| keymap.keys_for(context, action))
+        .collect();
+    if keys.is_empty() {
+        return "unbound".to_string();
+    }
+    if keys.iter().map(String::as_str).eq(["1", "2", "3", "4", "5", "6", "7", "8", "9"]) {
+        return "1-9".to_string();
+    }
+    keys.join("/")
+}
+
+/// Footer text: `key: label` pairs joined with `|`
+fn footer_hints(keymap: &Keymap, context: KeyContext, hints: &[(&[Action], &str)]) -> String {
+    hints
+        .iter()
+        .map(|(actions, label)| format!("{}: {}", keys_label(keymap, context, actions), label))
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
 fn render_history_popup(f: &mut Frame, area: Rect, app_state: &AppState) {
+    let theme = app_state.theme_manager.current_theme();
     let popup_area = centered_rect(80, 70, area);
     
     let history_items: Vec<ListItem> = app_state.server_manager.connection_history
@@ -568,12 +1797,12 @@ fn render_history_popup(f: &mut Frame, area: Rect, app_state: &AppState) {
             let time_str = entry.connected_at.format("%Y-%m-%d %H:%M:%S").to_string();
             let content = vec![
                 Line::from(vec![
-                    Span::styled(format!("{}. ", i + 1), Style::default().fg(TokyoNight::COMMENT)),
-                    Span::styled(&entry.server_name, Style::default().fg(TokyoNight::CYAN).add_modifier(Modifier::BOLD)),
+                    Span::styled(format!("{}. ", i + 1), Style::default().fg(theme.comment)),
+                    Span::styled(&entry.server_name, Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD)),
                 ]),
                 Line::from(vec![
                     Span::raw("    "),
-                    Span::styled(time_str, Style::default().fg(TokyoNight::COMMENT)),
+                    Span::styled(time_str, Style::default().fg(theme.comment)),
                 ]),
             ];
             ListItem::new(content)
@@ -583,7 +1812,7 @@ fn render_history_popup(f: &mut Frame, area: Rect, app_state: &AppState) {
     let history_text = if history_items.is_empty() {
         vec![Line::from(Span::styled(
             "No connection history yet. Connect to servers to see history here.",
-            Style::default().fg(TokyoNight::COMMENT)
+            Style::default().fg(theme.comment)
         ))]
     } else {
         vec![] // The list will be rendered separately
@@ -596,12 +1825,12 @@ fn render_history_popup(f: &mut Frame, area: Rect, app_state: &AppState) {
             .block(
                 Block::default()
                     .title(" Connection History ")
-                    .title_style(Style::default().fg(TokyoNight::CYAN).add_modifier(Modifier::BOLD))
+                    .title_style(Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD))
                     .borders(Borders::ALL)
-                    .border_style(Style::default().fg(TokyoNight::BORDER_HIGHLIGHT))
-                    .style(Style::default().bg(TokyoNight::BG_POPUP)),
+                    .border_style(Style::default().fg(theme.border_highlight))
+                    .style(Style::default().bg(theme.bg_popup)),
             )
-            .style(Style::default().fg(TokyoNight::FG))
+            .style(Style::default().fg(theme.fg))
             .alignment(Alignment::Center)
             .wrap(Wrap { trim: true });
         f.render_widget(history, popup_area);
@@ -610,12 +1839,12 @@ fn render_history_popup(f: &mut Frame, area: Rect, app_state: &AppState) {
             .block(
                 Block::default()
                     .title(format!(" Connection History ({}) ", app_state.server_manager.connection_history.len()))
-                    .title_style(Style::default().fg(TokyoNight::CYAN).add_modifier(Modifier::BOLD))
+                    .title_style(Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD))
                     .borders(Borders::ALL)
-                    .border_style(Style::default().fg(TokyoNight::BORDER_HIGHLIGHT))
-                    .style(Style::default().bg(TokyoNight::BG_POPUP)),
+                    .border_style(Style::default().fg(theme.border_highlight))
+                    .style(Style::default().bg(theme.bg_popup)),
             )
-            .style(Style::default().fg(TokyoNight::FG));
+            .style(Style::default().fg(theme.fg));
         f.render_widget(history_list, popup_area);
     }
     
@@ -627,13 +1856,17 @@ fn render_history_popup(f: &mut Frame, area: Rect, app_state: &AppState) {
         height: 1,
     };
     
-    let instructions = Paragraph::new("Press H, q, or Esc to return")
-        .style(Style::default().fg(TokyoNight::COMMENT))
+    let instructions = Paragraph::new(format!(
+        "Press {} to return",
+        keys_label(&app_state.keymap, KeyContext::History, &[Action::Back])
+    ))
+        .style(Style::default().fg(theme.comment))
         .alignment(Alignment::Center);
     f.render_widget(instructions, instruction_area);
 }
 
 fn render_confirm_delete_popup(f: &mut Frame, area: Rect, app_state: &AppState, server_id: &str) {
+    let theme = app_state.theme_manager.current_theme();
     let popup_area = centered_rect(50, 20, area);
     
     let server_name = app_state.server_manager.get_connection(server_id)
@@ -643,16 +1876,19 @@ fn render_confirm_delete_popup(f: &mut Frame, area: Rect, app_state: &AppState,
     let text = vec![
         Line::from(""),
         Line::from(Span::styled("⚠️  WARNING", 
-            Style::default().fg(TokyoNight::RED).add_modifier(Modifier::BOLD))),
+            Style::default().fg(theme.red).add_modifier(Modifier::BOLD))),
         Line::from(""),
         Line::from(vec![
             Span::raw("Delete server \""),
-            Span::styled(server_name, Style::default().fg(TokyoNight::CYAN)),
+            Span::styled(server_name, Style::default().fg(theme.cyan)),
             Span::raw("\"?"),
         ]),
         Line::from(""),
-        Line::from(Span::styled("y: Yes | n: No", 
-            Style::default().fg(TokyoNight::COMMENT))),
+        Line::from(Span::styled(footer_hints(&app_state.keymap, KeyContext::Confirm, &[
+                (&[Action::Confirm], "Yes"),
+                (&[Action::Cancel], "No"),
+            ]),
+            Style::default().fg(theme.comment))),
     ];
 
     f.render_widget(Clear, popup_area);
@@ -660,29 +1896,187 @@ fn render_confirm_delete_popup(f: &mut Frame, area: Rect, app_state: &AppState,
         .block(
             Block::default()
                 .title(" Confirm Delete ")
-                .title_style(Style::default().fg(TokyoNight::RED).add_modifier(Modifier::BOLD))
+                .title_style(Style::default().fg(theme.red).add_modifier(Modifier::BOLD))
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(TokyoNight::RED))
-                .style(Style::default().bg(TokyoNight::BG_POPUP)),
+                .border_style(Style::default().fg(theme.red))
+                .style(Style::default().bg(theme.bg_popup)),
         )
-        .style(Style::default().fg(TokyoNight::FG))
+        .style(Style::default().fg(theme.fg))
         .alignment(Alignment::Center);
 
     f.render_widget(confirm, popup_area);
 }
 
-fn render_confirm_discard_popup(f: &mut Frame, area: Rect) {
+fn render_command_palette(f: &mut Frame, area: Rect, app_state: &AppState) {
+    let theme = app_state.theme_manager.current_theme();
+    let matches = palette::filter_entries(palette::build_entries(app_state), &app_state.palette.query);
+
+    // Anchored near the top like an editor palette, so the list grows down
+    let width = (area.width * 3 / 5).clamp(40.min(area.width), area.width);
+    let height = (area.height * 3 / 5).max(8).min(area.height);
+    let popup_area = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + area.height / 8,
+        width,
+        height: height.min(area.height - area.height / 8),
+    };
+
+    f.render_widget(Clear, popup_area);
+    let block = Block::default()
+        .title(format!(" Command Palette ({}) ", matches.len()))
+        .title_style(Style::default().fg(theme.theme_secondary).add_modifier(Modifier::BOLD))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border_highlight))
+        .style(Style::default().bg(theme.bg_popup));
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(2), Constraint::Min(0)])
+        .split(inner);
+
+    let input = Paragraph::new(Line::from(vec![
+        Span::styled("> ", Style::default().fg(theme.theme_primary)),
+        Span::styled(app_state.palette.query.clone(), Style::default().fg(theme.fg)),
+        Span::styled("█", Style::default().fg(theme.theme_primary)),
+    ]))
+    .block(Block::default().borders(Borders::BOTTOM).border_style(Style::default().fg(theme.border)));
+    f.render_widget(input, chunks[0]);
+
+    if matches.is_empty() {
+        let empty = Paragraph::new("No matching commands")
+            .style(Style::default().fg(theme.comment))
+            .alignment(Alignment::Center);
+        f.render_widget(empty, chunks[1]);
+        return;
+    }
+
+    // Keep the selection in view by scrolling the window of visible rows
+    let selected = app_state.palette.selected_index.min(matches.len() - 1);
+    let rows = chunks[1].height.max(1) as usize;
+    let first = selected.saturating_sub(rows - 1);
+    let label_width = chunks[1].width.saturating_sub(14) as usize;
+
+    let items: Vec<ListItem> = matches
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(rows)
+        .map(|(i, m)| {
+            let is_selected = i == selected;
+            let base = if is_selected {
+                Style::default().fg(theme.fg).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme.fg)
+            };
+            let highlight = base.fg(theme.theme_primary).add_modifier(Modifier::UNDERLINED);
+
+            let mut spans: Vec<Span> = m
+                .entry
+                .label
+                .chars()
+                .take(label_width)
+                .enumerate()
+                .map(|(pos, c)| {
+                    let style = if m.positions.contains(&pos) { highlight } else { base };
+                    Span::styled(c.to_string(), style)
+                })
+                .collect();
+
+            if let Some(keys) = &m.entry.keys {
+                let used = m.entry.label.chars().count().min(label_width);
+                spans.push(Span::raw(" ".repeat(label_width.saturating_sub(used) + 2)));
+                spans.push(Span::styled(keys.clone(), Style::default().fg(theme.comment)));
+            }
+
+            let item = ListItem::new(Line::from(spans));
+            if is_selected {
+                item.style(Style::default().bg(theme.bg_highlight))
+            } else {
+                item
+            }
+        })
+        .collect();
+
+    f.render_widget(List::new(items), chunks[1]);
+}
+
+fn render_theme_selector_popup(f: &mut Frame, area: Rect, app_state: &AppState) {
+    let theme = app_state.theme_manager.current_theme();
+    let entries = app_state.theme_selector_entries();
+    let selected = app_state.theme_selector.selected_index;
+    let popup_area = centered_rect(40, 60, area);
+
+    let items: Vec<ListItem> = if entries.is_empty() {
+        vec![ListItem::new(Line::from(Span::styled(
+            "No themes match this filter",
+            Style::default().fg(theme.comment),
+        )))]
+    } else {
+        entries
+            .iter()
+            .enumerate()
+            .map(|(i, variant)| {
+                // A strip of the theme's own colours so it can be compared
+                // without previewing it
+                let palette = app_state.theme_manager.resolve(variant);
+                let swatch = [palette.bg, palette.theme_primary, palette.theme_secondary, palette.green, palette.red]
+                    .into_iter()
+                    .map(|c| Span::styled("  ", Style::default().bg(c)));
+
+                let marker = if i == selected { "▶ " } else { "  " };
+                let mode = if app_state.theme_manager.variant_is_dark(variant) { "dark" } else { "light" };
+                let mut spans = vec![Span::styled(
+                    format!("{}{:<22}", marker, variant.name()),
+                    if i == selected {
+                        Style::default().fg(theme.theme_primary).add_modifier(Modifier::BOLD)
+                    } else {
+                        Style::default().fg(theme.fg)
+                    },
+                )];
+                spans.extend(swatch);
+                spans.push(Span::styled(format!(" {}", mode), Style::default().fg(theme.comment)));
+
+                let item = ListItem::new(Line::from(spans));
+                if i == selected {
+                    item.style(Style::default().bg(theme.bg_highlight))
+                } else {
+                    item
+                }
+            })
+            .collect()
+    };
+
+    f.render_widget(Clear, popup_area);
+    let list = List::new(items).block(
+        Block::default()
+            .title(format!(" Themes ({}) ", app_state.theme_selector.filter.as_str()))
+            .title_style(Style::default().fg(theme.theme_secondary).add_modifier(Modifier::BOLD))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border_highlight))
+            .style(Style::default().bg(theme.bg_popup)),
+    );
+
+    f.render_widget(list, popup_area);
+}
+
+fn render_confirm_discard_popup(f: &mut Frame, area: Rect, app_state: &AppState) {
+    let theme = app_state.theme_manager.current_theme();
     let popup_area = centered_rect(50, 20, area);
 
     let text = vec![
         Line::from(""),
         Line::from(Span::styled("⚠️  Unsaved changes",
-            Style::default().fg(TokyoNight::RED).add_modifier(Modifier::BOLD))),
+            Style::default().fg(theme.red).add_modifier(Modifier::BOLD))),
         Line::from(""),
         Line::from("Discard your changes?"),
         Line::from(""),
-        Line::from(Span::styled("y: Discard | n: Keep editing",
-            Style::default().fg(TokyoNight::COMMENT))),
+        Line::from(Span::styled(footer_hints(&app_state.keymap, KeyContext::Confirm, &[
+                (&[Action::Confirm], "Discard"),
+                (&[Action::Cancel], "Keep editing"),
+            ]),
+            Style::default().fg(theme.comment))),
     ];
 
     f.render_widget(Clear, popup_area);
@@ -690,47 +2084,61 @@ fn render_confirm_discard_popup(f: &mut Frame, area: Rect) {
         .block(
             Block::default()
                 .title(" Discard Changes ")
-                .title_style(Style::default().fg(TokyoNight::RED).add_modifier(Modifier::BOLD))
+                .title_style(Style::default().fg(theme.red).add_modifier(Modifier::BOLD))
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(TokyoNight::RED))
-                .style(Style::default().bg(TokyoNight::BG_POPUP)),
+                .border_style(Style::default().fg(theme.red))
+                .style(Style::default().bg(theme.bg_popup)),
         )
-        .style(Style::default().fg(TokyoNight::FG))
+        .style(Style::default().fg(theme.fg))
         .alignment(Alignment::Center);
 
     f.render_widget(confirm, popup_area);
 }
 
 fn render_connecting_popup(f: &mut Frame, area: Rect, app_state: &AppState, server_id: &str) {
+    let theme = app_state.theme_manager.current_theme();
     let popup_area = centered_rect(40, 15, area);
     
-    let server_name = app_state.server_manager.get_connection(server_id)
-        .map(|c| c.name.as_str())
-        .unwrap_or("Unknown");
+    let server = app_state.server_manager.get_connection(server_id);
+    let server_name = server.map(|c| c.name.as_str()).unwrap_or("Unknown");
     
+    // A banner across the top says which environment this is
+    let banner = server.and_then(|c| match (c.environment, c.is_protected()) {
+        (Some(environment), protected) => Some((environment.title(), environment_color(environment, protected, theme))),
+        (None, true) => Some(("PROTECTED", theme.red)),
+        (None, false) => None,
+    });
+    let banner_line = match banner {
+        Some((title, color)) => Line::from(Span::styled(
+            format!(" ⚠ {} ", title),
+            Style::default().fg(theme.bg_popup).bg(color).add_modifier(Modifier::BOLD),
+        )),
+        None => Line::from(""),
+    };
     let text = vec![
-        Line::from(""),
+        banner_line,
         Line::from(vec![
-            Span::styled(app_state.get_globe_char(), Style::default().fg(TokyoNight::FG)),
+            Span::styled(app_state.get_globe_char(), Style::default().fg(theme.fg)),
             Span::raw(" → Connecting to "),
-            Span::styled(server_name, Style::default().fg(TokyoNight::CYAN)),
+            Span::styled(server_name, Style::default().fg(theme.cyan)),
         ]),
         Line::from(""),
         Line::from(Span::styled("Press Esc to cancel", 
-            Style::default().fg(TokyoNight::COMMENT))),
+            Style::default().fg(theme.comment))),
     ];
+    let border = banner.map_or(theme.blue, |(_, color)| color);
 
     f.render_widget(Clear, popup_area);
     let connecting = Paragraph::new(text)
         .block(
             Block::default()
                 .title(" Connecting... ")
-                .title_style(Style::default().fg(TokyoNight::BLUE).add_modifier(Modifier::BOLD))
+                .title_style(Style::default().fg(border).add_modifier(Modifier::BOLD))
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(TokyoNight::BLUE))
-                .style(Style::default().bg(TokyoNight::BG_POPUP)),
+                .border_style(Style::default().fg(border))
+                .style(Style::default().bg(theme.bg_popup)),
         )
-        .style(Style::default().fg(TokyoNight::FG))
+        .style(Style::default().fg(theme.fg))
         .alignment(Alignment::Center);
 
     f.render_widget(connecting, popup_area);
@@ -797,7 +2205,10 @@ fn create_progress_bar(progress: f32, width: usize) -> String {
     format!("{}{}", filled_str, empty_str)
 }
 
-fn render_server_form_popup(f: &mut Frame, area: Rect, app_state: &AppState) {
+/// Returns where each form element was drawn
+fn render_server_form_popup(f: &mut Frame, area: Rect, app_state: &AppState) -> Vec<(FormTarget, Rect)> {
+    let theme = app_state.theme_manager.current_theme();
+    let mut targets = Vec::new();
     if let Some(ref form) = app_state.server_form {
         let popup_area = centered_rect(80, 90, area);
         
@@ -822,39 +2233,41 @@ fn render_server_form_popup(f: &mut Frame, area: Rect, app_state: &AppState) {
 
         // Render instructions
         let instructions = Paragraph::new("Tab/Shift+Tab: Navigate | Enter: Save | Esc: Cancel")
-            .style(Style::default().fg(TokyoNight::COMMENT))
+            .style(Style::default().fg(theme.comment))
             .alignment(Alignment::Center);
         f.render_widget(instructions, form_chunks[0]);
 
         // Render form fields
-        render_form_fields(f, form_chunks[1], form);
+        targets = render_form_fields(f, form_chunks[1], form, theme);
 
         // Render action buttons
         let actions = vec![
             Line::from(vec![
-                Span::styled("[Enter]", Style::default().fg(TokyoNight::GREEN).add_modifier(Modifier::BOLD)),
+                Span::styled("[Enter]", Style::default().fg(theme.green).add_modifier(Modifier::BOLD)),
                 Span::raw(" Save   "),
-                Span::styled("[Esc]", Style::default().fg(TokyoNight::RED).add_modifier(Modifier::BOLD)),
+                Span::styled("[Esc]", Style::default().fg(theme.red).add_modifier(Modifier::BOLD)),
                 Span::raw(" Cancel"),
             ])
         ];
         let action_bar = Paragraph::new(actions)
-            .style(Style::default().fg(TokyoNight::FG))
+            .style(Style::default().fg(theme.fg))
             .alignment(Alignment::Center);
         f.render_widget(action_bar, form_chunks[2]);
 
         // Render the main popup block
         let popup_block = Block::default()
             .title(title)
-            .title_style(Style::default().fg(TokyoNight::THEME_GREEN).add_modifier(Modifier::BOLD))
+            .title_style(Style::default().fg(theme.theme_secondary).add_modifier(Modifier::BOLD))
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(TokyoNight::BORDER_HIGHLIGHT))
-            .style(Style::default().bg(TokyoNight::BG_POPUP));
+            .border_style(Style::default().fg(theme.border_highlight))
+            .style(Style::default().bg(theme.bg_popup));
         f.render_widget(popup_block, popup_area);
     }
+    targets
 }
 
-fn render_form_fields(f: &mut Frame, area: Rect, form: &crate::forms::ServerForm) {
+fn render_form_fields(f: &mut Frame, area: Rect, form: &crate::forms::ServerForm, theme: &Theme) -> Vec<(FormTarget, Rect)> {
+    let mut targets = Vec::new();
     let field_height = 3; // Input field with border
     let auth_height = 4;  // Auth method dropdown
     let _total_fields = form.fields.len() + 1 + 1; // fields + auth + tags
@@ -873,22 +2286,27 @@ fn render_form_fields(f: &mut Frame, area: Rect, form: &crate::forms::ServerForm
     // Render regular input fields
     for (i, field) in form.fields.iter().enumerate() {
         if let Some(field_area) = field_areas.get(i) {
-            render_input_field(f, *field_area, field, i == form.current_field && !form.auth_method_focused);
+            render_input_field(f, *field_area, field, i == form.current_field && !form.auth_method_focused, theme);
+            targets.push((FormTarget::Field(i), *field_area));
         }
     }
 
     // Render auth method dropdown
     if let Some(auth_area) = field_areas.get(form.fields.len()) {
-        render_auth_method_field(f, *auth_area, form);
+        render_auth_method_field(f, *auth_area, form, theme);
+        targets.push((FormTarget::AuthMethod, *auth_area));
     }
 
     // Render tags field
     if let Some(tags_area) = field_areas.get(form.fields.len() + 1) {
-        render_input_field(f, *tags_area, &form.tags_input, form.current_field == form.fields.len() && !form.auth_method_focused);
+        render_input_field(f, *tags_area, &form.tags_input, form.current_field == form.fields.len() && !form.auth_method_focused, theme);
+        targets.push((FormTarget::Field(form.fields.len()), *tags_area));
     }
+
+    targets
 }
 
-fn render_input_field(f: &mut Frame, area: Rect, field: &crate::forms::InputField, is_focused: bool) {
+fn render_input_field(f: &mut Frame, area: Rect, field: &crate::forms::InputField, is_focused: bool, theme: &Theme) {
 
     // Render input field
     let display_value = if field.value.is_empty() {
@@ -902,21 +2320,21 @@ fn render_input_field(f: &mut Frame, area: Rect, field: &crate::forms::InputFiel
     };
     
     let input_style = if is_focused {
-        Style::default().bg(TokyoNight::BG_HIGHLIGHT).fg(TokyoNight::THEME_GREEN)
+        Style::default().bg(theme.bg_highlight).fg(theme.theme_secondary)
     } else {
-        Style::default().bg(TokyoNight::BG).fg(
+        Style::default().bg(theme.bg).fg(
             if field.value.is_empty() {
-                TokyoNight::COMMENT
+                theme.comment
             } else {
-                TokyoNight::FG
+                theme.fg
             }
         )
     };
     
     let border_style = if is_focused {
-        Style::default().fg(TokyoNight::THEME_GREEN)
+        Style::default().fg(theme.theme_secondary)
     } else {
-        Style::default().fg(TokyoNight::BORDER)
+        Style::default().fg(theme.border)
     };
     
     let title = if is_focused {
@@ -946,7 +2364,7 @@ fn render_input_field(f: &mut Frame, area: Rect, field: &crate::forms::InputFiel
 }
 
 
-fn render_auth_method_field(f: &mut Frame, area: Rect, form: &crate::forms::ServerForm) {
+fn render_auth_method_field(f: &mut Frame, area: Rect, form: &crate::forms::ServerForm, theme: &Theme) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([Constraint::Length(1), Constraint::Length(2)])
@@ -954,9 +2372,9 @@ fn render_auth_method_field(f: &mut Frame, area: Rect, form: &crate::forms::Serv
 
     // Render label
     let label_style = if form.auth_method_focused {
-        Style::default().fg(TokyoNight::THEME_GREEN).add_modifier(Modifier::BOLD)
+        Style::default().fg(theme.theme_secondary).add_modifier(Modifier::BOLD)
     } else {
-        Style::default().fg(TokyoNight::CYAN)
+        Style::default().fg(theme.cyan)
     };
     let label = Paragraph::new("Authentication:")
         .style(label_style);
@@ -964,25 +2382,25 @@ fn render_auth_method_field(f: &mut Frame, area: Rect, form: &crate::forms::Serv
 
     // Render dropdown
     let dropdown_style = if form.auth_method_focused {
-        Style::default().bg(TokyoNight::BG_HIGHLIGHT).fg(TokyoNight::FG)
+        Style::default().bg(theme.bg_highlight).fg(theme.fg)
     } else {
-        Style::default().bg(TokyoNight::BG).fg(TokyoNight::FG)
+        Style::default().bg(theme.bg).fg(theme.fg)
     };
     
     let border_style = if form.auth_method_focused {
-        Style::default().fg(TokyoNight::THEME_GREEN)
+        Style::default().fg(theme.theme_secondary)
     } else {
-        Style::default().fg(TokyoNight::BORDER)
+        Style::default().fg(theme.border)
     };
 
     let auth_text = vec![
         Line::from(vec![
-            Span::styled("▼ ", Style::default().fg(TokyoNight::THEME_GREEN)),
+            Span::styled("▼ ", Style::default().fg(theme.theme_secondary)),
             Span::styled(form.auth_method.display_name(), dropdown_style),
         ]),
         Line::from(Span::styled(
             form.auth_method.description(),
-            Style::default().fg(TokyoNight::COMMENT)
+            Style::default().fg(theme.comment)
         )),
     ];
 
@@ -1071,6 +2489,7 @@ fn render_tooltip(f: &mut Frame, area: Rect, app_state: &AppState, tooltip: &cra
 }
 
 fn render_message_popup(f: &mut Frame, area: Rect, app_state: &AppState) {
+    let theme = app_state.theme_manager.current_theme();
     use chrono::Utc;
     
     let popup_area = centered_rect(40, 10, area);
@@ -1095,12 +2514,12 @@ fn render_message_popup(f: &mut Frame, area: Rect, app_state: &AppState) {
         .block(
             Block::default()
                 .title(" Info ")
-                .title_style(Style::default().fg(TokyoNight::CYAN))
+                .title_style(Style::default().fg(theme.cyan))
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(TokyoNight::BORDER_HIGHLIGHT))
-                .style(Style::default().bg(TokyoNight::BG_POPUP)),
+                .border_style(Style::default().fg(theme.border_highlight))
+                .style(Style::default().bg(theme.bg_popup)),
         )
-        .style(Style::default().fg(TokyoNight::FG))
+        .style(Style::default().fg(theme.fg))
         .alignment(Alignment::Center)
         .wrap(Wrap { trim: true });
 
@@ -1149,26 +2568,30 @@ fn get_status_line(app_state: &AppState) -> String {
     if connecting_count > 0 {
         status_parts.push(format!("{} {} connecting", app_state.get_globe_char(), connecting_count));
     }
+
+    if app_state.credentials.is_unlocked() {
+        status_parts.push("🔓 vault".to_string());
+    }
     
     status_parts.join(" | ")
 }
 
-fn get_health_color(status: &HealthStatus) -> Color {
+fn get_health_color(status: &HealthStatus, theme: &Theme) -> Color {
     match status {
-        HealthStatus::Online => TokyoNight::STATUS_ONLINE,
-        HealthStatus::Offline => TokyoNight::STATUS_OFFLINE,
-        HealthStatus::Connecting => TokyoNight::STATUS_CONNECTING,
-        HealthStatus::Warning => TokyoNight::STATUS_WARNING,
-        HealthStatus::Unknown => TokyoNight::STATUS_UNKNOWN,
+        HealthStatus::Online => theme.status_online,
+        HealthStatus::Offline => theme.status_offline,
+        HealthStatus::Connecting => theme.status_connecting,
+        HealthStatus::Warning => theme.status_warning,
+        HealthStatus::Unknown => theme.status_unknown,
     }
 }
 
-fn get_auth_color(status: &AuthStrength) -> Color {
+fn get_auth_color(status: &AuthStrength, theme: &Theme) -> Color {
     match status {
-        AuthStrength::Key | AuthStrength::Agent => TokyoNight::GREEN,
-        AuthStrength::Password => TokyoNight::ORANGE,
-        AuthStrength::Interactive => TokyoNight::COMMENT,
-        AuthStrength::Unknown => TokyoNight::COMMENT,
+        AuthStrength::Key | AuthStrength::Agent => theme.green,
+        AuthStrength::Password => theme.orange,
+        AuthStrength::Interactive => theme.comment,
+        AuthStrength::Unknown => theme.comment,
     }
 }
 
@@ -1207,6 +2630,7 @@ fn render_analytics_dashboard(f: &mut Frame, area: Rect, app_state: &AppState) {
 
 /// Render analytics overview section
 fn render_analytics_overview(f: &mut Frame, area: Rect, app_state: &AppState) {
+    let theme = app_state.theme_manager.current_theme();
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Percentage(25), Constraint::Percentage(25), Constraint::Percentage(25), Constraint::Percentage(25)])
@@ -1228,10 +2652,10 @@ fn render_analytics_overview(f: &mut Frame, area: Rect, app_state: &AppState) {
     };
     
     // Render stat boxes
-    let stats = [("Total Connections", total_connections.to_string(), TokyoNight::CYAN),
-        ("Success Rate", format!("{:.1}%", success_rate), TokyoNight::GREEN),
-        ("Active Sessions", app_state.server_manager.active_session_count.to_string(), TokyoNight::BLUE),
-        ("Online Servers", format!("{}/{}", app_state.server_manager.online_count(), app_state.server_manager.connection_count()), TokyoNight::THEME_GREEN)];
+    let stats = [("Total Connections", total_connections.to_string(), theme.cyan),
+        ("Success Rate", format!("{:.1}%", success_rate), theme.green),
+        ("Active Sessions", app_state.server_manager.active_session_count.to_string(), theme.blue),
+        ("Online Servers", format!("{}/{}", app_state.server_manager.online_count(), app_state.server_manager.connection_count()), theme.theme_secondary)];
     
     for (i, (label, value, color)) in stats.iter().enumerate() {
         if let Some(chunk) = chunks.get(i) {
@@ -1240,7 +2664,7 @@ fn render_analytics_overview(f: &mut Frame, area: Rect, app_state: &AppState) {
                     Span::styled(value, Style::default().fg(*color).add_modifier(Modifier::BOLD))
                 ),
                 Line::from(
-                    Span::styled(*label, Style::default().fg(TokyoNight::COMMENT))
+                    Span::styled(*label, Style::default().fg(theme.comment))
                 ),
             ];
             
@@ -1248,8 +2672,8 @@ fn render_analytics_overview(f: &mut Frame, area: Rect, app_state: &AppState) {
                 .block(
                     Block::default()
                         .borders(Borders::ALL)
-                        .border_style(Style::default().fg(TokyoNight::BORDER))
-                        .style(Style::default().bg(TokyoNight::BG))
+                        .border_style(Style::default().fg(theme.border))
+                        .style(Style::default().bg(theme.bg))
                 )
                 .alignment(Alignment::Center);
                 
@@ -1274,30 +2698,31 @@ fn render_analytics_details(f: &mut Frame, area: Rect, app_state: &AppState) {
 
 /// Render most used servers list
 fn render_most_used_servers(f: &mut Frame, area: Rect, app_state: &AppState) {
+    let theme = app_state.theme_manager.current_theme();
     let mut servers: Vec<_> = app_state.server_manager.connections.values().collect();
-    servers.sort_by(|a, b| b.stats.connection_count.cmp(&a.stats.connection_count));
+    servers.sort_by_key(|s| std::cmp::Reverse(s.stats.connection_count));
     
     let items: Vec<ListItem> = servers.iter().take(10).enumerate().map(|(i, conn)| {
         let rank_color = match i {
-            0 => TokyoNight::GREEN,
-            1 => TokyoNight::BLUE,  
-            2 => TokyoNight::ORANGE,
-            _ => TokyoNight::COMMENT,
+            0 => theme.green,
+            1 => theme.blue,  
+            2 => theme.orange,
+            _ => theme.comment,
         };
         
         let content = vec![
             Line::from(vec![
                 Span::styled(format!("{:2}.", i + 1), Style::default().fg(rank_color).add_modifier(Modifier::BOLD)),
                 Span::raw(" "),
-                Span::styled(&conn.name, Style::default().fg(TokyoNight::FG)),
+                Span::styled(&conn.name, Style::default().fg(theme.fg)),
                 Span::raw(" "),
-                Span::styled(format!("({})", conn.stats.connection_count), Style::default().fg(TokyoNight::CYAN)),
+                Span::styled(format!("({})", conn.stats.connection_count), Style::default().fg(theme.cyan)),
             ]),
             Line::from(vec![
                 Span::raw("    "),
-                Span::styled(conn.connection_string(), Style::default().fg(TokyoNight::COMMENT)),
+                Span::styled(conn.connection_string(), Style::default().fg(theme.comment)),
                 if let Some(latency) = conn.stats.latency {
-                    Span::styled(format!(" • {}ms", latency.as_millis()), Style::default().fg(TokyoNight::GREEN))
+                    Span::styled(format!(" • {}ms", latency.as_millis()), Style::default().fg(theme.green))
                 } else {
                     Span::raw("")
                 },
@@ -1311,18 +2736,19 @@ fn render_most_used_servers(f: &mut Frame, area: Rect, app_state: &AppState) {
         .block(
             Block::default()
                 .title(" 📈 Most Used Servers ")
-                .title_style(Style::default().fg(TokyoNight::CYAN).add_modifier(Modifier::BOLD))
+                .title_style(Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD))
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(TokyoNight::BORDER))
-                .style(Style::default().bg(TokyoNight::BG))
+                .border_style(Style::default().fg(theme.border))
+                .style(Style::default().bg(theme.bg))
         )
-        .style(Style::default().fg(TokyoNight::FG));
+        .style(Style::default().fg(theme.fg));
         
     f.render_widget(most_used, area);
 }
 
 /// Render connection insights panel  
 fn render_connection_insights(f: &mut Frame, area: Rect, app_state: &AppState) {
+    let theme = app_state.theme_manager.current_theme();
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
@@ -1344,23 +2770,23 @@ fn render_connection_insights(f: &mut Frame, area: Rect, app_state: &AppState) {
     
     let health_stats = vec![
         Line::from(Span::styled("📊 Server Health Distribution", 
-            Style::default().fg(TokyoNight::CYAN).add_modifier(Modifier::BOLD))),
+            Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD))),
         Line::from(""),
         Line::from(vec![
-            Span::styled("●", Style::default().fg(TokyoNight::STATUS_ONLINE)),
-            Span::styled(format!(" Online: {}", online), Style::default().fg(TokyoNight::FG)),
+            Span::styled("●", Style::default().fg(theme.status_online)),
+            Span::styled(format!(" Online: {}", online), Style::default().fg(theme.fg)),
         ]),
         Line::from(vec![
-            Span::styled("●", Style::default().fg(TokyoNight::STATUS_OFFLINE)),
-            Span::styled(format!(" Offline: {}", offline), Style::default().fg(TokyoNight::FG)),
+            Span::styled("●", Style::default().fg(theme.status_offline)),
+            Span::styled(format!(" Offline: {}", offline), Style::default().fg(theme.fg)),
         ]),
         Line::from(vec![
-            Span::styled("◐", Style::default().fg(TokyoNight::STATUS_CONNECTING)),
-            Span::styled(format!(" Connecting: {}", connecting), Style::default().fg(TokyoNight::FG)),
+            Span::styled("◐", Style::default().fg(theme.status_connecting)),
+            Span::styled(format!(" Connecting: {}", connecting), Style::default().fg(theme.fg)),
         ]),
         Line::from(vec![
-            Span::styled("▲", Style::default().fg(TokyoNight::STATUS_WARNING)),
-            Span::styled(format!(" Warning: {}", warning), Style::default().fg(TokyoNight::FG)),
+            Span::styled("▲", Style::default().fg(theme.status_warning)),
+            Span::styled(format!(" Warning: {}", warning), Style::default().fg(theme.fg)),
         ]),
     ];
     
@@ -1368,8 +2794,8 @@ fn render_connection_insights(f: &mut Frame, area: Rect, app_state: &AppState) {
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(TokyoNight::BORDER))
-                .style(Style::default().bg(TokyoNight::BG))
+                .border_style(Style::default().fg(theme.border))
+                .style(Style::default().bg(theme.bg))
         )
         .wrap(Wrap { trim: true });
         
@@ -1390,19 +2816,19 @@ fn render_connection_insights(f: &mut Frame, area: Rect, app_state: &AppState) {
     
     let insights_text = vec![
         Line::from(Span::styled("🔍 Connection Insights", 
-            Style::default().fg(TokyoNight::CYAN).add_modifier(Modifier::BOLD))),
+            Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD))),
         Line::from(""),
         Line::from(vec![
-            Span::styled("Recent Connections: ", Style::default().fg(TokyoNight::COMMENT)),
-            Span::styled(recent_connections.to_string(), Style::default().fg(TokyoNight::FG)),
+            Span::styled("Recent Connections: ", Style::default().fg(theme.comment)),
+            Span::styled(recent_connections.to_string(), Style::default().fg(theme.fg)),
         ]),
         Line::from(vec![
-            Span::styled("Average Latency: ", Style::default().fg(TokyoNight::COMMENT)),
-            Span::styled(avg_latency_str, Style::default().fg(TokyoNight::GREEN)),
+            Span::styled("Average Latency: ", Style::default().fg(theme.comment)),
+            Span::styled(avg_latency_str, Style::default().fg(theme.green)),
         ]),
         Line::from(vec![
-            Span::styled("Total Servers: ", Style::default().fg(TokyoNight::COMMENT)),
-            Span::styled(app_state.server_manager.connection_count().to_string(), Style::default().fg(TokyoNight::FG)),
+            Span::styled("Total Servers: ", Style::default().fg(theme.comment)),
+            Span::styled(app_state.server_manager.connection_count().to_string(), Style::default().fg(theme.fg)),
         ]),
     ];
     
@@ -1410,15 +2836,25 @@ fn render_connection_insights(f: &mut Frame, area: Rect, app_state: &AppState) {
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(TokyoNight::BORDER))
-                .style(Style::default().bg(TokyoNight::BG))
+                .border_style(Style::default().fg(theme.border))
+                .style(Style::default().bg(theme.bg))
         )
         .wrap(Wrap { trim: true });
         
     f.render_widget(insights_panel, chunks[1]);
 }
 
-fn render_sessions_view(f: &mut Frame, area: Rect, app_state: &AppState) {
+/// Lines each entry takes in the server and session lists, for mouse hit-testing
+const SERVER_ITEM_HEIGHT: u16 = 2;
+const SESSION_ITEM_HEIGHT: u16 = 3;
+
+/// Inside of a block drawn with `Borders::ALL`
+fn bordered_inner(area: Rect) -> Rect {
+    Block::default().borders(Borders::ALL).inner(area)
+}
+
+/// Returns the area of the session list
+fn render_sessions_view(f: &mut Frame, area: Rect, app_state: &mut AppState) -> Rect {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -1441,9 +2877,12 @@ fn render_sessions_view(f: &mut Frame, area: Rect, app_state: &AppState) {
     
     // Render session details panel
     render_session_details(f, main_chunks[1], app_state);
+
+    main_chunks[0]
 }
 
-fn render_session_list(f: &mut Frame, area: Rect, app_state: &AppState) {
+fn render_session_list(f: &mut Frame, area: Rect, app_state: &mut AppState) {
+    let theme = app_state.theme_manager.current_theme();
     let sessions = app_state.get_filtered_sessions();
     
     let items: Vec<ListItem> = sessions
@@ -1453,21 +2892,25 @@ fn render_session_list(f: &mut Frame, area: Rect, app_state: &AppState) {
             let is_selected = i == app_state.session_selected_index;
             let style = if is_selected {
                 Style::default()
-                    .bg(TokyoNight::BG_HIGHLIGHT)
-                    .fg(TokyoNight::THEME_GREEN)
+                    .bg(theme.bg_highlight)
+                    .fg(theme.theme_secondary)
                     .add_modifier(Modifier::BOLD)
+            } else if session.is_idle {
+                // Idle sessions fade into the background
+                Style::default().fg(theme.comment).add_modifier(Modifier::DIM)
             } else {
-                Style::default().fg(TokyoNight::FG)
+                Style::default().fg(theme.fg)
             };
+            let name_color = if session.is_idle { theme.comment } else { theme.fg };
 
             let (status_color, status_symbol, status_text) = if session.is_idle {
-                (TokyoNight::ORANGE, "💤", "IDLE")
+                (theme.orange, "💤", "IDLE")
             } else {
-                (TokyoNight::STATUS_ONLINE, "⚡", "ACTIVE")
+                (theme.status_online, "⚡", "ACTIVE")
             };
             
             let formatted_duration = session.format_duration();
-            let duration_color = get_duration_color(&formatted_duration);
+            let duration_color = get_duration_color(&formatted_duration, theme);
             
             // Create a visual progress bar for long sessions
             let progress_bar = create_duration_progress_bar(session.duration());
@@ -1480,38 +2923,49 @@ fn render_session_list(f: &mut Frame, area: Rect, app_state: &AppState) {
                     // Server name
                     Span::styled(&session.server_name, 
                         if is_selected { 
-                            Style::default().fg(TokyoNight::THEME_GREEN).add_modifier(Modifier::BOLD) 
+                            Style::default().fg(theme.theme_secondary).add_modifier(Modifier::BOLD) 
                         } else { 
-                            Style::default().fg(TokyoNight::FG).add_modifier(Modifier::BOLD) 
+                            Style::default().fg(name_color).add_modifier(Modifier::BOLD) 
                         }),
                     // Status badge
                     Span::raw(" "),
                     Span::styled(format!("[{}]", status_text), 
                         Style::default().fg(status_color).add_modifier(Modifier::BOLD)),
+                    match &session.kind {
+                        SessionKind::External => Span::raw(""),
+                        SessionKind::Embedded => Span::styled(" ⧉ tab", Style::default().fg(theme.comment)),
+                        SessionKind::Tmux(pane) => Span::styled(format!(" ⧉ tmux {}", pane), Style::default().fg(theme.comment)),
+                        SessionKind::Zellij(_) => Span::styled(" ⧉ zellij", Style::default().fg(theme.comment)),
+                    },
                 ]),
                 Line::from(vec![
                     Span::raw("    "),
                     // PID with icon
-                    Span::styled("🔸 ", Style::default().fg(TokyoNight::BLUE)),
+                    Span::styled("🔸 ", Style::default().fg(theme.blue)),
                     Span::styled(format!("PID: {}", session.pid), 
-                        Style::default().fg(TokyoNight::COMMENT)),
+                        Style::default().fg(theme.comment)),
                     Span::raw(" │ "),
                     // Duration with color coding
-                    Span::styled("⏱ ", Style::default().fg(TokyoNight::PURPLE)),
+                    Span::styled("⏱ ", Style::default().fg(theme.purple)),
                     Span::styled(formatted_duration, 
                         Style::default().fg(duration_color).add_modifier(Modifier::BOLD)),
                     Span::raw(" "),
                     // Progress bar
-                    Span::styled(progress_bar, Style::default().fg(TokyoNight::CYAN)),
+                    Span::styled(progress_bar, Style::default().fg(theme.cyan)),
+                    if session.process_state == ProcessState::Running {
+                        Span::raw("")
+                    } else {
+                        Span::styled(format!(" ⏸ {}", session.process_state.label()), Style::default().fg(theme.orange))
+                    },
                 ]),
                 Line::from(vec![
                     Span::raw("    "),
                     // Connection info
-                    Span::styled("🔗 ", Style::default().fg(TokyoNight::CYAN)),
+                    Span::styled("🔗 ", Style::default().fg(theme.cyan)),
                     Span::styled(session.window_title.chars().take(40).collect::<String>(), 
-                        Style::default().fg(TokyoNight::COMMENT)),
+                        Style::default().fg(theme.comment)),
                     if session.window_title.len() > 40 { 
-                        Span::styled("...", Style::default().fg(TokyoNight::COMMENT)) 
+                        Span::styled("...", Style::default().fg(theme.comment)) 
                     } else { 
                         Span::raw("") 
                     },
@@ -1528,17 +2982,22 @@ fn render_session_list(f: &mut Frame, area: Rect, app_state: &AppState) {
         .block(
             Block::default()
                 .title(title)
-                .title_style(Style::default().fg(TokyoNight::THEME_GREEN).add_modifier(Modifier::BOLD))
+                .title_style(Style::default().fg(theme.theme_secondary).add_modifier(Modifier::BOLD))
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(TokyoNight::BORDER))
-                .style(Style::default().bg(TokyoNight::BG)),
+                .border_style(Style::default().fg(theme.border))
+                .style(Style::default().bg(theme.bg)),
         )
-        .style(Style::default().fg(TokyoNight::FG));
+        .style(Style::default().fg(theme.fg));
 
-    f.render_widget(list, area);
+    let mut state = ListState::default()
+        .with_offset(app_state.session_list_offset)
+        .with_selected(Some(app_state.session_selected_index));
+    f.render_stateful_widget(list, area, &mut state);
+    app_state.session_list_offset = state.offset();
 }
 
 fn render_session_summary_header(f: &mut Frame, area: Rect, app_state: &AppState) {
+    let theme = app_state.theme_manager.current_theme();
     let sessions = app_state.get_filtered_sessions();
     let (active_count, idle_count) = sessions.iter().fold((0, 0), |(active, idle), session| {
         if session.is_idle { (active, idle + 1) } else { (active + 1, idle) }
@@ -1556,10 +3015,10 @@ fn render_session_summary_header(f: &mut Frame, area: Rect, app_state: &AppState
         .constraints([Constraint::Percentage(25), Constraint::Percentage(25), Constraint::Percentage(25), Constraint::Percentage(25)])
         .split(area);
 
-    let stats = [("📊 Total", sessions.len().to_string(), TokyoNight::CYAN),
-        ("⚡ Active", active_count.to_string(), TokyoNight::STATUS_ONLINE),
-        ("💤 Idle", idle_count.to_string(), TokyoNight::ORANGE),
-        ("⏱ Total Time", total_duration_str, TokyoNight::PURPLE)];
+    let stats = [("📊 Total", sessions.len().to_string(), theme.cyan),
+        ("⚡ Active", active_count.to_string(), theme.status_online),
+        ("💤 Idle", idle_count.to_string(), theme.orange),
+        ("⏱ Total Time", total_duration_str, theme.purple)];
 
     for (i, (label, value, color)) in stats.iter().enumerate() {
         if let Some(chunk) = chunks.get(i) {
@@ -1574,8 +3033,8 @@ fn render_session_summary_header(f: &mut Frame, area: Rect, app_state: &AppState
                 .block(
                     Block::default()
                         .borders(Borders::ALL)
-                        .border_style(Style::default().fg(TokyoNight::BORDER))
-                        .style(Style::default().bg(TokyoNight::BG))
+                        .border_style(Style::default().fg(theme.border))
+                        .style(Style::default().bg(theme.bg))
                 )
                 .alignment(Alignment::Center);
 
@@ -1585,55 +3044,71 @@ fn render_session_summary_header(f: &mut Frame, area: Rect, app_state: &AppState
 }
 
 fn render_session_details(f: &mut Frame, area: Rect, app_state: &AppState) {
+    let theme = app_state.theme_manager.current_theme();
     let sessions = app_state.get_filtered_sessions();
     
     if let Some(session) = sessions.get(app_state.session_selected_index) {
         let details = vec![
             Line::from(vec![
-                Span::styled("Server: ", Style::default().fg(TokyoNight::CYAN).add_modifier(Modifier::BOLD)),
-                Span::styled(&session.server_name, Style::default().fg(TokyoNight::FG)),
+                Span::styled("Server: ", Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD)),
+                Span::styled(&session.server_name, Style::default().fg(theme.fg)),
+            ]),
+            Line::from(vec![
+                Span::styled("Window Title: ", Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD)),
+                Span::styled(&session.window_title, Style::default().fg(theme.fg)),
             ]),
             Line::from(vec![
-                Span::styled("Window Title: ", Style::default().fg(TokyoNight::CYAN).add_modifier(Modifier::BOLD)),
-                Span::styled(&session.window_title, Style::default().fg(TokyoNight::FG)),
+                Span::styled("PID: ", Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD)),
+                Span::styled(session.pid.to_string(), Style::default().fg(theme.fg)),
             ]),
             Line::from(vec![
-                Span::styled("PID: ", Style::default().fg(TokyoNight::CYAN).add_modifier(Modifier::BOLD)),
-                Span::styled(session.pid.to_string(), Style::default().fg(TokyoNight::FG)),
+                Span::styled("Status: ", Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD)),
+                Span::styled(
+                    if session.is_idle {
+                        format!("Idle for {}", format_std_duration(session.idle_for(chrono::Utc::now())))
+                    } else {
+                        "Active".to_string()
+                    },
+                    Style::default().fg(if session.is_idle { theme.orange } else { theme.status_online })
+                ),
             ]),
             Line::from(vec![
-                Span::styled("Status: ", Style::default().fg(TokyoNight::CYAN).add_modifier(Modifier::BOLD)),
+                Span::styled("Process: ", Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD)),
                 Span::styled(
-                    if session.is_idle { "Idle" } else { "Active" },
-                    Style::default().fg(if session.is_idle { TokyoNight::ORANGE } else { TokyoNight::STATUS_ONLINE })
+                    session.process_state.label(),
+                    Style::default().fg(if session.process_state == ProcessState::Running { theme.fg } else { theme.orange })
                 ),
             ]),
             Line::from(vec![
-                Span::styled("Duration: ", Style::default().fg(TokyoNight::CYAN).add_modifier(Modifier::BOLD)),
-                Span::styled(session.format_duration(), Style::default().fg(TokyoNight::FG)),
+                Span::styled("Usage: ", Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD)),
+                Span::styled(session.format_usage().unwrap_or_else(|| "n/a".to_string()), Style::default().fg(theme.fg)),
+            ]),
+            Line::from(vec![
+                Span::styled("Duration: ", Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD)),
+                Span::styled(session.format_duration(), Style::default().fg(theme.fg)),
             ]),
             Line::from(vec![
-                Span::styled("Started: ", Style::default().fg(TokyoNight::CYAN).add_modifier(Modifier::BOLD)),
+                Span::styled("Started: ", Style::default().fg(theme.cyan).add_modifier(Modifier::BOLD)),
                 Span::styled(
                     session.started_at.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
-                    Style::default().fg(TokyoNight::FG)
+                    Style::default().fg(theme.fg)
                 ),
             ]),
             Line::from(""),
             Line::from(vec![
-                Span::styled("Controls:", Style::default().fg(TokyoNight::PURPLE).add_modifier(Modifier::BOLD)),
+                Span::styled("Controls:", Style::default().fg(theme.purple).add_modifier(Modifier::BOLD)),
             ]),
             Line::from(vec![
-                Span::styled("  d ", Style::default().fg(TokyoNight::THEME_GREEN).add_modifier(Modifier::BOLD)),
-                Span::styled("Kill session", Style::default().fg(TokyoNight::FG)),
+                Span::styled("  d ", Style::default().fg(theme.theme_secondary).add_modifier(Modifier::BOLD)),
+                Span::styled("Kill session", Style::default().fg(theme.fg)),
             ]),
             Line::from(vec![
-                Span::styled("  Enter ", Style::default().fg(TokyoNight::THEME_GREEN).add_modifier(Modifier::BOLD)),
-                Span::styled("Show session info", Style::default().fg(TokyoNight::FG)),
+                Span::styled("  Enter ", Style::default().fg(theme.theme_secondary).add_modifier(Modifier::BOLD)),
+                Span::styled("Show session info", Style::default().fg(theme.fg)),
             ]),
             Line::from(vec![
-                Span::styled("  r ", Style::default().fg(TokyoNight::THEME_GREEN).add_modifier(Modifier::BOLD)),
-                Span::styled("Refresh sessions", Style::default().fg(TokyoNight::FG)),
+                Span::styled("  r ", Style::default().fg(theme.theme_secondary).add_modifier(Modifier::BOLD)),
+                Span::styled("Refresh sessions", Style::default().fg(theme.fg)),
             ]),
         ];
 
@@ -1641,10 +3116,10 @@ fn render_session_details(f: &mut Frame, area: Rect, app_state: &AppState) {
             .block(
                 Block::default()
                     .title(" Session Details ")
-                    .title_style(Style::default().fg(TokyoNight::THEME_GREEN).add_modifier(Modifier::BOLD))
+                    .title_style(Style::default().fg(theme.theme_secondary).add_modifier(Modifier::BOLD))
                     .borders(Borders::ALL)
-                    .border_style(Style::default().fg(TokyoNight::BORDER))
-                    .style(Style::default().bg(TokyoNight::BG)),
+                    .border_style(Style::default().fg(theme.border))
+                    .style(Style::default().bg(theme.bg)),
             )
             .wrap(Wrap { trim: true });
 
@@ -1652,11 +3127,11 @@ fn render_session_details(f: &mut Frame, area: Rect, app_state: &AppState) {
     } else {
         let empty_message = vec![
             Line::from(vec![
-                Span::styled("No active sessions", Style::default().fg(TokyoNight::COMMENT)),
+                Span::styled("No active sessions", Style::default().fg(theme.comment)),
             ]),
             Line::from(""),
             Line::from(vec![
-                Span::styled("Connect to a server to start a session", Style::default().fg(TokyoNight::COMMENT)),
+                Span::styled("Connect to a server to start a session", Style::default().fg(theme.comment)),
             ]),
         ];
 
@@ -1664,10 +3139,10 @@ fn render_session_details(f: &mut Frame, area: Rect, app_state: &AppState) {
             .block(
                 Block::default()
                     .title(" Session Details ")
-                    .title_style(Style::default().fg(TokyoNight::THEME_GREEN).add_modifier(Modifier::BOLD))
+                    .title_style(Style::default().fg(theme.theme_secondary).add_modifier(Modifier::BOLD))
                     .borders(Borders::ALL)
-                    .border_style(Style::default().fg(TokyoNight::BORDER))
-                    .style(Style::default().bg(TokyoNight::BG)),
+                    .border_style(Style::default().fg(theme.border))
+                    .style(Style::default().bg(theme.bg)),
             )
             .alignment(Alignment::Center);
 
@@ -1676,22 +3151,22 @@ fn render_session_details(f: &mut Frame, area: Rect, app_state: &AppState) {
 }
 
 /// Get color for duration based on how long the session has been running
-fn get_duration_color(duration_str: &str) -> Color {
+fn get_duration_color(duration_str: &str, theme: &Theme) -> Color {
     if duration_str.contains('h') {
         // Long running sessions (hours) - red
-        TokyoNight::RED
+        theme.red
     } else if duration_str.contains('m') {
         let minutes: i32 = duration_str.split('m').next()
             .unwrap_or("0")
             .parse()
             .unwrap_or(0);
         if minutes > 30 {
-            TokyoNight::ORANGE  // 30+ minutes - orange
+            theme.orange  // 30+ minutes - orange
         } else {
-            TokyoNight::YELLOW  // Less than 30 minutes - yellow
+            theme.yellow  // Less than 30 minutes - yellow
         }
     } else {
-        TokyoNight::GREEN  // Seconds only - green
+        theme.green  // Seconds only - green
     }
 }
 
@@ -1713,6 +3188,48 @@ fn create_duration_progress_bar(duration: std::time::Duration) -> String {
     bar_char.repeat(5)
 }
 
+/// Load, memory and disk of a host, red where over their limit
+fn host_fact_lines(facts: &HostFacts, theme: &Theme) -> Vec<Line<'static>> {
+    let color = |fact: Fact| if facts.is_alert(fact) { theme.red } else { theme.green };
+    let mut lines = Vec::new();
+    if let Some(load) = facts.load {
+        let cpus = facts.cpus.map(|n| format!(" on {} CPU{}", n, if n == 1 { "" } else { "s" })).unwrap_or_default();
+        lines.push(Line::from(vec![
+            Span::styled("Load: ", Style::default().fg(theme.comment)),
+            Span::styled(format!("{:.2} {:.2} {:.2}{}", load[0], load[1], load[2], cpus), Style::default().fg(color(Fact::Load))),
+        ]));
+    }
+    for (label, usage, fact) in [("Memory: ", facts.memory, Fact::Memory), ("Disk /: ", facts.disk, Fact::Disk)] {
+        if let Some(usage) = usage {
+            lines.push(Line::from(vec![
+                Span::styled(label, Style::default().fg(theme.comment)),
+                Span::styled(
+                    format!("{} {:.0}%", usage_bar(usage.percent(), 10), usage.percent()),
+                    Style::default().fg(color(fact)),
+                ),
+            ]));
+        }
+    }
+    lines
+}
+
+/// `width` cells filled in proportion to `percent`
+fn usage_bar(percent: f32, width: usize) -> String {
+    let filled = ((percent / 100.0 * width as f32).round() as usize).min(width);
+    format!("{}{}", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+/// Uptime in days and hours, or hours and minutes for a fresh boot
+fn format_uptime(uptime: std::time::Duration) -> String {
+    let secs = uptime.as_secs();
+    let (days, hours, minutes) = (secs / 86400, secs % 86400 / 3600, secs % 3600 / 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else {
+        format!("{}h {}m", hours, minutes)
+    }
+}
+
 /// Format std::time::Duration for display
 fn format_std_duration(duration: std::time::Duration) -> String {
     let total_seconds = duration.as_secs();
@@ -0,0 +1,128 @@
+//! Reporting the real ssh pid from inside a terminal window.
+//!
+//! Terminal launchers like gnome-terminal, wezterm or kitty hand the window
+//! to a server process and exit, so the pid Ghost spawned says nothing about
+//! the ssh running in it. Instead the terminal runs
+//! `ghost --report-pid <socket> -- ssh …`: the wrapper connects to a socket
+//! Ghost is listening on, sends its own pid and then execs ssh, which keeps
+//! that pid.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Hidden command-line flag that switches `ghost` into wrapper mode
+pub const REPORT_PID_FLAG: &str = "--report-pid";
+
+/// How long to wait for the wrapper to check in. Terminals take a moment to
+/// start, but an unreachable Ghost binary (e.g. inside a sandboxed terminal)
+/// should not stall the UI for long.
+pub const REPORT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// A socket waiting for one wrapper to report its pid
+#[cfg(unix)]
+pub struct PidListener {
+    path: PathBuf,
+    listener: tokio::net::UnixListener,
+}
+
+#[cfg(unix)]
+impl PidListener {
+    /// Listen on a fresh socket in the user's runtime directory (or the
+    /// temp directory where there is none).
+    pub fn bind() -> anyhow::Result<Self> {
+        let dir = dirs::runtime_dir().unwrap_or_else(std::env::temp_dir);
+        let path = dir.join(format!("ghost-{}.sock", uuid::Uuid::new_v4()));
+        let listener = tokio::net::UnixListener::bind(&path)?;
+        Ok(Self { path, listener })
+    }
+
+    /// `program args` rewritten to run through the wrapper, or `None` if
+    /// Ghost's own executable can't be located.
+    pub fn wrap(&self, program: &str, args: &[String]) -> Option<(String, Vec<String>)> {
+        let exe = std::env::current_exe().ok()?;
+        let mut wrapped = vec![
+            REPORT_PID_FLAG.to_string(),
+            self.path.to_string_lossy().into_owned(),
+            "--".to_string(),
+            program.to_string(),
+        ];
+        wrapped.extend_from_slice(args);
+        Some((exe.to_string_lossy().into_owned(), wrapped))
+    }
+
+    /// The pid reported by the wrapper, if it checks in within `timeout`
+    pub async fn wait(&self, timeout: Duration) -> Option<u32> {
+        use tokio::io::AsyncReadExt;
+
+        let (mut stream, _) = tokio::time::timeout(timeout, self.listener.accept()).await.ok()?.ok()?;
+        let mut reply = String::new();
+        tokio::time::timeout(Duration::from_secs(1), stream.read_to_string(&mut reply))
+            .await
+            .ok()?
+            .ok()?;
+        reply.trim().parse().ok()
+    }
+}
+
+#[cfg(unix)]
+impl Drop for PidListener {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Wrapper mode: report our pid on `socket`, then become `argv`. Only
+/// returns if the exec fails.
+#[cfg(unix)]
+pub fn run(socket: &Path, argv: &[String]) -> anyhow::Result<()> {
+    use std::io::Write;
+    use std::os::unix::process::CommandExt;
+
+    // Ghost may have given up waiting; the connection matters more than the report
+    if let Ok(mut stream) = std::os::unix::net::UnixStream::connect(socket) {
+        let _ = write!(stream, "{}", std::process::id());
+    }
+
+    let (program, args) = argv
+        .split_first()
+        .ok_or_else(|| anyhow::anyhow!("{} needs a command to run", REPORT_PID_FLAG))?;
+    let error = std::process::Command::new(program).args(args).exec();
+    Err(anyhow::anyhow!("Failed to run {}: {}", program, error))
+}
+
+#[cfg(not(unix))]
+pub fn run(_socket: &Path, argv: &[String]) -> anyhow::Result<()> {
+    // No unix sockets to report on; just run the command
+    let (program, args) = argv
+        .split_first()
+        .ok_or_else(|| anyhow::anyhow!("{} needs a command to run", REPORT_PID_FLAG))?;
+    let status = std::process::Command::new(program).args(args).status()?;
+    std::process::exit(status.code().unwrap_or(1));
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn wrapper_report_reaches_the_listener() {
+        let listener = PidListener::bind().unwrap();
+        let (_, args) = listener.wrap("ssh", &["host".to_string()]).unwrap();
+        assert_eq!(args[0], REPORT_PID_FLAG);
+        assert_eq!(args[2..], ["--", "ssh", "host"]);
+
+        let socket = PathBuf::from(&args[1]);
+        std::thread::spawn(move || {
+            use std::io::Write;
+            let mut stream = std::os::unix::net::UnixStream::connect(socket).unwrap();
+            write!(stream, "4242").unwrap();
+        });
+        assert_eq!(listener.wait(Duration::from_secs(5)).await, Some(4242));
+    }
+
+    #[tokio::test]
+    async fn silence_times_out() {
+        let listener = PidListener::bind().unwrap();
+        assert_eq!(listener.wait(Duration::from_millis(50)).await, None);
+    }
+}